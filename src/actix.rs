@@ -0,0 +1,225 @@
+//! An `actix-web` extractor and responder of Bincode JSON bodies, like `actix_web::web::Json`.
+//!
+//! Request bodies must have the [CONTENT_TYPE] content type and are extracted under the
+//! [BincodeJsonConfig] in the app data, or the default one.
+
+use crate::{error::Error, limits::DecodeLimits, CONTENT_TYPE};
+use ::actix_web::{
+    body::{self, BodyStream, BoxBody},
+    dev::Payload,
+    error::PayloadError,
+    http::{header, StatusCode},
+    web, FromRequest, HttpRequest, HttpResponse, Responder, ResponseError,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt::{self, Display},
+    future::Future,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+};
+
+/// A Bincode JSON request or response body of a `T`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BincodeJson<T>(pub T);
+impl<T: DeserializeOwned + 'static> FromRequest for BincodeJson<T> {
+    type Error = BincodeJsonPayloadError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = BincodeJsonConfig::from_req(req);
+        let content_type = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        let is_content_type = content_type.is_some_and(crate::is_content_type);
+        let stream = BodyStream::new(payload.take());
+
+        Box::pin(async move {
+            if !is_content_type {
+                return Err(BincodeJsonPayloadError::MissingContentType);
+            }
+            let bytes = body::to_bytes_limited(stream, config.limit)
+                .await
+                .map_err(|_| BincodeJsonPayloadError::Overflow(config.limit))?
+                .map_err(BincodeJsonPayloadError::Payload)?;
+            crate::from_slice_with_limits(&bytes, &config.decode_limits)
+                .map(Self)
+                .map_err(BincodeJsonPayloadError::Decode)
+        })
+    }
+}
+impl<T: Serialize> Responder for BincodeJson<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        match crate::to_vec(&self.0) {
+            Ok(buf) => HttpResponse::Ok().content_type(CONTENT_TYPE).body(buf),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        }
+    }
+}
+impl<T> From<T> for BincodeJson<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+impl<T> Deref for BincodeJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for BincodeJson<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Configuration of [BincodeJson] extraction, registered as app data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BincodeJsonConfig {
+    /// Maximum size of a request body, in bytes, 2 MiB by default.
+    pub limit: usize,
+
+    /// Limits enforced while decoding a request body.
+    pub decode_limits: DecodeLimits,
+}
+impl Default for BincodeJsonConfig {
+    fn default() -> Self {
+        Self {
+            limit: 2 * 1024 * 1024,
+            decode_limits: DecodeLimits::default(),
+        }
+    }
+}
+impl BincodeJsonConfig {
+    /// Constructs a new [BincodeJsonConfig] with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum size of a request body, in bytes.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Sets the limits enforced while decoding a request body.
+    pub fn with_decode_limits(mut self, decode_limits: DecodeLimits) -> Self {
+        self.decode_limits = decode_limits;
+        self
+    }
+
+    /// Gets the configuration in the app data of `req`, either as itself or as `web::Data`.
+    fn from_req(req: &HttpRequest) -> Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Why a [BincodeJson] could not be extracted from a request.
+#[derive(Debug)]
+pub enum BincodeJsonPayloadError {
+    /// The request has no [CONTENT_TYPE] content type, responded with
+    /// `415 Unsupported Media Type`.
+    MissingContentType,
+
+    /// The body is larger than the configured limit, responded with `413 Payload Too Large`.
+    Overflow(usize),
+
+    /// The body could not be read, responded with `400 Bad Request`.
+    Payload(PayloadError),
+
+    /// The body could not be decoded, responded with `400 Bad Request`.
+    Decode(Error),
+}
+impl Display for BincodeJsonPayloadError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingContentType => write!(
+                formatter,
+                "expected request with `Content-Type: {}`",
+                CONTENT_TYPE
+            ),
+            Self::Overflow(x) => write!(formatter, "payload exceeds the limit of {} bytes", x),
+            Self::Payload(e) => write!(formatter, "failed to read the request body: {}", e),
+            Self::Decode(e) => write!(formatter, "failed to decode the request body: {}", e),
+        }
+    }
+}
+impl std::error::Error for BincodeJsonPayloadError {}
+impl ResponseError for BincodeJsonPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::MissingContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Overflow(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Payload(_) | Self::Decode(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::ready;
+    use ::actix_web::test::TestRequest;
+
+    fn extract<T: DeserializeOwned + 'static>(
+        req: TestRequest,
+    ) -> Result<T, BincodeJsonPayloadError> {
+        let (req, mut payload) = req.to_http_parts();
+        ready(BincodeJson::<T>::from_request(&req, &mut payload)).map(|json| json.0)
+    }
+
+    #[test]
+    fn round_trip() {
+        let req = TestRequest::default().to_http_request();
+        let response = BincodeJson(("key", 42u32)).respond_to(&req);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            CONTENT_TYPE
+        );
+        let body = ready(body::to_bytes(response.into_body())).unwrap();
+
+        let req = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, CONTENT_TYPE))
+            .set_payload(body);
+        let decoded: (String, u32) = extract(req).unwrap();
+        assert_eq!(decoded, (String::from("key"), 42));
+    }
+
+    #[test]
+    fn errors() {
+        let body = crate::to_vec(&"text").unwrap();
+        let req = || {
+            TestRequest::default()
+                .insert_header((header::CONTENT_TYPE, CONTENT_TYPE))
+                .set_payload(body.clone())
+        };
+
+        let error =
+            extract::<String>(TestRequest::default().set_payload(body.clone())).unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let error = extract::<u32>(req()).unwrap_err();
+        assert!(
+            matches!(&error, BincodeJsonPayloadError::Decode(e) if e.kind() == ErrorKind::TypeMismatch)
+        );
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+
+        let config = BincodeJsonConfig::new().with_limit(body.len() - 1);
+        let error = extract::<String>(req().app_data(config)).unwrap_err();
+        assert!(matches!(error, BincodeJsonPayloadError::Overflow(_)));
+        assert_eq!(error.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let limits = DecodeLimits::new().with_max_string_len(2);
+        let config = web::Data::new(BincodeJsonConfig::new().with_decode_limits(limits));
+        let error = extract::<String>(req().app_data(config)).unwrap_err();
+        assert!(matches!(&error, BincodeJsonPayloadError::Decode(e) if e.is_limit_exceeded()));
+    }
+}
@@ -0,0 +1,309 @@
+//! Conversions between arrays of objects and Arrow record batches, for columnar processing.
+//!
+//! Each object of the array is a row and each key a column. Missing keys and nulls are null
+//! cells. Inferred schemas have a nullable column per key, sorted by name, typed after the values:
+//! booleans, 64-bit integers, 64-bit floats, UTF-8 strings or binaries, where a column mixing
+//! integers and floats is a float column. Nested arrays and objects are unsupported.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value, ValueKind},
+};
+use arrow_array::{
+    cast::AsArray,
+    types::{
+        Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
+        UInt32Type, UInt64Type, UInt8Type,
+    },
+    Array, ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, NullArray, RecordBatch,
+    StringArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use std::{collections::BTreeMap, sync::Arc};
+
+impl Value {
+    /// Converts an array of objects into a [RecordBatch], inferring its schema with
+    /// [infer_schema].
+    pub fn to_record_batch(&self) -> Result<RecordBatch> {
+        self.to_record_batch_with_schema(Arc::new(infer_schema(self)?))
+    }
+
+    /// Converts an array of objects into a [RecordBatch] of `schema`, whose columns may be null,
+    /// booleans, 64-bit integers, 64-bit floats, UTF-8 strings or binaries. Keys which are not
+    /// in `schema` are ignored.
+    pub fn to_record_batch_with_schema(&self, schema: SchemaRef) -> Result<RecordBatch> {
+        let rows = rows(self)?;
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| column(&rows, field))
+            .collect::<Result<_>>()?;
+        RecordBatch::try_new(schema, columns).map_err(|e| Error::Custom(e.to_string()))
+    }
+
+    /// Converts a [RecordBatch] into an array of objects, one per row.
+    pub fn from_record_batch(batch: &RecordBatch) -> Result<Self> {
+        let schema = batch.schema();
+        let mut rows: Vec<Map<String, Value>> =
+            (0..batch.num_rows()).map(|_| Map::default()).collect();
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            for (i, row) in rows.iter_mut().enumerate() {
+                row.insert(field.name().clone(), cell(column, i)?);
+            }
+        }
+        Ok(Value::Array(rows.into_iter().map(Value::Object).collect()))
+    }
+}
+
+/// Infers the schema of the [RecordBatch] an array of objects converts into.
+pub fn infer_schema(value: &Value) -> Result<Schema> {
+    let mut columns: BTreeMap<&str, DataType> = BTreeMap::new();
+    for row in rows(value)? {
+        for (name, value) in row {
+            let found = match value {
+                Value::Null => DataType::Null,
+                Value::Boolean(_) => DataType::Boolean,
+                Value::Integer(_) => DataType::Int64,
+                Value::Float(_) => DataType::Float64,
+                Value::String(_) => DataType::Utf8,
+                Value::Blob(_) => DataType::Binary,
+                Value::Array(_) | Value::Object(_) => {
+                    return Err(Error::Unsupported(format!(
+                        "nested value in column {}",
+                        name
+                    )))
+                }
+            };
+            let column = columns.entry(name).or_insert(DataType::Null);
+            *column = match (&*column, found) {
+                (DataType::Null, found) => found,
+                (_, DataType::Null) => continue,
+                (DataType::Int64, DataType::Float64) => DataType::Float64,
+                (DataType::Float64, DataType::Int64) => DataType::Float64,
+                (column, found) if *column == found => found,
+                (column, found) => {
+                    return Err(Error::Unsupported(format!(
+                        "column {} mixing {} and {}",
+                        name, column, found
+                    )))
+                }
+            };
+        }
+    }
+    Ok(Schema::new(
+        columns
+            .into_iter()
+            .map(|(name, data_type)| Field::new(name, data_type, true))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Gets the rows of an array of objects.
+fn rows(value: &Value) -> Result<Vec<&Map<String, Value>>> {
+    let array = match value {
+        Value::Array(array) => array,
+        v => return Err(Error::Expected(ValueKind::Array.into(), v.kind().into())),
+    };
+    array
+        .iter()
+        .map(|row| match row {
+            Value::Object(object) => Ok(object),
+            v => Err(Error::Expected(ValueKind::Object.into(), v.kind().into())),
+        })
+        .collect()
+}
+
+/// Builds the column of `field` from `rows`.
+fn column(rows: &[&Map<String, Value>], field: &Field) -> Result<ArrayRef> {
+    Ok(match field.data_type() {
+        DataType::Null => Arc::new(NullArray::new(rows.len())),
+        DataType::Boolean => Arc::new(BooleanArray::from(cells(rows, field, |v| match v {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        })?)),
+        DataType::Int64 => Arc::new(Int64Array::from(cells(rows, field, |v| match v {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        })?)),
+        DataType::Float64 => Arc::new(Float64Array::from(cells(rows, field, |v| match v {
+            Value::Integer(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            _ => None,
+        })?)),
+        DataType::Utf8 => Arc::new(StringArray::from(cells(rows, field, |v| match v {
+            Value::String(s) => Some(s.as_str()),
+            _ => None,
+        })?)),
+        DataType::Binary => Arc::new(BinaryArray::from(cells(rows, field, |v| match v {
+            Value::Blob(b) => Some(b.as_slice()),
+            _ => None,
+        })?)),
+        data_type => return Err(Error::Unsupported(format!("Arrow type {}", data_type))),
+    })
+}
+
+/// Gets the cells of the column of `field`, converting non-null values with `convert`.
+fn cells<'a, T>(
+    rows: &[&'a Map<String, Value>],
+    field: &Field,
+    convert: impl Fn(&'a Value) -> Option<T>,
+) -> Result<Vec<Option<T>>> {
+    rows.iter()
+        .map(|row| match row.get(field.name()) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => convert(value).map(Some).ok_or_else(|| {
+                Error::Expected(
+                    format!("{} in column {}", field.data_type(), field.name()).into(),
+                    value.kind().into(),
+                )
+            }),
+        })
+        .collect()
+}
+
+/// Converts the cell of `column` at `row`.
+fn cell(column: &ArrayRef, row: usize) -> Result<Value> {
+    if column.is_null(row) {
+        return Ok(Value::Null);
+    }
+    Ok(match column.data_type() {
+        DataType::Null => Value::Null,
+        DataType::Boolean => Value::Boolean(column.as_boolean().value(row)),
+        DataType::Int8 => Value::Integer(column.as_primitive::<Int8Type>().value(row).into()),
+        DataType::Int16 => Value::Integer(column.as_primitive::<Int16Type>().value(row).into()),
+        DataType::Int32 => Value::Integer(column.as_primitive::<Int32Type>().value(row).into()),
+        DataType::Int64 => Value::Integer(column.as_primitive::<Int64Type>().value(row)),
+        DataType::UInt8 => Value::Integer(column.as_primitive::<UInt8Type>().value(row).into()),
+        DataType::UInt16 => Value::Integer(column.as_primitive::<UInt16Type>().value(row).into()),
+        DataType::UInt32 => Value::Integer(column.as_primitive::<UInt32Type>().value(row).into()),
+        DataType::UInt64 => {
+            let u = column.as_primitive::<UInt64Type>().value(row);
+            Value::Integer(
+                i64::try_from(u).map_err(|_| Error::Unsupported(format!("integer {}", u)))?,
+            )
+        }
+        DataType::Float32 => Value::Float(column.as_primitive::<Float32Type>().value(row).into()),
+        DataType::Float64 => Value::Float(column.as_primitive::<Float64Type>().value(row)),
+        DataType::Utf8 => Value::String(column.as_string::<i32>().value(row).into()),
+        DataType::LargeUtf8 => Value::String(column.as_string::<i64>().value(row).into()),
+        DataType::Binary => Value::Blob(column.as_binary::<i32>().value(row).into()),
+        DataType::LargeBinary => Value::Blob(column.as_binary::<i64>().value(row).into()),
+        data_type => return Err(Error::Unsupported(format!("Arrow type {}", data_type))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use arrow_array::Int32Array;
+
+    fn row(entries: Vec<(&str, Value)>) -> Value {
+        Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect(),
+        )
+    }
+
+    fn rows() -> Value {
+        Value::Array(vec![
+            row(vec![
+                ("b", Value::Boolean(true)),
+                ("i", Value::Integer(1)),
+                ("f", Value::Integer(2)),
+                ("s", Value::String("a".into())),
+                ("x", Value::Blob(vec![1])),
+            ]),
+            row(vec![
+                ("b", Value::Null),
+                ("f", Value::Float(2.5)),
+                ("s", Value::String("b".into())),
+                ("n", Value::Null),
+            ]),
+        ])
+    }
+
+    #[test]
+    fn round_trip() {
+        let batch = rows().to_record_batch().unwrap();
+        let schema = batch.schema();
+        let columns: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|field| (field.name().as_str(), field.data_type().clone()))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("b", DataType::Boolean),
+                ("f", DataType::Float64),
+                ("i", DataType::Int64),
+                ("n", DataType::Null),
+                ("s", DataType::Utf8),
+                ("x", DataType::Binary),
+            ]
+        );
+        assert_eq!(batch.num_rows(), 2);
+
+        let Value::Array(decoded) = Value::from_record_batch(&batch).unwrap() else {
+            panic!("expected an array");
+        };
+        let Value::Object(first) = &decoded[0] else {
+            panic!("expected an object");
+        };
+        assert!(matches!(first["f"], Value::Float(f) if f == 2.0));
+        assert!(first["n"].is_null());
+        let Value::Object(second) = &decoded[1] else {
+            panic!("expected an object");
+        };
+        assert!(second["b"].is_null() && second["i"].is_null() && second["x"].is_null());
+        assert!(matches!(&second["s"], Value::String(s) if s == "b"));
+    }
+
+    #[test]
+    fn foreign_columns() {
+        let schema = Schema::new(vec![Field::new("n", DataType::Int32, false)]);
+        let column: ArrayRef = Arc::new(Int32Array::from(vec![7]));
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![column]).unwrap();
+        let Value::Array(rows) = Value::from_record_batch(&batch).unwrap() else {
+            panic!("expected an array");
+        };
+        assert!(matches!(&rows[0], Value::Object(o) if matches!(o["n"], Value::Integer(7))));
+    }
+
+    #[test]
+    fn errors() {
+        let error = Value::Integer(1).to_record_batch().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = Value::Array(vec![Value::Integer(1)])
+            .to_record_batch()
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+        let nested = Value::Array(vec![row(vec![("a", Value::Array(Vec::new()))])]);
+        let error = nested.to_record_batch().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        let mixed = Value::Array(vec![
+            row(vec![("a", Value::Integer(1))]),
+            row(vec![("a", Value::String("x".into()))]),
+        ]);
+        let error = mixed.to_record_batch().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+        let wrong = Value::Array(vec![row(vec![("a", Value::Boolean(true))])]);
+        let error = wrong
+            .to_record_batch_with_schema(Arc::new(schema))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, false)]);
+        let missing = Value::Array(vec![row(vec![])]);
+        let error = missing
+            .to_record_batch_with_schema(Arc::new(schema))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+    }
+}
@@ -0,0 +1,184 @@
+//! Encodes and decodes `bincode-json` data over `tokio`'s asynchronous I/O traits.
+
+use crate::{
+    chunked::{ChunkedArray, ChunkedOptions},
+    error::{Error, Result},
+    incremental::{Feed, IncrementalDecoder},
+    value::Value,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Serialize the given data structure into an asynchronous writer.
+///
+/// The whole document is encoded in memory before it is written, as [Serialize] implementations
+/// cannot wait for the writer. To write a large array with bounded memory, use
+/// [to_writer_async_iter].
+pub async fn to_writer_async<W, T>(writer: &mut W, val: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let bytes = crate::to_vec(val)?;
+    writer.write_all(&bytes).await?;
+    Ok(())
+}
+
+/// Serialize the items of `items` as an array into an asynchronous writer, writing the output in
+/// chunks between items as [crate::to_writer_chunked_iter] does. Peak memory is then about a chunk
+/// plus the largest item, however many items there are.
+pub async fn to_writer_async_iter<W, I>(
+    writer: &mut W,
+    items: I,
+    options: &ChunkedOptions,
+) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    I: IntoIterator,
+    I::IntoIter: ExactSizeIterator,
+    I::Item: Serialize,
+{
+    let items = items.into_iter();
+    let mut array = ChunkedArray::new(items.len(), options)?;
+    for item in items {
+        array.element(&item)?;
+        let chunks = array.chunks();
+        if !chunks.is_empty() {
+            writer.write_all(chunks).await?;
+            chunks.clear();
+        }
+    }
+    writer.write_all(&array.finish()?).await?;
+    Ok(())
+}
+
+/// Deserialize an instance of type `T` from an asynchronous reader.
+///
+/// Exactly the bytes of one document are read from `reader`, so multiple documents written
+/// back-to-back may be read by calling this repeatedly. Since the reader is never read past the
+/// end of the document, a document made of many small values takes many small reads: wrap readers
+/// for which reads are costly, such as sockets, in a [tokio::io::BufReader].
+pub async fn from_reader_async<R, T>(reader: &mut R) -> Result<T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut decoder = IncrementalDecoder::<Value>::new();
+    let mut buf = Vec::new();
+    let mut needed = 1;
+    loop {
+        buf.resize(needed, 0);
+        reader
+            .read_exact(&mut buf)
+            .await
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::UnexpectedEof => Error::Eof,
+                _ => Error::Io(e),
+            })?;
+        match decoder.feed_value(&buf)? {
+            Feed::Ready(value) => return crate::from_value(value),
+            Feed::Pending(n) => needed = n,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::ready;
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    #[test]
+    fn round_trip() {
+        let docs = (vec![1u32, 2, 3], "x".repeat(1000));
+        let mut bytes = Vec::new();
+        ready(to_writer_async(&mut bytes, &docs.0)).unwrap();
+        ready(to_writer_async(&mut bytes, &docs.1)).unwrap();
+
+        let mut reader = bytes.as_slice();
+        let first: Vec<u32> = ready(from_reader_async(&mut reader)).unwrap();
+        let second: String = ready(from_reader_async(&mut reader)).unwrap();
+        assert_eq!((first, second), docs);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = crate::to_vec(&"truncated").unwrap();
+        let error = ready(from_reader_async::<_, String>(&mut &bytes[..4])).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+
+        let error = ready(from_reader_async::<_, String>(&mut &[9u8][..])).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Decode);
+    }
+
+    /// A writer recording the length of every write.
+    struct Writes(Vec<u8>, Vec<usize>);
+    impl AsyncWrite for Writes {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.0.extend_from_slice(buf);
+            self.1.push(buf.len());
+            Poll::Ready(Ok(buf.len()))
+        }
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn chunked_round_trip() {
+        let items: Vec<String> = (0..200).map(|i| "x".repeat(i % 40)).collect();
+        let expected = crate::to_vec(&items).unwrap();
+        for chunk_size in [1, 64, 1 << 20] {
+            let options = ChunkedOptions::new().with_chunk_size(chunk_size);
+            let mut writes = Writes(Vec::new(), Vec::new());
+            ready(to_writer_async_iter(&mut writes, &items, &options)).unwrap();
+            assert_eq!(writes.0, expected, "chunk size {}", chunk_size);
+            assert!(writes.1.iter().all(|&len| len < chunk_size.max(64) + 64));
+        }
+
+        let options = ChunkedOptions::new().with_chunk_size(256);
+        let mut writes = Writes(Vec::new(), Vec::new());
+        ready(to_writer_async_iter(&mut writes, &items, &options)).unwrap();
+        assert!(writes.1.len() > 1);
+        let decoded: Vec<String> = ready(from_reader_async(&mut &writes.0[..])).unwrap();
+        assert_eq!(decoded, items);
+    }
+
+    #[test]
+    fn chunked_errors() {
+        /// An iterator yielding one more item than its length.
+        struct Lying(std::ops::Range<u32>);
+        impl Iterator for Lying {
+            type Item = u32;
+            fn next(&mut self) -> Option<u32> {
+                self.0.next()
+            }
+        }
+        impl ExactSizeIterator for Lying {
+            fn len(&self) -> usize {
+                self.0.len() - 1
+            }
+        }
+
+        let options = ChunkedOptions::new();
+        let mut bytes = Vec::new();
+        let error = ready(to_writer_async_iter(&mut bytes, Lying(0..3), &options)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+        let keys = [std::collections::HashMap::from([(1, 1)])];
+        let error = ready(to_writer_async_iter(&mut bytes, &keys, &options)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,155 @@
+//! Conversions between `bincode-json` values and Apache Avro values, for exporting documents into
+//! Avro-based storage.
+//!
+//! Without a schema, integers convert to longs, floats to doubles, blobs to bytes and objects to
+//! maps; [Value::to_avro] resolves them against a schema instead, so that objects become records,
+//! strings become enums and so on. Converting back, records and maps become objects, enums become
+//! their symbol, unions are dropped in favor of the value they hold, dates, times and timestamps
+//! become integers, UUIDs and big decimals become their text, and decimals and durations become
+//! blobs of their Avro encoding.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value},
+};
+use apache_avro::{types::Value as AvroValue, Schema};
+
+impl Value {
+    /// Converts the value into an Avro value of `schema`.
+    pub fn to_avro(self, schema: &Schema) -> Result<AvroValue> {
+        AvroValue::from(self)
+            .resolve(schema)
+            .map_err(|e| Error::Custom(e.to_string()))
+    }
+}
+
+impl From<Value> for AvroValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => AvroValue::Null,
+            Value::Boolean(b) => AvroValue::Boolean(b),
+            Value::Blob(b) => AvroValue::Bytes(b),
+            Value::Array(a) => AvroValue::Array(a.into_iter().map(AvroValue::from).collect()),
+            Value::Integer(i) => AvroValue::Long(i),
+            Value::Float(f) => AvroValue::Double(f),
+            Value::Object(o) => AvroValue::Map(o.into_iter().map(|(k, v)| (k, v.into())).collect()),
+            Value::String(s) => AvroValue::String(s),
+        }
+    }
+}
+impl TryFrom<AvroValue> for Value {
+    type Error = Error;
+
+    fn try_from(value: AvroValue) -> Result<Self> {
+        Ok(match value {
+            AvroValue::Null => Value::Null,
+            AvroValue::Boolean(b) => Value::Boolean(b),
+            AvroValue::Int(i) | AvroValue::Date(i) | AvroValue::TimeMillis(i) => {
+                Value::Integer(i.into())
+            }
+            AvroValue::Long(i)
+            | AvroValue::TimeMicros(i)
+            | AvroValue::TimestampMillis(i)
+            | AvroValue::TimestampMicros(i)
+            | AvroValue::TimestampNanos(i)
+            | AvroValue::LocalTimestampMillis(i)
+            | AvroValue::LocalTimestampMicros(i)
+            | AvroValue::LocalTimestampNanos(i) => Value::Integer(i),
+            AvroValue::Float(f) => Value::Float(f.into()),
+            AvroValue::Double(f) => Value::Float(f),
+            AvroValue::Bytes(b) | AvroValue::Fixed(_, b) => Value::Blob(b),
+            AvroValue::String(s) | AvroValue::Enum(_, s) => Value::String(s),
+            AvroValue::Union(_, v) => (*v).try_into()?,
+            AvroValue::Array(a) => {
+                Value::Array(a.into_iter().map(Value::try_from).collect::<Result<_>>()?)
+            }
+            AvroValue::Map(m) => {
+                let mut map = Map::with_capacity(m.len());
+                for (k, v) in m {
+                    map.insert(k, v.try_into()?);
+                }
+                Value::Object(map)
+            }
+            AvroValue::Record(r) => {
+                let mut map = Map::with_capacity(r.len());
+                for (k, v) in r {
+                    map.insert(k, v.try_into()?);
+                }
+                Value::Object(map)
+            }
+            AvroValue::Decimal(d) => {
+                Value::Blob(Vec::try_from(d).map_err(|e| Error::Custom(e.to_string()))?)
+            }
+            AvroValue::BigDecimal(d) => Value::String(d.to_string()),
+            AvroValue::Duration(d) => Value::Blob(<[u8; 12]>::from(d).into()),
+            AvroValue::Uuid(u) => Value::String(u.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "Event",
+        "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["A", "B"]}},
+            {"name": "note", "type": ["null", "string"]},
+            {"name": "data", "type": "bytes"}
+        ]
+    }"#;
+
+    fn document() -> Value {
+        Value::Object(Map::from([
+            (String::from("id"), Value::Integer(7)),
+            (String::from("kind"), Value::String("B".into())),
+            (String::from("note"), Value::Null),
+            (String::from("data"), Value::Blob(vec![1, 2, 3])),
+        ]))
+    }
+
+    #[test]
+    fn round_trip() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let avro = document().to_avro(&schema).unwrap();
+        assert!(matches!(avro, AvroValue::Record(_)));
+
+        let mut writer = apache_avro::Writer::new(&schema, Vec::new()).unwrap();
+        writer.append_value(avro).unwrap();
+        let bytes = writer.into_inner().unwrap();
+        let avro = apache_avro::Reader::new(&bytes[..])
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let value = Value::try_from(avro).unwrap();
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+
+        let value = Value::try_from(AvroValue::from(document())).unwrap();
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let Value::Object(mut o) = document() else {
+            unreachable!()
+        };
+        o.insert(String::from("kind"), Value::String("C".into()));
+        let error = Value::Object(o).to_avro(&schema).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+
+        let error = Value::String("x".into()).to_avro(&schema).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+    }
+}
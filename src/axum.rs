@@ -0,0 +1,183 @@
+//! An `axum` extractor and response of Bincode JSON bodies, like `axum::Json`.
+//!
+//! Request bodies must have the [CONTENT_TYPE] content type and are decoded with the
+//! [DecodeLimits] in the request's extensions, e.g. added with an `axum::Extension` layer, or with
+//! the default ones. Their size is bounded by `axum`'s `DefaultBodyLimit`, like any other body.
+
+use crate::{error::Error, limits::DecodeLimits, CONTENT_TYPE};
+use ::axum::{
+    body::Bytes,
+    extract::{rejection::BytesRejection, FromRequest, Request},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt::{self, Display},
+    ops::{Deref, DerefMut},
+};
+
+/// A Bincode JSON request or response body of a `T`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BincodeJson<T>(pub T);
+impl<T, S> FromRequest<S> for BincodeJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = BincodeJsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        if !content_type.is_some_and(crate::is_content_type) {
+            return Err(BincodeJsonRejection::MissingContentType);
+        }
+
+        let limits = req
+            .extensions()
+            .get::<DecodeLimits>()
+            .copied()
+            .unwrap_or_default();
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(BincodeJsonRejection::Body)?;
+        crate::from_slice_with_limits(&bytes, &limits)
+            .map(Self)
+            .map_err(BincodeJsonRejection::Decode)
+    }
+}
+impl<T: Serialize> IntoResponse for BincodeJson<T> {
+    fn into_response(self) -> Response {
+        match crate::to_vec(&self.0) {
+            Ok(buf) => (
+                [(header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE))],
+                buf,
+            )
+                .into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
+impl<T> From<T> for BincodeJson<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+impl<T> Deref for BincodeJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for BincodeJson<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Why a [BincodeJson] could not be extracted from a request.
+#[derive(Debug)]
+pub enum BincodeJsonRejection {
+    /// The request has no [CONTENT_TYPE] content type, rejected with `415 Unsupported Media Type`.
+    MissingContentType,
+
+    /// The body could not be read, rejected like `axum` rejects `Bytes`.
+    Body(BytesRejection),
+
+    /// The body could not be decoded, rejected with `400 Bad Request`.
+    Decode(Error),
+}
+impl Display for BincodeJsonRejection {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingContentType => write!(
+                formatter,
+                "expected request with `Content-Type: {}`",
+                CONTENT_TYPE
+            ),
+            Self::Body(e) => write!(formatter, "{}", e),
+            Self::Decode(e) => write!(formatter, "failed to decode the request body: {}", e),
+        }
+    }
+}
+impl std::error::Error for BincodeJsonRejection {}
+impl IntoResponse for BincodeJsonRejection {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Body(e) => e.into_response(),
+            Self::MissingContentType => {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, self.to_string()).into_response()
+            }
+            Self::Decode(_) => (StatusCode::BAD_REQUEST, self.to_string()).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::ready;
+    use ::axum::body::{to_bytes, Body};
+
+    fn request(content_type: Option<&str>, body: Vec<u8>) -> Request {
+        let mut builder = Request::builder();
+        if let Some(content_type) = content_type {
+            builder = builder.header(header::CONTENT_TYPE, content_type);
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    fn extract<T: DeserializeOwned>(req: Request) -> Result<T, BincodeJsonRejection> {
+        ready(BincodeJson::<T>::from_request(req, &())).map(|json| json.0)
+    }
+
+    #[test]
+    fn round_trip() {
+        let response = BincodeJson(("key", 42u32)).into_response();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], CONTENT_TYPE);
+        let body = ready(to_bytes(response.into_body(), usize::MAX)).unwrap();
+
+        let content_type = format!("{}; charset=binary", CONTENT_TYPE);
+        let req = request(Some(&content_type), body.to_vec());
+        let decoded: (String, u32) = extract(req).unwrap();
+        assert_eq!(decoded, (String::from("key"), 42));
+    }
+
+    #[test]
+    fn rejections() {
+        let body = crate::to_vec(&"text").unwrap();
+
+        let rejection = extract::<String>(request(None, body.clone())).unwrap_err();
+        assert!(matches!(
+            rejection,
+            BincodeJsonRejection::MissingContentType
+        ));
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        let req = request(Some("application/json"), body.clone());
+        let rejection = extract::<String>(req).unwrap_err();
+        assert!(matches!(
+            rejection,
+            BincodeJsonRejection::MissingContentType
+        ));
+
+        let rejection = extract::<u32>(request(Some(CONTENT_TYPE), body.clone())).unwrap_err();
+        assert!(
+            matches!(&rejection, BincodeJsonRejection::Decode(e) if e.kind() == ErrorKind::TypeMismatch)
+        );
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+
+        let mut req = request(Some(CONTENT_TYPE), body);
+        req.extensions_mut()
+            .insert(DecodeLimits::new().with_max_string_len(2));
+        let rejection = extract::<String>(req).unwrap_err();
+        assert!(matches!(&rejection, BincodeJsonRejection::Decode(e) if e.is_limit_exceeded()));
+    }
+}
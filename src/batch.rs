@@ -0,0 +1,240 @@
+//! Batch encoding and decoding, for queue consumers which process messages in chunks.
+//!
+//! [to_vec_many] encodes a batch of items back to back into a single buffer allocated once at its
+//! exact size, rather than allocating a buffer per item. [from_slice_many] decodes a batch of
+//! messages. Both report the outcome of every item separately, so that one malformed item does
+//! not abort the whole batch.
+//!
+//! [to_vec_many_dict] encodes the items in the [key dictionary profile](crate::dict) with one
+//! dictionary shared across the batch, so that each key is written once per batch rather than
+//! once per item. Such items refer to the keys of earlier items, so they can only be decoded in
+//! order, by [from_slice_many_dict].
+
+use crate::{
+    dict::{Dict, DictOptions, Dictionary},
+    error::{Error, Result},
+    limits::DecodeLimits,
+    value::Value,
+    wire,
+};
+use alloc::{vec, vec::Vec};
+use bincode::error::DecodeError;
+use core::ops::Range;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The encodings of a batch of items, stored back to back in a single buffer.
+#[derive(Debug)]
+pub struct EncodedBatch {
+    bytes: Vec<u8>,
+    items: Vec<Result<Range<usize>>>,
+}
+impl EncodedBatch {
+    /// Gets the number of items in the batch.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the batch has no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Gets the encoding of the item at `index`, or the error encoding it failed with.
+    pub fn get(&self, index: usize) -> Option<core::result::Result<&[u8], &Error>> {
+        self.items.get(index).map(|item| self.item(item))
+    }
+
+    /// Iterates over the encodings of the items, or the errors encoding them failed with, in
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = core::result::Result<&[u8], &Error>> + '_ {
+        self.items.iter().map(|item| self.item(item))
+    }
+
+    /// Gets the encodings of the items which were encoded, back to back.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn item<'a>(
+        &'a self,
+        item: &'a Result<Range<usize>>,
+    ) -> core::result::Result<&'a [u8], &'a Error> {
+        match item {
+            Ok(range) => Ok(&self.bytes[range.clone()]),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Serialize every item of `items`, back to back in a single buffer.
+pub fn to_vec_many<T: Serialize>(items: &[T]) -> EncodedBatch {
+    let values: Vec<Result<Value>> = items.iter().map(crate::to_value).collect();
+    let len = values.iter().flatten().map(wire::encoded_len).sum();
+    let mut bytes = vec![0; len];
+    let mut start = 0;
+    let items = values
+        .into_iter()
+        .map(|value| {
+            let len = bincode::encode_into_slice(
+                value?,
+                &mut bytes[start..],
+                bincode::config::standard(),
+            )?;
+            start += len;
+            Ok(start - len..start)
+        })
+        .collect();
+    EncodedBatch { bytes, items }
+}
+
+/// Serialize every item of `items` in the key dictionary profile with `options`, back to back in
+/// a single buffer, sharing one dictionary across the batch.
+pub fn to_vec_many_dict<T: Serialize>(items: &[T], options: &DictOptions) -> EncodedBatch {
+    let values: Vec<Result<Value>> = items.iter().map(crate::to_value).collect();
+    let mut bytes = Vec::new();
+    let ranges: Vec<Result<Range<usize>>> = {
+        let mut dict = Dict::new(options);
+        values
+            .iter()
+            .map(|value| {
+                let start = bytes.len();
+                if let Ok(value) = value {
+                    dict.encode_document(value, &mut bytes)?;
+                }
+                Ok(start..bytes.len())
+            })
+            .collect()
+    };
+    let items = values
+        .into_iter()
+        .zip(ranges)
+        .map(|(value, range)| value.and(range))
+        .collect();
+    EncodedBatch { bytes, items }
+}
+
+/// Deserialize an instance of type `T` from every message of `messages`, enforcing the default
+/// [DecodeLimits] on each.
+pub fn from_slice_many<'a, T, I>(messages: I) -> Vec<Result<T>>
+where
+    T: DeserializeOwned,
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    from_slice_many_with_limits(messages, &DecodeLimits::default())
+}
+
+/// Deserialize an instance of type `T` from every message of `messages`, enforcing `limits` on
+/// each.
+pub fn from_slice_many_with_limits<'a, T, I>(messages: I, limits: &DecodeLimits) -> Vec<Result<T>>
+where
+    T: DeserializeOwned,
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    messages
+        .into_iter()
+        .map(|message| {
+            let (value, _) = wire::decode_from_slice(message, limits)?;
+            crate::from_value(value)
+        })
+        .collect()
+}
+
+/// Deserialize an instance of type `T` from every message of a batch encoded by
+/// [to_vec_many_dict], in order, enforcing the default [DecodeLimits] on each.
+pub fn from_slice_many_dict<'a, T, I>(messages: I) -> Vec<Result<T>>
+where
+    T: DeserializeOwned,
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    from_slice_many_dict_with_limits(messages, &DecodeLimits::default())
+}
+
+/// Deserialize an instance of type `T` from every message of a batch encoded by
+/// [to_vec_many_dict], in order, enforcing `limits` on each.
+///
+/// A message which cannot be decoded leaves the shared dictionary incomplete, so every message
+/// after it fails as well.
+pub fn from_slice_many_dict_with_limits<'a, T, I>(
+    messages: I,
+    limits: &DecodeLimits,
+) -> Vec<Result<T>>
+where
+    T: DeserializeOwned,
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    let mut dictionary = Dictionary::default();
+    let mut failed = false;
+    messages
+        .into_iter()
+        .map(|message| {
+            if failed {
+                return Err(DecodeError::Other("an earlier message of the batch failed").into());
+            }
+            let (value, _) = wire::decode_from_slice_with_dict(message, limits, &mut dictionary)
+                .inspect_err(|_| failed = true)?;
+            crate::from_value(value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::{borrow::ToOwned, collections::BTreeMap, string::String};
+
+    fn records() -> Vec<BTreeMap<String, u32>> {
+        (0..20)
+            .map(|i| [("sequence".to_owned(), i), ("partition".to_owned(), 7)].into())
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let batch = to_vec_many(&records());
+        assert_eq!(batch.len(), 20);
+        let decoded = from_slice_many::<BTreeMap<String, u32>, _>(batch.iter().map(|r| r.unwrap()));
+        let decoded: Vec<_> = decoded.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(decoded, records());
+    }
+
+    #[test]
+    fn per_item_errors() {
+        let messages: [&[u8]; 3] = [&crate::to_vec(&1u8).unwrap(), &[0xff, 0xff], &[4, 2]];
+        let decoded = from_slice_many::<u8, _>(messages);
+        assert_eq!(decoded[0].as_ref().unwrap(), &1);
+        assert!(decoded[1].is_err());
+        assert_eq!(decoded[2].as_ref().unwrap(), &1);
+    }
+
+    #[test]
+    fn shared_dictionary() {
+        let batch = to_vec_many_dict(&records(), &DictOptions::new());
+        assert!(batch.as_bytes().len() < to_vec_many(&records()).as_bytes().len());
+        let count = batch
+            .as_bytes()
+            .windows(8)
+            .filter(|w| *w == b"sequence")
+            .count();
+        assert_eq!(count, 1);
+
+        let messages: Vec<&[u8]> = batch.iter().map(|r| r.unwrap()).collect();
+        let decoded = from_slice_many_dict::<BTreeMap<String, u32>, _>(messages.iter().copied());
+        let decoded: Vec<_> = decoded.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(decoded, records());
+
+        // Later items refer to the keys of earlier ones.
+        let error = crate::from_slice_dict::<BTreeMap<String, u32>>(messages[1]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Decode);
+    }
+
+    #[test]
+    fn shared_dictionary_after_failure() {
+        let batch = to_vec_many_dict(&records(), &DictOptions::new());
+        let mut messages: Vec<&[u8]> = batch.iter().map(|r| r.unwrap()).collect();
+        let truncated = &messages[0][..4];
+        messages[0] = truncated;
+        let decoded = from_slice_many_dict::<BTreeMap<String, u32>, _>(messages);
+        assert!(decoded.iter().all(|r| r.is_err()));
+    }
+}
@@ -0,0 +1,279 @@
+//! A command-line tool for inspecting and converting Bincode JSON documents.
+//!
+//! Files default to the standard input and output when omitted or given as `-`. Blobs are
+//! rendered in JSON as `{"$binary": "<base64>"}`, so that conversions round-trip.
+
+use bincode_json::{delta::Delta, json::JsonOptions, Selector, Value};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    process::ExitCode,
+};
+
+const USAGE: &str = "\
+usage: bincode-json <command> [arguments]
+
+commands:
+  encode [input.json] [output]   convert JSON text into Bincode JSON
+  decode [input] [output.json]   convert Bincode JSON into pretty JSON text
+  show [input]                   print a document as an indented tree
+  get <path> [input]             print the values at a path, such as `a.b[*].c`, as JSON
+  diff <old> <new>               print the paths which differ between two documents";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args[..] {
+        ["encode", ref files @ ..] if files.len() <= 2 => encode(file(files, 0), file(files, 1)),
+        ["decode", ref files @ ..] if files.len() <= 2 => decode(file(files, 0), file(files, 1)),
+        ["show", ref files @ ..] if files.len() <= 1 => show(file(files, 0)),
+        ["get", path, ref files @ ..] if files.len() <= 1 => get(path, file(files, 0)),
+        ["diff", old, new] => diff(old, new),
+        ["help" | "-h" | "--help"] => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("bincode-json: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+fn file<'a>(files: &[&'a str], i: usize) -> &'a str {
+    files.get(i).copied().unwrap_or("-")
+}
+
+fn json_options() -> JsonOptions {
+    JsonOptions::new().with_tagged_blobs(true)
+}
+
+fn read(path: &str) -> Result<Vec<u8>> {
+    match path {
+        "-" => {
+            let mut buf = Vec::new();
+            io::stdin().read_to_end(&mut buf)?;
+            Ok(buf)
+        }
+        path => Ok(fs::read(path).map_err(|e| format!("{}: {}", path, e))?),
+    }
+}
+
+fn write(path: &str, bytes: &[u8]) -> Result<()> {
+    match path {
+        "-" => Ok(io::stdout().write_all(bytes)?),
+        path => Ok(fs::write(path, bytes).map_err(|e| format!("{}: {}", path, e))?),
+    }
+}
+
+fn read_value(path: &str) -> Result<Value> {
+    Ok(bincode_json::from_slice(&read(path)?)?)
+}
+
+fn to_json(value: Value) -> Result<String> {
+    Ok(serde_json::to_string_pretty(
+        &value.to_json_with(&json_options()),
+    )?)
+}
+
+fn encode(input: &str, output: &str) -> Result<()> {
+    let json: serde_json::Value = serde_json::from_slice(&read(input)?)?;
+    let value = Value::from_json_with(json, &json_options());
+    write(output, &value.to_vec()?)
+}
+
+fn decode(input: &str, output: &str) -> Result<()> {
+    let mut json = to_json(read_value(input)?)?;
+    json.push('\n');
+    write(output, json.as_bytes())
+}
+
+fn show(input: &str) -> Result<()> {
+    println!("{}", read_value(input)?.display_tree());
+    Ok(())
+}
+
+fn get(path: &str, input: &str) -> Result<()> {
+    let selector = Selector::compile(path)?;
+    let value = read_value(input)?;
+    for value in selector.select(&value) {
+        println!("{}", to_json(value.clone())?);
+    }
+    Ok(())
+}
+
+fn diff(old: &str, new: &str) -> Result<()> {
+    let old = read_value(old)?;
+    let delta = bincode_json::delta::diff(&old, &read_value(new)?);
+    print_delta(&old, &delta, &mut String::new())
+}
+
+/// Prints the paths changed by `delta` from `old` at `path`, prefixed by `-` if removed, `+` if
+/// added or `~` if replaced, followed by the new value.
+fn print_delta(old: &Value, delta: &Delta, path: &mut String) -> Result<()> {
+    let len = path.len();
+    match (old, delta) {
+        (_, Delta::Unchanged) => {}
+        (
+            Value::Array(old),
+            Delta::Array {
+                len: new_len,
+                patches,
+            },
+        ) => {
+            for (i, delta) in patches {
+                path.push_str(&format!("[{}]", i));
+                match old.get(*i) {
+                    Some(old) => print_delta(old, delta, path)?,
+                    None => print_added(delta, path)?,
+                }
+                path.truncate(len);
+            }
+            for i in *new_len..old.len() {
+                println!("- {}[{}]", path, i);
+            }
+        }
+        (Value::Object(old), Delta::Object { removed, patches }) => {
+            for key in removed {
+                println!("- {}", child_path(path, key));
+            }
+            for (key, delta) in patches {
+                if len != 0 {
+                    path.push('.');
+                }
+                path.push_str(key);
+                match old.get(key) {
+                    Some(old) => print_delta(old, delta, path)?,
+                    None => print_added(delta, path)?,
+                }
+                path.truncate(len);
+            }
+        }
+        (_, Delta::Replace(value)) => println!("~ {}: {}", display_path(path), to_line(value)?),
+        (_, delta) => println!("~ {}: {:?}", display_path(path), delta),
+    }
+    Ok(())
+}
+
+fn print_added(delta: &Delta, path: &str) -> Result<()> {
+    if let Delta::Replace(value) = delta {
+        println!("+ {}: {}", path, to_line(value)?);
+    }
+    Ok(())
+}
+
+fn to_line(value: &Value) -> Result<String> {
+    Ok(serde_json::to_string(
+        &value.clone().to_json_with(&json_options()),
+    )?)
+}
+
+fn display_path(path: &str) -> &str {
+    match path {
+        "" => "(root)",
+        path => path,
+    }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    match path {
+        "" => key.into(),
+        path => format!("{}.{}", path, key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A file in the temporary directory, removed when dropped.
+    struct TempFile(PathBuf);
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "bincode-json-cli-{}-{}",
+                std::process::id(),
+                name
+            ));
+            Self(path)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let json = TempFile::new("round-trip.json");
+        let document = TempFile::new("round-trip.bin");
+        let decoded = TempFile::new("round-trip-decoded.json");
+        let text = r#"{"a": [1, 2.5, null, true], "b": {"$binary": "AQID"}, "c": "text"}"#;
+        fs::write(&json.0, text).unwrap();
+
+        encode(json.path(), document.path()).unwrap();
+        let Value::Object(o) = read_value(document.path()).unwrap() else {
+            panic!("expected an object");
+        };
+        assert!(matches!(&o["b"], Value::Blob(b) if b == &[1, 2, 3]));
+
+        decode(document.path(), decoded.path()).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(text).unwrap();
+        let actual: serde_json::Value =
+            serde_json::from_slice(&fs::read(&decoded.0).unwrap()).unwrap();
+        assert_eq!(actual, expected);
+
+        show(document.path()).unwrap();
+        get("a[*]", document.path()).unwrap();
+        diff(document.path(), document.path()).unwrap();
+
+        let changed = TempFile::new("round-trip-changed.bin");
+        let json = serde_json::json!({"a": [1], "c": {"d": 1}, "e": "new"});
+        let value = Value::from_json_with(json, &json_options());
+        fs::write(&changed.0, value.to_vec().unwrap()).unwrap();
+        diff(document.path(), changed.path()).unwrap();
+        diff(changed.path(), document.path()).unwrap();
+    }
+
+    #[test]
+    fn errors() {
+        let missing = TempFile::new("missing");
+        let error = read(missing.path()).unwrap_err();
+        assert!(error.to_string().starts_with(missing.path()));
+
+        let json = TempFile::new("errors.json");
+        fs::write(&json.0, "{").unwrap();
+        assert!(encode(json.path(), missing.path()).is_err());
+        assert!(read_value(json.path()).is_err());
+
+        let document = TempFile::new("errors.bin");
+        fs::write(&document.0, Value::Null.to_vec().unwrap()).unwrap();
+        assert!(get("a[", document.path()).is_err());
+        assert!(diff(document.path(), missing.path()).is_err());
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(file(&["a"], 0), "a");
+        assert_eq!(file(&["a"], 1), "-");
+        assert_eq!(display_path(""), "(root)");
+        assert_eq!(display_path("a"), "a");
+        assert_eq!(child_path("", "b"), "b");
+        assert_eq!(child_path("a", "b"), "a.b");
+    }
+}
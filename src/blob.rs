@@ -0,0 +1,143 @@
+//! Redirecting the contents of large blobs out of decoded documents.
+//!
+//! Decoding with a [BlobSink] hands the contents of every blob above a size threshold to the
+//! sink as they are read, e.g. to stream them into a file or a hasher, so that they are never held
+//! in memory. The sink leaves a placeholder [Value] of its choosing in the blob's place.
+
+use crate::{
+    error::Result,
+    limits::DecodeLimits,
+    value::Value,
+    wire::{self, IoReader},
+};
+use bincode::{
+    de::{read::Reader, Decoder, DecoderImpl},
+    error::DecodeError,
+};
+use serde::de::DeserializeOwned;
+use std::io::{self, Read};
+
+/// Receives the contents of blobs above the size threshold while decoding.
+///
+/// Any closure of type `FnMut(usize, &mut dyn Read) -> Result<Value>` is a [BlobSink].
+pub trait BlobSink {
+    /// Receives the contents of a blob of `len` bytes from `contents`, returning the value left
+    /// in the blob's place. Contents which are not read are skipped.
+    fn write_blob(&mut self, len: usize, contents: &mut dyn Read) -> Result<Value>;
+}
+impl<F: FnMut(usize, &mut dyn Read) -> Result<Value>> BlobSink for F {
+    fn write_blob(&mut self, len: usize, contents: &mut dyn Read) -> Result<Value> {
+        self(len, contents)
+    }
+}
+
+/// A [Read] over the contents of a blob being decoded.
+struct Contents<'a, D> {
+    decoder: &'a mut D,
+    remaining: usize,
+}
+impl<D: Decoder> Read for Contents<'_, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.remaining);
+        self.decoder
+            .reader()
+            .read(&mut buf[..n])
+            .map_err(|e| match e {
+                DecodeError::Io { inner, .. } => inner,
+                e => io::Error::new(io::ErrorKind::InvalidData, e),
+            })?;
+        self.remaining -= n;
+        Ok(n)
+    }
+}
+
+/// Deserialize an instance of type `T` from a reader of Bincode JSON, handing the contents of
+/// every blob longer than `threshold` bytes to `sink`. The default [DecodeLimits] are enforced.
+pub fn from_reader_with_blob_sink<R, T, S>(reader: R, threshold: usize, sink: &mut S) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned,
+    S: BlobSink,
+{
+    let mut decoder = DecoderImpl::new(IoReader(reader), bincode::config::standard());
+    let value = wire::decode_value_with(
+        &mut decoder,
+        &DecodeLimits::default(),
+        0,
+        &mut |decoder, len| {
+            if len <= threshold {
+                return Ok(Value::Blob(wire::read_bytes(decoder, len)?));
+            }
+            let mut contents = Contents {
+                decoder,
+                remaining: len,
+            };
+            let value = sink.write_blob(len, &mut contents)?;
+            io::copy(&mut contents, &mut io::sink())?;
+            Ok(value)
+        },
+    )?;
+    crate::from_value(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{Error, ErrorKind};
+
+    fn document() -> Vec<u8> {
+        Value::Array(vec![
+            Value::Blob(vec![1; 4]),
+            Value::Blob(vec![2; 1000]),
+            Value::String("after".into()),
+        ])
+        .to_vec()
+        .unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut received = Vec::new();
+        let mut sink = |len: usize, contents: &mut dyn Read| {
+            let mut blob = Vec::new();
+            contents.read_to_end(&mut blob)?;
+            assert_eq!(blob.len(), len);
+            received.push(blob);
+            Ok(Value::Integer(len as i64))
+        };
+        let decoded: Vec<Value> =
+            from_reader_with_blob_sink(&document()[..], 4, &mut sink).unwrap();
+        assert_eq!(received, [vec![2; 1000]]);
+        assert!(matches!(&decoded[0], Value::Blob(blob) if blob == &[1; 4]));
+        assert!(matches!(decoded[1], Value::Integer(1000)));
+        assert!(matches!(&decoded[2], Value::String(s) if s == "after"));
+    }
+
+    #[test]
+    fn unread_contents_are_skipped() {
+        let mut sink = |_: usize, contents: &mut dyn Read| {
+            contents.read_exact(&mut [0; 10])?;
+            Ok(Value::Null)
+        };
+        let decoded: (Value, Option<u8>, String) =
+            from_reader_with_blob_sink(&document()[..], 4, &mut sink).unwrap();
+        assert_eq!(decoded.2, "after");
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = document();
+        let mut sink = |_: usize, contents: &mut dyn Read| {
+            io::copy(contents, &mut io::sink())?;
+            Ok(Value::Null)
+        };
+        let error = from_reader_with_blob_sink::<_, Vec<Value>, _>(&bytes[..100], 4, &mut sink)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+
+        let mut failing = |_: usize, _: &mut dyn Read| Err(Error::Custom("rejected".into()));
+        let error = from_reader_with_blob_sink::<_, Vec<Value>, _>(&bytes[..], 4, &mut failing)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+    }
+}
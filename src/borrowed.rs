@@ -0,0 +1,319 @@
+//! A borrowed counterpart of [Value], which decodes without copying strings and blobs.
+
+use crate::{
+    error::{BincodeError, Error, Result},
+    limits::{DecodeLimits, Limit},
+    value::{Map, Value},
+    wire::{self, SliceReader},
+};
+use alloc::{borrow::ToOwned, string::ToString, vec::Vec};
+use bincode::{
+    de::{read::BorrowReader, BorrowDecode, BorrowDecoder, Decode},
+    enc::{Encode, Encoder},
+    error::{DecodeError, EncodeError},
+};
+use serde::ser;
+
+/// Represents any valid `bincode-json` value, with strings, blobs and object keys borrowed from
+/// the decoded input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BorrowedValue<'a> {
+    /// Represents a `bincode-json` null value.
+    Null,
+
+    /// Represents a `bincode-json` bool value.
+    Boolean(bool),
+
+    /// Represents a `bincode-json` blob value.
+    Blob(&'a [u8]),
+
+    /// Represents a `bincode-json` array value.
+    Array(Vec<BorrowedValue<'a>>),
+
+    /// Represents a `bincode-json` integer value.
+    Integer(i64),
+
+    /// Represents a `bincode-json` float value.
+    Float(f64),
+
+    /// Represents a `bincode-json` object value.
+    Object(Map<&'a str, BorrowedValue<'a>>),
+
+    /// Represents a `bincode-json` string value.
+    String(&'a str),
+}
+impl<'a> BorrowedValue<'a> {
+    /// Decodes a value from the beginning of `bytes`, enforcing the default [DecodeLimits].
+    pub fn from_slice(bytes: &'a [u8]) -> Result<Self> {
+        Self::from_slice_with_limits(bytes, &DecodeLimits::default())
+    }
+
+    /// Decodes a value from the beginning of `bytes`, enforcing `limits`.
+    pub fn from_slice_with_limits(bytes: &'a [u8], limits: &DecodeLimits) -> Result<Self> {
+        limits.check(Limit::Bytes, bytes.len())?;
+        let mut decoder =
+            bincode::de::DecoderImpl::new(SliceReader::new(bytes), bincode::config::standard());
+        decode_borrowed(&mut decoder, limits, 0)
+    }
+
+    /// Copies the borrowed parts of the value, converting it into a [Value].
+    pub fn into_owned(self) -> Value {
+        match self {
+            Self::Null => Value::Null,
+            Self::Boolean(b) => Value::Boolean(b),
+            Self::Blob(b) => Value::Blob(b.to_vec()),
+            Self::Array(a) => Value::Array(a.into_iter().map(Self::into_owned).collect()),
+            Self::Integer(i) => Value::Integer(i),
+            Self::Float(f) => Value::Float(f),
+            Self::Object(o) => Value::Object(
+                o.into_iter()
+                    .map(|(k, v)| (k.to_owned(), v.into_owned()))
+                    .collect(),
+            ),
+            Self::String(s) => Value::String(s.to_owned()),
+        }
+    }
+
+    /// Returns `true` if this value is `Null`.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// Gets the string this value holds, if it is a string.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Gets the bytes this value holds, if it is a blob.
+    pub fn as_blob(&self) -> Option<&'a [u8]> {
+        match self {
+            Self::Blob(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+impl<'a> From<&'a Value> for BorrowedValue<'a> {
+    fn from(value: &'a Value) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::Boolean(b) => Self::Boolean(*b),
+            Value::Blob(b) => Self::Blob(b),
+            Value::Array(a) => Self::Array(a.iter().map(Self::from).collect()),
+            Value::Integer(i) => Self::Integer(*i),
+            Value::Float(f) => Self::Float(*f),
+            Value::Object(o) => Self::Object(o.iter().map(|(k, v)| (&k[..], v.into())).collect()),
+            Value::String(s) => Self::String(s),
+        }
+    }
+}
+impl<'de> BorrowDecode<'de> for BorrowedValue<'de> {
+    /// Decodes a value, enforcing the default [DecodeLimits]. Errors which bincode cannot
+    /// represent, such as exceeded limits, are reported as [DecodeError::OtherString].
+    fn borrow_decode<D: BorrowDecoder<'de>>(
+        decoder: &mut D,
+    ) -> core::result::Result<Self, DecodeError> {
+        decode_borrowed(decoder, &DecodeLimits::default(), 0).map_err(|e| match e {
+            Error::Bincode(BincodeError::Decode(e)) => e,
+            e => DecodeError::OtherString(e.to_string()),
+        })
+    }
+}
+impl Encode for BorrowedValue<'_> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> core::result::Result<(), EncodeError> {
+        match self {
+            Self::Null => wire::NULL.encode(encoder),
+            Self::Boolean(b) => {
+                wire::BOOLEAN.encode(encoder)?;
+                b.encode(encoder)
+            }
+            Self::Blob(b) => {
+                wire::BLOB.encode(encoder)?;
+                b.encode(encoder)
+            }
+            Self::Array(a) => {
+                wire::ARRAY.encode(encoder)?;
+                (a.len() as u64).encode(encoder)?;
+                a.iter().try_for_each(|v| v.encode(encoder))
+            }
+            Self::Integer(i) => {
+                wire::INTEGER.encode(encoder)?;
+                i.encode(encoder)
+            }
+            Self::Float(f) => {
+                wire::FLOAT.encode(encoder)?;
+                f.encode(encoder)
+            }
+            Self::Object(o) => {
+                wire::OBJECT.encode(encoder)?;
+                (o.len() as u64).encode(encoder)?;
+                for (k, v) in o {
+                    k.encode(encoder)?;
+                    v.encode(encoder)?;
+                }
+                Ok(())
+            }
+            Self::String(s) => {
+                wire::STRING.encode(encoder)?;
+                s.encode(encoder)
+            }
+        }
+    }
+}
+impl ser::Serialize for BorrowedValue<'_> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Null => serializer.serialize_none(),
+            Self::Boolean(b) => serializer.serialize_bool(*b),
+            Self::Blob(b) => serializer.serialize_bytes(b),
+            Self::Integer(n) => serializer.serialize_i64(*n),
+            Self::Float(f) => serializer.serialize_f64(*f),
+            Self::String(s) => serializer.serialize_str(s),
+            Self::Array(v) => v.serialize(serializer),
+            Self::Object(m) => {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(m.len()))?;
+                for (k, v) in m {
+                    map.serialize_entry(k, v)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// Takes `len` bytes from the input without copying them.
+pub(crate) fn take_bytes<'de, D: BorrowDecoder<'de>>(
+    decoder: &mut D,
+    len: usize,
+) -> Result<&'de [u8]> {
+    decoder.claim_bytes_read(len)?;
+    Ok(decoder.borrow_reader().take_bytes(len)?)
+}
+
+/// Decodes a length-prefixed string without copying it.
+pub(crate) fn take_str<'de, D: BorrowDecoder<'de>>(
+    decoder: &mut D,
+    limits: &DecodeLimits,
+) -> Result<&'de str> {
+    let len = wire::decode_len(decoder)?;
+    limits.check(Limit::StringLength, len)?;
+    core::str::from_utf8(take_bytes(decoder, len)?)
+        .map_err(|inner| Error::from(DecodeError::Utf8 { inner }))
+}
+
+/// Decodes a [BorrowedValue] nested at `depth`, enforcing `limits`.
+fn decode_borrowed<'de, D: BorrowDecoder<'de>>(
+    decoder: &mut D,
+    limits: &DecodeLimits,
+    depth: usize,
+) -> Result<BorrowedValue<'de>> {
+    match wire::decode_tag(decoder)? {
+        wire::NULL => Ok(BorrowedValue::Null),
+        wire::BOOLEAN => Ok(BorrowedValue::Boolean(bool::decode(decoder)?)),
+        wire::BLOB => {
+            let len = wire::decode_len(decoder)?;
+            limits.check(Limit::BlobLength, len)?;
+            Ok(BorrowedValue::Blob(take_bytes(decoder, len)?))
+        }
+        wire::ARRAY => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = wire::decode_len(decoder)?;
+            limits.check(Limit::Elements, len)?;
+            let mut array = Vec::with_capacity(len.min(wire::MAX_PREALLOCATED));
+            for _ in 0..len {
+                array.push(decode_borrowed(decoder, limits, depth + 1)?);
+            }
+            Ok(BorrowedValue::Array(array))
+        }
+        wire::INTEGER => Ok(BorrowedValue::Integer(i64::decode(decoder)?)),
+        wire::FLOAT => Ok(BorrowedValue::Float(f64::decode(decoder)?)),
+        wire::OBJECT => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = wire::decode_len(decoder)?;
+            limits.check(Limit::Elements, len)?;
+            let mut object = Map::with_capacity(len.min(wire::MAX_PREALLOCATED));
+            for _ in 0..len {
+                let key = take_str(decoder, limits)?;
+                object.insert(key, decode_borrowed(decoder, limits, depth + 1)?);
+            }
+            Ok(BorrowedValue::Object(object))
+        }
+        _ => Ok(BorrowedValue::String(take_str(decoder, limits)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::{string::String, vec};
+
+    fn document() -> Value {
+        Value::Array(vec![
+            Value::Null,
+            Value::Boolean(true),
+            Value::Blob(vec![1, 2, 3]),
+            Value::Integer(-42),
+            Value::Float(1.5),
+            Value::Object(Map::from([(
+                String::from("key"),
+                Value::String("text".into()),
+            )])),
+        ])
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = document();
+        let bytes = value.to_vec().unwrap();
+        let borrowed = BorrowedValue::from_slice(&bytes).unwrap();
+        assert_eq!(borrowed, BorrowedValue::from(&value));
+        assert_eq!(
+            bincode::encode_to_vec(&borrowed, bincode::config::standard()).unwrap(),
+            bytes
+        );
+        assert_eq!(borrowed.clone().into_owned().to_vec().unwrap(), bytes);
+        assert_eq!(crate::to_vec(&borrowed).unwrap(), bytes);
+
+        let (decoded, len): (BorrowedValue, _) =
+            bincode::borrow_decode_from_slice(&bytes, bincode::config::standard()).unwrap();
+        assert_eq!(decoded, borrowed);
+        assert_eq!(len, bytes.len());
+    }
+
+    #[test]
+    fn borrows_input() {
+        let bytes = crate::to_vec(&"text").unwrap();
+        let text = BorrowedValue::from_slice(&bytes).unwrap().as_str().unwrap();
+        assert_eq!(text, "text");
+        assert!(bytes.as_ptr_range().contains(&text.as_ptr()));
+
+        let bytes = Value::Blob(vec![1, 2, 3]).to_vec().unwrap();
+        let blob = BorrowedValue::from_slice(&bytes)
+            .unwrap()
+            .as_blob()
+            .unwrap();
+        assert!(bytes.as_ptr_range().contains(&blob.as_ptr()));
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = document().to_vec().unwrap();
+        for len in 0..bytes.len() {
+            let error = BorrowedValue::from_slice(&bytes[..len]).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Eof);
+        }
+
+        let error = BorrowedValue::from_slice(&[7, 1, 0xff]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Decode);
+        let limits = DecodeLimits::new().with_max_depth(0);
+        let error = BorrowedValue::from_slice_with_limits(&bytes, &limits).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+    }
+}
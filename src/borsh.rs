@@ -0,0 +1,147 @@
+//! Borsh interop, for bridging Bincode JSON documents to borsh-based data.
+//!
+//! [Value] implements borsh's traits, so values can be embedded in borsh structures: a value is a
+//! `u8` tag, numbered like its variant on the wire, followed by its payload in borsh's encoding.
+//! Objects are written sorted by key, so equal values have equal encodings, and floats follow
+//! borsh in rejecting NaNs. Decoding is bounded by the default nesting depth of [DecodeLimits].
+//!
+//! As borsh is not self-describing, typed documents are converted through a `T` implementing both
+//! trait families with [to_borsh] and [from_borsh].
+
+use crate::{
+    error::{Error, Result},
+    limits::DecodeLimits,
+    value::{Map, Value},
+    wire,
+};
+use ::borsh::{BorshDeserialize, BorshSerialize};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{self, Read, Write};
+
+impl BorshSerialize for Value {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&(self.variant_index() as u8), writer)?;
+        match self {
+            Value::Null => Ok(()),
+            Value::Boolean(b) => BorshSerialize::serialize(b, writer),
+            Value::Blob(b) => BorshSerialize::serialize(b, writer),
+            Value::Array(a) => BorshSerialize::serialize(a, writer),
+            Value::Integer(i) => BorshSerialize::serialize(i, writer),
+            Value::Float(f) => BorshSerialize::serialize(f, writer),
+            Value::Object(o) => {
+                let mut entries: Vec<_> = o.iter().collect();
+                entries.sort_unstable_by_key(|(k, _)| *k);
+                BorshSerialize::serialize(&entries, writer)
+            }
+            Value::String(s) => BorshSerialize::serialize(s, writer),
+        }
+    }
+}
+impl BorshDeserialize for Value {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        read_value(reader, DecodeLimits::default().max_depth)
+    }
+}
+
+/// Converts a Bincode JSON document of a `T` to borsh.
+pub fn to_borsh<T: DeserializeOwned + BorshSerialize>(bytes: &[u8]) -> Result<Vec<u8>> {
+    let value: T = crate::from_slice(bytes)?;
+    Ok(::borsh::to_vec(&value)?)
+}
+
+/// Converts a borsh encoding of a `T` to a Bincode JSON document.
+pub fn from_borsh<T: BorshDeserialize + Serialize>(bytes: &[u8]) -> Result<Vec<u8>> {
+    let value: T = ::borsh::from_slice(bytes)?;
+    crate::to_vec(&value)
+}
+
+/// Reads a value nested at most `depth` containers deep.
+fn read_value<R: Read>(reader: &mut R, depth: usize) -> io::Result<Value> {
+    let tag = u8::deserialize_reader(reader)?;
+    let container = matches!(tag as u32, wire::ARRAY | wire::OBJECT);
+    if container && depth == 0 {
+        return Err(invalid_data(Error::Unsupported(
+            "nesting deeper than the limit".into(),
+        )));
+    }
+
+    Ok(match tag as u32 {
+        wire::NULL => Value::Null,
+        wire::BOOLEAN => Value::Boolean(BorshDeserialize::deserialize_reader(reader)?),
+        wire::BLOB => Value::Blob(BorshDeserialize::deserialize_reader(reader)?),
+        wire::ARRAY => {
+            let len = u32::deserialize_reader(reader)? as usize;
+            let mut array = Vec::with_capacity(len.min(wire::MAX_PREALLOCATED));
+            for _ in 0..len {
+                array.push(read_value(reader, depth - 1)?);
+            }
+            Value::Array(array)
+        }
+        wire::INTEGER => Value::Integer(BorshDeserialize::deserialize_reader(reader)?),
+        wire::FLOAT => Value::Float(BorshDeserialize::deserialize_reader(reader)?),
+        wire::OBJECT => {
+            let len = u32::deserialize_reader(reader)? as usize;
+            let mut object = Map::with_capacity(len.min(wire::MAX_PREALLOCATED));
+            for _ in 0..len {
+                let key = String::deserialize_reader(reader)?;
+                object.insert(key, read_value(reader, depth - 1)?);
+            }
+            Value::Object(object)
+        }
+        wire::STRING => Value::String(BorshDeserialize::deserialize_reader(reader)?),
+        tag => {
+            return Err(invalid_data(Error::Unsupported(format!(
+                "value tag {}",
+                tag
+            ))))
+        }
+    })
+}
+
+fn invalid_data(e: Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::document;
+
+    #[test]
+    fn round_trip() {
+        let bytes = ::borsh::to_vec(&document()).unwrap();
+        assert_eq!(bytes, ::borsh::to_vec(&document()).unwrap());
+        let value: Value = ::borsh::from_slice(&bytes).unwrap();
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+
+        let document = crate::to_vec(&(String::from("key"), 42u32)).unwrap();
+        let bytes = to_borsh::<(String, u32)>(&document).unwrap();
+        assert_eq!(
+            bytes,
+            ::borsh::to_vec(&(String::from("key"), 42u32)).unwrap()
+        );
+        assert_eq!(from_borsh::<(String, u32)>(&bytes).unwrap(), document);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(::borsh::to_vec(&Value::Float(f64::NAN)).is_err());
+        assert!(::borsh::from_slice::<Value>(&[0xff]).is_err());
+        assert!(::borsh::from_slice::<Value>(&[wire::STRING as u8, 4, 0, 0, 0, b'a']).is_err());
+
+        let mut deep =
+            [wire::ARRAY as u8, 1, 0, 0, 0].repeat(DecodeLimits::default().max_depth + 1);
+        deep.push(wire::NULL as u8);
+        let error = ::borsh::from_slice::<Value>(&deep).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let error = to_borsh::<u32>(&crate::to_vec(&"text").unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = from_borsh::<String>(&[1, 0, 0, 0]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Io);
+    }
+}
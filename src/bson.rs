@@ -0,0 +1,124 @@
+//! Conversions between `bincode-json` values and BSON, for exchanging documents with MongoDB.
+//!
+//! Blobs convert to generic binaries. BSON types `bincode-json` has no counterpart of convert the
+//! closest way: datetimes become integers of milliseconds since the Unix epoch, timestamps become
+//! integers of their time in the high 32 bits and their increment in the low 32 bits, and object
+//! ids, decimals, regular expressions, symbols and JavaScript code become strings. Undefined,
+//! min/max keys and DB pointers become null.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value, ValueKind},
+};
+use ::bson::{spec::BinarySubtype, Binary, Bson, Document};
+
+impl From<Value> for Bson {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => Bson::Null,
+            Value::Boolean(b) => Bson::Boolean(b),
+            Value::Blob(bytes) => Bson::Binary(Binary {
+                subtype: BinarySubtype::Generic,
+                bytes,
+            }),
+            Value::Array(a) => Bson::Array(a.into_iter().map(Bson::from).collect()),
+            Value::Integer(i) => Bson::Int64(i),
+            Value::Float(f) => Bson::Double(f),
+            Value::Object(o) => Bson::Document(o.into_iter().map(|(k, v)| (k, v.into())).collect()),
+            Value::String(s) => Bson::String(s),
+        }
+    }
+}
+impl From<Bson> for Value {
+    fn from(bson: Bson) -> Self {
+        match bson {
+            Bson::Null | Bson::Undefined | Bson::MaxKey | Bson::MinKey | Bson::DbPointer(_) => {
+                Value::Null
+            }
+            Bson::Boolean(b) => Value::Boolean(b),
+            Bson::Binary(binary) => Value::Blob(binary.bytes),
+            Bson::Array(a) => Value::Array(a.into_iter().map(Value::from).collect()),
+            Bson::Int32(i) => Value::Integer(i.into()),
+            Bson::Int64(i) => Value::Integer(i),
+            Bson::Double(f) => Value::Float(f),
+            Bson::Document(d) => d.into(),
+            Bson::String(s) | Bson::Symbol(s) | Bson::JavaScriptCode(s) => Value::String(s),
+            Bson::JavaScriptCodeWithScope(code) => Value::String(code.code),
+            Bson::RegularExpression(regex) => {
+                Value::String(format!("/{}/{}", regex.pattern, regex.options))
+            }
+            Bson::ObjectId(id) => Value::String(id.to_hex()),
+            Bson::Decimal128(d) => Value::String(d.to_string()),
+            Bson::DateTime(dt) => Value::Integer(dt.timestamp_millis()),
+            Bson::Timestamp(ts) => Value::Integer(((ts.time as i64) << 32) | ts.increment as i64),
+        }
+    }
+}
+impl From<Document> for Value {
+    fn from(document: Document) -> Self {
+        let mut map = Map::with_capacity(document.len());
+        for (k, v) in document {
+            map.insert(k, v.into());
+        }
+        Value::Object(map)
+    }
+}
+impl TryFrom<Value> for Document {
+    type Error = Error;
+
+    /// Converts an object into a BSON document, failing if the value is not an object.
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(o) => Ok(o.into_iter().map(|(k, v)| (k, v.into())).collect()),
+            v => Err(Error::Expected(ValueKind::Object.into(), v.kind().into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::document;
+    use ::bson::{doc, oid::ObjectId, DateTime, Timestamp};
+
+    #[test]
+    fn round_trip() {
+        let document = Document::try_from(self::document()).unwrap();
+        assert_eq!(document.get_array("array").unwrap()[0], Bson::Int64(-42));
+        assert_eq!(document.get_binary_generic("blob").unwrap(), &vec![1, 2, 3]);
+
+        let value = Value::from(document);
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&self::document()).unwrap()
+        );
+    }
+
+    #[test]
+    fn foreign_types() {
+        let id = ObjectId::new();
+        let document = doc! {
+            "int32": 7i32,
+            "datetime": DateTime::from_millis(1_000),
+            "timestamp": Timestamp { time: 1, increment: 2 },
+            "id": id,
+            "undefined": Bson::Undefined,
+        };
+        let Value::Object(o) = Value::from(document) else {
+            panic!("expected an object");
+        };
+        assert!(matches!(o["int32"], Value::Integer(7)));
+        assert!(matches!(o["datetime"], Value::Integer(1_000)));
+        assert!(matches!(o["timestamp"], Value::Integer(0x1_0000_0002)));
+        assert!(matches!(&o["id"], Value::String(s) if *s == id.to_hex()));
+        assert!(o["undefined"].is_null());
+    }
+
+    #[test]
+    fn errors() {
+        let error = Document::try_from(Value::Integer(1)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        assert_eq!(error.mismatch().unwrap().0.kind(), Some(ValueKind::Object));
+    }
+}
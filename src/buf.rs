@@ -0,0 +1,159 @@
+//! Encodes and decodes `bincode-json` data over the `bytes` crate's buffer traits.
+
+use crate::{dict, error::Result, limits::DecodeLimits, wire};
+use bincode::{
+    de::{read::Reader, DecoderImpl},
+    enc::write::Writer,
+    error::{DecodeError, EncodeError},
+};
+use bytes::{Buf, BufMut};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A bincode [Writer] which puts the written bytes into a [BufMut].
+struct BufMutWriter<'a, B>(&'a mut B);
+impl<B: BufMut> Writer for BufMutWriter<'_, B> {
+    fn write(&mut self, bytes: &[u8]) -> core::result::Result<(), EncodeError> {
+        if self.0.remaining_mut() < bytes.len() {
+            return Err(EncodeError::UnexpectedEnd);
+        }
+        self.0.put_slice(bytes);
+        Ok(())
+    }
+}
+
+/// A bincode [Reader] which takes the read bytes from a [Buf].
+struct BufReader<'a, B>(&'a mut B);
+impl<B: Buf> Reader for BufReader<'_, B> {
+    fn read(&mut self, bytes: &mut [u8]) -> core::result::Result<(), DecodeError> {
+        if self.0.remaining() < bytes.len() {
+            return Err(DecodeError::UnexpectedEnd {
+                additional: bytes.len() - self.0.remaining(),
+            });
+        }
+        self.0.copy_to_slice(bytes);
+        Ok(())
+    }
+
+    fn peek_read(&mut self, n: usize) -> Option<&[u8]> {
+        self.0.chunk().get(..n)
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.0.advance(n)
+    }
+}
+
+/// Serialize the given data structure at the end of a [BufMut], e.g. a `BytesMut`.
+pub fn encode_to_buf<B: BufMut, T: Serialize>(buf: &mut B, val: &T) -> Result<()> {
+    let value = crate::to_value(val)?;
+    bincode::encode_into_writer(value, BufMutWriter(buf), bincode::config::standard())?;
+    Ok(())
+}
+
+/// Deserialize an instance of type `T` from the beginning of a [Buf], e.g. a `Bytes` or a
+/// `BytesMut`, enforcing the default [DecodeLimits]. The bytes of the document are consumed from
+/// `buf`.
+///
+/// Nothing is consumed when decoding fails, so an incomplete document can be retried once more
+/// bytes arrive. The only exception is a non-contiguous `buf` without the `std` feature, or one
+/// which does not expose all of its chunks through [Buf::chunks_vectored], which a failed decode
+/// may leave partially consumed.
+pub fn decode_from_buf<B: Buf, T: DeserializeOwned>(buf: &mut B) -> Result<T> {
+    let limits = DecodeLimits::default();
+    let value = if buf.chunk().len() == buf.remaining() {
+        let (value, len) = wire::decode_from_slice(buf.chunk(), &limits)?;
+        buf.advance(len);
+        value
+    } else if let Some(bytes) = copy_remaining(buf) {
+        let (value, len) = wire::decode_from_slice(&bytes, &limits)?;
+        buf.advance(len);
+        value
+    } else {
+        dict::reject_profile(buf.chunk().first().copied())?;
+        let mut decoder = DecoderImpl::new(BufReader(buf), bincode::config::standard());
+        wire::decode_value(&mut decoder, &limits, 0)?
+    };
+    crate::from_value(value)
+}
+
+/// Copies the remaining bytes of `buf` without consuming them, if `buf` exposes all its chunks.
+#[cfg(feature = "std")]
+fn copy_remaining<B: Buf>(buf: &B) -> Option<Vec<u8>> {
+    use std::io::IoSlice;
+
+    let mut slices = vec![IoSlice::new(&[]); 16];
+    loop {
+        let n = buf.chunks_vectored(&mut slices);
+        if slices[..n].iter().map(|s| s.len()).sum::<usize>() == buf.remaining() {
+            return Some(slices[..n].iter().flat_map(|s| s.iter().copied()).collect());
+        } else if n < slices.len() {
+            return None;
+        }
+        slices.resize(2 * slices.len(), IoSlice::new(&[]));
+    }
+}
+
+#[cfg(not(feature = "std"))]
+fn copy_remaining<B: Buf>(_: &B) -> Option<alloc::vec::Vec<u8>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::string::String;
+    use bytes::{Bytes, BytesMut};
+
+    #[test]
+    fn round_trip() {
+        let mut buf = BytesMut::new();
+        encode_to_buf(&mut buf, &("key", 42u32)).unwrap();
+        encode_to_buf(&mut buf, &"next").unwrap();
+
+        let decoded: (String, u32) = decode_from_buf(&mut buf).unwrap();
+        assert_eq!(decoded, (String::from("key"), 42));
+        assert_eq!(decode_from_buf::<_, String>(&mut buf).unwrap(), "next");
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn non_contiguous() {
+        let bytes = crate::to_vec(&("key", 42u32)).unwrap();
+        for split in 0..bytes.len() {
+            let mut buf = Bytes::copy_from_slice(&bytes[..split])
+                .chain(Bytes::copy_from_slice(&bytes[split..]));
+            let decoded: (String, u32) = decode_from_buf(&mut buf).unwrap();
+            assert_eq!(decoded, (String::from("key"), 42));
+            assert_eq!(buf.remaining(), 0);
+        }
+    }
+
+    #[test]
+    fn incomplete_consumes_nothing() {
+        let bytes = crate::to_vec(&("key", 42u32)).unwrap();
+        let mut buf = BytesMut::from(&bytes[..bytes.len() - 1]);
+        let error = decode_from_buf::<_, (String, u32)>(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+        assert_eq!(buf.len(), bytes.len() - 1);
+
+        buf.extend_from_slice(&bytes[bytes.len() - 1..]);
+        let decoded: (String, u32) = decode_from_buf(&mut buf).unwrap();
+        assert_eq!(decoded, (String::from("key"), 42));
+    }
+
+    #[test]
+    fn errors() {
+        let mut storage = [0; 4];
+        let mut slice = &mut storage[..];
+        let error = encode_to_buf(&mut slice, &"too long").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Encode);
+
+        let mut buf = Bytes::from(crate::to_vec(&"value").unwrap());
+        let error = decode_from_buf::<_, u32>(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let mut buf = Bytes::from_static(&[0xff]);
+        let error = decode_from_buf::<_, u32>(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
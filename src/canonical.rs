@@ -0,0 +1,113 @@
+//! Canonical, deterministic encoding of `bincode-json` documents.
+//!
+//! The canonical encoding of a document is a valid encoding which can be decoded as usual, and
+//! is byte-identical for semantically equal documents:
+//!  - Object entries are written in ascending byte order of their keys, rather than in the
+//!    nondeterministic iteration order of the map.
+//!  - Negative zero is written as positive zero.
+//!  - Every NaN is written as the canonical quiet NaN, `0x7ff8000000000000`.
+
+use crate::{error::Result, value::Value};
+use bincode::{
+    enc::{Encode, Encoder},
+    error::EncodeError,
+};
+use serde::Serialize;
+
+/// Bit pattern of the canonical NaN.
+const CANONICAL_NAN: u64 = 0x7ff8_0000_0000_0000;
+
+/// A wrapper around a [Value] which encodes it canonically.
+#[derive(Debug, Clone, Copy)]
+pub struct Canonical<'a>(pub &'a Value);
+impl Encode for Canonical<'_> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> std::result::Result<(), EncodeError> {
+        self.0.variant_index().encode(encoder)?;
+        match self.0 {
+            Value::Null => Ok(()),
+            Value::Boolean(b) => b.encode(encoder),
+            Value::Blob(b) => b.encode(encoder),
+            Value::Array(a) => {
+                (a.len() as u64).encode(encoder)?;
+                for v in a {
+                    Canonical(v).encode(encoder)?;
+                }
+                Ok(())
+            }
+            Value::Integer(i) => i.encode(encoder),
+            Value::Float(f) => normalize_float(*f).encode(encoder),
+            Value::Object(o) => {
+                let mut entries: Vec<_> = o.iter().collect();
+                entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                (entries.len() as u64).encode(encoder)?;
+                for (k, v) in entries {
+                    k.encode(encoder)?;
+                    Canonical(v).encode(encoder)?;
+                }
+                Ok(())
+            }
+            Value::String(s) => s.encode(encoder),
+        }
+    }
+}
+
+/// Normalizes negative zero and NaNs.
+fn normalize_float(f: f64) -> f64 {
+    if f.is_nan() {
+        f64::from_bits(CANONICAL_NAN)
+    } else if f == 0.0 {
+        0.0
+    } else {
+        f
+    }
+}
+
+/// Serialize the given data structure as a byte vector in the canonical encoding.
+pub fn to_vec_canonical<T: Serialize>(val: &T) -> Result<Vec<u8>> {
+    let value = crate::to_value(val)?;
+    Ok(bincode::encode_to_vec(
+        Canonical(&value),
+        bincode::config::standard(),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn round_trip() {
+        let val: BTreeMap<String, Vec<u32>> = (0..32).map(|i| (i.to_string(), vec![i])).collect();
+        let bytes = to_vec_canonical(&val).unwrap();
+        let decoded: BTreeMap<String, Vec<u32>> = crate::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, val);
+    }
+
+    #[test]
+    fn deterministic() {
+        let forward: HashMap<String, u32> = (0..32).map(|i| (i.to_string(), i)).collect();
+        let backward: HashMap<String, u32> = (0..32).rev().map(|i| (i.to_string(), i)).collect();
+        assert_eq!(
+            to_vec_canonical(&forward).unwrap(),
+            to_vec_canonical(&backward).unwrap()
+        );
+
+        assert_eq!(
+            to_vec_canonical(&-0.0f64).unwrap(),
+            to_vec_canonical(&0.0f64).unwrap()
+        );
+        let nan = to_vec_canonical(&f64::from_bits(0xfff0_0000_0000_0001)).unwrap();
+        assert_eq!(nan, to_vec_canonical(&f64::NAN).unwrap());
+        let decoded: f64 = crate::from_slice(&nan).unwrap();
+        assert_eq!(decoded.to_bits(), CANONICAL_NAN);
+    }
+
+    #[test]
+    fn errors() {
+        let val = HashMap::from([(1u32, 2u32)]);
+        let error = to_vec_canonical(&val).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,125 @@
+//! Conversions between `bincode-json` values and CBOR values, for exchanging documents with
+//! devices speaking CBOR.
+//!
+//! Blobs convert to byte strings and objects to maps keyed by text strings. Converting back, tags
+//! are dropped in favor of the values they tag, and integer map keys become their decimal string.
+//! Integers outside the range of an `i64` and map keys of any other type are unsupported.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value},
+};
+use ciborium::value::{Integer, Value as CborValue};
+
+impl From<Value> for CborValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => CborValue::Null,
+            Value::Boolean(b) => CborValue::Bool(b),
+            Value::Blob(b) => CborValue::Bytes(b),
+            Value::Array(a) => CborValue::Array(a.into_iter().map(CborValue::from).collect()),
+            Value::Integer(i) => CborValue::Integer(i.into()),
+            Value::Float(f) => CborValue::Float(f),
+            Value::Object(o) => CborValue::Map(
+                o.into_iter()
+                    .map(|(k, v)| (CborValue::Text(k), v.into()))
+                    .collect(),
+            ),
+            Value::String(s) => CborValue::Text(s),
+        }
+    }
+}
+impl TryFrom<CborValue> for Value {
+    type Error = Error;
+
+    fn try_from(value: CborValue) -> Result<Self> {
+        Ok(match value {
+            CborValue::Null => Value::Null,
+            CborValue::Bool(b) => Value::Boolean(b),
+            CborValue::Bytes(b) => Value::Blob(b),
+            CborValue::Array(a) => {
+                Value::Array(a.into_iter().map(Value::try_from).collect::<Result<_>>()?)
+            }
+            CborValue::Integer(i) => Value::Integer(integer(i)?),
+            CborValue::Float(f) => Value::Float(f),
+            CborValue::Map(m) => {
+                let mut map = Map::with_capacity(m.len());
+                for (k, v) in m {
+                    map.insert(key(k)?, v.try_into()?);
+                }
+                Value::Object(map)
+            }
+            CborValue::Text(s) => Value::String(s),
+            CborValue::Tag(_, value) => (*value).try_into()?,
+            _ => return Err(Error::Unsupported("CBOR value".into())),
+        })
+    }
+}
+
+/// Converts a CBOR integer, failing if it is outside the range of an `i64`.
+fn integer(i: Integer) -> Result<i64> {
+    i64::try_from(i).map_err(|_| Error::Unsupported(format!("integer {}", i128::from(i))))
+}
+
+/// Converts a CBOR map key to an object key.
+fn key(key: CborValue) -> Result<String> {
+    match key {
+        CborValue::Text(s) => Ok(s),
+        CborValue::Integer(i) => Ok(i128::from(i).to_string()),
+        CborValue::Tag(_, key) => self::key(*key),
+        _ => Err(Error::Unsupported(
+            "CBOR map key other than text or integer".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::document;
+
+    #[test]
+    fn round_trip() {
+        let cbor = CborValue::from(document());
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&cbor, &mut bytes).unwrap();
+        let cbor: CborValue = ciborium::from_reader(&bytes[..]).unwrap();
+
+        let value = Value::try_from(cbor).unwrap();
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+    }
+
+    #[test]
+    fn tags_and_integer_keys() {
+        let cbor = CborValue::Map(vec![
+            (
+                CborValue::Integer(7.into()),
+                CborValue::Text("seven".into()),
+            ),
+            (
+                CborValue::Tag(1, Box::new(CborValue::Text("time".into()))),
+                CborValue::Tag(1, Box::new(CborValue::Integer(1_000.into()))),
+            ),
+        ]);
+        let Value::Object(o) = Value::try_from(cbor).unwrap() else {
+            panic!("expected an object");
+        };
+        assert!(matches!(&o["7"], Value::String(s) if s == "seven"));
+        assert!(matches!(o["time"], Value::Integer(1_000)));
+    }
+
+    #[test]
+    fn errors() {
+        let big = CborValue::Integer(Integer::from(u64::MAX));
+        let error = Value::try_from(big).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+
+        let cbor = CborValue::Map(vec![(CborValue::Bool(true), CborValue::Null)]);
+        let error = Value::try_from(cbor).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
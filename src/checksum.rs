@@ -0,0 +1,149 @@
+//! Checksum framing of encoded `bincode-json` documents, for detecting corruption.
+//!
+//! A checksummed document is the encoded document followed by its little-endian checksum.
+
+use crate::error::{Error, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Represents a checksum algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Checksum {
+    /// CRC-32C (Castagnoli), 4 bytes. Requires the `crc32c` feature.
+    Crc32c,
+
+    /// 64-bit xxHash, 8 bytes. Requires the `xxhash` feature.
+    Xxh64,
+}
+impl Checksum {
+    /// Gets the identifier of the algorithm used in envelope headers.
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Crc32c => 1,
+            Self::Xxh64 => 2,
+        }
+    }
+
+    /// Gets the algorithm identified by `id`.
+    pub fn from_id(id: u8) -> Result<Self> {
+        match id {
+            1 => Ok(Self::Crc32c),
+            2 => Ok(Self::Xxh64),
+            _ => Err(Error::Unsupported(format!("checksum {}", id))),
+        }
+    }
+
+    /// Gets the length of the checksum, in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            Self::Crc32c => 4,
+            Self::Xxh64 => 8,
+        }
+    }
+
+    /// Computes the checksum of `bytes`.
+    pub fn compute(self, bytes: &[u8]) -> Result<u64> {
+        match self {
+            #[cfg(feature = "crc32c")]
+            Self::Crc32c => Ok(crc32c::crc32c(bytes) as u64),
+            #[cfg(feature = "xxhash")]
+            Self::Xxh64 => Ok(xxhash_rust::xxh64::xxh64(bytes, 0)),
+            #[allow(unreachable_patterns)]
+            other => {
+                let _ = bytes;
+                Err(Error::Unsupported(format!(
+                    "checksum {:?}, its feature is disabled",
+                    other
+                )))
+            }
+        }
+    }
+
+    /// Appends the checksum of `buf` to itself.
+    pub(crate) fn append(self, buf: &mut Vec<u8>) -> Result<()> {
+        let checksum = self.compute(buf)?;
+        buf.extend_from_slice(&checksum.to_le_bytes()[..self.size()]);
+        Ok(())
+    }
+
+    /// Verifies the checksum at the end of `bytes`, returning the bytes preceding it.
+    pub(crate) fn verify(self, bytes: &[u8]) -> Result<&[u8]> {
+        let split = bytes.len().checked_sub(self.size()).ok_or(Error::Eof)?;
+        let (payload, stored) = bytes.split_at(split);
+        let mut expected = [0; 8];
+        expected[..stored.len()].copy_from_slice(stored);
+        let expected = u64::from_le_bytes(expected);
+        let found = self.compute(payload)?;
+        if expected != found {
+            return Err(Error::ChecksumMismatch(expected, found));
+        }
+        Ok(payload)
+    }
+}
+
+/// Serialize the given data structure as a byte vector followed by its checksum.
+pub fn to_vec_checksummed<T: Serialize>(val: &T, checksum: Checksum) -> Result<Vec<u8>> {
+    let mut buf = crate::to_vec(val)?;
+    checksum.append(&mut buf)?;
+    Ok(buf)
+}
+
+/// Deserialize an instance of type `T` from bytes of Bincode JSON followed by their checksum,
+/// failing with [Error::ChecksumMismatch] if the data is corrupted.
+pub fn from_slice_checksummed<T: DeserializeOwned>(val: &[u8], checksum: Checksum) -> Result<T> {
+    crate::from_slice(checksum.verify(val)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    const CHECKSUMS: [Checksum; 2] = [Checksum::Crc32c, Checksum::Xxh64];
+
+    fn available() -> impl Iterator<Item = Checksum> {
+        CHECKSUMS
+            .into_iter()
+            .filter(|checksum| checksum.compute(&[]).is_ok())
+    }
+
+    #[test]
+    fn round_trip() {
+        for checksum in available() {
+            assert_eq!(Checksum::from_id(checksum.id()).unwrap(), checksum);
+            let bytes = to_vec_checksummed(&("key", 42u32), checksum).unwrap();
+            assert_eq!(
+                bytes.len(),
+                crate::to_vec(&("key", 42u32)).unwrap().len() + checksum.size()
+            );
+            let decoded: (String, u32) = from_slice_checksummed(&bytes, checksum).unwrap();
+            assert_eq!(decoded, (String::from("key"), 42));
+        }
+    }
+
+    #[test]
+    fn corruption() {
+        for checksum in available() {
+            let bytes = to_vec_checksummed(&"value", checksum).unwrap();
+            for i in 0..bytes.len() {
+                let mut corrupted = bytes.clone();
+                corrupted[i] ^= 1;
+                let error = from_slice_checksummed::<String>(&corrupted, checksum).unwrap_err();
+                assert_eq!(error.kind(), ErrorKind::Integrity);
+            }
+
+            let error = from_slice_checksummed::<String>(&bytes[..checksum.size() - 1], checksum)
+                .unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Eof);
+        }
+    }
+
+    #[test]
+    fn unsupported() {
+        let error = Checksum::from_id(0).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        for checksum in CHECKSUMS.into_iter().filter(|c| c.compute(&[]).is_err()) {
+            let error = to_vec_checksummed(&1u8, checksum).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Unsupported);
+        }
+    }
+}
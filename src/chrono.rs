@@ -0,0 +1,112 @@
+//! `chrono` integration, storing dates and times compactly as milliseconds since the Unix epoch.
+//!
+//! `chrono`'s own `serde` implementations always produce RFC 3339 strings. [DateTime<Utc>]s and
+//! [NaiveDate]s instead convert into integer milliseconds since 1970-01-01T00:00:00Z, where a date
+//! is its midnight in UTC, and fields opt into that representation with `#[serde(with =
+//! "bincode_json::chrono::millis")]`. The accessors also accept RFC 3339 strings and ISO 8601
+//! dates, as written by `chrono`'s own implementations.
+
+use crate::value::Value;
+use ::chrono::{DateTime, NaiveDate, Utc};
+
+impl From<DateTime<Utc>> for Value {
+    fn from(datetime: DateTime<Utc>) -> Self {
+        Value::Integer(datetime.timestamp_millis())
+    }
+}
+impl From<NaiveDate> for Value {
+    fn from(date: NaiveDate) -> Self {
+        date.and_time(Default::default()).and_utc().into()
+    }
+}
+
+impl Value {
+    /// Gets a [DateTime<Utc>] from milliseconds since the Unix epoch or an RFC 3339 string.
+    pub fn as_datetime(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Value::Integer(i) => DateTime::from_timestamp_millis(*i),
+            Value::String(s) => DateTime::parse_from_rfc3339(s)
+                .ok()
+                .map(|datetime| datetime.to_utc()),
+            _ => None,
+        }
+    }
+
+    /// Gets a [NaiveDate] from milliseconds since the Unix epoch, as the date in UTC, or an ISO
+    /// 8601 date.
+    pub fn as_date(&self) -> Option<NaiveDate> {
+        match self {
+            Value::Integer(i) => DateTime::from_timestamp_millis(*i).map(|d| d.date_naive()),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// (De)serializes a [DateTime<Utc>] as milliseconds since the Unix epoch, with `#[serde(with =
+/// "bincode_json::chrono::millis")]`.
+pub mod millis {
+    use ::chrono::{DateTime, Utc};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    /// Serializes a [DateTime<Utc>] as milliseconds since the Unix epoch.
+    pub fn serialize<S: Serializer>(
+        datetime: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(datetime.timestamp_millis())
+    }
+
+    /// Deserializes a [DateTime<Utc>] from milliseconds since the Unix epoch.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        let millis = i64::deserialize(deserializer)?;
+        DateTime::from_timestamp_millis(millis)
+            .ok_or_else(|| D::Error::custom(format!("timestamp {} is out of range", millis)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{de::Deserializer, error::ErrorKind, ser::Serializer};
+
+    #[test]
+    fn round_trip() {
+        let datetime = DateTime::parse_from_rfc3339("2024-02-29T12:34:56.789Z")
+            .unwrap()
+            .to_utc();
+        let value = Value::from(datetime);
+        assert!(matches!(value, Value::Integer(1709210096789)));
+        assert_eq!(value.as_datetime(), Some(datetime));
+        let string = Value::String("2024-02-29T14:34:56.789+02:00".into());
+        assert_eq!(string.as_datetime(), Some(datetime));
+
+        let date = NaiveDate::from_ymd_opt(1969, 12, 31).unwrap();
+        let value = Value::from(date);
+        assert!(matches!(value, Value::Integer(-86400000)));
+        assert_eq!(value.as_date(), Some(date));
+        assert_eq!(Value::String("1969-12-31".into()).as_date(), Some(date));
+
+        let value = millis::serialize(&datetime, Serializer::new()).unwrap();
+        assert!(matches!(value, Value::Integer(1709210096789)));
+        assert_eq!(
+            millis::deserialize(Deserializer::from(value)).unwrap(),
+            datetime
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Value::Integer(i64::MAX).as_datetime(), None);
+        assert_eq!(Value::String("yesterday".into()).as_datetime(), None);
+        assert_eq!(Value::String("2024-02-30".into()).as_date(), None);
+        assert_eq!(Value::Boolean(true).as_date(), None);
+
+        let error = millis::deserialize(Deserializer::from(Value::Integer(i64::MAX))).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+        let error = millis::deserialize(Deserializer::from(Value::Null)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,565 @@
+//! Encoding of huge documents with bounded memory, e.g. for exports of iterator-backed datasets.
+//!
+//! [to_writer_chunked] encodes a document straight from its [Serialize] implementation, rather
+//! than converting it to a [Value] first, and writes the output into the underlying [Write] in
+//! chunks as they fill, even in the middle of the document. Peak memory is then about a chunk plus
+//! the largest string or blob, however large the document. [to_writer_chunked_iter] encodes the
+//! items of an iterator as an array the same way. The output decodes to the same value as that of
+//! [crate::to_vec].
+//!
+//! As arrays and objects announce their length on the wire, sequences and maps whose length is not
+//! known upfront, such as structs with flattened fields, are buffered as a [Value] until they end.
+//! If encoding fails midway, the chunks written so far are left in the writer.
+
+use crate::{
+    error::{Description, Error, Result},
+    ser::{MapSerializer, SeqSerializer, Serializer},
+    value::{Value, ValueKind},
+    wire,
+};
+use bincode::config;
+use serde::{ser, Serialize};
+use std::io::Write;
+
+/// Options of [to_writer_chunked].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkedOptions {
+    chunk_size: usize,
+}
+impl Default for ChunkedOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 64 * 1024,
+        }
+    }
+}
+impl ChunkedOptions {
+    /// Constructs the default [ChunkedOptions], writing the output in chunks of 64 KiB.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of buffered bytes from which the output is written into the underlying
+    /// writer.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+}
+
+/// Serialize the given data structure into a [Write], writing the output in chunks as it is
+/// encoded.
+pub fn to_writer_chunked<W: Write, T: Serialize>(
+    writer: W,
+    val: &T,
+    options: &ChunkedOptions,
+) -> Result<()> {
+    let mut output = Output::new(writer, options);
+    val.serialize(ChunkedSerializer {
+        output: &mut output,
+    })?;
+    output.finish()
+}
+
+/// Serialize the items of `items` as an array into a [Write], writing the output in chunks as it
+/// is encoded, so that the items never need to be held in memory at once.
+pub fn to_writer_chunked_iter<W, I>(writer: W, items: I, options: &ChunkedOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator,
+    I::IntoIter: ExactSizeIterator,
+    I::Item: Serialize,
+{
+    let items = items.into_iter();
+    let mut output = Output::new(writer, options);
+    let mut array = Streaming::array(&mut output, items.len())?;
+    for item in items {
+        array.element(&item)?;
+    }
+    array.end()?;
+    output.finish()
+}
+
+/// An array encoded one element at a time into chunks which the caller writes out between
+/// elements, for writers which cannot be written from within [Serialize] implementations, such as
+/// asynchronous ones.
+#[cfg(feature = "tokio")]
+pub(crate) struct ChunkedArray {
+    output: Output<Vec<u8>>,
+    len: usize,
+    count: usize,
+}
+#[cfg(feature = "tokio")]
+impl ChunkedArray {
+    pub(crate) fn new(len: usize, options: &ChunkedOptions) -> Result<Self> {
+        let mut output = Output::new(Vec::new(), options);
+        Streaming::array(&mut output, len)?;
+        Ok(Self {
+            output,
+            len,
+            count: 0,
+        })
+    }
+
+    pub(crate) fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        let mut array = self.streaming();
+        let result = array.element(value);
+        self.count = array.count;
+        result
+    }
+
+    /// The chunks filled so far, to be written out and cleared.
+    pub(crate) fn chunks(&mut self) -> &mut Vec<u8> {
+        &mut self.output.writer
+    }
+
+    /// Ends the array, returning the rest of its output.
+    pub(crate) fn finish(mut self) -> Result<Vec<u8>> {
+        self.streaming().end()?;
+        let Output {
+            mut writer, buf, ..
+        } = self.output;
+        writer.extend_from_slice(&buf);
+        Ok(writer)
+    }
+
+    fn streaming(&mut self) -> Streaming<'_, Vec<u8>> {
+        Streaming {
+            output: &mut self.output,
+            len: self.len,
+            count: self.count,
+            unit: "elements",
+        }
+    }
+}
+
+/// A buffer of output, written into the underlying writer whenever a chunk fills.
+struct Output<W> {
+    writer: W,
+    buf: Vec<u8>,
+    chunk_size: usize,
+}
+impl<W: Write> Output<W> {
+    fn new(writer: W, options: &ChunkedOptions) -> Self {
+        Self {
+            writer,
+            buf: Vec::with_capacity(options.chunk_size),
+            chunk_size: options.chunk_size,
+        }
+    }
+
+    fn encode<E: bincode::Encode>(&mut self, val: E) -> Result<()> {
+        bincode::encode_into_std_write(val, &mut self.buf, config::standard())?;
+        if self.buf.len() >= self.chunk_size {
+            self.writer.write_all(&self.buf)?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.writer.write_all(&self.buf)?;
+        Ok(())
+    }
+}
+
+/// A serializer encoding straight into an [Output], mapping data as [Serializer] does.
+struct ChunkedSerializer<'a, W> {
+    output: &'a mut Output<W>,
+}
+impl<'a, W: Write> ser::Serializer for ChunkedSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Seq<'a, W>;
+    type SerializeTuple = Streaming<'a, W>;
+    type SerializeTupleStruct = Streaming<'a, W>;
+    type SerializeTupleVariant = Streaming<'a, W>;
+    type SerializeMap = Map<'a, W>;
+    type SerializeStruct = Streaming<'a, W>;
+    type SerializeStructVariant = Streaming<'a, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.output.encode((wire::BOOLEAN, v))
+    }
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(v as _)
+    }
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(v as _)
+    }
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(v as _)
+    }
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.output.encode((wire::INTEGER, v))
+    }
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_i64(v as _)
+    }
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_i64(v as _)
+    }
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_i64(v as _)
+    }
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.serialize_i64(v as _)
+    }
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.serialize_f64(v as _)
+    }
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.output.encode((wire::FLOAT, v))
+    }
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.output.encode((wire::STRING, v))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.output.encode((wire::BLOB, v))
+    }
+    fn serialize_none(self) -> Result<()> {
+        self.output.encode(wire::NULL)
+    }
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<()> {
+        self.output.encode((wire::ARRAY, 0u64))
+    }
+    fn serialize_unit_struct(self, _: &'static str) -> Result<()> {
+        self.serialize_unit()
+    }
+    fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<()> {
+        self.serialize_str(variant)
+    }
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.output.encode((wire::OBJECT, 1u64, variant))?;
+        value.serialize(self)
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<Seq<'a, W>> {
+        Ok(match len {
+            Some(len) => Seq::Streaming(Streaming::array(self.output, len)?),
+            None => Seq::Buffered(self.output, Serializer::new().serialize_seq(None)?),
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<Streaming<'a, W>> {
+        Streaming::array(self.output, len)
+    }
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<Streaming<'a, W>> {
+        self.serialize_tuple(len)
+    }
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Streaming<'a, W>> {
+        self.output.encode((wire::OBJECT, 1u64, variant))?;
+        Streaming::array(self.output, len)
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<Map<'a, W>> {
+        Ok(match len {
+            Some(len) => Map::Streaming(Streaming::object(self.output, len)?),
+            None => Map::Buffered(self.output, Serializer::new().serialize_map(None)?),
+        })
+    }
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<Streaming<'a, W>> {
+        Streaming::object(self.output, len)
+    }
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Streaming<'a, W>> {
+        self.output.encode((wire::OBJECT, 1u64, variant))?;
+        Streaming::object(self.output, len)
+    }
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// An array or object of known length, whose elements or entries are encoded as they come.
+struct Streaming<'a, W> {
+    output: &'a mut Output<W>,
+    len: usize,
+    count: usize,
+    unit: &'static str,
+}
+impl<'a, W: Write> Streaming<'a, W> {
+    fn array(output: &'a mut Output<W>, len: usize) -> Result<Self> {
+        output.encode((wire::ARRAY, len as u64))?;
+        Ok(Self {
+            output,
+            len,
+            count: 0,
+            unit: "elements",
+        })
+    }
+
+    fn object(output: &'a mut Output<W>, len: usize) -> Result<Self> {
+        output.encode((wire::OBJECT, len as u64))?;
+        Ok(Self {
+            output,
+            len,
+            count: 0,
+            unit: "entries",
+        })
+    }
+
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.count()?;
+        self.value(value)
+    }
+
+    fn key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        self.count()?;
+        match key.serialize(Serializer::new())? {
+            Value::String(s) => self.output.encode(s.as_str()),
+            other => Err(Error::Expected(
+                Description::from("type str").with_kind(ValueKind::String),
+                other.kind().into(),
+            )),
+        }
+    }
+
+    fn value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        value.serialize(ChunkedSerializer {
+            output: &mut *self.output,
+        })
+    }
+
+    /// Counts an element or entry, failing if there are more than the announced length, as the
+    /// length has already been written.
+    fn count(&mut self) -> Result<()> {
+        self.count += 1;
+        match self.count <= self.len {
+            true => Ok(()),
+            false => Err(self.mismatch()),
+        }
+    }
+
+    fn end(self) -> Result<()> {
+        match self.count == self.len {
+            true => Ok(()),
+            false => Err(self.mismatch()),
+        }
+    }
+
+    fn mismatch(&self) -> Error {
+        Error::Expected(
+            format!("{} {}", self.len, self.unit).into(),
+            format!("{} {}", self.count, self.unit).into(),
+        )
+    }
+}
+impl<W: Write> ser::SerializeTuple for Streaming<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+    fn end(self) -> Result<()> {
+        Streaming::end(self)
+    }
+}
+impl<W: Write> ser::SerializeTupleStruct for Streaming<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+    fn end(self) -> Result<()> {
+        Streaming::end(self)
+    }
+}
+impl<W: Write> ser::SerializeTupleVariant for Streaming<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+    fn end(self) -> Result<()> {
+        Streaming::end(self)
+    }
+}
+impl<W: Write> ser::SerializeStruct for Streaming<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key(key)?;
+        self.value(value)
+    }
+    fn end(self) -> Result<()> {
+        Streaming::end(self)
+    }
+}
+impl<W: Write> ser::SerializeStructVariant for Streaming<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key(key)?;
+        self.value(value)
+    }
+    fn end(self) -> Result<()> {
+        Streaming::end(self)
+    }
+}
+
+/// A sequence, buffered as a [Value] when its length is unknown.
+enum Seq<'a, W> {
+    Streaming(Streaming<'a, W>),
+    Buffered(&'a mut Output<W>, SeqSerializer),
+}
+impl<W: Write> ser::SerializeSeq for Seq<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self {
+            Self::Streaming(array) => array.element(value),
+            Self::Buffered(_, seq) => seq.serialize_element(value),
+        }
+    }
+    fn end(self) -> Result<()> {
+        match self {
+            Self::Streaming(array) => array.end(),
+            Self::Buffered(output, seq) => output.encode(&seq.end()?),
+        }
+    }
+}
+
+/// A map, buffered as a [Value] when its length is unknown.
+enum Map<'a, W> {
+    Streaming(Streaming<'a, W>),
+    Buffered(&'a mut Output<W>, MapSerializer),
+}
+impl<W: Write> ser::SerializeMap for Map<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        match self {
+            Self::Streaming(object) => object.key(key),
+            Self::Buffered(_, map) => map.serialize_key(key),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        match self {
+            Self::Streaming(object) => object.value(value),
+            Self::Buffered(_, map) => map.serialize_value(value),
+        }
+    }
+
+    fn end(self) -> Result<()> {
+        match self {
+            Self::Streaming(object) => object.end(),
+            Self::Buffered(output, map) => output.encode(&map.end()?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    type Record = (u64, String, Option<Vec<u8>>, f64, BTreeMap<String, bool>);
+
+    fn records() -> Vec<Record> {
+        (0..200)
+            .map(|i| {
+                (
+                    i * 1000,
+                    "x".repeat(i as usize % 40),
+                    (i % 3 == 0).then(|| vec![i as u8; 5]),
+                    i as f64 / 3.0,
+                    [(format!("key{}", i), i % 2 == 0)].into(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_as_to_vec() {
+        let expected = crate::to_vec(&records()).unwrap();
+        for chunk_size in [1, 7, 64, 1 << 20] {
+            let options = ChunkedOptions::new().with_chunk_size(chunk_size);
+            let mut output = Vec::new();
+            to_writer_chunked(&mut output, &records(), &options).unwrap();
+            assert_eq!(output, expected, "chunk size {}", chunk_size);
+
+            let mut output = Vec::new();
+            to_writer_chunked_iter(&mut output, records(), &options).unwrap();
+            assert_eq!(output, expected, "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn writes_in_chunks() {
+        struct Writes(Vec<usize>);
+        impl Write for Writes {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.push(buf.len());
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writes = Writes(Vec::new());
+        let options = ChunkedOptions::new().with_chunk_size(256);
+        to_writer_chunked(&mut writes, &records(), &options).unwrap();
+        assert!(writes.0.len() > 1);
+        assert!(writes.0.iter().all(|&len| len < 256 + 64));
+    }
+}
@@ -0,0 +1,142 @@
+//! A `tokio-util` codec for length-prefixed `bincode-json` frames.
+
+use crate::{
+    error::{Error, Result},
+    frame::{self, encode_frame, LENGTH_PREFIX_LEN},
+};
+use bytes::{Buf, BytesMut};
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Default value of [BincodeJsonCodec::max_frame_size], 8 MiB.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = frame::DEFAULT_MAX_FRAME_SIZE;
+
+/// A codec which encodes and decodes values of type `T` as length-prefixed frames, for use with
+/// `tokio_util::codec::Framed` and friends.
+///
+/// The frame layout is the same as the one used by the `framed` module.
+pub struct BincodeJsonCodec<T> {
+    max_frame_size: usize,
+    _marker: PhantomData<fn(T) -> T>,
+}
+impl<T> Default for BincodeJsonCodec<T> {
+    fn default() -> Self {
+        Self {
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            _marker: PhantomData,
+        }
+    }
+}
+impl<T> Clone for BincodeJsonCodec<T> {
+    fn clone(&self) -> Self {
+        Self {
+            max_frame_size: self.max_frame_size,
+            _marker: PhantomData,
+        }
+    }
+}
+impl<T> BincodeJsonCodec<T> {
+    /// Constructs a new [BincodeJsonCodec] with default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum size of a frame's payload, in bytes. Larger frames are rejected in both
+    /// directions with [Error::FrameTooLarge].
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Gets the maximum size of a frame's payload, in bytes.
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+}
+impl<T: Serialize> Encoder<T> for BincodeJsonCodec<T> {
+    type Error = Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<()> {
+        let mut frame = Vec::new();
+        encode_frame(&item, &mut frame)?;
+        let len = frame.len() - LENGTH_PREFIX_LEN;
+        if len > self.max_frame_size {
+            return Err(Error::FrameTooLarge(len));
+        }
+        dst.extend_from_slice(&frame);
+        Ok(())
+    }
+}
+impl<T: DeserializeOwned> Decoder for BincodeJsonCodec<T> {
+    type Item = T;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>> {
+        if src.len() < LENGTH_PREFIX_LEN {
+            return Ok(None);
+        }
+        let mut header = [0; LENGTH_PREFIX_LEN];
+        header.copy_from_slice(&src[..LENGTH_PREFIX_LEN]);
+        let len = u32::from_be_bytes(header) as usize;
+        if len > self.max_frame_size {
+            return Err(Error::FrameTooLarge(len));
+        }
+        if src.len() < LENGTH_PREFIX_LEN + len {
+            src.reserve(LENGTH_PREFIX_LEN + len - src.len());
+            return Ok(None);
+        }
+
+        src.advance(LENGTH_PREFIX_LEN);
+        let payload = src.split_to(len);
+        crate::from_slice(&payload).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn round_trip() {
+        let mut codec = BincodeJsonCodec::<String>::new();
+        let mut buf = BytesMut::new();
+        codec.encode("one".to_owned(), &mut buf).unwrap();
+        codec.encode("two".to_owned(), &mut buf).unwrap();
+
+        // Frames are only decoded once complete.
+        let mut partial = buf.split_to(5);
+        assert_eq!(codec.decode(&mut partial).unwrap(), None);
+        partial.unsplit(buf);
+        assert_eq!(codec.decode(&mut partial).unwrap().as_deref(), Some("one"));
+        assert_eq!(codec.decode(&mut partial).unwrap().as_deref(), Some("two"));
+        assert_eq!(codec.decode(&mut partial).unwrap(), None);
+        assert!(partial.is_empty());
+    }
+
+    #[test]
+    fn frame_too_large() {
+        let mut codec = BincodeJsonCodec::<String>::new().with_max_frame_size(4);
+        let mut buf = BytesMut::new();
+        let error = codec
+            .encode("a longer value".to_owned(), &mut buf)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+        assert!(buf.is_empty());
+
+        BincodeJsonCodec::new()
+            .encode("a longer value".to_owned(), &mut buf)
+            .unwrap();
+        let error = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+    }
+
+    #[test]
+    fn malformed_payload() {
+        let mut codec = BincodeJsonCodec::<String>::new();
+        let mut buf = BytesMut::from(&[0, 0, 0, 1, 9][..]);
+        let error = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Decode);
+    }
+}
@@ -0,0 +1,488 @@
+//! A counterpart of [Value] which stores short strings inline, avoiding an allocation per string.
+//!
+//! Strings and object keys of a [CompactValue] are [CompactString]s, which keep up to 24 bytes
+//! inline and only allocate beyond that. As most keys and many string values of real documents
+//! are that short, decoding a [CompactValue] performs a fraction of the allocations of decoding a
+//! [Value]. Its accessors mirror those of [Value], and both convert into each other.
+//!
+//! The `compact_str` feature adds this type rather than changing the strings of [Value] itself:
+//! features are unified across a build, so a feature changing the fields of [Value] would break
+//! every other crate in the build which matches on [Value::String] or builds a [Value::Object].
+
+use crate::{
+    borrowed::{take_bytes, take_str},
+    error::Result,
+    limits::{DecodeLimits, Limit},
+    value::{Map, Value, ValueKind},
+    wire::{self, SliceReader},
+};
+use bincode::{
+    de::{BorrowDecoder, Decode, Decoder},
+    enc::{Encode, Encoder},
+    error::EncodeError,
+};
+use compact_str::CompactString;
+use serde::{de, ser};
+use std::fmt;
+
+/// Represents any valid `bincode-json` value, with strings and object keys stored inline when
+/// short.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompactValue {
+    /// Represents a `bincode-json` null value.
+    Null,
+
+    /// Represents a `bincode-json` bool value.
+    Boolean(bool),
+
+    /// Represents a `bincode-json` blob value.
+    Blob(Vec<u8>),
+
+    /// Represents a `bincode-json` array value.
+    Array(Vec<CompactValue>),
+
+    /// Represents a `bincode-json` integer value.
+    Integer(i64),
+
+    /// Represents a `bincode-json` float value.
+    Float(f64),
+
+    /// Represents a `bincode-json` object value.
+    Object(Map<CompactString, CompactValue>),
+
+    /// Represents a `bincode-json` string value.
+    String(CompactString),
+}
+impl CompactValue {
+    /// Decodes a value from the beginning of `bytes`, enforcing the default [DecodeLimits].
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        Self::from_slice_with_limits(bytes, &DecodeLimits::default())
+    }
+
+    /// Decodes a value from the beginning of `bytes`, enforcing `limits`.
+    pub fn from_slice_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self> {
+        limits.check(Limit::Bytes, bytes.len())?;
+        let mut decoder =
+            bincode::de::DecoderImpl::new(SliceReader::new(bytes), bincode::config::standard());
+        decode_compact(&mut decoder, limits, 0)
+            .map_err(|e| e.at_offset(decoder.reader().position()))
+    }
+
+    /// Gets the type of the value.
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Self::Null => ValueKind::Null,
+            Self::Boolean(_) => ValueKind::Boolean,
+            Self::Blob(_) => ValueKind::Blob,
+            Self::Array(_) => ValueKind::Array,
+            Self::Integer(_) => ValueKind::Integer,
+            Self::Float(_) => ValueKind::Float,
+            Self::Object(_) => ValueKind::Object,
+            Self::String(_) => ValueKind::String,
+        }
+    }
+
+    /// Returns `true` if this value is `Null`.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// Gets the string this value holds, if it is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Gets the integer this value holds, if it is an integer.
+    pub fn as_integer(&self) -> Option<&i64> {
+        match self {
+            Self::Integer(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Gets the float this value holds, if it is a float.
+    pub fn as_float(&self) -> Option<&f64> {
+        match self {
+            Self::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    /// Gets the bytes this value holds, if it is a blob.
+    pub fn as_blob(&self) -> Option<&[u8]> {
+        match self {
+            Self::Blob(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Gets the bool this value holds, if it is a bool.
+    pub fn as_bool(&self) -> Option<&bool> {
+        match self {
+            Self::Boolean(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Gets the elements of this value, if it is an array.
+    pub fn as_array(&self) -> Option<&[CompactValue]> {
+        match self {
+            Self::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Gets the entries of this value, if it is an object.
+    pub fn as_object(&self) -> Option<&Map<CompactString, CompactValue>> {
+        match self {
+            Self::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Gets the value of the entry of `key`, if this value is an object which has one.
+    pub fn get(&self, key: &str) -> Option<&CompactValue> {
+        self.as_object()?.get(key)
+    }
+
+    /// Gets the element at `index`, if this value is an array which has one.
+    pub fn get_index(&self, index: usize) -> Option<&CompactValue> {
+        self.as_array()?.get(index)
+    }
+}
+impl From<Value> for CompactValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::Boolean(b) => Self::Boolean(b),
+            Value::Blob(b) => Self::Blob(b),
+            Value::Array(a) => Self::Array(a.into_iter().map(Self::from).collect()),
+            Value::Integer(i) => Self::Integer(i),
+            Value::Float(f) => Self::Float(f),
+            Value::Object(o) => {
+                Self::Object(o.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+            }
+            Value::String(s) => Self::String(s.into()),
+        }
+    }
+}
+impl From<CompactValue> for Value {
+    fn from(value: CompactValue) -> Self {
+        match value {
+            CompactValue::Null => Self::Null,
+            CompactValue::Boolean(b) => Self::Boolean(b),
+            CompactValue::Blob(b) => Self::Blob(b),
+            CompactValue::Array(a) => Self::Array(a.into_iter().map(Self::from).collect()),
+            CompactValue::Integer(i) => Self::Integer(i),
+            CompactValue::Float(f) => Self::Float(f),
+            CompactValue::Object(o) => {
+                Self::Object(o.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+            }
+            CompactValue::String(s) => Self::String(s.into()),
+        }
+    }
+}
+impl Encode for CompactValue {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> core::result::Result<(), EncodeError> {
+        match self {
+            Self::Null => wire::NULL.encode(encoder),
+            Self::Boolean(b) => {
+                wire::BOOLEAN.encode(encoder)?;
+                b.encode(encoder)
+            }
+            Self::Blob(b) => {
+                wire::BLOB.encode(encoder)?;
+                b.encode(encoder)
+            }
+            Self::Array(a) => {
+                wire::ARRAY.encode(encoder)?;
+                a.encode(encoder)
+            }
+            Self::Integer(i) => {
+                wire::INTEGER.encode(encoder)?;
+                i.encode(encoder)
+            }
+            Self::Float(f) => {
+                wire::FLOAT.encode(encoder)?;
+                f.encode(encoder)
+            }
+            Self::Object(o) => {
+                wire::OBJECT.encode(encoder)?;
+                (o.len() as u64).encode(encoder)?;
+                for (k, v) in o {
+                    k.as_str().encode(encoder)?;
+                    v.encode(encoder)?;
+                }
+                Ok(())
+            }
+            Self::String(s) => {
+                wire::STRING.encode(encoder)?;
+                s.as_str().encode(encoder)
+            }
+        }
+    }
+}
+impl ser::Serialize for CompactValue {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Null => serializer.serialize_none(),
+            Self::Boolean(b) => serializer.serialize_bool(*b),
+            Self::Blob(b) => serializer.serialize_bytes(b),
+            Self::Integer(n) => serializer.serialize_i64(*n),
+            Self::Float(f) => serializer.serialize_f64(*f),
+            Self::String(s) => serializer.serialize_str(s),
+            Self::Array(v) => v.serialize(serializer),
+            Self::Object(m) => {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(m.len()))?;
+                for (k, v) in m {
+                    map.serialize_entry(k.as_str(), v)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+impl<'de> de::Deserialize<'de> for CompactValue {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+/// A key of a [CompactValue::Object], deserialized without allocating when short.
+struct Key(CompactString);
+impl<'de> de::Deserialize<'de> for Key {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(KeyVisitor)
+    }
+}
+
+struct KeyVisitor;
+impl<'de> de::Visitor<'de> for KeyVisitor {
+    type Value = Key;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> core::result::Result<Key, E> {
+        Ok(Key(v.into()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> core::result::Result<Key, E> {
+        Ok(Key(v.into()))
+    }
+}
+
+struct Visitor;
+impl<'de> de::Visitor<'de> for Visitor {
+    type Value = CompactValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a Bincode JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> core::result::Result<CompactValue, E> {
+        Ok(CompactValue::Boolean(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> core::result::Result<CompactValue, E> {
+        Ok(CompactValue::Integer(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> core::result::Result<CompactValue, E> {
+        Ok(CompactValue::Integer(v as _))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> core::result::Result<CompactValue, E> {
+        Ok(CompactValue::Float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> core::result::Result<CompactValue, E> {
+        Ok(CompactValue::String(v.into()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> core::result::Result<CompactValue, E> {
+        Ok(CompactValue::String(v.into()))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> core::result::Result<CompactValue, E> {
+        Ok(CompactValue::Blob(v.into()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> core::result::Result<CompactValue, E> {
+        Ok(CompactValue::Blob(v))
+    }
+
+    fn visit_none<E: de::Error>(self) -> core::result::Result<CompactValue, E> {
+        Ok(CompactValue::Null)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> core::result::Result<CompactValue, E> {
+        Ok(CompactValue::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> core::result::Result<CompactValue, D::Error> {
+        de::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> core::result::Result<CompactValue, A::Error> {
+        let mut array =
+            Vec::with_capacity(seq.size_hint().unwrap_or(0).min(wire::MAX_PREALLOCATED));
+        while let Some(value) = seq.next_element()? {
+            array.push(value);
+        }
+        Ok(CompactValue::Array(array))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> core::result::Result<CompactValue, A::Error> {
+        let mut object =
+            Map::with_capacity(map.size_hint().unwrap_or(0).min(wire::MAX_PREALLOCATED));
+        while let Some((Key(key), value)) = map.next_entry()? {
+            object.insert(key, value);
+        }
+        Ok(CompactValue::Object(object))
+    }
+}
+
+/// Decodes a [CompactValue] nested at `depth`, enforcing `limits`.
+fn decode_compact<'de, D: BorrowDecoder<'de>>(
+    decoder: &mut D,
+    limits: &DecodeLimits,
+    depth: usize,
+) -> Result<CompactValue> {
+    match wire::decode_tag(decoder)? {
+        wire::NULL => Ok(CompactValue::Null),
+        wire::BOOLEAN => Ok(CompactValue::Boolean(bool::decode(decoder)?)),
+        wire::BLOB => {
+            let len = wire::decode_len(decoder)?;
+            limits.check(Limit::BlobLength, len)?;
+            Ok(CompactValue::Blob(take_bytes(decoder, len)?.into()))
+        }
+        wire::ARRAY => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = wire::decode_len(decoder)?;
+            limits.check(Limit::Elements, len)?;
+            let mut array = Vec::with_capacity(len.min(wire::MAX_PREALLOCATED));
+            for _ in 0..len {
+                array.push(decode_compact(decoder, limits, depth + 1)?);
+            }
+            Ok(CompactValue::Array(array))
+        }
+        wire::INTEGER => Ok(CompactValue::Integer(i64::decode(decoder)?)),
+        wire::FLOAT => Ok(CompactValue::Float(f64::decode(decoder)?)),
+        wire::OBJECT => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = wire::decode_len(decoder)?;
+            limits.check(Limit::Elements, len)?;
+            let mut object = Map::with_capacity(len.min(wire::MAX_PREALLOCATED));
+            for _ in 0..len {
+                let key = take_str(decoder, limits)?.into();
+                object.insert(key, decode_compact(decoder, limits, depth + 1)?);
+            }
+            Ok(CompactValue::Object(object))
+        }
+        _ => Ok(CompactValue::String(take_str(decoder, limits)?.into())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn document() -> Value {
+        Value::Object(Map::from([
+            (String::from("null"), Value::Null),
+            (String::from("bool"), Value::Boolean(true)),
+            (String::from("blob"), Value::Blob(vec![1, 2, 3])),
+            (
+                String::from("array"),
+                Value::Array(vec![Value::Integer(-42)]),
+            ),
+            (String::from("float"), Value::Float(1.5)),
+            (String::from("string"), Value::String("x".repeat(40))),
+        ]))
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = crate::to_vec(&document()).unwrap();
+        let compact = CompactValue::from_slice(&bytes).unwrap();
+        assert_eq!(compact, CompactValue::from(document()));
+        assert_eq!(compact.kind(), ValueKind::Object);
+        assert_eq!(
+            compact.get("bool").and_then(CompactValue::as_bool),
+            Some(&true)
+        );
+        assert_eq!(
+            compact.get("float").and_then(CompactValue::as_float),
+            Some(&1.5)
+        );
+        assert_eq!(
+            compact.get("blob").and_then(CompactValue::as_blob),
+            Some(&[1, 2, 3][..])
+        );
+        let array = compact.get("array").unwrap();
+        assert_eq!(
+            array.get_index(0).and_then(CompactValue::as_integer),
+            Some(&-42)
+        );
+        assert_eq!(
+            compact
+                .get("string")
+                .and_then(CompactValue::as_str)
+                .map(str::len),
+            Some(40)
+        );
+        assert!(compact.get("null").unwrap().is_null());
+        assert_eq!(
+            crate::to_vec_canonical(&Value::from(compact.clone())).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+
+        let encoded = bincode::encode_to_vec(&compact, bincode::config::standard()).unwrap();
+        assert_eq!(CompactValue::from_slice(&encoded).unwrap(), compact);
+        let decoded: CompactValue = crate::from_slice(&crate::to_vec(&compact).unwrap()).unwrap();
+        assert_eq!(decoded, compact);
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = crate::to_vec(&document()).unwrap();
+        for len in 0..bytes.len() {
+            let error = CompactValue::from_slice(&bytes[..len]).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Eof);
+            assert!(error.offset().unwrap() <= len);
+        }
+        let error = CompactValue::from_slice(&[8]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Decode);
+
+        let limits = DecodeLimits::new().with_max_string_len(8);
+        let error = CompactValue::from_slice_with_limits(&bytes, &limits).unwrap_err();
+        assert!(error.is_limit_exceeded());
+
+        assert!(CompactValue::Integer(1).as_str().is_none());
+    }
+}
@@ -0,0 +1,283 @@
+//! Transparent compression of encoded `bincode-json` documents.
+//!
+//! A compressed document starts with a single byte identifying its [Compression] codec, followed
+//! by the encoded document compressed with that codec.
+//!
+//! The limit on the input size of [DecodeLimits] applies to the decompressed document, so that a
+//! small compressed payload cannot expand into more memory than allowed.
+
+use crate::{
+    error::{Error, Result},
+    limits::{DecodeLimits, Limit},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{Read, Write};
+
+/// Represents a compression codec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// Stores the encoded document as is.
+    None,
+
+    /// Compresses with Zstandard at its default level. Requires the `zstd` feature.
+    Zstd,
+
+    /// Compresses with raw DEFLATE at its default level. Requires the `deflate` feature.
+    Deflate,
+}
+impl Compression {
+    /// Gets the identifier of the codec written in the header.
+    pub fn id(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zstd => 1,
+            Self::Deflate => 2,
+        }
+    }
+
+    /// Gets the codec identified by `id`.
+    pub fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Zstd),
+            2 => Ok(Self::Deflate),
+            _ => Err(Error::Unsupported(format!("compression codec {}", id))),
+        }
+    }
+
+    /// Returns `true` if the feature the codec requires is enabled.
+    pub fn is_available(self) -> bool {
+        match self {
+            Self::None => true,
+            Self::Zstd => cfg!(feature = "zstd"),
+            Self::Deflate => cfg!(feature = "deflate"),
+        }
+    }
+
+    fn encoder<W: Write>(self, writer: W) -> Result<Encoder<W>> {
+        match self {
+            Self::None => Ok(Encoder::None(writer)),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Ok(Encoder::Zstd(zstd::stream::write::Encoder::new(writer, 0)?)),
+            #[cfg(feature = "deflate")]
+            Self::Deflate => Ok(Encoder::Deflate(flate2::write::DeflateEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            ))),
+            #[allow(unreachable_patterns)]
+            other => Err(other.unsupported()),
+        }
+    }
+
+    fn decoder<R: Read>(self, reader: R) -> Result<Decoder<R>> {
+        match self {
+            Self::None => Ok(Decoder::None(reader)),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Ok(Decoder::Zstd(zstd::stream::read::Decoder::new(reader)?)),
+            #[cfg(feature = "deflate")]
+            Self::Deflate => Ok(Decoder::Deflate(flate2::read::DeflateDecoder::new(reader))),
+            #[allow(unreachable_patterns)]
+            other => Err(other.unsupported()),
+        }
+    }
+
+    fn unsupported(self) -> Error {
+        Error::Unsupported(format!(
+            "compression codec {:?}, its feature is disabled",
+            self
+        ))
+    }
+}
+
+/// A writer compressing with one of the codecs.
+enum Encoder<W: Write> {
+    None(W),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+    #[cfg(feature = "deflate")]
+    Deflate(flate2::write::DeflateEncoder<W>),
+}
+impl<W: Write> Encoder<W> {
+    /// Writes out the trailing data of the compressed stream, returning the underlying writer.
+    fn finish(self) -> std::io::Result<W> {
+        let mut writer = match self {
+            Self::None(w) => w,
+            #[cfg(feature = "zstd")]
+            Self::Zstd(w) => w.finish()?,
+            #[cfg(feature = "deflate")]
+            Self::Deflate(w) => w.finish()?,
+        };
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::None(w) => w.write(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(w) => w.write(buf),
+            #[cfg(feature = "deflate")]
+            Self::Deflate(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::None(w) => w.flush(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(w) => w.flush(),
+            #[cfg(feature = "deflate")]
+            Self::Deflate(w) => w.flush(),
+        }
+    }
+}
+
+/// A reader decompressing with one of the codecs.
+enum Decoder<R: Read> {
+    None(R),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::read::Decoder<'static, std::io::BufReader<R>>),
+    #[cfg(feature = "deflate")]
+    Deflate(flate2::read::DeflateDecoder<R>),
+}
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::None(r) => r.read(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(r) => r.read(buf),
+            #[cfg(feature = "deflate")]
+            Self::Deflate(r) => r.read(buf),
+        }
+    }
+}
+
+/// Compresses `bytes` with `codec`, without writing a header.
+pub(crate) fn compress(codec: Compression, bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = codec.encoder(Vec::new())?;
+    encoder.write_all(bytes)?;
+    Ok(encoder.finish()?)
+}
+
+/// Decompresses `bytes` which were compressed with `codec` and have no header, enforcing the
+/// limit on the input size of `limits` on the decompressed bytes.
+pub(crate) fn decompress(
+    codec: Compression,
+    bytes: &[u8],
+    limits: &DecodeLimits,
+) -> Result<Vec<u8>> {
+    read_limited(codec.decoder(bytes)?, limits)
+}
+
+/// Reads `reader` to its end, failing as soon as it yields more bytes than `limits` allow.
+fn read_limited<R: Read>(reader: R, limits: &DecodeLimits) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    // Reading a byte past the limit tells input which exceeds it from input which fills it.
+    reader
+        .take((limits.max_bytes as u64).saturating_add(1))
+        .read_to_end(&mut buf)?;
+    limits.check(Limit::Bytes, buf.len())?;
+    Ok(buf)
+}
+
+/// Serialize the given data structure as a compressed byte vector.
+pub fn to_vec_compressed<T: Serialize>(val: &T, codec: Compression) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    to_writer_compressed(&mut buf, val, codec)?;
+    Ok(buf)
+}
+
+/// Deserialize an instance of type `T` from compressed bytes of Bincode JSON, enforcing the
+/// default [DecodeLimits].
+pub fn from_slice_compressed<T: DeserializeOwned>(val: &[u8]) -> Result<T> {
+    from_reader_compressed(val)
+}
+
+/// Deserialize an instance of type `T` from compressed bytes of Bincode JSON, enforcing `limits`.
+pub fn from_slice_compressed_with_limits<T: DeserializeOwned>(
+    val: &[u8],
+    limits: &DecodeLimits,
+) -> Result<T> {
+    from_reader_compressed_with_limits(val, limits)
+}
+
+/// Serialize the given data structure into a writer, compressed with `codec`.
+///
+/// Nothing is written if `codec` is not [available](Compression::is_available).
+pub fn to_writer_compressed<W: Write, T: Serialize>(
+    mut writer: W,
+    val: &T,
+    codec: Compression,
+) -> Result<()> {
+    if !codec.is_available() {
+        return Err(codec.unsupported());
+    }
+    let value = crate::to_value(val)?;
+    writer.write_all(&[codec.id()])?;
+    let mut encoder = codec.encoder(writer)?;
+    bincode::encode_into_std_write(value, &mut encoder, bincode::config::standard())?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Deserialize an instance of type `T` from a reader of compressed Bincode JSON, enforcing the
+/// default [DecodeLimits].
+pub fn from_reader_compressed<R: Read, T: DeserializeOwned>(reader: R) -> Result<T> {
+    from_reader_compressed_with_limits(reader, &DecodeLimits::default())
+}
+
+/// Deserialize an instance of type `T` from a reader of compressed Bincode JSON, enforcing
+/// `limits`.
+pub fn from_reader_compressed_with_limits<R: Read, T: DeserializeOwned>(
+    mut reader: R,
+    limits: &DecodeLimits,
+) -> Result<T> {
+    let mut id = [0];
+    reader.read_exact(&mut id)?;
+    let buf = read_limited(Compression::from_id(id[0])?.decoder(reader)?, limits)?;
+    crate::from_slice_with_limits(&buf, limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    const CODECS: [Compression; 3] = [Compression::None, Compression::Zstd, Compression::Deflate];
+
+    #[test]
+    fn round_trip() {
+        let val = vec!["repeated".to_owned(); 100];
+        for codec in CODECS.into_iter().filter(|codec| codec.is_available()) {
+            let bytes = to_vec_compressed(&val, codec).unwrap();
+            assert_eq!(bytes[0], codec.id());
+            assert_eq!(Compression::from_id(bytes[0]).unwrap(), codec);
+            let decoded: Vec<String> = from_slice_compressed(&bytes).unwrap();
+            assert_eq!(decoded, val);
+        }
+    }
+
+    #[test]
+    fn unavailable_codec_writes_nothing() {
+        for codec in CODECS.into_iter().filter(|codec| !codec.is_available()) {
+            let mut buf = Vec::new();
+            let error = to_writer_compressed(&mut buf, &1u8, codec).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Unsupported);
+            assert!(buf.is_empty());
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let error = from_slice_compressed::<u8>(&[9, 0]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        let error = from_slice_compressed::<u8>(&[]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+
+        let bytes = to_vec_compressed(&"x".repeat(100), Compression::None).unwrap();
+        let limits = DecodeLimits::new().with_max_bytes(50);
+        let error = from_slice_compressed_with_limits::<String>(&bytes, &limits).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+    }
+}
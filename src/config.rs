@@ -0,0 +1,628 @@
+//! Wire layout configurations and their named presets.
+//!
+//! Every value is written as its variant discriminant followed by its payload:
+//!
+//! | Discriminant | Variant   | Payload                                                      |
+//! |--------------|-----------|--------------------------------------------------------------|
+//! | 0            | `Null`    | none                                                         |
+//! | 1            | `Boolean` | 1 byte, `0` or `1`                                           |
+//! | 2            | `Blob`    | length, then the bytes                                       |
+//! | 3            | `Array`   | length, then every element                                   |
+//! | 4            | `Integer` | the integer, as an `i64`                                     |
+//! | 5            | `Float`   | the IEEE 754 binary64 bits, always 8 bytes                   |
+//! | 6            | `Object`  | length, then every key (as a string) followed by its value   |
+//! | 7            | `String`  | length, then the UTF-8 bytes                                 |
+//!
+//! How `Integer` payloads are written depends on the [IntEncoding], and how discriminants and
+//! lengths are written depends on their [Width]s; multi-byte numbers, including floats, use the
+//! [Endian].
+//!
+//! With [IntEncoding::Variable] or [Width::Variable], an unsigned integer `n` is written as a
+//! single byte if `n < 251`, and otherwise as a marker byte `251`, `252` or `253` followed by `n`
+//! as a `u16`, `u32` or `u64` respectively. Signed integers are zigzag-encoded first, i.e.
+//! `0, -1, 1, -2` become `0, 1, 2, 3`. With [IntEncoding::Fixed], integers are written as `i64`s,
+//! and with a fixed [Width], discriminants and lengths are written with that width; encoding a
+//! length too large for it fails.
+//!
+//! The presets follow bincode: [Config::varint] writes everything variable-length, as bincode 2.x
+//! does, while the fixed presets write discriminants as `u32`s and lengths as `u64`s, as bincode
+//! 1.x does.
+//!
+//! ## Test vectors
+//! | Value          | [Config::varint]    | [Config::fixed_little_endian]       | [Config::fixed_big_endian]          |
+//! |----------------|---------------------|-------------------------------------|-------------------------------------|
+//! | `null`         | `00`                | `00 00 00 00`                       | `00 00 00 00`                       |
+//! | `true`         | `01 01`             | `01 00 00 00 01`                    | `00 00 00 01 01`                    |
+//! | `-2`           | `04 03`             | `04 00 00 00 fe ff ff ff ff ff ff ff` | `00 00 00 04 ff ff ff ff ff ff ff fe` |
+//! | `300`          | `04 fb 58 02`       | `04 00 00 00 2c 01 00 00 00 00 00 00` | `00 00 00 04 00 00 00 00 00 00 01 2c` |
+//! | `1.5`          | `05 00 00 00 00 00 00 f8 3f` | `05 00 00 00 00 00 00 00 00 00 f8 3f` | `00 00 00 05 3f f8 00 00 00 00 00 00` |
+//! | `"hi"`         | `07 02 68 69`       | `07 00 00 00 02 00 00 00 00 00 00 00 68 69` | `00 00 00 07 00 00 00 00 00 00 00 02 68 69` |
+//! | blob `ab`      | `02 01 ab`          | `02 00 00 00 01 00 00 00 00 00 00 00 ab` | `00 00 00 02 00 00 00 00 00 00 00 01 ab` |
+//! | `[null]`       | `03 01 00`          | `03 00 00 00 01 00 00 00 00 00 00 00 00 00 00 00` | `00 00 00 03 00 00 00 00 00 00 00 01 00 00 00 00` |
+//! | `{"a": 1}`     | `06 01 01 61 04 02` | `06 00 00 00 01 00 00 00 00 00 00 00 01 00 00 00 00 00 00 00 61 04 00 00 00 01 00 00 00 00 00 00 00` | `00 00 00 06 00 00 00 00 00 00 00 01 00 00 00 00 00 00 00 01 61 00 00 00 04 00 00 00 00 00 00 00 01` |
+
+use crate::{
+    error::{Error, Result},
+    limits::{DecodeLimits, Limit},
+    value::{Map, Value},
+    wire::{self, SliceReader},
+};
+use bincode::{
+    de::read::BorrowReader,
+    error::{AllowedEnumVariants, DecodeError, IntegerType},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// How integers are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntEncoding {
+    /// Variable-length encoding, as used by bincode 2.x by default.
+    Variable,
+
+    /// Fixed-length encoding, as used by bincode 1.x by default.
+    Fixed,
+}
+
+/// The byte order of multi-byte numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// How discriminants or lengths are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Width {
+    /// Variable-length encoding, like integers with [IntEncoding::Variable].
+    Variable,
+
+    /// A single byte.
+    U8,
+
+    /// 2 bytes.
+    U16,
+
+    /// 4 bytes.
+    U32,
+
+    /// 8 bytes.
+    U64,
+}
+impl Width {
+    /// Gets the number of bytes of a fixed width.
+    fn bytes(self) -> Option<usize> {
+        match self {
+            Self::Variable => None,
+            Self::U8 => Some(1),
+            Self::U16 => Some(2),
+            Self::U32 => Some(4),
+            Self::U64 => Some(8),
+        }
+    }
+}
+
+/// A wire layout configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Config {
+    /// How `Integer` payloads are encoded.
+    pub int_encoding: IntEncoding,
+
+    /// The byte order of multi-byte numbers.
+    pub endian: Endian,
+
+    /// How variant discriminants are encoded.
+    pub discriminant_width: Width,
+
+    /// How the lengths of blobs, strings, arrays and objects are encoded.
+    pub length_width: Width,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+impl Config {
+    /// The configuration used by [crate::to_vec] and [crate::from_slice], same as
+    /// [Config::varint].
+    pub fn standard() -> Self {
+        Self::varint()
+    }
+
+    /// Variable-length little-endian integers, discriminants and lengths.
+    pub fn varint() -> Self {
+        Self {
+            int_encoding: IntEncoding::Variable,
+            endian: Endian::Little,
+            discriminant_width: Width::Variable,
+            length_width: Width::Variable,
+        }
+    }
+
+    /// Fixed-length little-endian integers, `u32` discriminants and `u64` lengths.
+    pub fn fixed_little_endian() -> Self {
+        Self {
+            int_encoding: IntEncoding::Fixed,
+            endian: Endian::Little,
+            discriminant_width: Width::U32,
+            length_width: Width::U64,
+        }
+    }
+
+    /// Fixed-length big-endian integers, `u32` discriminants and `u64` lengths.
+    pub fn fixed_big_endian() -> Self {
+        Self {
+            endian: Endian::Big,
+            ..Self::fixed_little_endian()
+        }
+    }
+
+    /// The configuration matching the layout written by bincode 1.x's `serialize`, same as
+    /// [Config::fixed_little_endian].
+    #[cfg(feature = "legacy")]
+    pub fn legacy() -> Self {
+        Self::fixed_little_endian()
+    }
+
+    /// Sets how variant discriminants are encoded.
+    pub fn with_discriminant_width(mut self, width: Width) -> Self {
+        self.discriminant_width = width;
+        self
+    }
+
+    /// Sets how lengths are encoded.
+    pub fn with_length_width(mut self, width: Width) -> Self {
+        self.length_width = width;
+        self
+    }
+}
+
+/// Serialize the given data structure as a byte vector laid out with `config`.
+pub fn to_vec_with_config<T: Serialize>(val: &T, config: Config) -> Result<Vec<u8>> {
+    let value = crate::to_value(val)?;
+    let mut writer = Writer {
+        config,
+        buf: Vec::with_capacity(wire::encoded_len(&value)),
+    };
+    writer.value(&value)?;
+    Ok(writer.buf)
+}
+
+/// Deserialize an instance of type `T` from bytes of Bincode JSON laid out with `config`,
+/// enforcing the default [DecodeLimits].
+pub fn from_slice_with_config<T: DeserializeOwned>(val: &[u8], config: Config) -> Result<T> {
+    from_slice_with_config_and_limits(val, config, &DecodeLimits::default())
+}
+
+/// Deserialize an instance of type `T` from bytes of Bincode JSON laid out with `config`,
+/// enforcing `limits`.
+pub fn from_slice_with_config_and_limits<T: DeserializeOwned>(
+    val: &[u8],
+    config: Config,
+    limits: &DecodeLimits,
+) -> Result<T> {
+    limits.check(Limit::Bytes, val.len())?;
+    let mut reader = Reader {
+        config,
+        limits,
+        reader: SliceReader::new(val),
+    };
+    let value = reader
+        .value(0)
+        .map_err(|e| e.at_offset(reader.reader.position()))?;
+    crate::from_value(value)
+}
+
+/// Encodes values laid out with a [Config].
+struct Writer {
+    config: Config,
+    buf: Vec<u8>,
+}
+impl Writer {
+    fn value(&mut self, value: &Value) -> Result<()> {
+        let tag = match value {
+            Value::Null => wire::NULL,
+            Value::Boolean(_) => wire::BOOLEAN,
+            Value::Blob(_) => wire::BLOB,
+            Value::Array(_) => wire::ARRAY,
+            Value::Integer(_) => wire::INTEGER,
+            Value::Float(_) => wire::FLOAT,
+            Value::Object(_) => wire::OBJECT,
+            Value::String(_) => wire::STRING,
+        };
+        self.uint(self.config.discriminant_width, tag.into(), "discriminant")?;
+        match value {
+            Value::Null => {}
+            Value::Boolean(b) => self.buf.push(*b as u8),
+            Value::Blob(b) => self.bytes(b)?,
+            Value::Array(a) => {
+                self.len(a.len())?;
+                for item in a {
+                    self.value(item)?;
+                }
+            }
+            Value::Integer(i) => match self.config.int_encoding {
+                IntEncoding::Variable => self.uint(Width::Variable, zigzag(*i), "integer")?,
+                IntEncoding::Fixed => self.fixed(*i as u64, 8),
+            },
+            Value::Float(f) => self.fixed(f.to_bits(), 8),
+            Value::Object(o) => {
+                self.len(o.len())?;
+                for (k, v) in o {
+                    self.bytes(k.as_bytes())?;
+                    self.value(v)?;
+                }
+            }
+            Value::String(s) => self.bytes(s.as_bytes())?,
+        }
+        Ok(())
+    }
+
+    fn len(&mut self, len: usize) -> Result<()> {
+        self.uint(self.config.length_width, len as u64, "length")
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.len(bytes.len())?;
+        self.buf.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Writes `n` with `width`, failing if it does not fit.
+    fn uint(&mut self, width: Width, n: u64, what: &str) -> Result<()> {
+        match width.bytes() {
+            Some(bytes) if bytes < 8 && n >> (bytes * 8) != 0 => Err(Error::Unsupported(format!(
+                "{} {} wider than {} bytes",
+                what, n, bytes
+            ))),
+            Some(bytes) => {
+                self.fixed(n, bytes);
+                Ok(())
+            }
+            None => {
+                match n {
+                    0..=250 => self.buf.push(n as u8),
+                    251..=0xffff => {
+                        self.buf.push(251);
+                        self.fixed(n, 2);
+                    }
+                    0x1_0000..=0xffff_ffff => {
+                        self.buf.push(252);
+                        self.fixed(n, 4);
+                    }
+                    _ => {
+                        self.buf.push(253);
+                        self.fixed(n, 8);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes the low `bytes` bytes of `n`.
+    fn fixed(&mut self, n: u64, bytes: usize) {
+        match self.config.endian {
+            Endian::Little => self.buf.extend_from_slice(&n.to_le_bytes()[..bytes]),
+            Endian::Big => self.buf.extend_from_slice(&n.to_be_bytes()[8 - bytes..]),
+        }
+    }
+}
+
+/// Decodes values laid out with a [Config], enforcing limits.
+struct Reader<'a> {
+    config: Config,
+    limits: &'a DecodeLimits,
+    reader: SliceReader<'a>,
+}
+impl<'a> Reader<'a> {
+    fn value(&mut self, depth: usize) -> Result<Value> {
+        let tag = self.uint(self.config.discriminant_width)?;
+        match u32::try_from(tag).unwrap_or(u32::MAX) {
+            wire::NULL => Ok(Value::Null),
+            wire::BOOLEAN => match self.reader.take_bytes(1)?[0] {
+                0 => Ok(Value::Boolean(false)),
+                1 => Ok(Value::Boolean(true)),
+                b => Err(DecodeError::InvalidBooleanValue(b).into()),
+            },
+            wire::BLOB => {
+                let len = self.len()?;
+                self.limits.check(Limit::BlobLength, len)?;
+                Ok(Value::Blob(self.reader.take_bytes(len)?.to_vec()))
+            }
+            wire::ARRAY => {
+                self.limits.check(Limit::Depth, depth + 1)?;
+                let len = self.len()?;
+                self.limits.check(Limit::Elements, len)?;
+                let mut array = Vec::with_capacity(len.min(wire::MAX_PREALLOCATED));
+                for _ in 0..len {
+                    array.push(self.value(depth + 1)?);
+                }
+                Ok(Value::Array(array))
+            }
+            wire::INTEGER => Ok(Value::Integer(match self.config.int_encoding {
+                IntEncoding::Variable => unzigzag(self.uint(Width::Variable)?),
+                IntEncoding::Fixed => self.fixed(8)? as i64,
+            })),
+            wire::FLOAT => Ok(Value::Float(f64::from_bits(self.fixed(8)?))),
+            wire::OBJECT => {
+                self.limits.check(Limit::Depth, depth + 1)?;
+                let len = self.len()?;
+                self.limits.check(Limit::Elements, len)?;
+                let mut object = Map::with_capacity(len.min(wire::MAX_PREALLOCATED));
+                for _ in 0..len {
+                    let key = self.string()?;
+                    object.insert(key, self.value(depth + 1)?);
+                }
+                Ok(Value::Object(object))
+            }
+            wire::STRING => Ok(Value::String(self.string()?)),
+            found => Err(DecodeError::UnexpectedVariant {
+                type_name: "Value",
+                allowed: &AllowedEnumVariants::Range {
+                    min: wire::NULL,
+                    max: wire::STRING,
+                },
+                found,
+            }
+            .into()),
+        }
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        let len = self.uint(self.config.length_width)?;
+        Ok(usize::try_from(len).map_err(|_| DecodeError::OutsideUsizeRange(len))?)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.len()?;
+        self.limits.check(Limit::StringLength, len)?;
+        wire::into_string(self.reader.take_bytes(len)?.to_vec())
+    }
+
+    /// Reads an unsigned integer written with `width`.
+    fn uint(&mut self, width: Width) -> Result<u64> {
+        if let Some(bytes) = width.bytes() {
+            return self.fixed(bytes);
+        }
+        match self.reader.take_bytes(1)?[0] {
+            n @ 0..=250 => Ok(n.into()),
+            251 => self.fixed(2),
+            252 => self.fixed(4),
+            253 => self.fixed(8),
+            _ => Err(DecodeError::InvalidIntegerType {
+                expected: IntegerType::U64,
+                found: IntegerType::Reserved,
+            }
+            .into()),
+        }
+    }
+
+    /// Reads a number of `bytes` bytes.
+    fn fixed(&mut self, bytes: usize) -> Result<u64> {
+        let src = self.reader.take_bytes(bytes)?;
+        let mut buf = [0; 8];
+        Ok(match self.config.endian {
+            Endian::Little => {
+                buf[..bytes].copy_from_slice(src);
+                u64::from_le_bytes(buf)
+            }
+            Endian::Big => {
+                buf[8 - bytes..].copy_from_slice(src);
+                u64::from_be_bytes(buf)
+            }
+        })
+    }
+}
+
+fn zigzag(i: i64) -> u64 {
+    ((i << 1) ^ (i >> 63)) as u64
+}
+
+fn unzigzag(n: u64) -> i64 {
+    ((n >> 1) as i64) ^ -((n & 1) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{Map, Value};
+
+    fn hex(s: &str) -> Vec<u8> {
+        s.split(' ')
+            .map(|b| u8::from_str_radix(b, 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_vectors() {
+        let mut object = Map::default();
+        object.insert("a".into(), Value::Integer(1));
+        let vectors = [
+            (Value::Null, ["00", "00 00 00 00", "00 00 00 00"]),
+            (
+                Value::Boolean(true),
+                ["01 01", "01 00 00 00 01", "00 00 00 01 01"],
+            ),
+            (
+                Value::Integer(-2),
+                [
+                    "04 03",
+                    "04 00 00 00 fe ff ff ff ff ff ff ff",
+                    "00 00 00 04 ff ff ff ff ff ff ff fe",
+                ],
+            ),
+            (
+                Value::Integer(300),
+                [
+                    "04 fb 58 02",
+                    "04 00 00 00 2c 01 00 00 00 00 00 00",
+                    "00 00 00 04 00 00 00 00 00 00 01 2c",
+                ],
+            ),
+            (
+                Value::Float(1.5),
+                [
+                    "05 00 00 00 00 00 00 f8 3f",
+                    "05 00 00 00 00 00 00 00 00 00 f8 3f",
+                    "00 00 00 05 3f f8 00 00 00 00 00 00",
+                ],
+            ),
+            (
+                Value::String("hi".into()),
+                [
+                    "07 02 68 69",
+                    "07 00 00 00 02 00 00 00 00 00 00 00 68 69",
+                    "00 00 00 07 00 00 00 00 00 00 00 02 68 69",
+                ],
+            ),
+            (
+                Value::Blob(vec![0xab]),
+                [
+                    "02 01 ab",
+                    "02 00 00 00 01 00 00 00 00 00 00 00 ab",
+                    "00 00 00 02 00 00 00 00 00 00 00 01 ab",
+                ],
+            ),
+            (
+                Value::Array(vec![Value::Null]),
+                [
+                    "03 01 00",
+                    "03 00 00 00 01 00 00 00 00 00 00 00 00 00 00 00",
+                    "00 00 00 03 00 00 00 00 00 00 00 01 00 00 00 00",
+                ],
+            ),
+            (
+                Value::Object(object),
+                [
+                    "06 01 01 61 04 02",
+                    "06 00 00 00 01 00 00 00 00 00 00 00 01 00 00 00 00 00 00 00 61 04 00 00 00 \
+                     01 00 00 00 00 00 00 00",
+                    "00 00 00 06 00 00 00 00 00 00 00 01 00 00 00 00 00 00 00 01 61 00 00 00 04 \
+                     00 00 00 00 00 00 00 01",
+                ],
+            ),
+        ];
+        let configs = [
+            Config::varint(),
+            Config::fixed_little_endian(),
+            Config::fixed_big_endian(),
+        ];
+        for (value, expected) in &vectors {
+            for (config, expected) in configs.iter().zip(expected) {
+                let bytes = to_vec_with_config(value, *config).unwrap();
+                assert_eq!(bytes, hex(expected), "{:?} with {:?}", value, config);
+                let decoded: Value = from_slice_with_config(&bytes, *config).unwrap();
+                assert_eq!(
+                    crate::to_vec(&decoded).unwrap(),
+                    crate::to_vec(value).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let val = (
+            1u64 << 40,
+            i64::MIN,
+            -0.25f64,
+            "text".to_owned(),
+            vec![Some(1u8), None],
+        );
+        for int_encoding in [IntEncoding::Variable, IntEncoding::Fixed] {
+            for endian in [Endian::Little, Endian::Big] {
+                let config = Config {
+                    int_encoding,
+                    endian,
+                    ..Config::varint()
+                };
+                let bytes = to_vec_with_config(&val, config).unwrap();
+                let decoded: (u64, i64, f64, String, Vec<Option<u8>>) =
+                    from_slice_with_config(&bytes, config).unwrap();
+                assert_eq!(decoded, val, "{:?}", config);
+            }
+        }
+        assert_eq!(
+            to_vec_with_config(&val, Config::standard()).unwrap(),
+            crate::to_vec(&val).unwrap()
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let config = Config::fixed_big_endian();
+        let bytes = to_vec_with_config(&vec![vec![vec![1u8]]], config).unwrap();
+        let limits = DecodeLimits::new().with_max_depth(2);
+        let error =
+            from_slice_with_config_and_limits::<Value>(&bytes, config, &limits).unwrap_err();
+        assert_eq!(error.kind(), crate::ErrorKind::LimitExceeded);
+    }
+
+    #[test]
+    fn widths() {
+        let val = (
+            vec!["x".repeat(70000), String::new()],
+            vec![1u8, 2, 3],
+            -3i64,
+            Some(true),
+        );
+        let widths = [
+            Width::Variable,
+            Width::U8,
+            Width::U16,
+            Width::U32,
+            Width::U64,
+        ];
+        for int_encoding in [IntEncoding::Variable, IntEncoding::Fixed] {
+            for endian in [Endian::Little, Endian::Big] {
+                for discriminant_width in widths {
+                    for length_width in widths {
+                        let config = Config {
+                            int_encoding,
+                            endian,
+                            discriminant_width,
+                            length_width,
+                        };
+                        let result = to_vec_with_config(&val, config);
+                        if matches!(length_width, Width::U8 | Width::U16) {
+                            let error = result.unwrap_err();
+                            assert_eq!(error.kind(), crate::ErrorKind::Unsupported);
+                            continue;
+                        }
+                        let bytes = result.unwrap();
+                        let decoded: (Vec<String>, Vec<u8>, i64, Option<bool>) =
+                            from_slice_with_config(&bytes, config).unwrap();
+                        assert_eq!(decoded, val, "{:?}", config);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn width_layout() {
+        let config = Config::varint()
+            .with_discriminant_width(Width::U8)
+            .with_length_width(Width::U16);
+        let bytes = to_vec_with_config(&"hi", config).unwrap();
+        assert_eq!(bytes, hex("07 02 00 68 69"));
+        let bytes = to_vec_with_config(&"hi", config.with_discriminant_width(Width::U16)).unwrap();
+        assert_eq!(bytes, hex("07 00 02 00 68 69"));
+
+        let config = Config::fixed_big_endian().with_length_width(Width::U32);
+        let bytes = to_vec_with_config(&Value::Blob(vec![0xab]), config).unwrap();
+        assert_eq!(bytes, hex("00 00 00 02 00 00 00 01 ab"));
+        let decoded: Value = from_slice_with_config(&bytes, config).unwrap();
+        assert!(matches!(decoded, Value::Blob(b) if b == [0xab]));
+    }
+
+    #[test]
+    fn malformed() {
+        let config = Config::varint().with_discriminant_width(Width::U16);
+        let error = from_slice_with_config::<Value>(&hex("08 00"), config).unwrap_err();
+        assert_eq!(error.kind(), crate::ErrorKind::Decode);
+        let error = from_slice_with_config::<Value>(&hex("07 00 05 00 68"), config).unwrap_err();
+        assert_eq!(error.kind(), crate::ErrorKind::Eof);
+        assert_eq!(error.offset(), Some(3));
+    }
+}
@@ -0,0 +1,252 @@
+//! CSV export and import of arrays of flat objects, e.g. for quick exports to analysts.
+//!
+//! Each object is a row and each key a column, and the header lists the keys of every object,
+//! sorted. Nulls and missing keys are written as empty cells, blobs as base64 and other values as
+//! their text. Nested objects and arrays are unsupported unless [Nested::Flatten] flattens them
+//! into columns with [Value::flatten].
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value, ValueKind},
+};
+use std::{
+    borrow::Cow,
+    collections::BTreeSet,
+    io::{Read, Write},
+};
+
+/// How nested objects and arrays in a row are handled.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Nested {
+    /// Nested values are unsupported.
+    #[default]
+    Reject,
+
+    /// Rows are flattened with [Value::flatten], joining the keys of nested values with the given
+    /// separator.
+    Flatten(String),
+}
+
+/// Options of [to_csv] and [from_csv].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CsvOptions {
+    /// How nested objects and arrays in a row are handled.
+    pub nested: Nested,
+
+    /// Whether [from_csv] infers the types of cells: empty cells become nulls, and booleans,
+    /// integers and floats are parsed. Otherwise every cell is a string.
+    pub infer_types: bool,
+}
+impl CsvOptions {
+    /// Constructs a new [CsvOptions] with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how nested objects and arrays in a row are handled.
+    pub fn with_nested(mut self, nested: Nested) -> Self {
+        self.nested = nested;
+        self
+    }
+
+    /// Sets whether [from_csv] infers the types of cells.
+    pub fn with_infer_types(mut self, infer_types: bool) -> Self {
+        self.infer_types = infer_types;
+        self
+    }
+}
+
+/// Writes an array of objects to `writer` as CSV, one row per object.
+pub fn to_csv<W: Write>(writer: W, value: &Value, options: &CsvOptions) -> Result<()> {
+    let rows = match value {
+        Value::Array(rows) => rows
+            .iter()
+            .map(|row| self::row(row, options))
+            .collect::<Result<Vec<_>>>()?,
+        v => return Err(Error::Expected(ValueKind::Array.into(), v.kind().into())),
+    };
+    let header: BTreeSet<&str> = rows
+        .iter()
+        .flat_map(|row| row.keys())
+        .map(String::as_str)
+        .collect();
+
+    let mut writer = ::csv::Writer::from_writer(writer);
+    writer.write_record(&header).map_err(csv_error)?;
+    for row in &rows {
+        writer
+            .write_record(header.iter().map(|key| cell(row.get(*key))))
+            .map_err(csv_error)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads CSV with a header from `reader` into an array of objects, one per row.
+///
+/// Rows are never unflattened, and blobs written by [to_csv] are read back as base64 strings.
+pub fn from_csv<R: Read>(reader: R, options: &CsvOptions) -> Result<Value> {
+    let mut reader = ::csv::Reader::from_reader(reader);
+    let header = reader.headers().map_err(csv_error)?.clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let mut row = Map::with_capacity(header.len());
+        for (key, cell) in header.iter().zip(&record) {
+            let value = match options.infer_types {
+                true => infer(cell),
+                false => Value::String(cell.into()),
+            };
+            row.insert(key.into(), value);
+        }
+        rows.push(Value::Object(row));
+    }
+    Ok(Value::Array(rows))
+}
+
+/// Gets the cells of a row, flattening it if configured.
+fn row<'a>(row: &'a Value, options: &CsvOptions) -> Result<Cow<'a, Map<String, Value>>> {
+    Ok(match (row, &options.nested) {
+        (Value::Object(_), Nested::Flatten(separator)) => match row.clone().flatten(separator) {
+            Value::Object(object) => Cow::Owned(object),
+            _ => unreachable!("flattened objects are objects"),
+        },
+        (Value::Object(object), Nested::Reject) => {
+            if let Some((key, _)) = object
+                .iter()
+                .find(|(_, v)| matches!(v, Value::Array(_) | Value::Object(_)))
+            {
+                return Err(Error::Unsupported(format!(
+                    "nested value in column {}",
+                    key
+                )));
+            }
+            Cow::Borrowed(object)
+        }
+        (v, _) => return Err(Error::Expected(ValueKind::Object.into(), v.kind().into())),
+    })
+}
+
+/// Gets the text of a cell. Nested values are only left in rows as the empty objects and arrays
+/// flattening keeps, which are written as empty cells.
+fn cell(value: Option<&Value>) -> Cow<'_, [u8]> {
+    match value {
+        None | Some(Value::Null | Value::Array(_) | Value::Object(_)) => Cow::Borrowed(b""),
+        Some(Value::Boolean(b)) => Cow::Owned(b.to_string().into_bytes()),
+        Some(Value::Integer(i)) => Cow::Owned(i.to_string().into_bytes()),
+        Some(Value::Float(f)) => Cow::Owned(f.to_string().into_bytes()),
+        Some(Value::String(s)) => Cow::Borrowed(s.as_bytes()),
+        Some(Value::Blob(b)) => Cow::Owned(base64::encode(b).into_bytes()),
+    }
+}
+
+/// Infers the type of a cell.
+fn infer(cell: &str) -> Value {
+    if cell.is_empty() {
+        Value::Null
+    } else if let Ok(b) = cell.parse() {
+        Value::Boolean(b)
+    } else if let Ok(i) = cell.parse() {
+        Value::Integer(i)
+    } else if let Ok(f) = cell.parse() {
+        Value::Float(f)
+    } else {
+        Value::String(cell.into())
+    }
+}
+
+/// Converts a CSV error into an [Error].
+fn csv_error(e: ::csv::Error) -> Error {
+    Error::Custom(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn row(entries: Vec<(&str, Value)>) -> Value {
+        Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect(),
+        )
+    }
+
+    fn write(value: &Value, options: &CsvOptions) -> Result<String> {
+        let mut buf = Vec::new();
+        to_csv(&mut buf, value, options)?;
+        Ok(String::from_utf8(buf).unwrap())
+    }
+
+    #[test]
+    fn round_trip() {
+        let rows = Value::Array(vec![
+            row(vec![
+                ("b", Value::Boolean(true)),
+                ("i", Value::Integer(-1)),
+                ("x", Value::Blob(vec![1, 2, 3])),
+            ]),
+            row(vec![
+                ("f", Value::Float(1.5)),
+                ("s", Value::String("a,b".into())),
+                ("i", Value::Null),
+            ]),
+        ]);
+        let text = write(&rows, &CsvOptions::new()).unwrap();
+        assert_eq!(text, "b,f,i,s,x\ntrue,,-1,,AQID\n,1.5,,\"a,b\",\n");
+
+        let options = CsvOptions::new().with_infer_types(true);
+        let Value::Array(decoded) = from_csv(text.as_bytes(), &options).unwrap() else {
+            panic!("expected an array");
+        };
+        let Value::Object(first) = &decoded[0] else {
+            panic!("expected an object");
+        };
+        assert!(matches!(first["b"], Value::Boolean(true)));
+        assert!(matches!(first["i"], Value::Integer(-1)));
+        assert!(first["f"].is_null());
+        assert!(matches!(&first["x"], Value::String(s) if s == "AQID"));
+        let Value::Object(second) = &decoded[1] else {
+            panic!("expected an object");
+        };
+        assert!(matches!(second["f"], Value::Float(f) if f == 1.5));
+        assert!(matches!(&second["s"], Value::String(s) if s == "a,b"));
+
+        let Value::Array(strings) = from_csv(text.as_bytes(), &CsvOptions::new()).unwrap() else {
+            panic!("expected an array");
+        };
+        assert!(
+            matches!(&strings[0], Value::Object(o) if matches!(&o["i"], Value::String(s) if s == "-1"))
+        );
+    }
+
+    #[test]
+    fn flatten() {
+        let rows = Value::Array(vec![row(vec![(
+            "a",
+            row(vec![
+                ("b", Value::Integer(1)),
+                ("c", Value::Array(Vec::new())),
+            ]),
+        )])]);
+        let options = CsvOptions::new().with_nested(Nested::Flatten(String::from(".")));
+        assert_eq!(write(&rows, &options).unwrap(), "a.b,a.c\n1,\n");
+    }
+
+    #[test]
+    fn errors() {
+        let error = write(&Value::Integer(1), &CsvOptions::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = write(&Value::Array(vec![Value::Integer(1)]), &CsvOptions::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+        let nested = Value::Array(vec![row(vec![("a", Value::Array(Vec::new()))])]);
+        let error = write(&nested, &CsvOptions::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+
+        let error = from_csv(&b"a,b\n1,2,3\n"[..], &CsvOptions::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+    }
+}
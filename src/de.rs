@@ -2,12 +2,16 @@
 
 use crate::{
     error::{Error, Result},
-    value::Value,
+    limits::{DecodeLimits, Limit},
+    value::{Map, Value},
+    wire,
+};
+use serde::de::{
+    self, value::StrDeserializer, Deserialize, DeserializeOwned, IntoDeserializer, Visitor,
 };
-use serde::de::{self, Deserialize, Visitor};
 
-#[cfg(not(features = "preserve_order"))]
-use std::collections::hash_map as map;
+use alloc::{format, string::String};
+use core::{marker::PhantomData, ops::Range};
 
 macro_rules! forward_to_deserialize {
     ($(
@@ -25,7 +29,7 @@ macro_rules! forward_to_deserialize {
             self,
             $(_: $ty,)*
             _visitor: V,
-        ) -> ::std::result::Result<V::Value, Self::Error>
+        ) -> ::core::result::Result<V::Value, Self::Error>
             where V: ::serde::de::Visitor<'de>
         {
             Err(::serde::de::Error::custom("unexpected Enum"))
@@ -38,7 +42,7 @@ macro_rules! forward_to_deserialize {
             self,
             $(_: $ty,)*
             visitor: V,
-        ) -> ::std::result::Result<V::Value, Self::Error>
+        ) -> ::core::result::Result<V::Value, Self::Error>
             where V: ::serde::de::Visitor<'de>
         {
             self.deserialize_any(visitor)
@@ -54,6 +58,52 @@ impl From<Value> for Deserializer {
         Self { value: Some(value) }
     }
 }
+impl Deserializer {
+    /// Deserializes an enum, either a unit variant name or an object with a single variant key.
+    fn enum_value<'de, V>(mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let value = match self.value.take() {
+            Some(Value::Object(value)) => value,
+            Some(Value::String(variant)) => {
+                return visitor.visit_enum(EnumDeserializer {
+                    val: Value::String(variant),
+                    deserializer: VariantDeserializer { val: None },
+                });
+            }
+            Some(v) => {
+                return Err(Error::Expected(v.kind().into(), "expected an enum".into()));
+            }
+            None => {
+                return Err(Error::Eof);
+            }
+        };
+
+        let mut iter = value.into_iter();
+
+        let (variant, value) = match iter.next() {
+            Some(v) => v,
+            None => {
+                return Err(Error::Expected(
+                    "variant name".into(),
+                    "empty object".into(),
+                ))
+            }
+        };
+
+        match iter.next() {
+            Some((k, _)) => Err(Error::Expected(
+                "map with a single key".into(),
+                format!("extra key \"{}\"", k).into(),
+            )),
+            None => visitor.visit_enum(EnumDeserializer {
+                val: Value::String(variant),
+                deserializer: VariantDeserializer { val: Some(value) },
+            }),
+        }
+    }
+}
 impl<'de> de::Deserializer<'de> for Deserializer {
     type Error = Error;
 
@@ -69,6 +119,7 @@ impl<'de> de::Deserializer<'de> for Deserializer {
                 let len = a.len();
                 visitor.visit_seq(SeqDeserializer {
                     iter: a.into_iter(),
+                    index: 0,
                     len,
                 })
             }
@@ -78,7 +129,7 @@ impl<'de> de::Deserializer<'de> for Deserializer {
                 let len = o.len();
                 visitor.visit_map(MapDeserializer {
                     iter: o.into_iter(),
-                    value: None,
+                    entry: None,
                     len,
                 })
             }
@@ -96,56 +147,29 @@ impl<'de> de::Deserializer<'de> for Deserializer {
             None => Err(Error::Eof),
         }
     }
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+            .map_err(|e| e.in_type(name, fields))
+    }
     fn deserialize_enum<V>(
-        mut self,
-        _name: &str,
-        _variants: &'static [&'static str],
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let value = match self.value.take() {
-            Some(Value::Object(value)) => value,
-            Some(Value::String(variant)) => {
-                return visitor.visit_enum(EnumDeserializer {
-                    val: Value::String(variant),
-                    deserializer: VariantDeserializer { val: None },
-                });
-            }
-            Some(v) => {
-                return Err(Error::Expected(
-                    v.error_description().into(),
-                    "expected an enum".into(),
-                ));
-            }
-            None => {
-                return Err(Error::Eof);
-            }
-        };
-
-        let mut iter = value.into_iter();
-
-        let (variant, value) = match iter.next() {
-            Some(v) => v,
-            None => {
-                return Err(Error::Expected(
-                    "variant name".into(),
-                    "empty object".into(),
-                ))
-            }
-        };
-
-        match iter.next() {
-            Some((k, _)) => Err(Error::Expected(
-                "map with a single key".into(),
-                format!("extra key \"{}\"", k),
-            )),
-            None => visitor.visit_enum(EnumDeserializer {
-                val: Value::String(variant),
-                deserializer: VariantDeserializer { val: Some(value) },
-            }),
-        }
+        self.enum_value(visitor)
+            .map_err(|e| e.in_type(name, variants))
     }
     fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
     where
@@ -175,7 +199,6 @@ impl<'de> de::Deserializer<'de> for Deserializer {
         deserialize_map();
         deserialize_unit_struct(name: &'static str);
         deserialize_tuple_struct(name: &'static str, len: usize);
-        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
         deserialize_tuple(len: usize);
         deserialize_identifier();
         deserialize_ignored_any();
@@ -184,7 +207,8 @@ impl<'de> de::Deserializer<'de> for Deserializer {
 }
 
 struct SeqDeserializer {
-    iter: std::vec::IntoIter<Value>,
+    iter: alloc::vec::IntoIter<Value>,
+    index: usize,
     len: usize,
 }
 impl<'de> de::Deserializer<'de> for SeqDeserializer {
@@ -243,10 +267,11 @@ impl<'de> de::SeqAccess<'de> for SeqDeserializer {
             None => Ok(None),
             Some(value) => {
                 self.len -= 1;
+                self.index += 1;
                 let de = Deserializer::from(value);
                 match seed.deserialize(de) {
                     Ok(value) => Ok(Some(value)),
-                    Err(err) => Err(err),
+                    Err(err) => Err(err.at_index(self.index - 1)),
                 }
             }
         }
@@ -258,8 +283,8 @@ impl<'de> de::SeqAccess<'de> for SeqDeserializer {
 }
 
 struct MapDeserializer {
-    iter: map::IntoIter<String, Value>,
-    value: Option<Value>,
+    iter: <Map<String, Value> as IntoIterator>::IntoIter,
+    entry: Option<(String, Value)>,
     len: usize,
 }
 impl<'de> de::MapAccess<'de> for MapDeserializer {
@@ -272,13 +297,10 @@ impl<'de> de::MapAccess<'de> for MapDeserializer {
         match self.iter.next() {
             Some((key, value)) => {
                 self.len -= 1;
-                self.value = Some(value);
-
-                let de = Deserializer::from(Value::String(key));
-                match seed.deserialize(de) {
-                    Ok(val) => Ok(Some(val)),
-                    Err(e) => Err(e),
-                }
+                let de: StrDeserializer<Error> = key.as_str().into_deserializer();
+                let key_value = seed.deserialize(de);
+                self.entry = Some((key, value));
+                key_value.map(Some)
             }
             None => Ok(None),
         }
@@ -288,9 +310,9 @@ impl<'de> de::MapAccess<'de> for MapDeserializer {
     where
         V: de::DeserializeSeed<'de>,
     {
-        let value = self.value.take().ok_or(Error::Eof)?;
+        let (key, value) = self.entry.take().ok_or(Error::Eof)?;
         let de = Deserializer::from(value);
-        seed.deserialize(de)
+        seed.deserialize(de).map_err(|e| e.at_key(&key))
     }
 
     fn size_hint(&self) -> Option<usize> {
@@ -388,12 +410,13 @@ impl<'de> de::VariantAccess<'de> for VariantDeserializer {
             Value::Array(fields) => {
                 let des = SeqDeserializer {
                     len: fields.len(),
+                    index: 0,
                     iter: fields.into_iter(),
                 };
                 de::Deserializer::deserialize_any(des, visitor)
             }
             other => Err(Error::Expected(
-                other.error_description().into(),
+                other.kind().into(),
                 "expected a tuple".into(),
             )),
         }
@@ -408,14 +431,144 @@ impl<'de> de::VariantAccess<'de> for VariantDeserializer {
                 let des = MapDeserializer {
                     len: fields.len(),
                     iter: fields.into_iter(),
-                    value: None,
+                    entry: None,
                 };
                 de::Deserializer::deserialize_any(des, visitor)
             }
             ref other => Err(Error::Expected(
-                other.error_description().into(),
+                other.kind().into(),
                 "expected a struct".into(),
             )),
         }
     }
 }
+
+/// An iterator that deserializes a byte slice containing back-to-back encoded values into
+/// instances of type `T`.
+///
+/// Each item is yielded together with the byte range it occupied in the slice. Iteration stops
+/// after the first malformed value, since the boundary of the next one is unknown at that point.
+pub struct StreamDeserializer<'a, T> {
+    slice: &'a [u8],
+    offset: usize,
+    limits: DecodeLimits,
+    failed: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+impl<'a, T> StreamDeserializer<'a, T> {
+    /// Constructs a new [StreamDeserializer] over `slice`, enforcing the default [DecodeLimits].
+    pub fn new(slice: &'a [u8]) -> Self {
+        Self::with_limits(slice, DecodeLimits::default())
+    }
+
+    /// Constructs a new [StreamDeserializer] over `slice`, enforcing `limits` on every value, so
+    /// that [DecodeLimits::max_bytes] bounds the bytes of each value rather than of the slice.
+    pub fn with_limits(slice: &'a [u8], limits: DecodeLimits) -> Self {
+        Self {
+            slice,
+            offset: 0,
+            limits,
+            failed: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gets the number of bytes consumed so far, i.e. the offset of the next value.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+}
+impl<T: DeserializeOwned> Iterator for StreamDeserializer<'_, T> {
+    type Item = Result<(T, Range<usize>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.offset >= self.slice.len() {
+            return None;
+        }
+
+        let start = self.offset;
+        // The value is decoded from a window of at most `max_bytes` bytes, so that the limit
+        // applies to the bytes of each value rather than to the rest of the slice.
+        let rest = &self.slice[start..];
+        let window = &rest[..rest.len().min(self.limits.max_bytes)];
+        let limits = DecodeLimits {
+            max_bytes: usize::MAX,
+            ..self.limits
+        };
+        match wire::decode_from_slice(window, &limits) {
+            Ok((value, len)) => {
+                self.offset += len;
+                Some(crate::from_value(value).map(|val| (val, start..self.offset)))
+            }
+            Err(err) => {
+                self.failed = true;
+                if err.is_eof() && window.len() < rest.len() {
+                    let err = Error::LimitExceeded(Limit::Bytes, window.len() + 1);
+                    return Some(Err(err.at_offset(start)));
+                }
+                // Offsets are made relative to the whole slice rather than to the value.
+                Some(Err(match err {
+                    Error::Offset(offset, e) => Error::Offset(start + offset, e),
+                    e => e,
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::{string::ToString, vec::Vec};
+
+    fn stream(items: &[&str]) -> Vec<u8> {
+        items
+            .iter()
+            .flat_map(|item| crate::to_vec(item).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn stream_round_trip() {
+        let bytes = stream(&["a", "bc", "def"]);
+        let items: Vec<(String, Range<usize>)> = StreamDeserializer::new(&bytes)
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(items[0], ("a".to_string(), 0..3));
+        assert_eq!(items[1], ("bc".to_string(), 3..7));
+        assert_eq!(items[2], ("def".to_string(), 7..12));
+    }
+
+    #[test]
+    fn stream_limits_each_value() {
+        let item = "0123456789".repeat(4);
+        let items = [item.as_str(); 5];
+        let bytes = stream(&items);
+        assert!(bytes.len() > 200);
+
+        let limits = DecodeLimits::new().with_max_bytes(64);
+        let decoded: Vec<String> = StreamDeserializer::with_limits(&bytes, limits)
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(decoded, items);
+
+        let limits = DecodeLimits::new().with_max_bytes(16);
+        let mut decoder = StreamDeserializer::<String>::with_limits(&bytes, limits);
+        let error = decoder.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+        assert_eq!(error.offset(), Some(0));
+        assert!(decoder.next().is_none());
+    }
+
+    #[test]
+    fn stream_stops_after_malformed_value() {
+        let mut bytes = stream(&["a"]);
+        bytes.push(0xee);
+        let mut decoder = StreamDeserializer::<String>::new(&bytes);
+        assert_eq!(decoder.next().unwrap().unwrap().0, "a");
+        let error = decoder.next().unwrap().unwrap_err();
+        assert_eq!(error.offset(), Some(4));
+        assert!(decoder.next().is_none());
+    }
+}
@@ -0,0 +1,69 @@
+//! `rust_decimal` integration, storing [Decimal]s losslessly as their decimal strings.
+//!
+//! [Decimal]s have up to 28 significant digits, more than either integers or floats hold, so they
+//! convert into strings like `"-12.3400"`, keeping their scale. With `rust_decimal`'s `serde`
+//! feature, its default implementations serialize them the same way. Floats are never coerced into
+//! [Decimal]s, as they are not exact.
+
+use crate::{
+    error::{Error, Result},
+    value::Value,
+};
+use rust_decimal::Decimal;
+
+impl From<Decimal> for Value {
+    fn from(decimal: Decimal) -> Self {
+        Value::String(decimal.to_string())
+    }
+}
+impl TryFrom<Value> for Decimal {
+    type Error = Error;
+
+    /// Converts a decimal string or an integer into a [Decimal].
+    fn try_from(value: Value) -> Result<Self> {
+        value
+            .as_decimal()
+            .ok_or_else(|| Error::Expected("decimal string or integer".into(), value.kind().into()))
+    }
+}
+
+impl Value {
+    /// Gets a [Decimal] from a decimal string or an integer.
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Value::Integer(i) => Some((*i).into()),
+            Value::String(s) => Decimal::from_str_exact(s).ok(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn round_trip() {
+        let decimal = Decimal::from_str_exact("-12.3400").unwrap();
+        let value = Value::from(decimal);
+        assert!(matches!(&value, Value::String(s) if s == "-12.3400"));
+        let back = Decimal::try_from(value).unwrap();
+        assert_eq!(back, decimal);
+        assert_eq!(back.scale(), 4);
+
+        let max = Value::from(Decimal::MAX);
+        assert_eq!(max.as_decimal(), Some(Decimal::MAX));
+        assert_eq!(Value::Integer(-7).as_decimal(), Some(Decimal::from(-7)));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Value::String("1.2.3".into()).as_decimal(), None);
+        assert_eq!(Value::String("1e400".into()).as_decimal(), None);
+        assert_eq!(Value::Float(0.5).as_decimal(), None);
+
+        let error = Decimal::try_from(Value::Float(0.5)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
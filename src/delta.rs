@@ -0,0 +1,359 @@
+//! Compact deltas between two [Value]s, for replicating changes rather than whole snapshots.
+//!
+//! [diff] describes how to turn one value into another as a [Delta], which only carries the parts
+//! that changed: unchanged elements and entries of arrays and objects are left out, and changed
+//! ones are described recursively. [apply] replays a [Delta] on the old value.
+//!
+//! Deltas have a binary encoding of their own, written by [Delta::to_vec] and read by
+//! [Delta::from_slice], in which replaced values are encoded like documents.
+
+use crate::{
+    error::{Error, Result},
+    limits::{DecodeLimits, Limit},
+    value::{Value, ValueKind},
+    wire::{self, SliceReader},
+};
+use alloc::{format, string::String, vec::Vec};
+use bincode::{
+    de::{Decode, Decoder},
+    enc::{Encode, Encoder},
+    error::{AllowedEnumVariants, DecodeError, EncodeError},
+};
+
+const UNCHANGED: u32 = 0;
+const REPLACE: u32 = 1;
+const ARRAY: u32 = 2;
+const OBJECT: u32 = 3;
+
+/// The changes turning one [Value] into another.
+#[derive(Debug, Clone)]
+pub enum Delta {
+    /// The value is unchanged.
+    Unchanged,
+
+    /// The value is replaced as a whole.
+    Replace(Value),
+
+    /// The array is resized to `len` elements, after which the element at each index is patched.
+    /// Elements past the old length are patched with [Delta::Replace], in ascending order.
+    Array {
+        len: usize,
+        patches: Vec<(usize, Delta)>,
+    },
+
+    /// The entries of the `removed` keys are removed from the object, after which the entry of
+    /// each key is patched. Entries of new keys are patched with [Delta::Replace].
+    Object {
+        removed: Vec<String>,
+        patches: Vec<(String, Delta)>,
+    },
+}
+impl Delta {
+    /// Returns `true` if this delta changes nothing.
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, Self::Unchanged)
+    }
+
+    /// Encodes the delta as a byte vector.
+    pub fn to_vec(&self) -> Vec<u8> {
+        bincode::encode_to_vec(self, bincode::config::standard())
+            .expect("encoding a delta into a vector never fails")
+    }
+
+    /// Decodes a delta from bytes, enforcing the default [DecodeLimits].
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        Self::from_slice_with_limits(bytes, &DecodeLimits::default())
+    }
+
+    /// Decodes a delta from bytes, enforcing `limits` on the delta as a whole, where each level
+    /// of patches counts as a level of nesting.
+    pub fn from_slice_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self> {
+        limits.check(Limit::Bytes, bytes.len())?;
+        let mut decoder =
+            bincode::de::DecoderImpl::new(SliceReader::new(bytes), bincode::config::standard());
+        decode_delta(&mut decoder, limits, 0)
+    }
+}
+impl Encode for Delta {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> core::result::Result<(), EncodeError> {
+        match self {
+            Self::Unchanged => UNCHANGED.encode(encoder),
+            Self::Replace(value) => {
+                REPLACE.encode(encoder)?;
+                value.encode(encoder)
+            }
+            Self::Array { len, patches } => {
+                ARRAY.encode(encoder)?;
+                (*len as u64).encode(encoder)?;
+                (patches.len() as u64).encode(encoder)?;
+                for (index, delta) in patches {
+                    (*index as u64).encode(encoder)?;
+                    delta.encode(encoder)?;
+                }
+                Ok(())
+            }
+            Self::Object { removed, patches } => {
+                OBJECT.encode(encoder)?;
+                removed.encode(encoder)?;
+                (patches.len() as u64).encode(encoder)?;
+                for (key, delta) in patches {
+                    key.encode(encoder)?;
+                    delta.encode(encoder)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Computes the [Delta] turning `old` into `new`.
+pub fn diff(old: &Value, new: &Value) -> Delta {
+    match (old, new) {
+        (Value::Array(old), Value::Array(new)) => {
+            let mut patches = Vec::new();
+            let mut shared = false;
+            for (index, value) in new.iter().enumerate() {
+                let delta = match old.get(index) {
+                    Some(old) => diff(old, value),
+                    None => Delta::Replace(value.clone()),
+                };
+                match delta {
+                    Delta::Unchanged => shared = true,
+                    Delta::Replace(_) => patches.push((index, delta)),
+                    _ => {
+                        shared = true;
+                        patches.push((index, delta));
+                    }
+                }
+            }
+            if patches.is_empty() && old.len() == new.len() {
+                Delta::Unchanged
+            } else if shared || new.is_empty() {
+                Delta::Array {
+                    len: new.len(),
+                    patches,
+                }
+            } else {
+                Delta::Replace(Value::Array(new.clone()))
+            }
+        }
+        (Value::Object(old), Value::Object(new)) => {
+            let mut removed: Vec<String> = old
+                .keys()
+                .filter(|key| !new.contains_key(*key))
+                .cloned()
+                .collect();
+            removed.sort_unstable();
+            let mut patches = Vec::new();
+            let mut shared = false;
+            for (key, value) in new {
+                let delta = match old.get(key) {
+                    Some(old) => diff(old, value),
+                    None => Delta::Replace(value.clone()),
+                };
+                match delta {
+                    Delta::Unchanged => shared = true,
+                    Delta::Replace(_) => patches.push((key.clone(), delta)),
+                    _ => {
+                        shared = true;
+                        patches.push((key.clone(), delta));
+                    }
+                }
+            }
+            patches.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            if patches.is_empty() && removed.is_empty() {
+                Delta::Unchanged
+            } else if shared || new.is_empty() {
+                Delta::Object { removed, patches }
+            } else {
+                Delta::Replace(Value::Object(new.clone()))
+            }
+        }
+        (old, new) if same(old, new) => Delta::Unchanged,
+        (_, new) => Delta::Replace(new.clone()),
+    }
+}
+
+/// Applies `delta` to `old`, failing if `delta` was not computed from a value shaped like it.
+pub fn apply(old: Value, delta: &Delta) -> Result<Value> {
+    match delta {
+        Delta::Unchanged => Ok(old),
+        Delta::Replace(value) => Ok(value.clone()),
+        Delta::Array { len, patches } => {
+            let Value::Array(mut array) = old else {
+                return Err(expected(ValueKind::Array, &old));
+            };
+            array.truncate(*len);
+            for (index, delta) in patches {
+                if let Some(element) = array.get_mut(*index) {
+                    *element = apply(core::mem::replace(element, Value::Null), delta)?;
+                } else if let (true, Delta::Replace(value)) = (*index == array.len(), delta) {
+                    array.push(value.clone());
+                } else {
+                    return Err(Error::Custom(format!(
+                        "delta patches index {} of an array of {} elements",
+                        index,
+                        array.len()
+                    )));
+                }
+            }
+            if array.len() != *len {
+                return Err(Error::Custom(format!(
+                    "delta resizes an array to {} elements but patches {}",
+                    len,
+                    array.len()
+                )));
+            }
+            Ok(Value::Array(array))
+        }
+        Delta::Object { removed, patches } => {
+            let Value::Object(mut object) = old else {
+                return Err(expected(ValueKind::Object, &old));
+            };
+            for key in removed {
+                object.remove(key);
+            }
+            for (key, delta) in patches {
+                if let Some(entry) = object.get_mut(key) {
+                    *entry = apply(core::mem::replace(entry, Value::Null), delta)?;
+                } else if let Delta::Replace(value) = delta {
+                    object.insert(key.clone(), value.clone());
+                } else {
+                    return Err(Error::Missing(key.clone()));
+                }
+            }
+            Ok(Value::Object(object))
+        }
+    }
+}
+
+/// Checks whether two values are identical, comparing floats by their bits.
+pub(crate) fn same(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Null, Value::Null) => true,
+        (Value::Boolean(a), Value::Boolean(b)) => a == b,
+        (Value::Blob(a), Value::Blob(b)) => a == b,
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+        }
+        (Value::Integer(a), Value::Integer(b)) => a == b,
+        (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(k, a)| b.get(k).is_some_and(|b| same(a, b)))
+        }
+        (Value::String(a), Value::String(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn expected(expected: ValueKind, found: &Value) -> Error {
+    Error::Expected(expected.into(), found.kind().into())
+}
+
+/// Decodes a [Delta] nested at `depth`, enforcing `limits`.
+fn decode_delta<D: Decoder>(decoder: &mut D, limits: &DecodeLimits, depth: usize) -> Result<Delta> {
+    match u32::decode(decoder)? {
+        UNCHANGED => Ok(Delta::Unchanged),
+        REPLACE => Ok(Delta::Replace(wire::decode_value(decoder, limits, depth)?)),
+        ARRAY => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = wire::decode_len(decoder)?;
+            let count = wire::decode_len(decoder)?;
+            limits.check(Limit::Elements, count)?;
+            let mut patches = Vec::with_capacity(count.min(wire::MAX_PREALLOCATED));
+            for _ in 0..count {
+                let index = wire::decode_len(decoder)?;
+                patches.push((index, decode_delta(decoder, limits, depth + 1)?));
+            }
+            Ok(Delta::Array { len, patches })
+        }
+        OBJECT => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let count = wire::decode_len(decoder)?;
+            limits.check(Limit::Elements, count)?;
+            let mut removed = Vec::with_capacity(count.min(wire::MAX_PREALLOCATED));
+            for _ in 0..count {
+                removed.push(wire::decode_string(decoder, limits)?);
+            }
+            let count = wire::decode_len(decoder)?;
+            limits.check(Limit::Elements, count)?;
+            let mut patches = Vec::with_capacity(count.min(wire::MAX_PREALLOCATED));
+            for _ in 0..count {
+                let key = wire::decode_string(decoder, limits)?;
+                patches.push((key, decode_delta(decoder, limits, depth + 1)?));
+            }
+            Ok(Delta::Object { removed, patches })
+        }
+        tag => Err(DecodeError::UnexpectedVariant {
+            type_name: "Delta",
+            allowed: &AllowedEnumVariants::Range {
+                min: UNCHANGED,
+                max: OBJECT,
+            },
+            found: tag,
+        }
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{borrow::ToOwned, collections::BTreeMap, vec};
+
+    type Document = BTreeMap<String, (Vec<u32>, Option<String>)>;
+
+    fn document(entries: &[(&str, &[u32], Option<&str>)]) -> Document {
+        entries
+            .iter()
+            .map(|(key, array, text)| {
+                ((*key).to_owned(), (array.to_vec(), text.map(str::to_owned)))
+            })
+            .collect()
+    }
+
+    fn round_trip(old: &Document, new: &Document) {
+        let (old_value, new_value) = (crate::to_value(old).unwrap(), crate::to_value(new).unwrap());
+        let delta = diff(&old_value, &new_value);
+        let delta = Delta::from_slice(&delta.to_vec()).unwrap();
+        let applied: Document = crate::from_value(apply(old_value, &delta).unwrap()).unwrap();
+        assert_eq!(&applied, new);
+    }
+
+    #[test]
+    fn apply_diff() {
+        let old = document(&[("a", &[1, 2, 3], None), ("b", &[], Some("x"))]);
+        round_trip(&old, &old);
+        round_trip(
+            &old,
+            &document(&[("a", &[1, 5], None), ("b", &[], Some("x"))]),
+        );
+        round_trip(&old, &document(&[("a", &[1, 2, 3, 4, 5], Some("y"))]));
+        round_trip(&old, &document(&[("c", &[9], None)]));
+        round_trip(&old, &Document::new());
+    }
+
+    #[test]
+    fn only_changes() {
+        let old = document(&[("a", &[1; 100], None), ("b", &[], None)]);
+        let new = document(&[("a", &[1; 100], None), ("b", &[], Some("x"))]);
+        let (old, new) = (
+            crate::to_value(&old).unwrap(),
+            crate::to_value(&new).unwrap(),
+        );
+        assert!(diff(&old, &old).is_unchanged());
+        assert!(diff(&old, &new).to_vec().len() < 20);
+    }
+
+    #[test]
+    fn mismatched_value() {
+        let delta = diff(
+            &crate::to_value(&vec![1, 2]).unwrap(),
+            &crate::to_value(&vec![1, 3]).unwrap(),
+        );
+        let error = apply(Value::Null, &delta).unwrap_err();
+        let (expected, found) = error.mismatch().unwrap();
+        assert_eq!(expected.kind(), Some(ValueKind::Array));
+        assert_eq!(found.kind(), Some(ValueKind::Null));
+    }
+}
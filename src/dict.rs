@@ -0,0 +1,381 @@
+//! An alternate wire profile which writes each object key once, shrinking documents whose
+//! objects repeat the same keys, such as arrays of records.
+//!
+//! A document in this profile starts with the byte [MARKER], which never starts a document in the
+//! standard profile, followed by the document encoded as usual except for object keys: each key is
+//! a varint `n`, where 0 is followed by a new key, which is added to the dictionary of the
+//! document, and any other `n` refers to the `n`th key added.
+//!
+//! Decoding this profile is opt-in: [from_slice_dict] and [crate::from_slice_many_dict] decode
+//! documents of either profile, while [crate::from_slice] and the other decoders reject documents
+//! starting with the marker, so that untrusted input cannot opt itself into reference expansion.
+//!
+//! With [DictOptions::with_dedupe_strings], string values are written like keys as well, sharing
+//! the dictionary, so that repeated enum-like strings are written once. Such documents start with
+//! [STRINGS_MARKER] instead.
+//!
+//! When decoding, strings repeated by reference count towards [DecodeLimits::max_expanded_bytes]
+//! and [DecodeLimits::max_bytes] once per reference, as each reference is expanded, so that a
+//! small document cannot expand into more memory than allowed.
+
+use crate::{
+    error::{Error, Result},
+    limits::{DecodeLimits, Limit},
+    value::{Map, Value},
+    wire,
+};
+use alloc::{format, string::String, vec::Vec};
+use bincode::{
+    de::{Decode, Decoder},
+    enc::{write::Writer, Encode, Encoder, EncoderImpl},
+    error::{DecodeError, EncodeError},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The first byte of documents in the key dictionary profile.
+pub const MARKER: u8 = 0xff;
+
+/// The first byte of documents in the key dictionary profile whose string values are
+/// deduplicated too.
+pub const STRINGS_MARKER: u8 = 0xfe;
+
+/// Options of the key dictionary profile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DictOptions {
+    /// Whether string values are written through the dictionary, like keys.
+    pub dedupe_strings: bool,
+}
+impl DictOptions {
+    /// Constructs a new [DictOptions] which only deduplicates keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether string values are written through the dictionary, like keys.
+    pub fn with_dedupe_strings(mut self, dedupe_strings: bool) -> Self {
+        self.dedupe_strings = dedupe_strings;
+        self
+    }
+}
+
+/// Serialize the given data structure as a byte vector in the key dictionary profile.
+pub fn to_vec_dict<T: Serialize>(val: &T) -> Result<Vec<u8>> {
+    to_vec_dict_with(val, &DictOptions::default())
+}
+
+/// Serialize the given data structure as a byte vector in the key dictionary profile, with
+/// `options`.
+pub fn to_vec_dict_with<T: Serialize>(val: &T, options: &DictOptions) -> Result<Vec<u8>> {
+    let value = crate::to_value(val)?;
+    let mut bytes = Vec::new();
+    Dict::new(options).encode_document(&value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Deserialize an instance of type `T` from bytes of Bincode JSON in the key dictionary profile
+/// or the standard one, enforcing the default [DecodeLimits].
+pub fn from_slice_dict<T: DeserializeOwned>(val: &[u8]) -> Result<T> {
+    from_slice_dict_with_limits(val, &DecodeLimits::default())
+}
+
+/// Deserialize an instance of type `T` from bytes of Bincode JSON in the key dictionary profile
+/// or the standard one, enforcing `limits`.
+pub fn from_slice_dict_with_limits<T: DeserializeOwned>(
+    val: &[u8],
+    limits: &DecodeLimits,
+) -> Result<T> {
+    crate::decode_instrumented(val.len(), || {
+        let (value, _) =
+            wire::decode_from_slice_with_dict(val, limits, &mut Dictionary::default())?;
+        crate::from_value(value)
+    })
+}
+
+/// Fails if `first`, the first byte of a document, is the marker of the key dictionary profile,
+/// which only the decoders of this profile accept.
+pub(crate) fn reject_profile(first: Option<u8>) -> Result<()> {
+    match first {
+        Some(MARKER | STRINGS_MARKER) => Err(Error::Unsupported(
+            "document of the key dictionary profile, which from_slice_dict decodes".into(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// A bincode [Writer] appending to a byte vector.
+struct VecWriter<'a>(&'a mut Vec<u8>);
+impl Writer for VecWriter<'_> {
+    fn write(&mut self, bytes: &[u8]) -> core::result::Result<(), EncodeError> {
+        self.0.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// The dictionary of a document being encoded, or of a batch of documents sharing it.
+pub(crate) struct Dict<'v> {
+    strings: Map<&'v str, u64>,
+    dedupe_strings: bool,
+}
+impl<'v> Dict<'v> {
+    /// Constructs a new, empty [Dict] encoding with `options`.
+    pub(crate) fn new(options: &DictOptions) -> Self {
+        Self {
+            strings: Map::with_capacity(0),
+            dedupe_strings: options.dedupe_strings,
+        }
+    }
+
+    /// Appends `value` to `bytes` as a document in the key dictionary profile, referring to the
+    /// strings already in the dictionary.
+    pub(crate) fn encode_document(&mut self, value: &'v Value, bytes: &mut Vec<u8>) -> Result<()> {
+        bytes.push(match self.dedupe_strings {
+            true => STRINGS_MARKER,
+            false => MARKER,
+        });
+        let mut encoder = EncoderImpl::new(VecWriter(bytes), bincode::config::standard());
+        self.encode_value(value, &mut encoder)?;
+        Ok(())
+    }
+
+    /// Encodes `value`, writing strings missing from the dictionary in full and adding them.
+    fn encode_value<E: Encoder>(
+        &mut self,
+        value: &'v Value,
+        encoder: &mut E,
+    ) -> core::result::Result<(), EncodeError> {
+        match value {
+            Value::Array(a) => {
+                wire::ARRAY.encode(encoder)?;
+                (a.len() as u64).encode(encoder)?;
+                a.iter().try_for_each(|v| self.encode_value(v, encoder))
+            }
+            Value::Object(o) => {
+                wire::OBJECT.encode(encoder)?;
+                (o.len() as u64).encode(encoder)?;
+                for (k, v) in o {
+                    self.encode_str(k, encoder)?;
+                    self.encode_value(v, encoder)?;
+                }
+                Ok(())
+            }
+            Value::String(s) if self.dedupe_strings => {
+                wire::STRING.encode(encoder)?;
+                self.encode_str(s, encoder)
+            }
+            value => value.encode(encoder),
+        }
+    }
+
+    /// Encodes a reference to `s`, or `s` in full if it is not in the dictionary yet.
+    fn encode_str<E: Encoder>(
+        &mut self,
+        s: &'v str,
+        encoder: &mut E,
+    ) -> core::result::Result<(), EncodeError> {
+        match self.strings.get(s) {
+            Some(n) => n.encode(encoder),
+            None => {
+                0u64.encode(encoder)?;
+                s.encode(encoder)?;
+                let n = self.strings.len() as u64 + 1;
+                self.strings.insert(s, n);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The dictionary of a document being decoded, or of a batch of documents sharing it.
+#[derive(Default)]
+pub(crate) struct Dictionary {
+    strings: Vec<String>,
+    /// The size of the input of the current document.
+    input_len: usize,
+    /// The length of every string of the current document expanded from a reference so far.
+    expanded: usize,
+    dedupe_strings: bool,
+}
+impl Dictionary {
+    /// Decodes the rest of a document in the key dictionary profile, which started with
+    /// `marker`, enforcing `limits`, where `input_len` is the size of the whole input. Strings
+    /// added by previous documents stay in the dictionary.
+    pub(crate) fn decode_document<D: Decoder>(
+        &mut self,
+        decoder: &mut D,
+        limits: &DecodeLimits,
+        marker: u8,
+        input_len: usize,
+    ) -> Result<Value> {
+        self.input_len = input_len;
+        self.expanded = 0;
+        self.dedupe_strings = marker == STRINGS_MARKER;
+        self.decode_value(decoder, limits, 0)
+    }
+
+    /// Decodes a [Value] nested at `depth`, enforcing `limits`.
+    fn decode_value<D: Decoder>(
+        &mut self,
+        decoder: &mut D,
+        limits: &DecodeLimits,
+        depth: usize,
+    ) -> Result<Value> {
+        match wire::decode_tag(decoder)? {
+            wire::ARRAY => {
+                limits.check(Limit::Depth, depth + 1)?;
+                let len = wire::decode_len(decoder)?;
+                limits.check(Limit::Elements, len)?;
+                let mut array = Vec::with_capacity(len.min(wire::MAX_PREALLOCATED));
+                for _ in 0..len {
+                    array.push(self.decode_value(decoder, limits, depth + 1)?);
+                }
+                Ok(Value::Array(array))
+            }
+            wire::OBJECT => {
+                limits.check(Limit::Depth, depth + 1)?;
+                let len = wire::decode_len(decoder)?;
+                limits.check(Limit::Elements, len)?;
+                let mut object = Map::with_capacity(len.min(wire::MAX_PREALLOCATED));
+                for _ in 0..len {
+                    let key = self.decode_str(decoder, limits)?;
+                    let value = self.decode_value(decoder, limits, depth + 1)?;
+                    object.insert(key, value);
+                }
+                Ok(Value::Object(object))
+            }
+            wire::NULL => Ok(Value::Null),
+            wire::BOOLEAN => Ok(Value::Boolean(bool::decode(decoder)?)),
+            wire::BLOB => {
+                let len = wire::decode_len(decoder)?;
+                limits.check(Limit::BlobLength, len)?;
+                Ok(Value::Blob(wire::read_bytes(decoder, len)?))
+            }
+            wire::INTEGER => Ok(Value::Integer(i64::decode(decoder)?)),
+            wire::FLOAT => Ok(Value::Float(f64::decode(decoder)?)),
+            _ if self.dedupe_strings => Ok(Value::String(self.decode_str(decoder, limits)?)),
+            _ => Ok(Value::String(wire::decode_string(decoder, limits)?)),
+        }
+    }
+
+    /// Decodes a string written by [Dict::encode_str], i.e. a key or a deduplicated string value.
+    fn decode_str<D: Decoder>(&mut self, decoder: &mut D, limits: &DecodeLimits) -> Result<String> {
+        match u64::decode(decoder)? {
+            0 => {
+                let s = wire::decode_string(decoder, limits)?;
+                self.strings.push(s.clone());
+                Ok(s)
+            }
+            n => {
+                let s = usize::try_from(n - 1)
+                    .ok()
+                    .and_then(|i| self.strings.get(i))
+                    .ok_or_else(|| {
+                        DecodeError::OtherString(format!("unknown string reference {}", n))
+                    })?;
+                self.expanded = self.expanded.saturating_add(s.len());
+                limits.check(Limit::ExpandedBytes, self.expanded)?;
+                limits.check(Limit::Bytes, self.input_len.saturating_add(self.expanded))?;
+                Ok(s.clone())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, value::Value};
+    use alloc::{borrow::ToOwned, collections::BTreeMap, string::ToString, vec};
+
+    fn records() -> Vec<BTreeMap<String, String>> {
+        (0..50)
+            .map(|i| {
+                let mut record = BTreeMap::new();
+                record.insert("identifier".to_owned(), format!("id-{}", i));
+                record.insert("status".to_owned(), "active".to_owned());
+                record
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        for options in [
+            DictOptions::new(),
+            DictOptions::new().with_dedupe_strings(true),
+        ] {
+            let bytes = to_vec_dict_with(&records(), &options).unwrap();
+            assert!(bytes.len() < crate::to_vec(&records()).unwrap().len());
+            let decoded: Vec<BTreeMap<String, String>> = from_slice_dict(&bytes).unwrap();
+            assert_eq!(decoded, records());
+        }
+    }
+
+    #[test]
+    fn writes_keys_once() {
+        let bytes = to_vec_dict(&records()).unwrap();
+        let occurrences =
+            |needle: &[u8]| bytes.windows(needle.len()).filter(|w| *w == needle).count();
+        assert_eq!(occurrences(b"identifier"), 1);
+        assert_eq!(occurrences(b"active"), 50);
+
+        let options = DictOptions::new().with_dedupe_strings(true);
+        let bytes = to_vec_dict_with(&records(), &options).unwrap();
+        assert_eq!(bytes[0], STRINGS_MARKER);
+        assert_eq!(bytes.windows(6).filter(|w| *w == b"active").count(), 1);
+    }
+
+    #[test]
+    fn unknown_reference() {
+        // An object of one entry whose key refers to the first string of an empty dictionary.
+        let bytes = [MARKER, wire::OBJECT as u8, 1, 1, wire::NULL as u8];
+        let error = from_slice_dict::<Value>(&bytes).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Decode);
+    }
+
+    #[test]
+    fn opt_in() {
+        let bytes = to_vec_dict(&records()).unwrap();
+        let error = crate::from_slice::<Value>(&bytes).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+
+        let bytes = crate::to_vec(&records()).unwrap();
+        let decoded: Vec<BTreeMap<String, String>> = from_slice_dict(&bytes).unwrap();
+        assert_eq!(decoded, records());
+    }
+
+    #[test]
+    fn references_count_towards_max_bytes() {
+        let key = "k".repeat(1000);
+        let array: Vec<BTreeMap<&str, ()>> = vec![[(key.as_str(), ())].into(); 100];
+        let bytes = to_vec_dict(&array).unwrap();
+        assert!(bytes.len() < 2000);
+
+        let limits = DecodeLimits::new().with_max_bytes(50_000);
+        let error = from_slice_dict_with_limits::<Value>(&bytes, &limits).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+        let limits = DecodeLimits::new().with_max_bytes(200_000);
+        from_slice_dict_with_limits::<Value>(&bytes, &limits).unwrap();
+
+        let limits = DecodeLimits::new().with_max_expanded_bytes(50_000);
+        let error = from_slice_dict_with_limits::<Value>(&bytes, &limits).unwrap_err();
+        assert!(error.to_string().starts_with("expanded size"), "{}", error);
+    }
+
+    #[test]
+    fn expansion_is_capped_by_default() {
+        // An array of 40000 objects, each with the same key of 2000 bytes: about 160 KB of input
+        // expanding to 80 MB of keys.
+        let (len, key_len) = (40_000u16, 2000u16);
+        let mut bytes = vec![MARKER, wire::ARRAY as u8, 251];
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend_from_slice(&[wire::OBJECT as u8, 1, 0, 251]);
+        bytes.extend_from_slice(&key_len.to_le_bytes());
+        bytes.extend(core::iter::repeat_n(b'k', key_len.into()));
+        bytes.push(wire::NULL as u8);
+        for _ in 1..len {
+            bytes.extend_from_slice(&[wire::OBJECT as u8, 1, 1, wire::NULL as u8]);
+        }
+        let error = from_slice_dict::<Value>(&bytes).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+    }
+}
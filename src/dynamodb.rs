@@ -0,0 +1,153 @@
+//! Conversions between `bincode-json` values and DynamoDB's `AttributeValue`s and items.
+//!
+//! Numbers are written as their decimal text, and read back as integers if they fit an `i64`, or
+//! as floats otherwise; non-finite floats have no DynamoDB number and are unsupported. String,
+//! number and binary sets are read as arrays, and arrays are always written as lists.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value, ValueKind},
+};
+use aws_sdk_dynamodb::{primitives::Blob, types::AttributeValue};
+use std::collections::HashMap;
+
+impl TryFrom<Value> for AttributeValue {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self> {
+        Ok(match value {
+            Value::Null => AttributeValue::Null(true),
+            Value::Boolean(b) => AttributeValue::Bool(b),
+            Value::Blob(b) => AttributeValue::B(Blob::new(b)),
+            Value::Array(a) => {
+                AttributeValue::L(a.into_iter().map(Value::try_into).collect::<Result<_>>()?)
+            }
+            Value::Integer(i) => AttributeValue::N(i.to_string()),
+            Value::Float(f) if !f.is_finite() => {
+                return Err(Error::Unsupported(format!("float {} in DynamoDB", f)))
+            }
+            Value::Float(f) => AttributeValue::N(f.to_string()),
+            Value::Object(o) => AttributeValue::M(Value::Object(o).try_into()?),
+            Value::String(s) => AttributeValue::S(s),
+        })
+    }
+}
+impl TryFrom<Value> for HashMap<String, AttributeValue> {
+    type Error = Error;
+
+    /// Converts an object into a DynamoDB item, failing if the value is not an object.
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(o) => o.into_iter().map(|(k, v)| Ok((k, v.try_into()?))).collect(),
+            v => Err(Error::Expected(ValueKind::Object.into(), v.kind().into())),
+        }
+    }
+}
+impl TryFrom<AttributeValue> for Value {
+    type Error = Error;
+
+    fn try_from(value: AttributeValue) -> Result<Self> {
+        Ok(match value {
+            AttributeValue::Null(_) => Value::Null,
+            AttributeValue::Bool(b) => Value::Boolean(b),
+            AttributeValue::B(b) => Value::Blob(b.into_inner()),
+            AttributeValue::N(n) => number(&n)?,
+            AttributeValue::S(s) => Value::String(s),
+            AttributeValue::L(l) => {
+                Value::Array(l.into_iter().map(Value::try_from).collect::<Result<_>>()?)
+            }
+            AttributeValue::M(m) => m.try_into()?,
+            AttributeValue::Ss(ss) => Value::Array(ss.into_iter().map(Value::String).collect()),
+            AttributeValue::Ns(ns) => {
+                Value::Array(ns.iter().map(|n| number(n)).collect::<Result<_>>()?)
+            }
+            AttributeValue::Bs(bs) => Value::Array(
+                bs.into_iter()
+                    .map(|b| Value::Blob(b.into_inner()))
+                    .collect(),
+            ),
+            _ => {
+                return Err(Error::Unsupported(
+                    "unknown DynamoDB attribute value".into(),
+                ))
+            }
+        })
+    }
+}
+impl TryFrom<HashMap<String, AttributeValue>> for Value {
+    type Error = Error;
+
+    fn try_from(value: HashMap<String, AttributeValue>) -> Result<Self> {
+        let mut map = Map::with_capacity(value.len());
+        for (k, v) in value {
+            map.insert(k, v.try_into()?);
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+/// Parses a DynamoDB number.
+fn number(n: &str) -> Result<Value> {
+    if let Ok(i) = n.parse() {
+        Ok(Value::Integer(i))
+    } else if let Ok(f) = n.parse() {
+        Ok(Value::Float(f))
+    } else {
+        Err(Error::Expected(
+            "DynamoDB number".into(),
+            format!("{:?}", n).into(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::document;
+
+    #[test]
+    fn round_trip() {
+        let item = HashMap::<String, AttributeValue>::try_from(document()).unwrap();
+        assert_eq!(item["float"], AttributeValue::N("1.5".into()));
+        assert_eq!(
+            item["array"],
+            AttributeValue::L(vec![AttributeValue::N("-42".into())])
+        );
+
+        let value = Value::try_from(item).unwrap();
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+    }
+
+    #[test]
+    fn sets() {
+        let set = AttributeValue::Ns(vec!["1".into(), "2.5".into()]);
+        let Value::Array(a) = Value::try_from(set).unwrap() else {
+            panic!("expected an array");
+        };
+        assert!(matches!(a[0], Value::Integer(1)));
+        assert!(matches!(a[1], Value::Float(f) if f == 2.5));
+
+        let set = AttributeValue::Ss(vec!["a".into()]);
+        assert!(matches!(Value::try_from(set).unwrap(), Value::Array(a) if a.len() == 1));
+        let set = AttributeValue::Bs(vec![Blob::new(vec![1])]);
+        assert!(
+            matches!(Value::try_from(set).unwrap(), Value::Array(a) if matches!(&a[0], Value::Blob(b) if b == &[1]))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for f in [f64::NAN, f64::INFINITY] {
+            let error = AttributeValue::try_from(Value::Float(f)).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Unsupported);
+        }
+        let error = HashMap::<String, AttributeValue>::try_from(Value::Null).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = Value::try_from(AttributeValue::N("one".into())).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,222 @@
+//! Self-describing envelopes around encoded `bincode-json` documents.
+//!
+//! An enveloped document has the following layout:
+//!
+//! | Field    | Size     | Description                                                   |
+//! |----------|----------|---------------------------------------------------------------|
+//! | magic    | 4 bytes  | Always [MAGIC].                                               |
+//! | version  | 1 byte   | The envelope format version, currently [VERSION].             |
+//! | flags    | 1 byte   | Bits 0-1: [Compression] id. Bits 2-3: [Checksum] id, or 0.    |
+//! | body     | variable | The encoded document, compressed with the flagged codec.      |
+//! | checksum | variable | The flagged checksum of all preceding bytes, if any.          |
+//!
+//! Reserved flag bits must be zero; envelopes using them are rejected as unsupported.
+
+use crate::{
+    checksum::Checksum,
+    compress::{self, Compression},
+    error::{Error, Result},
+    limits::DecodeLimits,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Magic bytes which start every envelope.
+pub const MAGIC: [u8; 4] = *b"BJSN";
+
+/// The envelope format version written by this crate.
+pub const VERSION: u8 = 1;
+
+const HEADER_LEN: usize = MAGIC.len() + 2;
+const COMPRESSION_MASK: u8 = 0b0011;
+const CHECKSUM_SHIFT: u8 = 2;
+const RESERVED_MASK: u8 = 0b1111_0000;
+
+/// Options of an envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Envelope {
+    /// The codec the body is compressed with.
+    pub compression: Compression,
+
+    /// The algorithm the envelope is checksummed with, if any.
+    pub checksum: Option<Checksum>,
+}
+impl Default for Envelope {
+    fn default() -> Self {
+        Self {
+            compression: Compression::None,
+            checksum: None,
+        }
+    }
+}
+impl Envelope {
+    /// Constructs a new [Envelope] with neither compression nor checksum.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the codec the body is compressed with.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the algorithm the envelope is checksummed with.
+    pub fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = Some(checksum);
+        self
+    }
+
+    fn flags(&self) -> u8 {
+        let checksum = self.checksum.map_or(0, Checksum::id);
+        self.compression.id() | checksum << CHECKSUM_SHIFT
+    }
+
+    fn from_flags(flags: u8) -> Result<Self> {
+        if flags & RESERVED_MASK != 0 {
+            return Err(Error::Unsupported(format!("envelope flags {:#x}", flags)));
+        }
+        let checksum = match flags >> CHECKSUM_SHIFT {
+            0 => None,
+            id => Some(Checksum::from_id(id)?),
+        };
+        Ok(Self {
+            compression: Compression::from_id(flags & COMPRESSION_MASK)?,
+            checksum,
+        })
+    }
+}
+
+/// Serialize the given data structure as a byte vector wrapped in an envelope.
+pub fn to_vec_enveloped<T: Serialize>(val: &T, envelope: Envelope) -> Result<Vec<u8>> {
+    let payload = crate::to_vec(val)?;
+    let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
+    buf.extend_from_slice(&MAGIC);
+    buf.push(VERSION);
+    buf.push(envelope.flags());
+    match envelope.compression {
+        Compression::None => buf.extend_from_slice(&payload),
+        codec => buf.extend_from_slice(&compress::compress(codec, &payload)?),
+    }
+    if let Some(checksum) = envelope.checksum {
+        checksum.append(&mut buf)?;
+    }
+    Ok(buf)
+}
+
+/// Deserialize an instance of type `T` from bytes of Bincode JSON wrapped in an envelope,
+/// enforcing the default [DecodeLimits].
+pub fn from_slice_enveloped<T: DeserializeOwned>(val: &[u8]) -> Result<T> {
+    from_slice_enveloped_with_limits(val, &DecodeLimits::default())
+}
+
+/// Deserialize an instance of type `T` from bytes of Bincode JSON wrapped in an envelope,
+/// enforcing `limits` on the decompressed document.
+pub fn from_slice_enveloped_with_limits<T: DeserializeOwned>(
+    val: &[u8],
+    limits: &DecodeLimits,
+) -> Result<T> {
+    let (envelope, body) = open(val)?;
+    match envelope.compression {
+        Compression::None => crate::from_slice_with_limits(body, limits),
+        codec => crate::from_slice_with_limits(&compress::decompress(codec, body, limits)?, limits),
+    }
+}
+
+/// Parses and verifies the envelope of `bytes`, returning its options and its body.
+pub fn open(bytes: &[u8]) -> Result<(Envelope, &[u8])> {
+    if bytes.len() < HEADER_LEN || bytes[..MAGIC.len()] != MAGIC {
+        return Err(Error::Expected(
+            "a bincode-json envelope".into(),
+            "foreign data".into(),
+        ));
+    }
+    let version = bytes[MAGIC.len()];
+    if version != VERSION {
+        return Err(Error::Unsupported(format!("envelope version {}", version)));
+    }
+    let envelope = Envelope::from_flags(bytes[MAGIC.len() + 1])?;
+    let bytes = match envelope.checksum {
+        Some(checksum) => checksum.verify(bytes)?,
+        None => bytes,
+    };
+    let body = bytes.get(HEADER_LEN..).ok_or(Error::Eof)?;
+    Ok((envelope, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn envelopes() -> Vec<Envelope> {
+        let codecs = [Compression::None, Compression::Zstd, Compression::Deflate];
+        let checksums = [None, Some(Checksum::Crc32c), Some(Checksum::Xxh64)];
+        let mut envelopes = Vec::new();
+        for compression in codecs.into_iter().filter(|codec| codec.is_available()) {
+            for checksum in checksums {
+                if checksum.is_none_or(|checksum| checksum.compute(&[]).is_ok()) {
+                    envelopes.push(Envelope {
+                        compression,
+                        checksum,
+                    });
+                }
+            }
+        }
+        envelopes
+    }
+
+    #[test]
+    fn round_trip() {
+        let val = vec![String::from("repeated"); 20];
+        for envelope in envelopes() {
+            let bytes = to_vec_enveloped(&val, envelope).unwrap();
+            assert_eq!(bytes[..MAGIC.len()], MAGIC);
+            assert_eq!(bytes[MAGIC.len()], VERSION);
+            assert_eq!(open(&bytes).unwrap().0, envelope);
+            let decoded: Vec<String> = from_slice_enveloped(&bytes).unwrap();
+            assert_eq!(decoded, val);
+        }
+    }
+
+    #[test]
+    fn rejects_foreign_data() {
+        let bytes = to_vec_enveloped(&1u8, Envelope::new()).unwrap();
+
+        let error = from_slice_enveloped::<u8>(&crate::to_vec(&1u8).unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = from_slice_enveloped::<u8>(&bytes[..HEADER_LEN - 1]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+        let mut versioned = bytes.clone();
+        versioned[MAGIC.len()] = VERSION + 1;
+        let error = from_slice_enveloped::<u8>(&versioned).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+
+        let mut reserved = bytes.clone();
+        reserved[MAGIC.len() + 1] |= 0b1000_0000;
+        let error = from_slice_enveloped::<u8>(&reserved).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn corruption() {
+        for envelope in envelopes().into_iter().filter(|e| e.checksum.is_some()) {
+            let mut bytes = to_vec_enveloped(&"value", envelope).unwrap();
+            let last = bytes.len() - 1;
+            bytes[last] ^= 1;
+            let error = from_slice_enveloped::<String>(&bytes).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Integrity);
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let val = "x".repeat(100);
+        let limits = DecodeLimits::new().with_max_bytes(50);
+        for envelope in envelopes() {
+            let bytes = to_vec_enveloped(&val, envelope).unwrap();
+            let error = from_slice_enveloped_with_limits::<String>(&bytes, &limits).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+        }
+    }
+}
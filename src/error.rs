@@ -1,50 +1,569 @@
 //! When serializing or deserializing `bincode-json` goes wrong.
 
+use crate::{limits::Limit, value::ValueKind};
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+};
+use core::fmt::{self, Display};
 use serde::de::{Expected, Unexpected};
-use std::fmt::{self, Display};
 
 /// This type represents all possible errors that can occur when serializing or
 /// deserializing `bincode-json` data.
+///
+/// New variants may be added in minor releases, so matching should fall back to [Error::kind] or
+/// the predicates such as [Error::is_eof].
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     Bincode(BincodeError),
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
     Custom(String),
-    Expected(String, String),
+    Expected(Description, Description),
     Duplicated(String),
     Missing(String),
-    Unknown(String),
+    Unknown(String, &'static [&'static str], Option<&'static str>),
+    FrameTooLarge(usize),
+    Unsupported(String),
+    ChecksumMismatch(u64, u64),
+    InvalidSignature,
+    LimitExceeded(Limit, usize),
     Eof,
+    At(String, Box<Error>),
+    Offset(usize, Box<Error>),
+    #[cfg(feature = "backtrace")]
+    Traced(Box<Error>, Box<std::backtrace::Backtrace>),
+    Remote(Box<ErrorRepr>),
 }
 impl Display for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Bincode(e) => write!(formatter, "bincode error: {}", e),
+            #[cfg(feature = "std")]
+            Self::Io(e) => write!(formatter, "io error: {}", e),
             Self::Custom(s) => write!(formatter, "custom error: {}", s),
             Self::Expected(e, f) => write!(formatter, "expected {}, found {}", e, f),
             Self::Duplicated(x) => write!(formatter, "field {} was duplicated", x),
             Self::Missing(x) => write!(formatter, "field {} was missing", x),
-            Self::Unknown(x) => write!(formatter, "field or variant {} was unknown", x),
+            Self::Unknown(x, expected, ty) => {
+                write!(formatter, "field or variant {} was unknown", x)?;
+                if let Some(ty) = ty {
+                    write!(formatter, " in {}", ty)?;
+                }
+                match expected {
+                    [] => write!(formatter, ", expected none"),
+                    [first, rest @ ..] => {
+                        write!(formatter, ", expected one of `{}`", first)?;
+                        rest.iter().try_for_each(|x| write!(formatter, ", `{}`", x))
+                    }
+                }
+            }
+            Self::FrameTooLarge(x) => write!(formatter, "frame of {} bytes is too large", x),
+            Self::Unsupported(x) => write!(formatter, "unsupported {}", x),
+            Self::ChecksumMismatch(e, f) => write!(
+                formatter,
+                "checksum mismatch: expected {:#x}, found {:#x}",
+                e, f
+            ),
+            Self::InvalidSignature => write!(formatter, "invalid signature"),
+            Self::LimitExceeded(l, x) => write!(formatter, "{} of {} exceeds the limit", l, x),
             Self::Eof => write!(formatter, "unexpected eof"),
+            Self::At(path, e) => match e.untraced() {
+                Self::Expected(e, f) => {
+                    write!(formatter, "expected {} at \"{}\", found {}", e, path, f)
+                }
+                e => write!(formatter, "{} at \"{}\"", e, path),
+            },
+            Self::Offset(offset, e) => write!(formatter, "{} at byte {}", e, offset),
+            #[cfg(feature = "backtrace")]
+            Self::Traced(e, _) => e.fmt(formatter),
+            Self::Remote(repr) => repr.fmt(formatter),
         }
     }
 }
+impl Error {
+    /// Gets the category of the error.
+    pub fn kind(&self) -> ErrorKind {
+        use bincode::error::DecodeError;
+
+        match self {
+            Self::Bincode(BincodeError::Encode(_)) => ErrorKind::Encode,
+            Self::Bincode(BincodeError::Decode(e)) => match e {
+                DecodeError::UnexpectedEnd { .. } => ErrorKind::Eof,
+                DecodeError::LimitExceeded => ErrorKind::LimitExceeded,
+                #[cfg(feature = "std")]
+                DecodeError::Io { inner, .. }
+                    if inner.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    ErrorKind::Eof
+                }
+                #[cfg(feature = "std")]
+                DecodeError::Io { .. } => ErrorKind::Io,
+                _ => ErrorKind::Decode,
+            },
+            #[cfg(feature = "std")]
+            Self::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => ErrorKind::Eof,
+            #[cfg(feature = "std")]
+            Self::Io(_) => ErrorKind::Io,
+            Self::Custom(_) => ErrorKind::Custom,
+            Self::Expected(_, _) => ErrorKind::TypeMismatch,
+            Self::Duplicated(_) => ErrorKind::DuplicateField,
+            Self::Missing(_) => ErrorKind::MissingField,
+            Self::Unknown(..) => ErrorKind::UnknownField,
+            Self::FrameTooLarge(_) | Self::LimitExceeded(_, _) => ErrorKind::LimitExceeded,
+            Self::Unsupported(_) => ErrorKind::Unsupported,
+            Self::ChecksumMismatch(_, _) | Self::InvalidSignature => ErrorKind::Integrity,
+            Self::Eof => ErrorKind::Eof,
+            Self::At(_, e) | Self::Offset(_, e) => e.kind(),
+            #[cfg(feature = "backtrace")]
+            Self::Traced(e, _) => e.kind(),
+            Self::Remote(repr) => repr.kind,
+        }
+    }
+
+    /// Gets the path of the value the error occurred at, such as `config.retries[2]`, if known.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::At(path, _) => Some(path),
+            Self::Offset(_, e) => e.path(),
+            #[cfg(feature = "backtrace")]
+            Self::Traced(e, _) => e.path(),
+            Self::Remote(repr) => repr.path.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Gets the byte offset in the input where decoding stopped, if known, which locates corrupt
+    /// input.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Self::Offset(offset, _) => Some(*offset),
+            Self::At(_, e) => e.offset(),
+            #[cfg(feature = "backtrace")]
+            Self::Traced(e, _) => e.offset(),
+            Self::Remote(repr) => repr.offset,
+            _ => None,
+        }
+    }
+
+    /// Gets the backtrace captured where the error was created, if any. Backtraces are captured
+    /// by errors raised through `serde`, bincode and I/O, subject to the `RUST_BACKTRACE` and
+    /// `RUST_LIB_BACKTRACE` environment variables.
+    #[cfg(feature = "backtrace")]
+    pub fn backtrace(&self) -> Option<&std::backtrace::Backtrace> {
+        match self {
+            Self::Traced(_, backtrace) => Some(backtrace),
+            Self::At(_, e) | Self::Offset(_, e) => e.backtrace(),
+            _ => None,
+        }
+    }
+
+    /// Captures a backtrace of the creation of the error, with the `backtrace` feature.
+    fn traced(self) -> Self {
+        #[cfg(feature = "backtrace")]
+        return Self::Traced(
+            Box::new(self),
+            Box::new(std::backtrace::Backtrace::capture()),
+        );
+        #[cfg(not(feature = "backtrace"))]
+        self
+    }
+
+    /// Names the type of an unknown field or variant among `names`, if not named yet.
+    pub(crate) fn in_type(mut self, ty: &'static str, names: &'static [&'static str]) -> Self {
+        if let Self::Unknown(_, expected, unnamed @ None) = self.untraced_mut() {
+            if *expected == names {
+                *unnamed = Some(ty);
+            }
+        }
+        self
+    }
+
+    fn untraced_mut(&mut self) -> &mut Self {
+        match self {
+            #[cfg(feature = "backtrace")]
+            Self::Traced(e, _) => e,
+            e => e,
+        }
+    }
+
+    /// Drops the backtrace of the error.
+    #[cfg(feature = "std")]
+    fn into_untraced(self) -> Self {
+        match self {
+            #[cfg(feature = "backtrace")]
+            Self::Traced(e, _) => *e,
+            e => e,
+        }
+    }
+
+    /// Gets the error without its backtrace.
+    pub(crate) fn untraced(&self) -> &Self {
+        match self {
+            #[cfg(feature = "backtrace")]
+            Self::Traced(e, _) => e,
+            e => e,
+        }
+    }
+
+    /// Attaches the byte offset in the input where decoding stopped.
+    pub(crate) fn at_offset(self, offset: usize) -> Self {
+        match self {
+            e @ Self::Offset(_, _) => e,
+            e => Self::Offset(offset, Box::new(e)),
+        }
+    }
+
+    /// Prefixes the path of the error with the entry of `key`.
+    pub(crate) fn at_key(self, key: &str) -> Self {
+        match self {
+            Self::At(path, e) if path.starts_with('[') => Self::At(format!("{}{}", key, path), e),
+            Self::At(path, e) => Self::At(format!("{}.{}", key, path), e),
+            e => Self::At(key.into(), Box::new(e)),
+        }
+    }
+
+    /// Prefixes the path of the error with the element at `index`.
+    pub(crate) fn at_index(self, index: usize) -> Self {
+        match self {
+            Self::At(path, e) if path.starts_with('[') => {
+                Self::At(format!("[{}]{}", index, path), e)
+            }
+            Self::At(path, e) => Self::At(format!("[{}].{}", index, path), e),
+            e => Self::At(format!("[{}]", index), Box::new(e)),
+        }
+    }
+
+    /// Returns `true` if the input ended before a complete document was read.
+    pub fn is_eof(&self) -> bool {
+        self.kind() == ErrorKind::Eof
+    }
+
+    /// Returns `true` if a value was not of the expected type.
+    pub fn is_type_mismatch(&self) -> bool {
+        self.kind() == ErrorKind::TypeMismatch
+    }
+
+    /// Returns `true` if the input exceeded a [crate::DecodeLimits] limit or a frame size limit.
+    pub fn is_limit_exceeded(&self) -> bool {
+        self.kind() == ErrorKind::LimitExceeded
+    }
+
+    /// Returns `true` if an I/O operation failed, other than by ending early.
+    pub fn is_io(&self) -> bool {
+        self.kind() == ErrorKind::Io
+    }
+
+    /// Gets the descriptions of the expected and the found value of a type mismatch.
+    pub fn mismatch(&self) -> Option<(&Description, &Description)> {
+        match self {
+            Self::Expected(expected, found) => Some((expected, found)),
+            Self::At(_, e) | Self::Offset(_, e) => e.mismatch(),
+            #[cfg(feature = "backtrace")]
+            Self::Traced(e, _) => e.mismatch(),
+            _ => None,
+        }
+    }
+}
+
+/// The category of an [Error].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Encoding with bincode failed.
+    Encode,
+
+    /// The input is malformed.
+    Decode,
+
+    /// An I/O operation failed.
+    Io,
+
+    /// The input ended before a complete document was read.
+    Eof,
+
+    /// A value was not of the expected type.
+    TypeMismatch,
+
+    /// A required field was missing.
+    MissingField,
+
+    /// A field was duplicated.
+    DuplicateField,
+
+    /// A field or variant was unknown.
+    UnknownField,
+
+    /// A value or operation is not supported.
+    Unsupported,
+
+    /// A checksum or signature did not match.
+    Integrity,
+
+    /// The input exceeded a limit.
+    LimitExceeded,
+
+    /// A custom error, such as one raised by a `Serialize` or `Deserialize` implementation.
+    Custom,
+}
+impl ErrorKind {
+    const ALL: [Self; 12] = [
+        Self::Encode,
+        Self::Decode,
+        Self::Io,
+        Self::Eof,
+        Self::TypeMismatch,
+        Self::MissingField,
+        Self::DuplicateField,
+        Self::UnknownField,
+        Self::Unsupported,
+        Self::Integrity,
+        Self::LimitExceeded,
+        Self::Custom,
+    ];
+    const NAMES: [&'static str; 12] = [
+        "encode",
+        "decode",
+        "io",
+        "eof",
+        "type_mismatch",
+        "missing_field",
+        "duplicate_field",
+        "unknown_field",
+        "unsupported",
+        "integrity",
+        "limit_exceeded",
+        "custom",
+    ];
+
+    /// Gets the name of the kind, such as `type_mismatch`.
+    pub fn as_str(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+
+    /// Gets the kind named `name`, as returned by [ErrorKind::as_str].
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .position(|n| *n == name)
+            .map(|i| Self::ALL[i])
+    }
+}
+impl Display for ErrorKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+/// A snapshot of an [Error] which can be serialized, e.g. to transport deserialization failures
+/// back to RPC callers, and turned back into an [Error] with the same kind, path, byte offset and
+/// message.
+///
+/// [Error] itself serializes and deserializes through this representation, as a struct of the
+/// fields `kind`, `message`, `path` and `offset`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ErrorRepr {
+    /// The category of the error.
+    pub kind: ErrorKind,
+
+    /// The message of the error, as displayed.
+    pub message: String,
+
+    /// The path of the value the error occurred at, if known.
+    pub path: Option<String>,
+
+    /// The byte offset in the input where decoding stopped, if known.
+    pub offset: Option<usize>,
+}
+impl Display for ErrorRepr {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.message)
+    }
+}
+impl From<&Error> for ErrorRepr {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::Remote(repr) => (**repr).clone(),
+            error => Self {
+                kind: error.kind(),
+                message: error.to_string(),
+                path: error.path().map(Into::into),
+                offset: error.offset(),
+            },
+        }
+    }
+}
+impl From<ErrorRepr> for Error {
+    fn from(repr: ErrorRepr) -> Self {
+        Self::Remote(Box::new(repr))
+    }
+}
+impl serde::Serialize for ErrorRepr {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut s = serializer.serialize_struct("ErrorRepr", 4)?;
+        s.serialize_field("kind", self.kind.as_str())?;
+        s.serialize_field("message", &self.message)?;
+        s.serialize_field("path", &self.path)?;
+        s.serialize_field("offset", &self.offset.map(|offset| offset as u64))?;
+        s.end()
+    }
+}
+impl<'de> serde::Deserialize<'de> for ErrorRepr {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> core::result::Result<Self, D::Error> {
+        deserializer.deserialize_struct("ErrorRepr", REPR_FIELDS, ReprVisitor)
+    }
+}
+impl serde::Serialize for Error {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        ErrorRepr::from(self).serialize(serializer)
+    }
+}
+impl<'de> serde::Deserialize<'de> for Error {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> core::result::Result<Self, D::Error> {
+        ErrorRepr::deserialize(deserializer).map(Into::into)
+    }
+}
+
+const REPR_FIELDS: &[&str] = &["kind", "message", "path", "offset"];
+
+struct ReprVisitor;
+impl<'de> serde::de::Visitor<'de> for ReprVisitor {
+    type Value = ErrorRepr;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct ErrorRepr")
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> core::result::Result<ErrorRepr, A::Error> {
+        use serde::de::Error;
+
+        let missing = |i| A::Error::invalid_length(i, &"struct ErrorRepr with 4 elements");
+        let kind: String = seq.next_element()?.ok_or_else(|| missing(0))?;
+        let message = seq.next_element()?.ok_or_else(|| missing(1))?;
+        let path = seq.next_element()?.ok_or_else(|| missing(2))?;
+        let offset: Option<u64> = seq.next_element()?.ok_or_else(|| missing(3))?;
+        Ok(ErrorRepr {
+            kind: repr_kind(&kind)?,
+            message,
+            path,
+            offset: offset.map(|offset| offset as usize),
+        })
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> core::result::Result<ErrorRepr, A::Error> {
+        use serde::de::Error;
+
+        let (mut kind, mut message, mut path, mut offset) = (None, None, None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "kind" => kind = Some(repr_kind(&map.next_value::<String>()?)?),
+                "message" => message = Some(map.next_value()?),
+                "path" => path = map.next_value()?,
+                "offset" => offset = map.next_value::<Option<u64>>()?,
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(ErrorRepr {
+            kind: kind.ok_or_else(|| A::Error::missing_field("kind"))?,
+            message: message.ok_or_else(|| A::Error::missing_field("message"))?,
+            path,
+            offset: offset.map(|offset| offset as usize),
+        })
+    }
+}
+
+/// Parses the name of an [ErrorKind].
+fn repr_kind<E: serde::de::Error>(name: &str) -> core::result::Result<ErrorKind, E> {
+    ErrorKind::from_name(name).ok_or_else(|| E::unknown_variant(name, &ErrorKind::NAMES))
+}
+
 impl From<bincode::error::EncodeError> for Error {
     fn from(value: bincode::error::EncodeError) -> Self {
-        Self::Bincode(value.into())
+        Self::Bincode(value.into()).traced()
     }
 }
 impl From<bincode::error::DecodeError> for Error {
     fn from(value: bincode::error::DecodeError) -> Self {
-        Self::Bincode(value.into())
+        Self::Bincode(value.into()).traced()
+    }
+}
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value).traced()
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Bincode(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::At(_, e) | Self::Offset(_, e) => e.source(),
+            #[cfg(feature = "backtrace")]
+            Self::Traced(e, _) => e.source(),
+            _ => None,
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    /// Converts an [Error] into an I/O error of the matching [std::io::ErrorKind], such as
+    /// `UnexpectedEof` for [ErrorKind::Eof] and `InvalidData` for malformed or mismatched input.
+    /// Wrapped I/O errors are unwrapped.
+    fn from(error: Error) -> Self {
+        use bincode::error::DecodeError;
+        use std::io;
+
+        let error = match error.into_untraced() {
+            Error::Io(e)
+            | Error::Bincode(BincodeError::Decode(DecodeError::Io { inner: e, .. })) => return e,
+            error => error,
+        };
+        let kind = match error.kind() {
+            ErrorKind::Eof => io::ErrorKind::UnexpectedEof,
+            ErrorKind::Io | ErrorKind::Custom => io::ErrorKind::Other,
+            ErrorKind::Encode => io::ErrorKind::InvalidInput,
+            ErrorKind::Decode
+            | ErrorKind::TypeMismatch
+            | ErrorKind::MissingField
+            | ErrorKind::DuplicateField
+            | ErrorKind::UnknownField
+            | ErrorKind::Integrity
+            | ErrorKind::LimitExceeded => io::ErrorKind::InvalidData,
+            ErrorKind::Unsupported => io::ErrorKind::Unsupported,
+        };
+        io::Error::new(kind, error)
     }
 }
-impl std::error::Error for Error {}
 impl serde::ser::Error for Error {
     fn custom<T>(msg: T) -> Self
     where
         T: Display,
     {
-        Self::Custom(msg.to_string())
+        Self::Custom(msg.to_string()).traced()
     }
 }
 impl serde::de::Error for Error {
@@ -52,28 +571,103 @@ impl serde::de::Error for Error {
     where
         T: Display,
     {
-        Self::Custom(msg.to_string())
+        Self::Custom(msg.to_string()).traced()
     }
     fn invalid_type(unexp: Unexpected, exp: &dyn Expected) -> Self {
-        Self::Expected(exp.to_string(), unexp.to_string())
+        Self::Expected(exp.to_string().into(), preview(unexp)).traced()
     }
     fn invalid_value(unexp: Unexpected, exp: &dyn Expected) -> Self {
-        Self::Expected(exp.to_string(), unexp.to_string())
+        Self::Expected(exp.to_string().into(), preview(unexp)).traced()
     }
     fn invalid_length(len: usize, exp: &dyn Expected) -> Self {
-        Self::Expected(format!("length {}", len), exp.to_string())
+        Self::Expected(format!("length {}", len).into(), exp.to_string().into()).traced()
     }
-    fn unknown_variant(variant: &str, _: &'static [&'static str]) -> Self {
-        Self::Unknown(variant.into())
+    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+        Self::Unknown(variant.into(), expected, None).traced()
     }
-    fn unknown_field(field: &str, _: &'static [&'static str]) -> Self {
-        Self::Unknown(field.into())
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        Self::Unknown(field.into(), expected, None).traced()
     }
     fn missing_field(field: &'static str) -> Self {
-        Self::Missing(field.into())
+        Self::Missing(field.into()).traced()
     }
     fn duplicate_field(field: &'static str) -> Self {
-        Self::Duplicated(field.into())
+        Self::Duplicated(field.into()).traced()
+    }
+}
+
+/// The number of characters of unexpected strings shown in errors before truncating them.
+const PREVIEW: usize = 32;
+
+/// Describes an unexpected value, truncating long strings.
+fn preview(unexp: Unexpected) -> Description {
+    let kind = match unexp {
+        Unexpected::Bool(_) => Some(ValueKind::Boolean),
+        Unexpected::Unsigned(_) | Unexpected::Signed(_) => Some(ValueKind::Integer),
+        Unexpected::Float(_) => Some(ValueKind::Float),
+        Unexpected::Char(_) | Unexpected::Str(_) => Some(ValueKind::String),
+        Unexpected::Bytes(_) => Some(ValueKind::Blob),
+        Unexpected::Unit | Unexpected::Option => Some(ValueKind::Null),
+        Unexpected::Seq => Some(ValueKind::Array),
+        Unexpected::Map => Some(ValueKind::Object),
+        _ => None,
+    };
+    let text = match unexp {
+        Unexpected::Str(s) => match s.char_indices().nth(PREVIEW) {
+            Some((end, _)) => format!("string {:?}… ({} bytes)", &s[..end], s.len()),
+            None => format!("string {:?}", s),
+        },
+        unexp => unexp.to_string(),
+    };
+    Description { kind, text }
+}
+
+/// A description of an expected or found value in an [Error::Expected], along with its type
+/// where known.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Description {
+    kind: Option<ValueKind>,
+    text: String,
+}
+impl Description {
+    /// Gets the type of the value, if the description determines it.
+    pub fn kind(&self) -> Option<ValueKind> {
+        self.kind
+    }
+
+    /// Gets the description as text, such as `type string` or `u32`.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Sets the type of the value the description determines.
+    pub(crate) fn with_kind(mut self, kind: ValueKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+}
+impl Display for Description {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.text)
+    }
+}
+impl From<ValueKind> for Description {
+    fn from(kind: ValueKind) -> Self {
+        Self {
+            kind: Some(kind),
+            text: kind.description().into(),
+        }
+    }
+}
+impl From<String> for Description {
+    /// Constructs a [Description] from text, without a type.
+    fn from(text: String) -> Self {
+        Self { kind: None, text }
+    }
+}
+impl From<&str> for Description {
+    fn from(text: &str) -> Self {
+        String::from(text).into()
     }
 }
 
@@ -91,7 +685,15 @@ impl Display for BincodeError {
         }
     }
 }
-impl std::error::Error for BincodeError {}
+#[cfg(feature = "std")]
+impl std::error::Error for BincodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Encode(e) => Some(e),
+            Self::Decode(e) => Some(e),
+        }
+    }
+}
 impl From<bincode::error::EncodeError> for BincodeError {
     fn from(value: bincode::error::EncodeError) -> Self {
         Self::Encode(value)
@@ -104,4 +706,312 @@ impl From<bincode::error::DecodeError> for BincodeError {
 }
 
 /// Alias for a Result with the error type [Error].
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{limits::DecodeLimits, value::Value};
+    use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+    #[test]
+    fn kinds() {
+        let bytes = crate::to_vec(&vec![1u32, 2, 3]).unwrap();
+        let cases: Vec<(Error, ErrorKind)> = vec![
+            (
+                crate::from_slice::<Vec<u32>>(&bytes[..2]).unwrap_err(),
+                ErrorKind::Eof,
+            ),
+            (
+                crate::from_slice::<u32>(&[0xff]).unwrap_err(),
+                ErrorKind::Unsupported,
+            ),
+            (
+                crate::from_slice::<u32>(&[8]).unwrap_err(),
+                ErrorKind::Decode,
+            ),
+            (
+                crate::from_slice::<String>(&bytes).unwrap_err(),
+                ErrorKind::TypeMismatch,
+            ),
+            (
+                crate::from_slice_with_limits::<Vec<u32>>(
+                    &bytes,
+                    &DecodeLimits::new().with_max_elements(2),
+                )
+                .unwrap_err(),
+                ErrorKind::LimitExceeded,
+            ),
+            (Error::Custom("custom".into()), ErrorKind::Custom),
+            (Error::Missing("a".into()), ErrorKind::MissingField),
+            (Error::Duplicated("a".into()), ErrorKind::DuplicateField),
+            (
+                Error::Unknown("a".into(), &[], None),
+                ErrorKind::UnknownField,
+            ),
+            (Error::FrameTooLarge(1), ErrorKind::LimitExceeded),
+            (Error::ChecksumMismatch(1, 2), ErrorKind::Integrity),
+            (Error::InvalidSignature, ErrorKind::Integrity),
+            (Error::At("a".into(), Box::new(Error::Eof)), ErrorKind::Eof),
+            (
+                Error::Offset(1, Box::new(Error::InvalidSignature)),
+                ErrorKind::Integrity,
+            ),
+            (
+                std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into(),
+                ErrorKind::Eof,
+            ),
+            (std::io::Error::other("io").into(), ErrorKind::Io),
+        ];
+        for (error, kind) in cases {
+            assert_eq!(error.kind(), kind, "{}", error);
+            assert_eq!(error.is_eof(), kind == ErrorKind::Eof);
+            assert_eq!(error.is_type_mismatch(), kind == ErrorKind::TypeMismatch);
+            assert_eq!(error.is_limit_exceeded(), kind == ErrorKind::LimitExceeded);
+            assert_eq!(error.is_io(), kind == ErrorKind::Io);
+        }
+    }
+
+    #[test]
+    fn kind_names() {
+        for kind in ErrorKind::ALL {
+            assert_eq!(ErrorKind::from_name(kind.as_str()), Some(kind));
+            assert_eq!(kind.to_string(), kind.as_str());
+        }
+        assert_eq!(ErrorKind::TypeMismatch.as_str(), "type_mismatch");
+        assert_eq!(ErrorKind::from_name("TypeMismatch"), None);
+    }
+
+    #[test]
+    fn type_errors() {
+        let long = "x".repeat(PREVIEW + 8);
+        let error = crate::from_slice::<u32>(&crate::to_vec(&long).unwrap()).unwrap_err();
+        let preview = "x".repeat(PREVIEW);
+        assert_eq!(
+            error.to_string(),
+            format!("expected u32, found string \"{}\"… (40 bytes)", preview)
+        );
+        let error = crate::from_slice::<bool>(&crate::to_vec(&"yes").unwrap()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected a boolean, found string \"yes\""
+        );
+
+        let document = BTreeMap::from([("a", vec![Value::Integer(1), Value::String("x".into())])]);
+        let bytes = crate::to_vec(&document).unwrap();
+        let error = crate::from_slice::<BTreeMap<String, Vec<u32>>>(&bytes).unwrap_err();
+        assert_eq!(error.path(), Some("a[1]"));
+        assert_eq!(
+            error.to_string(),
+            "expected u32 at \"a[1]\", found string \"x\""
+        );
+
+        let nested = vec![BTreeMap::from([("b", Value::Boolean(true))])];
+        let bytes = crate::to_vec(&nested).unwrap();
+        let error = crate::from_slice::<Vec<BTreeMap<String, u32>>>(&bytes).unwrap_err();
+        assert_eq!(error.path(), Some("[0].b"));
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        assert_eq!(Error::Eof.path(), None);
+    }
+
+    #[test]
+    fn offsets() {
+        let bytes = crate::to_vec(&vec![1u32, 2, 3]).unwrap();
+        let error = crate::from_slice::<Vec<u32>>(&bytes[..5]).unwrap_err();
+        assert_eq!(error.offset(), Some(5));
+        assert!(error.to_string().ends_with(" at byte 5"), "{}", error);
+        assert_eq!(error.kind(), ErrorKind::Eof);
+
+        let mut corrupt = bytes.clone();
+        corrupt[6] = 0xff;
+        let error = crate::from_slice::<Vec<u32>>(&corrupt).unwrap_err();
+        assert_eq!(error.offset(), Some(7));
+        assert_eq!(error.kind(), ErrorKind::Decode);
+
+        // Type mismatches are found after decoding, so do not locate bytes.
+        let error = crate::from_slice::<Vec<String>>(&bytes).unwrap_err();
+        assert_eq!(error.offset(), None);
+
+        let offset = Error::Offset(3, Box::new(Error::Eof)).at_offset(9);
+        assert_eq!(offset.offset(), Some(3));
+        let at = Error::At("a".into(), Box::new(Error::Offset(3, Box::new(Error::Eof))));
+        assert_eq!(at.offset(), Some(3));
+    }
+
+    #[test]
+    fn sources() {
+        use std::error::Error as _;
+
+        let bytes = crate::to_vec(&vec![1u32, 2, 3]).unwrap();
+        let error = crate::from_slice::<Vec<u32>>(&bytes[..5]).unwrap_err();
+        let source = error.source().unwrap();
+        let bincode = source.downcast_ref::<BincodeError>().unwrap();
+        assert!(matches!(bincode, BincodeError::Decode(_)));
+        let inner = bincode.source().unwrap();
+        assert!(inner.is::<bincode::error::DecodeError>());
+        assert!(inner.source().is_none());
+
+        let io = Error::from(std::io::Error::other("disk"));
+        let source = io.source().unwrap();
+        assert_eq!(source.to_string(), "disk");
+        let at = Error::At("a".into(), Box::new(io));
+        assert!(at.source().unwrap().is::<std::io::Error>());
+
+        assert!(Error::Custom("custom".into()).source().is_none());
+        assert!(Error::Eof.source().is_none());
+    }
+
+    #[test]
+    fn mismatch_kinds() {
+        let error = crate::from_slice::<u32>(&crate::to_vec(&"text").unwrap()).unwrap_err();
+        let (expected, found) = error.mismatch().unwrap();
+        assert_eq!((expected.kind(), expected.as_str()), (None, "u32"));
+        assert_eq!(found.kind(), Some(ValueKind::String));
+        assert_eq!(found.as_str(), "string \"text\"");
+
+        let bytes = crate::to_vec(&BTreeMap::from([("a", vec![1.5f64])])).unwrap();
+        let error = crate::from_slice::<BTreeMap<String, Vec<bool>>>(&bytes).unwrap_err();
+        let (_, found) = error.mismatch().unwrap();
+        assert_eq!(found.kind(), Some(ValueKind::Float));
+
+        let error = Error::Expected(ValueKind::Object.into(), ValueKind::Blob.into());
+        let (expected, found) = error.mismatch().unwrap();
+        assert_eq!(expected.kind(), Some(ValueKind::Object));
+        assert_eq!(found.to_string(), "type blob");
+        assert_eq!(error.to_string(), "expected type object, found type blob");
+
+        assert!(Error::Eof.mismatch().is_none());
+        assert_eq!(Description::from("text").kind(), None);
+    }
+
+    #[cfg(feature = "backtrace")]
+    #[test]
+    fn backtraces() {
+        let error = crate::from_slice::<u32>(&crate::to_vec(&"text").unwrap()).unwrap_err();
+        assert!(error.backtrace().is_some());
+        assert!(matches!(error, Error::Traced(..)));
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        assert!(error.mismatch().is_some());
+        assert_eq!(error.to_string(), "expected u32, found string \"text\"");
+
+        let bytes = crate::to_vec(&vec![1u32]).unwrap();
+        let error = crate::from_slice::<Vec<u32>>(&bytes[..2]).unwrap_err();
+        assert!(error.backtrace().is_some());
+        assert_eq!(error.offset(), Some(2));
+        assert!(Error::from(std::io::Error::other("io"))
+            .backtrace()
+            .is_some());
+
+        assert!(Error::Eof.backtrace().is_none());
+        assert!(Error::Custom("custom".into()).backtrace().is_none());
+    }
+
+    #[test]
+    fn unknown_names() {
+        let duration = BTreeMap::from([("secs", 1u64), ("nanos", 0), ("millis", 0)]);
+        let bytes = crate::to_vec(&duration).unwrap();
+        let error = crate::from_slice::<std::time::Duration>(&bytes).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnknownField);
+        assert_eq!(
+            error.to_string(),
+            "field or variant millis was unknown in Duration, expected one of `secs`, `nanos`"
+        );
+
+        let bytes = crate::to_vec(&"Maybe").unwrap();
+        let error = crate::from_slice::<core::result::Result<u32, u32>>(&bytes).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnknownField);
+        assert_eq!(
+            error.to_string(),
+            "field or variant Maybe was unknown in Result, expected one of `Ok`, `Err`"
+        );
+
+        let error = Error::Unknown("a".into(), &[], None);
+        assert_eq!(
+            error.to_string(),
+            "field or variant a was unknown, expected none"
+        );
+
+        let bytes = crate::to_vec(&BTreeMap::from([("secs", 1u64)])).unwrap();
+        let error = crate::from_slice::<std::time::Duration>(&bytes).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::MissingField);
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let bytes = crate::to_vec(&BTreeMap::from([("a", vec![1u32])])).unwrap();
+        let error = crate::from_slice::<BTreeMap<String, Vec<u32>>>(&bytes[..6]).unwrap_err();
+        let repr = ErrorRepr::from(&error);
+        assert_eq!(repr.kind, ErrorKind::Eof);
+        assert_eq!(repr.offset, Some(6));
+
+        let decoded: Error = crate::from_slice(&crate::to_vec(&error).unwrap()).unwrap();
+        assert_eq!(decoded.kind(), error.kind());
+        assert_eq!(decoded.to_string(), error.to_string());
+        assert_eq!(decoded.offset(), error.offset());
+        assert_eq!(ErrorRepr::from(&decoded), repr);
+
+        let error = crate::from_slice::<BTreeMap<String, Vec<String>>>(&bytes).unwrap_err();
+        let decoded: Error = crate::from_value(crate::to_value(&error).unwrap()).unwrap();
+        assert_eq!(decoded.path(), Some("a[0]"));
+        assert!(decoded.is_type_mismatch());
+
+        // Tuples of the fields deserialize too.
+        let seq = ("custom", "message", None::<String>, Some(3u64));
+        let decoded: Error = crate::from_slice(&crate::to_vec(&seq).unwrap()).unwrap();
+        assert_eq!(decoded.kind(), ErrorKind::Custom);
+        assert_eq!(decoded.offset(), Some(3));
+    }
+
+    #[test]
+    fn serde_errors() {
+        let repr = BTreeMap::from([("kind", "nope"), ("message", "message")]);
+        let error = crate::from_slice::<Error>(&crate::to_vec(&repr).unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnknownField);
+        assert!(error.to_string().contains("`type_mismatch`"), "{}", error);
+
+        let repr = BTreeMap::from([("kind", "eof")]);
+        let error = crate::from_slice::<Error>(&crate::to_vec(&repr).unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::MissingField);
+
+        let error = crate::from_slice::<Error>(&crate::to_vec(&("eof",)).unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn io_errors() {
+        use std::io;
+
+        let bytes = crate::to_vec(&vec![1u32]).unwrap();
+        let cases: Vec<(Error, io::ErrorKind)> = vec![
+            (
+                crate::from_slice::<Vec<u32>>(&bytes[..2]).unwrap_err(),
+                io::ErrorKind::UnexpectedEof,
+            ),
+            (
+                crate::from_slice::<String>(&bytes).unwrap_err(),
+                io::ErrorKind::InvalidData,
+            ),
+            (
+                crate::from_slice::<u32>(&[8]).unwrap_err(),
+                io::ErrorKind::InvalidData,
+            ),
+            (Error::InvalidSignature, io::ErrorKind::InvalidData),
+            (Error::FrameTooLarge(1), io::ErrorKind::InvalidData),
+            (Error::Unsupported("a".into()), io::ErrorKind::Unsupported),
+            (Error::Custom("a".into()), io::ErrorKind::Other),
+        ];
+        for (error, kind) in cases {
+            let message = error.to_string();
+            let io = io::Error::from(error);
+            assert_eq!(io.kind(), kind, "{}", message);
+            assert_eq!(io.to_string(), message);
+            assert!(io.get_ref().unwrap().is::<Error>());
+        }
+
+        // Wrapped I/O errors are unwrapped rather than wrapped again.
+        let error = Error::from(io::Error::new(io::ErrorKind::PermissionDenied, "denied"));
+        let io = io::Error::from(error);
+        assert_eq!(io.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(io.to_string(), "denied");
+    }
+}
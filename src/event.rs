@@ -0,0 +1,630 @@
+//! A low-level event model of documents, for transforming them with bounded memory.
+//!
+//! A document is a sequence of [Event]s in depth-first order: values other than arrays and objects
+//! are a single event, while an array is a [Event::StartArray] followed by its elements and a
+//! [Event::EndArray], and an object is a [Event::StartObject] followed by a [Event::Key] and a
+//! value per entry and a [Event::EndObject]. As on the wire, containers announce their length.
+//!
+//! [EventReader] produces the events of encoded bytes, borrowing strings and blobs from them, and
+//! [EventWriter] writes events back as wire bytes, checking that they form well-formed documents.
+//! Neither holds more than a frame per level of nesting, so documents of any size stream through
+//! them. Both handle sequences of concatenated documents.
+//!
+//! [parse_with] pushes the nodes of encoded documents into a [DocumentVisitor] instead, along with
+//! their paths, for jobs which only aggregate a few facts of each document.
+
+use crate::{
+    borrowed::{take_bytes, take_str},
+    error::{Description, Error, Result},
+    limits::{DecodeLimits, Limit},
+    value::ValueKind,
+    wire::{self, SliceReader},
+};
+use bincode::{
+    config::Configuration,
+    de::{Decode, Decoder, DecoderImpl},
+};
+use std::io::Write;
+
+/// An event of a document.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event<'a> {
+    /// A null value.
+    Null,
+
+    /// A bool value.
+    Boolean(bool),
+
+    /// A blob value.
+    Blob(&'a [u8]),
+
+    /// The start of an array of the given number of elements.
+    StartArray(usize),
+
+    /// The end of an array.
+    EndArray,
+
+    /// An integer value.
+    Integer(i64),
+
+    /// A float value.
+    Float(f64),
+
+    /// The start of an object of the given number of entries.
+    StartObject(usize),
+
+    /// The key of an object entry, followed by the events of its value.
+    Key(&'a str),
+
+    /// The end of an object.
+    EndObject,
+
+    /// A string value.
+    String(&'a str),
+}
+impl Event<'_> {
+    /// Gets the kind of the event, for error messages.
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Null => "type null",
+            Self::Boolean(_) => "type boolean",
+            Self::Blob(_) => "type blob",
+            Self::StartArray(_) => "start of array",
+            Self::EndArray => "end of array",
+            Self::Integer(_) => "type integer",
+            Self::Float(_) => "type float",
+            Self::StartObject(_) => "start of object",
+            Self::Key(_) => "key",
+            Self::EndObject => "end of object",
+            Self::String(_) => "type string",
+        }
+    }
+
+    /// Gets the type of the value the event starts, if it starts one.
+    fn kind(&self) -> Option<ValueKind> {
+        match self {
+            Self::Null => Some(ValueKind::Null),
+            Self::Boolean(_) => Some(ValueKind::Boolean),
+            Self::Blob(_) => Some(ValueKind::Blob),
+            Self::StartArray(_) => Some(ValueKind::Array),
+            Self::Integer(_) => Some(ValueKind::Integer),
+            Self::Float(_) => Some(ValueKind::Float),
+            Self::StartObject(_) => Some(ValueKind::Object),
+            Self::String(_) => Some(ValueKind::String),
+            Self::EndArray | Self::Key(_) | Self::EndObject => None,
+        }
+    }
+}
+
+/// An array or object being read or written.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    object: bool,
+    remaining: usize,
+    awaiting_value: bool,
+}
+
+/// Reads the [Event]s of encoded bytes.
+pub struct EventReader<'a> {
+    decoder: DecoderImpl<SliceReader<'a>, Configuration>,
+    len: usize,
+    position: usize,
+    limits: DecodeLimits,
+    stack: Vec<Frame>,
+    failed: bool,
+}
+impl<'a> EventReader<'a> {
+    /// Constructs a new [EventReader] over `bytes`, enforcing the default [DecodeLimits].
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_limits(bytes, DecodeLimits::default())
+    }
+
+    /// Constructs a new [EventReader] over `bytes`, enforcing `limits`.
+    pub fn with_limits(bytes: &'a [u8], limits: DecodeLimits) -> Self {
+        Self {
+            decoder: DecoderImpl::new(SliceReader::new(bytes), bincode::config::standard()),
+            len: bytes.len(),
+            position: 0,
+            limits,
+            stack: Vec::new(),
+            failed: false,
+        }
+    }
+
+    /// Gets the number of bytes read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Gets the current nesting depth, which is 0 between documents.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Reads the next event, or `None` at the end of the input between documents.
+    pub fn next_event(&mut self) -> Result<Option<Event<'a>>> {
+        if self.failed {
+            return Ok(None);
+        }
+        let event = self.read_event();
+        self.position = self.decoder.reader().position();
+        self.failed = event.is_err();
+        event
+    }
+
+    fn read_event(&mut self) -> Result<Option<Event<'a>>> {
+        match self.stack.last_mut() {
+            Some(frame) if frame.object && !frame.awaiting_value => {
+                if frame.remaining == 0 {
+                    self.stack.pop();
+                    return Ok(Some(Event::EndObject));
+                }
+                frame.remaining -= 1;
+                frame.awaiting_value = true;
+                let key = take_str(&mut self.decoder, &self.limits)?;
+                return Ok(Some(Event::Key(key)));
+            }
+            Some(frame) if frame.object => frame.awaiting_value = false,
+            Some(frame) => {
+                if frame.remaining == 0 {
+                    self.stack.pop();
+                    return Ok(Some(Event::EndArray));
+                }
+                frame.remaining -= 1;
+            }
+            None if self.position == self.len => return Ok(None),
+            None => self.limits.check(Limit::Bytes, self.len)?,
+        }
+
+        let decoder = &mut self.decoder;
+        Ok(Some(match wire::decode_tag(decoder)? {
+            wire::NULL => Event::Null,
+            wire::BOOLEAN => Event::Boolean(bool::decode(decoder)?),
+            wire::BLOB => {
+                let len = wire::decode_len(decoder)?;
+                self.limits.check(Limit::BlobLength, len)?;
+                Event::Blob(take_bytes(decoder, len)?)
+            }
+            wire::INTEGER => Event::Integer(i64::decode(decoder)?),
+            wire::FLOAT => Event::Float(f64::decode(decoder)?),
+            tag @ (wire::ARRAY | wire::OBJECT) => {
+                self.limits.check(Limit::Depth, self.stack.len() + 1)?;
+                let len = wire::decode_len(decoder)?;
+                self.limits.check(Limit::Elements, len)?;
+                let object = tag == wire::OBJECT;
+                self.stack.push(Frame {
+                    object,
+                    remaining: len,
+                    awaiting_value: false,
+                });
+                if object {
+                    Event::StartObject(len)
+                } else {
+                    Event::StartArray(len)
+                }
+            }
+            _ => Event::String(take_str(decoder, &self.limits)?),
+        }))
+    }
+}
+impl<'a> Iterator for EventReader<'a> {
+    type Item = Result<Event<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+/// Writes [Event]s as wire bytes into a [Write].
+pub struct EventWriter<W> {
+    writer: W,
+    stack: Vec<Frame>,
+}
+impl<W: Write> EventWriter<W> {
+    /// Constructs a new [EventWriter] writing into `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Writes `event`, failing with [Error::Expected] if it cannot follow the events written so
+    /// far, such as a value where a key is expected or more elements than an array announced.
+    pub fn write_event(&mut self, event: &Event<'_>) -> Result<()> {
+        match (self.stack.last_mut(), event) {
+            (Some(frame), Event::Key(_)) if frame.object && !frame.awaiting_value => {
+                if frame.remaining == 0 {
+                    return Err(unexpected("end of object", event));
+                }
+                frame.remaining -= 1;
+                frame.awaiting_value = true;
+            }
+            (Some(frame), Event::EndObject) if frame.object && !frame.awaiting_value => {
+                if frame.remaining != 0 {
+                    return Err(unexpected("key", event));
+                }
+                self.stack.pop();
+            }
+            (Some(frame), Event::EndArray) if !frame.object => {
+                if frame.remaining != 0 {
+                    return Err(unexpected("value", event));
+                }
+                self.stack.pop();
+            }
+            (Some(frame), _) if frame.object && !frame.awaiting_value => {
+                return Err(unexpected("key", event));
+            }
+            (_, Event::Key(_) | Event::EndArray | Event::EndObject) => {
+                return Err(unexpected("value", event));
+            }
+            (Some(frame), _) if frame.object => frame.awaiting_value = false,
+            (Some(frame), _) => {
+                if frame.remaining == 0 {
+                    return Err(unexpected("end of array", event));
+                }
+                frame.remaining -= 1;
+            }
+            (None, _) => {}
+        }
+
+        match *event {
+            Event::Null => self.encode(wire::NULL),
+            Event::Boolean(b) => self.encode((wire::BOOLEAN, b)),
+            Event::Blob(b) => self.encode((wire::BLOB, b)),
+            Event::StartArray(len) => {
+                self.stack.push(Frame {
+                    object: false,
+                    remaining: len,
+                    awaiting_value: false,
+                });
+                self.encode((wire::ARRAY, len as u64))
+            }
+            Event::Integer(i) => self.encode((wire::INTEGER, i)),
+            Event::Float(f) => self.encode((wire::FLOAT, f)),
+            Event::StartObject(len) => {
+                self.stack.push(Frame {
+                    object: true,
+                    remaining: len,
+                    awaiting_value: false,
+                });
+                self.encode((wire::OBJECT, len as u64))
+            }
+            Event::Key(key) => self.encode(key),
+            Event::EndArray | Event::EndObject => Ok(()),
+            Event::String(s) => self.encode((wire::STRING, s)),
+        }
+    }
+
+    /// Returns `true` if every document written so far is complete.
+    pub fn is_complete(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Unwraps the underlying writer, failing with [Error::Eof] if the last document is
+    /// incomplete.
+    pub fn into_inner(self) -> Result<W> {
+        if !self.is_complete() {
+            return Err(Error::Eof);
+        }
+        Ok(self.writer)
+    }
+
+    fn encode<E: bincode::Encode>(&mut self, val: E) -> Result<()> {
+        bincode::encode_into_std_write(val, &mut self.writer, bincode::config::standard())?;
+        Ok(())
+    }
+}
+
+fn unexpected(expected: &str, found: &Event<'_>) -> Error {
+    let description = Description::from(found.description());
+    let description = match found.kind() {
+        Some(kind) => description.with_kind(kind),
+        None => description,
+    };
+    Error::Expected(expected.into(), description)
+}
+
+/// A step of the path to a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathSegment<'a> {
+    /// The value of the entry of a key.
+    Key(&'a str),
+
+    /// The element at an index.
+    Index(usize),
+}
+
+/// Callbacks of [parse_with] per node of a document, given the path to the node from the root of
+/// its document. Every callback does nothing by default, and an error aborts parsing.
+pub trait DocumentVisitor<'a> {
+    /// Visits a value other than an array or an object, which is one of the value [Event]s.
+    fn visit_value(&mut self, path: &[PathSegment<'a>], value: Event<'a>) -> Result<()> {
+        let _ = (path, value);
+        Ok(())
+    }
+
+    /// Visits the start of an array of `len` elements.
+    fn start_array(&mut self, path: &[PathSegment<'a>], len: usize) -> Result<()> {
+        let _ = (path, len);
+        Ok(())
+    }
+
+    /// Visits the end of an array.
+    fn end_array(&mut self, path: &[PathSegment<'a>]) -> Result<()> {
+        let _ = path;
+        Ok(())
+    }
+
+    /// Visits the start of an object of `len` entries.
+    fn start_object(&mut self, path: &[PathSegment<'a>], len: usize) -> Result<()> {
+        let _ = (path, len);
+        Ok(())
+    }
+
+    /// Visits the end of an object.
+    fn end_object(&mut self, path: &[PathSegment<'a>]) -> Result<()> {
+        let _ = path;
+        Ok(())
+    }
+}
+
+/// Parses the documents in `bytes`, pushing their nodes into `visitor` without materializing any
+/// value, and enforcing the default [DecodeLimits].
+pub fn parse_with<'a, V: DocumentVisitor<'a>>(bytes: &'a [u8], visitor: &mut V) -> Result<()> {
+    parse_with_limits(bytes, visitor, DecodeLimits::default())
+}
+
+/// Parses the documents in `bytes` like [parse_with], enforcing `limits`.
+pub fn parse_with_limits<'a, V: DocumentVisitor<'a>>(
+    bytes: &'a [u8],
+    visitor: &mut V,
+    limits: DecodeLimits,
+) -> Result<()> {
+    let mut reader = EventReader::with_limits(bytes, limits);
+    let mut path = Vec::new();
+    // The index of the next element of each open array, or `None` for objects.
+    let mut containers: Vec<Option<usize>> = Vec::new();
+    while let Some(event) = reader.next_event()? {
+        match event {
+            Event::Key(key) => {
+                path.push(PathSegment::Key(key));
+                continue;
+            }
+            Event::EndArray | Event::EndObject => {
+                containers.pop();
+                if event == Event::EndArray {
+                    visitor.end_array(&path)?;
+                } else {
+                    visitor.end_object(&path)?;
+                }
+            }
+            event => {
+                if let Some(Some(index)) = containers.last_mut() {
+                    path.push(PathSegment::Index(*index));
+                    *index += 1;
+                }
+                match event {
+                    Event::StartArray(len) => {
+                        visitor.start_array(&path, len)?;
+                        containers.push(Some(0));
+                        continue;
+                    }
+                    Event::StartObject(len) => {
+                        visitor.start_object(&path, len)?;
+                        containers.push(None);
+                        continue;
+                    }
+                    value => visitor.visit_value(&path, value)?,
+                }
+            }
+        }
+        if !containers.is_empty() {
+            path.pop();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::ErrorKind,
+        value::{Map, Value},
+    };
+
+    fn document() -> Value {
+        Value::Array(vec![
+            Value::Null,
+            Value::Boolean(true),
+            Value::Object(Map::from([(
+                String::from("blob"),
+                Value::Blob(vec![1, 2, 3]),
+            )])),
+            Value::Integer(-42),
+            Value::Float(1.5),
+            Value::String("text".into()),
+        ])
+    }
+
+    fn events() -> Vec<Event<'static>> {
+        vec![
+            Event::StartArray(6),
+            Event::Null,
+            Event::Boolean(true),
+            Event::StartObject(1),
+            Event::Key("blob"),
+            Event::Blob(&[1, 2, 3]),
+            Event::EndObject,
+            Event::Integer(-42),
+            Event::Float(1.5),
+            Event::String("text"),
+            Event::EndArray,
+        ]
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut bytes = crate::to_vec(&document()).unwrap();
+        bytes.extend(crate::to_vec(&Value::Integer(7)).unwrap());
+
+        let mut reader = EventReader::new(&bytes);
+        let mut read = Vec::new();
+        while let Some(event) = reader.next_event().unwrap() {
+            if event == Event::EndArray {
+                assert_eq!(reader.depth(), 0);
+            }
+            read.push(event);
+        }
+        assert_eq!(reader.position(), bytes.len());
+        let mut expected = events();
+        expected.push(Event::Integer(7));
+        assert_eq!(read, expected);
+
+        let mut writer = EventWriter::new(Vec::new());
+        for event in &read {
+            writer.write_event(event).unwrap();
+        }
+        assert!(writer.is_complete());
+        writer.flush().unwrap();
+        assert_eq!(writer.into_inner().unwrap(), bytes);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let bytes = crate::to_vec(&document()).unwrap();
+        let mut reader = EventReader::new(&bytes[..bytes.len() - 1]);
+        let error = reader.by_ref().find_map(Result::err).unwrap();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+        assert!(reader.next().is_none());
+
+        let error = EventReader::new(&[8]).next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Decode);
+
+        let limits = DecodeLimits::new().with_max_depth(1);
+        let nested = crate::to_vec(&Value::Array(vec![document()])).unwrap();
+        let error = EventReader::with_limits(&nested, limits)
+            .find_map(Result::err)
+            .unwrap();
+        assert!(error.is_limit_exceeded());
+    }
+
+    #[test]
+    fn malformed_events() {
+        let mut writer = EventWriter::new(Vec::new());
+        let error = writer.write_event(&Event::Key("key")).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = writer.write_event(&Event::EndArray).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+        writer.write_event(&Event::StartObject(1)).unwrap();
+        let error = writer.write_event(&Event::Null).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = writer.write_event(&Event::EndObject).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        writer.write_event(&Event::Key("key")).unwrap();
+        writer.write_event(&Event::StartArray(0)).unwrap();
+        let error = writer.write_event(&Event::Null).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        writer.write_event(&Event::EndArray).unwrap();
+        assert!(!writer.is_complete());
+        let error = writer.into_inner().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+    }
+
+    /// Records every callback with the path it was given.
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+    impl<'a> DocumentVisitor<'a> for Recorder {
+        fn visit_value(&mut self, path: &[PathSegment<'a>], value: Event<'a>) -> Result<()> {
+            self.0.push(format!("{:?} {:?}", path, value));
+            Ok(())
+        }
+
+        fn start_array(&mut self, path: &[PathSegment<'a>], len: usize) -> Result<()> {
+            self.0.push(format!("{:?} [{}", path, len));
+            Ok(())
+        }
+
+        fn end_array(&mut self, path: &[PathSegment<'a>]) -> Result<()> {
+            self.0.push(format!("{:?} ]", path));
+            Ok(())
+        }
+
+        fn start_object(&mut self, path: &[PathSegment<'a>], len: usize) -> Result<()> {
+            self.0.push(format!("{:?} {{{}", path, len));
+            Ok(())
+        }
+
+        fn end_object(&mut self, path: &[PathSegment<'a>]) -> Result<()> {
+            self.0.push(format!("{:?} }}", path));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn visitor() {
+        let mut bytes = crate::to_vec(&document()).unwrap();
+        bytes.extend(crate::to_vec(&Value::Integer(7)).unwrap());
+        let mut recorder = Recorder::default();
+        parse_with(&bytes, &mut recorder).unwrap();
+        assert_eq!(
+            recorder.0,
+            [
+                "[] [6",
+                "[Index(0)] Null",
+                "[Index(1)] Boolean(true)",
+                "[Index(2)] {1",
+                "[Index(2), Key(\"blob\")] Blob([1, 2, 3])",
+                "[Index(2)] }",
+                "[Index(3)] Integer(-42)",
+                "[Index(4)] Float(1.5)",
+                "[Index(5)] String(\"text\")",
+                "[] ]",
+                "[] Integer(7)",
+            ]
+        );
+
+        // Every callback defaults to doing nothing.
+        struct Ignore;
+        impl DocumentVisitor<'_> for Ignore {}
+        parse_with(&bytes, &mut Ignore).unwrap();
+    }
+
+    #[test]
+    fn visitor_errors() {
+        /// Fails on the first integer.
+        struct Reject;
+        impl<'a> DocumentVisitor<'a> for Reject {
+            fn visit_value(&mut self, _: &[PathSegment<'a>], value: Event<'a>) -> Result<()> {
+                match value {
+                    Event::Integer(_) => Err(Error::Custom("integer".into())),
+                    _ => Ok(()),
+                }
+            }
+        }
+        let bytes = crate::to_vec(&document()).unwrap();
+        let error = parse_with(&bytes, &mut Reject).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+
+        let mut recorder = Recorder::default();
+        let error = parse_with(&bytes[..bytes.len() - 1], &mut recorder).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+        assert_eq!(recorder.0.len(), 8);
+
+        let limits = DecodeLimits::new().with_max_elements(1);
+        let error = parse_with_limits(&bytes, &mut Recorder::default(), limits).unwrap_err();
+        assert!(error.is_limit_exceeded());
+    }
+}
@@ -0,0 +1,174 @@
+//! A `figment` provider of configuration stored as Bincode JSON, to layer with other sources.
+//!
+//! Like `figment`'s file providers, a missing file provides no configuration, and the whole
+//! document is the configuration of the default profile unless the provider is [nested], where
+//! each top-level key is a profile.
+//!
+//! [nested]: BincodeJson::nested
+
+use crate::value::Value;
+use ::figment::{
+    value::{Dict, Map},
+    Error, Metadata, Profile, Provider,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// A `figment` [Provider] of a Bincode JSON file or [Value].
+#[derive(Debug, Clone)]
+pub struct BincodeJson {
+    source: Source,
+    profile: Option<Profile>,
+}
+impl BincodeJson {
+    /// Constructs a new [BincodeJson] provider of the document in the file at `path`.
+    pub fn file(path: impl AsRef<Path>) -> Self {
+        Self {
+            source: Source::File(path.as_ref().into()),
+            profile: Some(Profile::Default),
+        }
+    }
+
+    /// Constructs a new [BincodeJson] provider of `value`.
+    pub fn value(value: Value) -> Self {
+        Self {
+            source: Source::Value(value),
+            profile: Some(Profile::Default),
+        }
+    }
+
+    /// Sets the profile the document is the configuration of.
+    pub fn with_profile(mut self, profile: impl Into<Profile>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Makes each top-level key of the document a profile, whose value is its configuration.
+    pub fn nested(mut self) -> Self {
+        self.profile = None;
+        self
+    }
+
+    /// Loads the document, which is `None` for a missing file.
+    fn load(&self) -> Result<Option<Value>, String> {
+        match &self.source {
+            Source::File(path) => match fs::read(path) {
+                Ok(bytes) => Ok(Some(crate::from_slice(&bytes).map_err(|e| e.to_string())?)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.to_string()),
+            },
+            Source::Value(value) => Ok(Some(value.clone())),
+        }
+    }
+}
+impl Provider for BincodeJson {
+    fn metadata(&self) -> Metadata {
+        match &self.source {
+            Source::File(path) => Metadata::from("Bincode JSON file", path.as_path()),
+            Source::Value(_) => Metadata::named("Bincode JSON value"),
+        }
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, Error> {
+        let Some(value) = self.load()? else {
+            return Ok(Map::new());
+        };
+        let dict = dict(value)?;
+        let profiles = match &self.profile {
+            Some(profile) => profile.collect(dict),
+            None => dict
+                .into_iter()
+                .map(|(profile, value)| {
+                    let dict = value
+                        .into_dict()
+                        .ok_or_else(|| format!("expected profile {} to be an object", profile))?;
+                    Ok((Profile::new(&profile), dict))
+                })
+                .collect::<Result<_, String>>()?,
+        };
+        Ok(profiles)
+    }
+}
+
+/// Where the document of a [BincodeJson] provider is.
+#[derive(Debug, Clone)]
+enum Source {
+    File(PathBuf),
+    Value(Value),
+}
+
+/// Converts a document into a `figment` dictionary.
+fn dict(value: Value) -> Result<Dict, String> {
+    let description = value.error_description();
+    ::figment::value::Value::serialize(value)
+        .map_err(|e| e.to_string())?
+        .into_dict()
+        .ok_or_else(|| format!("expected type object, found {}", description))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Map as ValueMap;
+    use ::figment::Figment;
+    use std::collections::BTreeMap;
+
+    fn config(port: i64) -> Value {
+        Value::Object(ValueMap::from([(
+            String::from("port"),
+            Value::Integer(port),
+        )]))
+    }
+
+    #[test]
+    fn round_trip() {
+        let figment = Figment::from(BincodeJson::value(config(8080)));
+        let extracted: BTreeMap<String, u32> = figment.extract().unwrap();
+        assert_eq!(extracted, BTreeMap::from([(String::from("port"), 8080)]));
+
+        let nested = Value::Object(ValueMap::from([
+            (String::from("default"), config(8080)),
+            (String::from("debug"), config(3000)),
+        ]));
+        let figment = Figment::from(BincodeJson::value(nested.clone()).nested());
+        assert_eq!(figment.extract_inner::<u32>("port").unwrap(), 8080);
+        let figment = figment.select("debug");
+        assert_eq!(figment.extract_inner::<u32>("port").unwrap(), 3000);
+
+        let figment =
+            Figment::from(BincodeJson::value(config(3000)).with_profile("debug")).select("debug");
+        assert_eq!(figment.extract_inner::<u32>("port").unwrap(), 3000);
+
+        let path =
+            std::env::temp_dir().join(format!("bincode-json-figment-{}", std::process::id()));
+        fs::write(&path, crate::to_vec(&config(8080)).unwrap()).unwrap();
+        let figment = Figment::from(BincodeJson::file(&path));
+        let port = figment.extract_inner::<u32>("port");
+        fs::remove_file(&path).unwrap();
+        assert_eq!(port.unwrap(), 8080);
+
+        let missing = BincodeJson::file(path);
+        assert!(missing.data().unwrap().is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let error = BincodeJson::value(Value::Integer(1)).data().unwrap_err();
+        assert!(error.to_string().contains("expected type object"));
+
+        let nested = Value::Object(ValueMap::from([(String::from("debug"), Value::Integer(1))]));
+        let error = BincodeJson::value(nested).nested().data().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("expected profile debug to be an object"));
+
+        let path =
+            std::env::temp_dir().join(format!("bincode-json-figment-bad-{}", std::process::id()));
+        fs::write(&path, [0xff]).unwrap();
+        let result = BincodeJson::file(&path).data();
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}
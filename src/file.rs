@@ -0,0 +1,190 @@
+//! Reading and writing whole `bincode-json` documents from/to files.
+
+use crate::error::Result;
+#[cfg(feature = "mmap")]
+use crate::{borrowed::BorrowedValue, raw::RawDocument};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Deserialize an instance of type `T` from a file of Bincode JSON.
+pub fn from_file<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T> {
+    crate::from_slice(&fs::read(path)?)
+}
+
+/// Deserialize an instance of type `T` from a memory-mapped file of Bincode JSON, which avoids
+/// copying large files into memory. Requires the `mmap` feature.
+///
+/// # Safety
+/// The file must not be modified, e.g. truncated, while it is mapped. Files written by [to_file]
+/// are replaced by a rename and never modified in place, so replacing them concurrently is fine.
+#[cfg(feature = "mmap")]
+pub unsafe fn from_file_mapped<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T> {
+    MappedFile::open(path)?.decode()
+}
+
+/// A memory-mapped file of Bincode JSON, whose contents can be decoded without copying them.
+/// Requires the `mmap` feature.
+#[cfg(feature = "mmap")]
+pub struct MappedFile {
+    map: memmap2::Mmap,
+}
+#[cfg(feature = "mmap")]
+impl MappedFile {
+    /// Maps the file at `path` into memory.
+    ///
+    /// # Safety
+    /// The file must not be modified, e.g. truncated, while it is mapped, as for
+    /// [from_file_mapped].
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        Ok(Self {
+            map: memmap2::Mmap::map(&file)?,
+        })
+    }
+
+    /// Gets the contents of the file.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Deserialize an instance of type `T` from the file.
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        crate::from_slice(&self.map)
+    }
+
+    /// Decodes the file as a [BorrowedValue], whose strings and blobs point into the mapping
+    /// rather than being copied.
+    pub fn to_borrowed(&self) -> Result<BorrowedValue<'_>> {
+        BorrowedValue::from_slice(&self.map)
+    }
+
+    /// Gets a [RawDocument] over the file, which decodes only the parts that are accessed.
+    pub fn raw(&self) -> RawDocument<'_> {
+        RawDocument::new(&self.map)
+    }
+}
+
+/// Serialize the given data structure into a file, atomically.
+///
+/// The document is written to a temporary file next to `path`, synced to disk and then renamed
+/// over `path`, so readers see either the previous document or the new one, never a partial one.
+pub fn to_file<T: Serialize, P: AsRef<Path>>(path: P, val: &T) -> Result<()> {
+    let path = path.as_ref();
+    let bytes = crate::to_vec(val)?;
+    let temp = temp_path(path);
+    // Only a temporary file created by this call is removed on failure, never one which belongs
+    // to a concurrent call.
+    let file = File::options().write(true).create_new(true).open(&temp)?;
+    let result = write_synced(file, &bytes).and_then(|_| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result?;
+    sync_parent(path);
+    Ok(())
+}
+
+/// Gets the path of a temporary file to write `path`, unique to the process and the call.
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+fn write_synced(mut file: File, bytes: &[u8]) -> std::io::Result<()> {
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+/// Syncs the directory containing `path`, so that a rename into it is durable. Failures are
+/// ignored, as not every platform supports syncing directories.
+fn sync_parent(path: &Path) {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if let Ok(dir) = File::open(parent) {
+        let _ = dir.sync_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    /// A file removed when dropped.
+    struct TempFile(PathBuf);
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "bincode-json-file-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_file(&path);
+            Self(path)
+        }
+    }
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let file = TempFile::new("round-trip");
+        to_file(&file.0, &vec![1u32, 2, 3]).unwrap();
+        to_file(&file.0, &("key", 42u32)).unwrap();
+        let decoded: (String, u32) = from_file(&file.0).unwrap();
+        assert_eq!(decoded, (String::from("key"), 42));
+
+        let dir = file.0.parent().unwrap();
+        let prefix = format!(".{}.", file.0.file_name().unwrap().to_string_lossy());
+        assert!(!fs::read_dir(dir).unwrap().any(|entry| entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .starts_with(&prefix)));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped() {
+        let file = TempFile::new("mapped");
+        to_file(&file.0, &("key", 42u32)).unwrap();
+        let decoded: (String, u32) = unsafe { from_file_mapped(&file.0) }.unwrap();
+        assert_eq!(decoded, (String::from("key"), 42));
+
+        let mapped = unsafe { MappedFile::open(&file.0) }.unwrap();
+        assert_eq!(mapped.as_bytes(), fs::read(&file.0).unwrap());
+        assert!(mapped.to_borrowed().is_ok());
+    }
+
+    #[test]
+    fn errors() {
+        let file = TempFile::new("errors");
+        let error = from_file::<u32, _>(&file.0).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Io);
+
+        to_file(&file.0, &"value").unwrap();
+        let error = from_file::<u32, _>(&file.0).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+        let missing_dir = file.0.join("missing").join("file");
+        let error = to_file(&missing_dir, &1u8).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Io);
+    }
+}
@@ -0,0 +1,114 @@
+//! The length-prefixed frame layout shared by the streaming adapters.
+//!
+//! Every frame consists of a 4-byte big-endian length followed by that many bytes of an encoded
+//! document.
+
+use crate::error::{Error, Result};
+use serde::Serialize;
+use std::io::{self, Read};
+
+/// Length of the big-endian length prefix preceding every frame.
+pub(crate) const LENGTH_PREFIX_LEN: usize = 4;
+
+/// Default maximum size of a frame's payload accepted by the streaming adapters, 8 MiB.
+#[cfg(any(feature = "codec", feature = "futures"))]
+pub(crate) const DEFAULT_MAX_FRAME_SIZE: usize = 8 * 1024 * 1024;
+
+/// Encodes `val` as a frame, appending it to `buf`.
+pub(crate) fn encode_frame<T: Serialize>(val: &T, buf: &mut Vec<u8>) -> Result<()> {
+    let payload = crate::to_vec(val)?;
+    let len = u32::try_from(payload.len()).map_err(|_| Error::FrameTooLarge(payload.len()))?;
+    buf.reserve(LENGTH_PREFIX_LEN + payload.len());
+    buf.extend_from_slice(&len.to_be_bytes());
+    buf.extend_from_slice(&payload);
+    Ok(())
+}
+
+/// Fills `buf` as far as possible, returning the number of bytes read before the end of input.
+pub(crate) fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    /// A reader which is interrupted before every read and fails once `data` runs out, unless
+    /// `eof` is set.
+    struct Flaky<'a> {
+        data: &'a [u8],
+        interrupted: bool,
+        eof: bool,
+    }
+
+    impl Read for Flaky<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupted = !self.interrupted;
+            if self.interrupted {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            if self.data.is_empty() && !self.eof {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            let n = buf.len().min(self.data.len()).min(3);
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut buf = vec![0xaa];
+        encode_frame(&"text", &mut buf).unwrap();
+        encode_frame(&7u32, &mut buf).unwrap();
+        let payload = crate::to_vec(&"text").unwrap();
+        assert_eq!(
+            buf[1..LENGTH_PREFIX_LEN + 1],
+            (payload.len() as u32).to_be_bytes()
+        );
+        assert_eq!(buf[LENGTH_PREFIX_LEN + 1..][..payload.len()], payload[..]);
+
+        let mut reader = Flaky {
+            data: &buf[1..],
+            interrupted: false,
+            eof: true,
+        };
+        let mut len = [0; LENGTH_PREFIX_LEN];
+        assert_eq!(read_full(&mut reader, &mut len).unwrap(), LENGTH_PREFIX_LEN);
+        let mut frame = vec![0; u32::from_be_bytes(len) as usize];
+        assert_eq!(read_full(&mut reader, &mut frame).unwrap(), frame.len());
+        assert_eq!(crate::from_slice::<String>(&frame).unwrap(), "text");
+
+        assert_eq!(read_full(&mut reader, &mut len).unwrap(), LENGTH_PREFIX_LEN);
+        let mut frame = vec![0; u32::from_be_bytes(len) as usize + 2];
+        assert_eq!(read_full(&mut reader, &mut frame).unwrap(), frame.len() - 2);
+        assert_eq!(read_full(&mut reader, &mut frame).unwrap(), 0);
+    }
+
+    #[test]
+    fn errors() {
+        let mut reader = Flaky {
+            data: &[1, 2],
+            interrupted: false,
+            eof: false,
+        };
+        let error = read_full(&mut reader, &mut [0; LENGTH_PREFIX_LEN]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+
+        let mut buf = vec![];
+        let error = encode_frame(&std::collections::HashMap::from([(1, 1)]), &mut buf);
+        assert_eq!(error.unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert!(buf.is_empty());
+    }
+}
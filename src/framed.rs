@@ -0,0 +1,213 @@
+//! Length-prefixed framing of `bincode-json` documents over `futures`' asynchronous I/O traits.
+
+use crate::{
+    error::{Error, Result},
+    frame::{encode_frame, DEFAULT_MAX_FRAME_SIZE, LENGTH_PREFIX_LEN},
+};
+use futures::{
+    io::{AsyncRead, AsyncWrite},
+    Sink, Stream,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+/// A [Sink] which writes values of type `T` as length-prefixed frames into an [AsyncWrite].
+pub struct ValueSink<W, T> {
+    writer: W,
+    buf: Vec<u8>,
+    written: usize,
+    _marker: PhantomData<fn(T)>,
+}
+impl<W, T> ValueSink<W, T> {
+    /// Constructs a new [ValueSink] writing into `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buf: Vec::new(),
+            written: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Consumes the sink, returning the underlying writer. Frames which were not flushed are lost.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+impl<W: AsyncWrite + Unpin, T> ValueSink<W, T> {
+    fn poll_write_buf(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        while self.written < self.buf.len() {
+            let n = ready!(Pin::new(&mut self.writer).poll_write(cx, &self.buf[self.written..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(Error::Io(std::io::ErrorKind::WriteZero.into())));
+            }
+            self.written += n;
+        }
+        self.buf.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+impl<W: AsyncWrite + Unpin, T: Serialize> Sink<T> for ValueSink<W, T> {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().poll_write_buf(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<()> {
+        encode_frame(&item, &mut self.get_mut().buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_buf(cx))?;
+        Poll::Ready(ready!(Pin::new(&mut this.writer).poll_flush(cx)).map_err(Error::Io))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_buf(cx))?;
+        Poll::Ready(ready!(Pin::new(&mut this.writer).poll_close(cx)).map_err(Error::Io))
+    }
+}
+
+/// A [Stream] which reads values of type `T` from length-prefixed frames of an [AsyncRead].
+pub struct ValueStream<R, T> {
+    reader: R,
+    header: [u8; LENGTH_PREFIX_LEN],
+    payload: Option<Vec<u8>>,
+    filled: usize,
+    max_frame_size: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+impl<R, T> ValueStream<R, T> {
+    /// Constructs a new [ValueStream] reading from `reader`, accepting frames of up to 8 MiB.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            header: [0; LENGTH_PREFIX_LEN],
+            payload: None,
+            filled: 0,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets the maximum size of a frame's payload, in bytes. Larger frames are rejected with
+    /// [Error::FrameTooLarge] before their payload is allocated.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Gets the maximum size of a frame's payload, in bytes.
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consumes the stream, returning the underlying reader. A partially read frame is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+impl<R: AsyncRead + Unpin, T: DeserializeOwned> Stream for ValueStream<R, T> {
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let buf = match &mut this.payload {
+                Some(payload) => &mut payload[this.filled..],
+                None => &mut this.header[this.filled..],
+            };
+            if buf.is_empty() {
+                match this.payload.take() {
+                    Some(payload) => {
+                        this.filled = 0;
+                        return Poll::Ready(Some(crate::from_slice(&payload)));
+                    }
+                    None => {
+                        let len = u32::from_be_bytes(this.header) as usize;
+                        // The header is kept, so the stream keeps failing rather than reading the
+                        // payload as the next frame.
+                        if len > this.max_frame_size {
+                            return Poll::Ready(Some(Err(Error::FrameTooLarge(len))));
+                        }
+                        this.payload = Some(vec![0; len]);
+                        this.filled = 0;
+                        continue;
+                    }
+                }
+            }
+
+            let n = ready!(Pin::new(&mut this.reader).poll_read(cx, buf))?;
+            if n == 0 {
+                return match (this.filled, &this.payload) {
+                    (0, None) => Poll::Ready(None),
+                    _ => Poll::Ready(Some(Err(Error::Eof))),
+                };
+            }
+            this.filled += n;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::ready;
+    use futures::{io::Cursor, SinkExt, StreamExt};
+
+    fn frames(values: &[&str]) -> Vec<u8> {
+        let mut sink = ValueSink::new(Cursor::new(Vec::new()));
+        for value in values {
+            ready(sink.feed(*value)).unwrap();
+        }
+        ready(sink.close()).unwrap();
+        sink.into_inner().into_inner()
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = frames(&["one", "two"]);
+        let stream = ValueStream::<_, String>::new(Cursor::new(bytes));
+        let values: Vec<_> = ready(stream.collect::<Vec<_>>());
+        let values: Vec<String> = values.into_iter().collect::<Result<_>>().unwrap();
+        assert_eq!(values, ["one", "two"]);
+    }
+
+    #[test]
+    fn truncated_frame() {
+        let bytes = frames(&["one"]);
+        let mut stream = ValueStream::<_, String>::new(Cursor::new(&bytes[..bytes.len() - 1]));
+        let error = ready(stream.next()).unwrap().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+    }
+
+    #[test]
+    fn frame_too_large() {
+        let bytes = frames(&["a longer value"]);
+        let mut stream = ValueStream::<_, String>::new(Cursor::new(bytes)).with_max_frame_size(4);
+        assert_eq!(stream.max_frame_size(), 4);
+        for _ in 0..2 {
+            let error = ready(stream.next()).unwrap().unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Stable content hashing of `bincode-json` documents.
+//!
+//! Documents are hashed over their canonical encoding, so semantically equal documents always
+//! produce the same digest.
+
+use crate::{canonical::Canonical, error::Result, value::Value};
+use bincode::{enc::write::Writer, error::EncodeError};
+use digest::{Digest, Output};
+use serde::Serialize;
+
+/// A bincode [Writer] which feeds the written bytes into a digest.
+struct DigestWriter<'a, D>(&'a mut D);
+impl<D: Digest> Writer for DigestWriter<'_, D> {
+    fn write(&mut self, bytes: &[u8]) -> std::result::Result<(), EncodeError> {
+        self.0.update(bytes);
+        Ok(())
+    }
+}
+
+impl Value {
+    /// Computes the digest of the canonical encoding of the value with the hash function `D`.
+    pub fn content_hash<D: Digest>(&self) -> Output<D> {
+        let mut digest = D::new();
+        bincode::encode_into_writer(
+            Canonical(self),
+            DigestWriter(&mut digest),
+            bincode::config::standard(),
+        )
+        .expect("canonical encoding into a digest never fails");
+        digest.finalize()
+    }
+}
+
+/// Computes the digest of the canonical encoding of the given data structure with the hash
+/// function `D`.
+pub fn hash_value<D: Digest, T: Serialize>(val: &T) -> Result<Output<D>> {
+    Ok(crate::to_value(val)?.content_hash::<D>())
+}
+
+#[cfg(all(test, feature = "hmac"))]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use sha2::Sha256;
+    use std::collections::HashMap;
+
+    #[test]
+    fn stable() {
+        let forward: HashMap<String, f64> = (0..32).map(|i| (i.to_string(), 0.0)).collect();
+        let backward: HashMap<String, f64> = (0..32).rev().map(|i| (i.to_string(), -0.0)).collect();
+        let hash = hash_value::<Sha256, _>(&forward).unwrap();
+        assert_eq!(hash, hash_value::<Sha256, _>(&backward).unwrap());
+        assert_eq!(
+            hash,
+            crate::to_value(&forward).unwrap().content_hash::<Sha256>()
+        );
+
+        let bytes = crate::to_vec_canonical(&forward).unwrap();
+        assert_eq!(hash, Sha256::digest(&bytes));
+        assert_ne!(
+            hash,
+            hash_value::<Sha256, _>(&HashMap::from([("0", 1.0)])).unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = hash_value::<Sha256, _>(&HashMap::from([(1u8, 2u8)])).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
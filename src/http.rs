@@ -0,0 +1,109 @@
+//! `reqwest` helpers sending and receiving Bincode JSON bodies, like its `json` methods.
+
+pub use crate::CONTENT_TYPE;
+
+use crate::{
+    error::{Error, Result},
+    limits::DecodeLimits,
+};
+use reqwest::{header, RequestBuilder, Response};
+use serde::{de::DeserializeOwned, Serialize};
+use std::future::Future;
+
+/// Extends [RequestBuilder] with sending Bincode JSON bodies.
+pub trait RequestBuilderExt: Sized {
+    /// Sets the body to `val` encoded as Bincode JSON, and the content type to [CONTENT_TYPE].
+    fn bincode_json<T: Serialize>(self, val: &T) -> Result<Self>;
+}
+impl RequestBuilderExt for RequestBuilder {
+    fn bincode_json<T: Serialize>(self, val: &T) -> Result<Self> {
+        let body = crate::to_vec(val)?;
+        Ok(self.header(header::CONTENT_TYPE, CONTENT_TYPE).body(body))
+    }
+}
+
+/// Extends [Response] with receiving Bincode JSON bodies.
+pub trait ResponseExt {
+    /// Decodes the body as Bincode JSON, enforcing the default [DecodeLimits].
+    fn bincode_json<T: DeserializeOwned>(self) -> impl Future<Output = Result<T>> + Send;
+
+    /// Decodes the body as Bincode JSON, enforcing `limits`.
+    fn bincode_json_with_limits<T: DeserializeOwned>(
+        self,
+        limits: DecodeLimits,
+    ) -> impl Future<Output = Result<T>> + Send;
+}
+impl ResponseExt for Response {
+    async fn bincode_json<T: DeserializeOwned>(self) -> Result<T> {
+        self.bincode_json_with_limits(DecodeLimits::default()).await
+    }
+
+    async fn bincode_json_with_limits<T: DeserializeOwned>(
+        self,
+        limits: DecodeLimits,
+    ) -> Result<T> {
+        let content_type = self
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        if !crate::is_content_type(content_type) {
+            return Err(Error::Expected(
+                format!("content type {}", CONTENT_TYPE).into(),
+                format!("content type {:?}", content_type).into(),
+            ));
+        }
+
+        let bytes = self
+            .bytes()
+            .await
+            .map_err(|e| Error::Custom(e.to_string()))?;
+        crate::from_slice_with_limits(&bytes, &limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::ready;
+
+    fn response(content_type: &str, body: Vec<u8>) -> Response {
+        http::Response::builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .body(body)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn round_trip() {
+        let request = reqwest::Client::new()
+            .post("http://localhost/")
+            .bincode_json(&("key", 42u32))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()[header::CONTENT_TYPE], CONTENT_TYPE);
+        let body = request.body().unwrap().as_bytes().unwrap().to_vec();
+
+        let decoded: (String, u32) = ready(response(CONTENT_TYPE, body).bincode_json()).unwrap();
+        assert_eq!(decoded, (String::from("key"), 42));
+    }
+
+    #[test]
+    fn errors() {
+        let body = crate::to_vec(&"text").unwrap();
+
+        let error =
+            ready(response("application/json", body.clone()).bincode_json::<String>()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = ready(response(CONTENT_TYPE, body.clone()).bincode_json::<u32>()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+        let limits = DecodeLimits::new().with_max_string_len(2);
+        let error = ready(response(CONTENT_TYPE, body).bincode_json_with_limits::<String>(limits))
+            .unwrap_err();
+        assert!(error.is_limit_exceeded());
+    }
+}
@@ -0,0 +1,395 @@
+//! Incremental decoding of `bincode-json` documents from data arriving in pieces.
+//!
+//! An [IncrementalDecoder] is fed bytes as they arrive, e.g. from a socket, and keeps the state
+//! of the partially decoded document in between, so no byte is ever decoded twice. Once the length
+//! of a blob or string is known, the decoder waits for all of it to arrive before reading it, so
+//! feeding a long value in small pieces costs no more than feeding it at once.
+//!
+//! Only documents in the standard profile are supported; documents of the key dictionary profile
+//! fail with [Error::Unsupported], and must be decoded whole with [crate::from_slice_dict].
+
+use crate::{
+    dict,
+    error::{BincodeError, Error, Result},
+    limits::{DecodeLimits, Limit},
+    value::{Map, Value},
+    wire::{self, SliceReader},
+};
+use alloc::{string::String, vec::Vec};
+use bincode::{
+    config::Config,
+    de::{Decode, Decoder, DecoderImpl},
+    error::DecodeError,
+};
+use core::marker::PhantomData;
+use serde::de::DeserializeOwned;
+
+/// The outcome of feeding bytes to an [IncrementalDecoder].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Feed<T> {
+    /// A complete document was decoded.
+    Ready(T),
+
+    /// The document is incomplete and at least this many more bytes are needed.
+    Pending(usize),
+}
+
+/// A resumable decoder of documents of type `T`.
+pub struct IncrementalDecoder<T> {
+    buf: Vec<u8>,
+    wanted: usize,
+    read: usize,
+    stack: Vec<Frame>,
+    limits: DecodeLimits,
+    _marker: PhantomData<fn() -> T>,
+}
+impl<T> Default for IncrementalDecoder<T> {
+    fn default() -> Self {
+        Self {
+            buf: Vec::new(),
+            wanted: 0,
+            read: 0,
+            stack: Vec::new(),
+            limits: DecodeLimits::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+impl<T> IncrementalDecoder<T> {
+    /// Constructs a new [IncrementalDecoder] enforcing the default [DecodeLimits].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the limits enforced while decoding.
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Gets the bytes which were fed but not decoded yet: the incomplete part of the current
+    /// document, or whatever followed a document which was just decoded.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Discards the partially decoded document and every buffered byte.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.wanted = 0;
+        self.read = 0;
+        self.stack.clear();
+    }
+
+    /// Feeds `bytes` to the decoder like [IncrementalDecoder::feed], returning the document as a
+    /// [Value].
+    pub(crate) fn feed_value(&mut self, bytes: &[u8]) -> Result<Feed<Value>> {
+        self.buf.extend_from_slice(bytes);
+        let result = self.advance();
+        if result.is_err() {
+            self.reset();
+        }
+        result
+    }
+
+    /// Decodes as much of the buffered input as possible, returning the root value once complete.
+    fn advance(&mut self) -> Result<Feed<Value>> {
+        if self.buf.len() < self.wanted {
+            return Ok(Feed::Pending(self.wanted - self.buf.len()));
+        }
+
+        let mut pos = 0;
+        let outcome = loop {
+            if self.stack.is_empty() && self.read == 0 {
+                dict::reject_profile(self.buf.get(pos).copied())?;
+            }
+            let mut decoder = DecoderImpl::new(
+                SliceReader::new(&self.buf[pos..]),
+                bincode::config::standard(),
+            );
+            let token = match self.stack.last() {
+                Some(Frame::Object { key: None, .. }) => {
+                    decode_string(&mut decoder, &self.limits).map(Token::Key)
+                }
+                _ => decode_token(&mut decoder, &self.limits, self.stack.len()),
+            };
+            let token = match token {
+                Ok(token) => token,
+                Err(e) => match e.untraced() {
+                    Error::Bincode(BincodeError::Decode(DecodeError::UnexpectedEnd {
+                        additional,
+                    })) => {
+                        self.wanted = self.buf.len() - pos + additional;
+                        self.limits.check(Limit::Bytes, self.read + self.wanted)?;
+                        break Ok(Feed::Pending(*additional));
+                    }
+                    _ => break Err(e),
+                },
+            };
+
+            pos += decoder.reader().position();
+            self.read += decoder.reader().position();
+            self.limits.check(Limit::Bytes, self.read)?;
+            let value = match token {
+                Token::Key(key) => {
+                    if let Some(Frame::Object { key: slot, .. }) = self.stack.last_mut() {
+                        *slot = Some(key);
+                    }
+                    continue;
+                }
+                Token::Value(value) => value,
+                Token::Array(0) => Value::Array(Vec::new()),
+                Token::Object(0) => Value::Object(Map::default()),
+                Token::Array(len) => {
+                    self.stack.push(Frame::Array {
+                        items: Vec::with_capacity(len.min(wire::MAX_PREALLOCATED)),
+                        remaining: len,
+                    });
+                    continue;
+                }
+                Token::Object(len) => {
+                    self.stack.push(Frame::Object {
+                        entries: Map::with_capacity(len.min(wire::MAX_PREALLOCATED)),
+                        remaining: len,
+                        key: None,
+                    });
+                    continue;
+                }
+            };
+            if let Some(root) = self.complete(value) {
+                self.wanted = 0;
+                self.read = 0;
+                break Ok(Feed::Ready(root));
+            }
+        };
+        self.buf.drain(..pos);
+        outcome
+    }
+
+    /// Adds a complete value to the innermost container, returning it back if it is the root.
+    fn complete(&mut self, mut value: Value) -> Option<Value> {
+        while let Some(frame) = self.stack.last_mut() {
+            if !frame.push(value) {
+                return None;
+            }
+            value = self
+                .stack
+                .pop()
+                .expect("the innermost container was just filled")
+                .into_value();
+        }
+        Some(value)
+    }
+}
+impl<T: DeserializeOwned> IncrementalDecoder<T> {
+    /// Feeds `bytes` to the decoder, returning the document once it is complete.
+    ///
+    /// Bytes following a complete document are kept for the next one, which is decoded by the
+    /// next call; `feed(&[])` decodes it from the buffered bytes alone. If the bytes are not a
+    /// valid document, the decoder is [reset](IncrementalDecoder::reset).
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<Feed<T>> {
+        match self.feed_value(bytes)? {
+            Feed::Ready(value) => crate::from_value(value).map(Feed::Ready),
+            Feed::Pending(needed) => Ok(Feed::Pending(needed)),
+        }
+    }
+}
+
+/// A container whose elements are being decoded.
+enum Frame {
+    Array {
+        items: Vec<Value>,
+        remaining: usize,
+    },
+    Object {
+        entries: Map<String, Value>,
+        remaining: usize,
+        key: Option<String>,
+    },
+}
+impl Frame {
+    /// Adds an element, returning `true` if the container is complete.
+    fn push(&mut self, value: Value) -> bool {
+        match self {
+            Self::Array { items, remaining } => {
+                items.push(value);
+                *remaining -= 1;
+                *remaining == 0
+            }
+            Self::Object {
+                entries,
+                remaining,
+                key,
+            } => {
+                entries.insert(key.take().unwrap_or_default(), value);
+                *remaining -= 1;
+                *remaining == 0
+            }
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            Self::Array { items, .. } => Value::Array(items),
+            Self::Object { entries, .. } => Value::Object(entries),
+        }
+    }
+}
+
+/// The smallest decodable unit of a document.
+enum Token {
+    Key(String),
+    Value(Value),
+    Array(usize),
+    Object(usize),
+}
+
+/// Decodes a scalar value, or the header of a container nested at `depth`.
+fn decode_token<C: Config>(
+    decoder: &mut DecoderImpl<SliceReader<'_>, C>,
+    limits: &DecodeLimits,
+    depth: usize,
+) -> Result<Token> {
+    let tag = wire::decode_tag(decoder)?;
+    match tag {
+        wire::ARRAY | wire::OBJECT => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = wire::decode_len(decoder)?;
+            limits.check(Limit::Elements, len)?;
+            Ok(match tag {
+                wire::ARRAY => Token::Array(len),
+                _ => Token::Object(len),
+            })
+        }
+        wire::NULL => Ok(Token::Value(Value::Null)),
+        wire::BOOLEAN => Ok(Token::Value(Value::Boolean(bool::decode(decoder)?))),
+        wire::BLOB => {
+            let len = wire::decode_len(decoder)?;
+            limits.check(Limit::BlobLength, len)?;
+            ensure_buffered(decoder, len)?;
+            Ok(Token::Value(Value::Blob(wire::read_bytes(decoder, len)?)))
+        }
+        wire::INTEGER => Ok(Token::Value(Value::Integer(i64::decode(decoder)?))),
+        wire::FLOAT => Ok(Token::Value(Value::Float(f64::decode(decoder)?))),
+        _ => Ok(Token::Value(Value::String(decode_string(decoder, limits)?))),
+    }
+}
+
+/// Decodes a length-prefixed string.
+fn decode_string<C: Config>(
+    decoder: &mut DecoderImpl<SliceReader<'_>, C>,
+    limits: &DecodeLimits,
+) -> Result<String> {
+    let len = wire::decode_len(decoder)?;
+    limits.check(Limit::StringLength, len)?;
+    ensure_buffered(decoder, len)?;
+    wire::into_string(wire::read_bytes(decoder, len)?)
+}
+
+/// Fails with the number of missing bytes unless `len` bytes are buffered, so that the pending
+/// token is retried only once all of it has arrived.
+fn ensure_buffered<C: Config>(
+    decoder: &mut DecoderImpl<SliceReader<'_>, C>,
+    len: usize,
+) -> Result<()> {
+    let remaining = decoder.reader().remaining();
+    if len > remaining {
+        return Err(DecodeError::UnexpectedEnd {
+            additional: len - remaining,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::{vec, vec::Vec};
+
+    fn feed_bytewise(decoder: &mut IncrementalDecoder<Value>, bytes: &[u8]) -> Vec<Feed<Value>> {
+        bytes
+            .iter()
+            .map(|b| decoder.feed_value(&[*b]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_in_pieces() {
+        let value = Value::Array(vec![
+            Value::Integer(-7),
+            Value::String("bincode-json".into()),
+            Value::Object(Map::from([("key".into(), Value::Boolean(true))])),
+            Value::Array(Vec::new()),
+        ]);
+        let bytes = crate::to_vec(&value).unwrap();
+        let mut decoder = IncrementalDecoder::<Value>::new();
+        let feeds = feed_bytewise(&mut decoder, &bytes);
+        assert!(feeds[..feeds.len() - 1]
+            .iter()
+            .all(|feed| matches!(feed, Feed::Pending(_))));
+        match feeds.last() {
+            Some(Feed::Ready(decoded)) => assert_eq!(crate::to_vec(decoded).unwrap(), bytes),
+            other => panic!("expected a document, got {:?}", other),
+        }
+        assert!(decoder.buffered().is_empty());
+    }
+
+    #[test]
+    fn back_to_back_documents() {
+        let mut bytes = crate::to_vec(&1u8).unwrap();
+        bytes.extend(crate::to_vec(&"two").unwrap());
+        let mut decoder = IncrementalDecoder::<String>::new();
+        assert_eq!(
+            decoder.feed(&bytes).map_err(|e| e.kind()),
+            Err(ErrorKind::TypeMismatch)
+        );
+
+        let mut decoder = IncrementalDecoder::<Value>::new();
+        assert!(matches!(
+            decoder.feed_value(&bytes).unwrap(),
+            Feed::Ready(Value::Integer(1))
+        ));
+        assert!(
+            matches!(decoder.feed_value(&[]).unwrap(), Feed::Ready(Value::String(s)) if s == "two")
+        );
+    }
+
+    #[test]
+    fn large_blob_bytewise() {
+        let blob = vec![0xa5; 1 << 20];
+        let bytes = crate::to_vec(&Value::Blob(blob.clone())).unwrap();
+        let header = bytes.len() - blob.len();
+        let mut decoder = IncrementalDecoder::<Value>::new();
+        let feeds = feed_bytewise(&mut decoder, &bytes);
+
+        // Once the length is known, every feed reports exactly what is still missing.
+        for (i, feed) in feeds.iter().enumerate().take(bytes.len() - 1).skip(header) {
+            assert!(matches!(feed, Feed::Pending(n) if *n == bytes.len() - i - 1));
+        }
+        assert!(matches!(feeds.last(), Some(Feed::Ready(Value::Blob(b))) if *b == blob));
+    }
+
+    #[test]
+    fn limits() {
+        let bytes = crate::to_vec(&Value::String("x".repeat(100))).unwrap();
+        let limits = DecodeLimits::default().with_max_bytes(50);
+        let mut decoder = IncrementalDecoder::<Value>::new().with_limits(limits);
+        // The declared length alone exceeds the limit, before the string arrives.
+        let error = decoder.feed_value(&bytes[..2]).unwrap_err();
+        assert!(error.is_limit_exceeded());
+        assert!(decoder.buffered().is_empty());
+    }
+
+    #[test]
+    fn malformed() {
+        let mut decoder = IncrementalDecoder::<Value>::new();
+        let error = decoder.feed_value(&[9]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Decode);
+        assert!(decoder.buffered().is_empty());
+
+        let error = decoder.feed_value(&[dict::MARKER, 0]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
@@ -0,0 +1,273 @@
+//! Sidecar indexes of the byte offsets of values within encoded documents, for random access.
+//!
+//! [to_vec_indexed] encodes a document as usual while recording where each value down to a given
+//! depth starts and ends, so that a reader holding the [OffsetIndex] can seek straight to a value
+//! in a large document on disk rather than scanning it. Depth 1 indexes the entries or elements of
+//! the top-level value, depth 2 also theirs, and so on. Values are indexed by their paths, written
+//! like those of [crate::extract], e.g. `a.b[3]`.
+//!
+//! An index is itself stored as a document by [OffsetIndex::to_vec], mapping each path to an array
+//! of its start and end offsets.
+
+use crate::{
+    error::{Error, Result},
+    raw::RawDocument,
+    value::{Map, Value, ValueKind},
+    wire,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    io::{Read, Seek, SeekFrom, Write},
+    ops::Range,
+};
+
+/// The byte ranges of the values within an encoded document, by path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OffsetIndex {
+    ranges: HashMap<String, Range<u64>>,
+}
+impl OffsetIndex {
+    /// Constructs a new, empty [OffsetIndex].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the byte range of the value at `path`, if it is indexed.
+    pub fn get(&self, path: &str) -> Option<Range<u64>> {
+        self.ranges.get(path).cloned()
+    }
+
+    /// Gets the number of indexed values.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Returns `true` if no value is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Iterates over the paths of the indexed values and their byte ranges, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Range<u64>)> {
+        self.ranges
+            .iter()
+            .map(|(path, range)| (path.as_str(), range.clone()))
+    }
+
+    /// Gets a view of the value at `path` in the indexed document `doc`, if it is indexed.
+    pub fn get_raw<'a>(&self, doc: &'a [u8], path: &str) -> Option<RawDocument<'a>> {
+        let range = self.get(path)?;
+        let bytes = doc.get(range.start as usize..range.end as usize)?;
+        Some(RawDocument::new(bytes))
+    }
+
+    /// Deserialize an instance of type `T` from the value at `path` in the indexed document read
+    /// by `reader`, seeking to it and reading only its bytes. Fails with [Error::Missing] if the
+    /// value is not indexed.
+    pub fn read_at<R: Read + Seek, T: DeserializeOwned>(
+        &self,
+        mut reader: R,
+        path: &str,
+    ) -> Result<T> {
+        let range = self.get(path).ok_or_else(|| Error::Missing(path.into()))?;
+        reader.seek(SeekFrom::Start(range.start))?;
+        let mut buf = Vec::new();
+        reader.take(range.end - range.start).read_to_end(&mut buf)?;
+        crate::from_slice(&buf)
+    }
+
+    /// Encodes the index as a document.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut map = Map::with_capacity(self.ranges.len());
+        for (path, range) in &self.ranges {
+            let range = vec![
+                Value::Integer(range.start as i64),
+                Value::Integer(range.end as i64),
+            ];
+            map.insert(path.clone(), Value::Array(range));
+        }
+        bincode::encode_to_vec(Value::Object(map), bincode::config::standard())
+            .expect("encoding a value into a vector never fails")
+    }
+
+    /// Decodes an index from a document written by [OffsetIndex::to_vec].
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        let map = match crate::from_slice(bytes)? {
+            Value::Object(map) => map,
+            value => {
+                return Err(Error::Expected(
+                    ValueKind::Object.into(),
+                    value.kind().into(),
+                ))
+            }
+        };
+        let mut ranges = HashMap::with_capacity(map.len());
+        for (path, range) in map {
+            let range = match range {
+                Value::Array(a) => match a[..] {
+                    [Value::Integer(start), Value::Integer(end)] if 0 <= start && start <= end => {
+                        start as u64..end as u64
+                    }
+                    _ => return Err(Error::Expected("byte range".into(), path.into())),
+                },
+                _ => return Err(Error::Expected("byte range".into(), path.into())),
+            };
+            ranges.insert(path, range);
+        }
+        Ok(Self { ranges })
+    }
+}
+
+/// Serialize the given data structure as a byte vector, along with the index of its values down
+/// to `depth`.
+pub fn to_vec_indexed<T: Serialize>(val: &T, depth: usize) -> Result<(Vec<u8>, OffsetIndex)> {
+    let mut buf = Vec::new();
+    let mut index = OffsetIndex::new();
+    encode_indexed(
+        &crate::to_value(val)?,
+        &mut buf,
+        &mut String::new(),
+        depth,
+        &mut index,
+    )?;
+    Ok((buf, index))
+}
+
+/// Serialize the given data structure into a writer, returning the index of its values down to
+/// `depth`, with offsets relative to the start of the document.
+pub fn to_writer_indexed<W: Write, T: Serialize>(
+    mut writer: W,
+    val: &T,
+    depth: usize,
+) -> Result<OffsetIndex> {
+    let (buf, index) = to_vec_indexed(val, depth)?;
+    writer.write_all(&buf)?;
+    Ok(index)
+}
+
+/// Encodes `value` into `buf`, recording the ranges of the values nested in it down to `depth`
+/// below `path`.
+fn encode_indexed(
+    value: &Value,
+    buf: &mut Vec<u8>,
+    path: &mut String,
+    depth: usize,
+    index: &mut OffsetIndex,
+) -> Result<()> {
+    let config = bincode::config::standard();
+    let len = path.len();
+    let mut child = |path: &mut String, value: &Value, buf: &mut Vec<u8>| {
+        let start = buf.len() as u64;
+        encode_indexed(value, buf, path, depth - 1, index)?;
+        index.ranges.insert(path.clone(), start..buf.len() as u64);
+        path.truncate(len);
+        Ok::<_, Error>(())
+    };
+    match value {
+        Value::Array(a) if depth > 0 => {
+            bincode::encode_into_std_write((wire::ARRAY, a.len() as u64), buf, config)?;
+            for (i, v) in a.iter().enumerate() {
+                path.push_str(&format!("[{}]", i));
+                child(path, v, buf)?;
+            }
+        }
+        Value::Object(o) if depth > 0 => {
+            bincode::encode_into_std_write((wire::OBJECT, o.len() as u64), buf, config)?;
+            for (k, v) in o {
+                bincode::encode_into_std_write(k, buf, config)?;
+                if len != 0 {
+                    path.push('.');
+                }
+                path.push_str(k);
+                child(path, v, buf)?;
+            }
+        }
+        value => {
+            bincode::encode_into_std_write(value, buf, config)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use std::io::Cursor;
+
+    fn document() -> Value {
+        let user = |name: &str| {
+            Value::Object(Map::from([
+                (String::from("name"), Value::String(name.into())),
+                (String::from("id"), Value::Integer(name.len() as i64)),
+            ]))
+        };
+        Value::Object(Map::from([
+            (
+                String::from("users"),
+                Value::Array(vec![user("a"), user("bc")]),
+            ),
+            (String::from("count"), Value::Integer(2)),
+        ]))
+    }
+
+    #[test]
+    fn round_trip() {
+        let (bytes, index) = to_vec_indexed(&document(), 3).unwrap();
+        assert_eq!(index.len(), 8);
+        for (path, _) in index.iter() {
+            let indexed = index.get_raw(&bytes, path).unwrap().to_value().unwrap();
+            let extracted: Value = crate::extract(&bytes, path).unwrap();
+            assert_eq!(
+                crate::to_vec_canonical(&indexed).unwrap(),
+                crate::to_vec_canonical(&extracted).unwrap()
+            );
+        }
+
+        let name: String = index.read_at(Cursor::new(&bytes), "users[1].name").unwrap();
+        assert_eq!(name, "bc");
+
+        let mut written = Vec::new();
+        assert_eq!(
+            to_writer_indexed(&mut written, &document(), 1)
+                .unwrap()
+                .len(),
+            2
+        );
+        let decoded: Value = crate::from_slice(&written).unwrap();
+        assert_eq!(
+            crate::to_vec_canonical(&decoded).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+
+        assert_eq!(OffsetIndex::from_slice(&index.to_vec()).unwrap(), index);
+        assert!(to_vec_indexed(&document(), 0).unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let (bytes, index) = to_vec_indexed(&document(), 1).unwrap();
+        assert_eq!(index.get("users[0]"), None);
+        let error = index
+            .read_at::<_, Value>(Cursor::new(&bytes), "users[0]")
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::MissingField);
+        let error = index
+            .read_at::<_, String>(Cursor::new(&bytes), "count")
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        assert!(index.get_raw(&bytes[..4], "count").is_none());
+
+        let error = OffsetIndex::from_slice(&crate::to_vec(&1u32).unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let reversed = Value::Object(Map::from([(
+            String::from("a"),
+            Value::Array(vec![Value::Integer(2), Value::Integer(1)]),
+        )]));
+        let error = OffsetIndex::from_slice(&crate::to_vec(&reversed).unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = OffsetIndex::from_slice(&[0xff]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
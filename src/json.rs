@@ -0,0 +1,525 @@
+//! Options of the conversion between [Value] and [serde_json::Value].
+//!
+//! JSON has no binary type, so [Value::to_json] renders blobs as base64 strings, which convert
+//! back into strings. With [JsonOptions::with_tagged_blobs], blobs are rendered as
+//! `{"$binary": "<base64>"}` instead, which `From<serde_json::Value>` recognizes, making the
+//! round-trip lossless.
+//!
+//! Blobs are encoded as standard, padded base64 by default. [JsonOptions::with_blob_encoding]
+//! selects another [BlobEncoding], which [Value::from_json_with] then expects of tagged blobs.
+//! With the `base64-simd` feature, base64 is encoded and decoded with SIMD instructions where the
+//! CPU supports them, which speeds up converting blob-heavy documents.
+//!
+//! [JsonOptions::with_extended_json] renders values as MongoDB Extended JSON v2 instead, so that
+//! 64-bit integers, blobs and non-finite floats survive tools which treat every JSON number as a
+//! double: integers as `{"$numberLong": "<digits>"}`, blobs as
+//! `{"$binary": {"base64": "<base64>", "subType": "00"}}` and non-finite floats as
+//! `{"$numberDouble": "NaN"}`. Finite floats are plain numbers, as in relaxed Extended JSON.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value},
+};
+
+/// The key of the single-entry object a tagged blob is rendered as.
+pub const BINARY_TAG: &str = "$binary";
+
+/// The key of an Extended JSON 64-bit integer.
+const NUMBER_LONG: &str = "$numberLong";
+
+/// The key of an Extended JSON 32-bit integer.
+const NUMBER_INT: &str = "$numberInt";
+
+/// The key of an Extended JSON float.
+const NUMBER_DOUBLE: &str = "$numberDouble";
+
+/// The text encoding blobs are rendered in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BlobEncoding {
+    /// Standard base64 with padding.
+    #[default]
+    Base64,
+
+    /// Standard base64 without padding.
+    Base64NoPad,
+
+    /// URL-safe base64 with padding.
+    UrlSafeBase64,
+
+    /// URL-safe base64 without padding.
+    UrlSafeBase64NoPad,
+
+    /// Lowercase hexadecimal. Uppercase digits are accepted when decoding.
+    Hex,
+}
+impl BlobEncoding {
+    /// Encodes `blob` as text.
+    pub fn encode(self, blob: &[u8]) -> String {
+        #[cfg(feature = "base64-simd")]
+        if let Some(engine) = self.simd_engine() {
+            return engine.encode_to_string(blob);
+        }
+        match self.base64_config() {
+            Some(config) => base64::encode_config(blob, config),
+            None => {
+                const DIGITS: &[u8; 16] = b"0123456789abcdef";
+                let mut hex = String::with_capacity(blob.len() * 2);
+                for b in blob {
+                    hex.push(DIGITS[(b >> 4) as usize] as char);
+                    hex.push(DIGITS[(b & 0xf) as usize] as char);
+                }
+                hex
+            }
+        }
+    }
+
+    /// Decodes text encoded by [BlobEncoding::encode], returning `None` if it is invalid.
+    pub fn decode(self, text: &str) -> Option<Vec<u8>> {
+        // The SIMD decoder only accepts canonical base64, so other text falls back to the
+        // lenient decoder.
+        #[cfg(feature = "base64-simd")]
+        if let Some(Ok(blob)) = self.simd_engine().map(|engine| engine.decode_to_vec(text)) {
+            return Some(blob);
+        }
+        match self.base64_config() {
+            Some(config) => base64::decode_config(text, config).ok(),
+            None => {
+                let pairs = text.as_bytes().chunks_exact(2);
+                if !pairs.remainder().is_empty() {
+                    return None;
+                }
+                pairs
+                    .map(|pair| Some(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+                    .collect()
+            }
+        }
+    }
+
+    fn base64_config(self) -> Option<base64::Config> {
+        match self {
+            Self::Base64 => Some(base64::STANDARD),
+            Self::Base64NoPad => Some(base64::STANDARD_NO_PAD),
+            Self::UrlSafeBase64 => Some(base64::URL_SAFE),
+            Self::UrlSafeBase64NoPad => Some(base64::URL_SAFE_NO_PAD),
+            Self::Hex => None,
+        }
+    }
+
+    #[cfg(feature = "base64-simd")]
+    fn simd_engine(self) -> Option<&'static base64_simd::Base64> {
+        match self {
+            Self::Base64 => Some(&base64_simd::STANDARD),
+            Self::Base64NoPad => Some(&base64_simd::STANDARD_NO_PAD),
+            Self::UrlSafeBase64 => Some(&base64_simd::URL_SAFE),
+            Self::UrlSafeBase64NoPad => Some(&base64_simd::URL_SAFE_NO_PAD),
+            Self::Hex => None,
+        }
+    }
+}
+
+/// Options of [Value::to_json_with] and [Value::from_json_with].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct JsonOptions {
+    /// Whether blobs are rendered as `{"$binary": "<encoded>"}` rather than plain strings.
+    pub tagged_blobs: bool,
+
+    /// The text encoding blobs are rendered in.
+    pub blob_encoding: BlobEncoding,
+
+    /// Whether values are rendered as MongoDB Extended JSON v2. This takes precedence over the
+    /// options of blobs.
+    pub extended_json: bool,
+}
+impl JsonOptions {
+    /// Constructs a new [JsonOptions] with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether blobs are rendered as `{"$binary": "<encoded>"}` rather than plain strings.
+    pub fn with_tagged_blobs(mut self, tagged_blobs: bool) -> Self {
+        self.tagged_blobs = tagged_blobs;
+        self
+    }
+
+    /// Sets the text encoding blobs are rendered in.
+    pub fn with_blob_encoding(mut self, blob_encoding: BlobEncoding) -> Self {
+        self.blob_encoding = blob_encoding;
+        self
+    }
+
+    /// Sets whether values are rendered as MongoDB Extended JSON v2.
+    pub fn with_extended_json(mut self, extended_json: bool) -> Self {
+        self.extended_json = extended_json;
+        self
+    }
+}
+
+impl Value {
+    /// Converts a [Value] to a [serde_json::Value] with `options`.
+    pub fn to_json_with(self, options: &JsonOptions) -> serde_json::Value {
+        match self.convert_to_json(options, false) {
+            Ok(json) => json,
+            Err(_) => unreachable!("lenient conversions never fail"),
+        }
+    }
+
+    /// Converts a [Value] to a [serde_json::Value] with `options`, failing with
+    /// [Error::Unsupported] rather than degrading data JSON cannot represent exactly: blobs and
+    /// non-finite floats, unless [JsonOptions::tagged_blobs] or [JsonOptions::extended_json]
+    /// is set respectively.
+    pub fn try_to_json_with(self, options: &JsonOptions) -> Result<serde_json::Value> {
+        self.convert_to_json(options, true)
+    }
+
+    /// Converts a [Value] to a [serde_json::Value], degrading lossy data unless `strict`.
+    fn convert_to_json(self, options: &JsonOptions, strict: bool) -> Result<serde_json::Value> {
+        Ok(match self {
+            Self::Null => serde_json::Value::Null,
+            Self::Blob(blob) if options.extended_json => {
+                let mut binary = serde_json::Map::with_capacity(2);
+                binary.insert("base64".into(), BlobEncoding::Base64.encode(&blob).into());
+                binary.insert("subType".into(), "00".into());
+                extended(BINARY_TAG, binary.into())
+            }
+            Self::Blob(blob) => {
+                let encoded = serde_json::Value::String(options.blob_encoding.encode(&blob));
+                if options.tagged_blobs {
+                    let mut map = serde_json::Map::with_capacity(1);
+                    map.insert(BINARY_TAG.into(), encoded);
+                    serde_json::Value::Object(map)
+                } else if strict {
+                    return Err(Error::Unsupported("untagged blob in JSON".into()));
+                } else {
+                    encoded
+                }
+            }
+            Self::Boolean(b) => serde_json::Value::Bool(b),
+            Self::Integer(i) if options.extended_json => {
+                extended(NUMBER_LONG, i.to_string().into())
+            }
+            Self::Integer(i) => serde_json::Value::Number(i.into()),
+            Self::Float(f) => match serde_json::Number::from_f64(f) {
+                Some(n) => serde_json::Value::Number(n),
+                None if options.extended_json => {
+                    let text = match f {
+                        f if f.is_nan() => "NaN",
+                        f if f > 0.0 => "Infinity",
+                        _ => "-Infinity",
+                    };
+                    extended(NUMBER_DOUBLE, text.into())
+                }
+                None if strict => {
+                    return Err(Error::Unsupported(format!("float {} in JSON", f)));
+                }
+                None => serde_json::Value::String(f.to_string()),
+            },
+            Self::Object(o) => {
+                let mut map = serde_json::Map::with_capacity(o.len());
+                for (k, v) in o {
+                    map.insert(k, v.convert_to_json(options, strict)?);
+                }
+                serde_json::Value::Object(map)
+            }
+            Self::String(s) => serde_json::Value::String(s),
+            Self::Array(a) => {
+                let mut arr = Vec::with_capacity(a.len());
+                for v in a {
+                    arr.push(v.convert_to_json(options, strict)?);
+                }
+                serde_json::Value::Array(arr)
+            }
+        })
+    }
+
+    /// Converts a [serde_json::Value] to a [Value] with `options`, decoding tagged blobs in
+    /// [JsonOptions::blob_encoding], or Extended JSON if [JsonOptions::extended_json] is set.
+    ///
+    /// Tagged blobs are recognized regardless of [JsonOptions::tagged_blobs], while plain strings
+    /// always convert to strings, as they cannot be told apart from blobs.
+    pub fn from_json_with(json: serde_json::Value, options: &JsonOptions) -> Self {
+        match json {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::Bool(b) => Self::Boolean(b),
+            serde_json::Value::Array(array) => {
+                let mut arr: Vec<Value> = Vec::with_capacity(array.len());
+                for i in array {
+                    arr.push(Self::from_json_with(i, options));
+                }
+                Self::Array(arr)
+            }
+            serde_json::Value::Number(n) => {
+                if n.is_i64() {
+                    Self::Integer(n.as_i64().unwrap())
+                } else if n.is_u64() {
+                    Self::Integer(n.as_u64().unwrap() as _)
+                } else if n.is_f64() {
+                    Self::Float(n.as_f64().unwrap())
+                } else {
+                    unreachable!()
+                }
+            }
+            serde_json::Value::Object(o) => match parse_extended(&o, options)
+                .or_else(|| tagged_blob(&o, options.blob_encoding).map(Self::Blob))
+            {
+                Some(value) => value,
+                None => {
+                    let mut map = Map::with_capacity(o.len());
+                    for (k, v) in o {
+                        map.insert(k, Self::from_json_with(v, options));
+                    }
+                    Self::Object(map)
+                }
+            },
+            serde_json::Value::String(s) => Self::String(s),
+        }
+    }
+}
+
+/// Renders a single-entry Extended JSON object.
+fn extended(key: &str, value: serde_json::Value) -> serde_json::Value {
+    let mut map = serde_json::Map::with_capacity(1);
+    map.insert(key.into(), value);
+    serde_json::Value::Object(map)
+}
+
+/// Recognizes an Extended JSON integer, float or blob, if [JsonOptions::extended_json] is set.
+fn parse_extended(
+    object: &serde_json::Map<String, serde_json::Value>,
+    options: &JsonOptions,
+) -> Option<Value> {
+    if !options.extended_json || object.len() != 1 {
+        return None;
+    }
+    let (key, value) = object.iter().next()?;
+    match (key.as_str(), value) {
+        (NUMBER_LONG | NUMBER_INT, serde_json::Value::String(s)) => {
+            s.parse().ok().map(Value::Integer)
+        }
+        (NUMBER_DOUBLE, serde_json::Value::String(s)) => match s.as_str() {
+            "Infinity" => Some(Value::Float(f64::INFINITY)),
+            "-Infinity" => Some(Value::Float(f64::NEG_INFINITY)),
+            "NaN" => Some(Value::Float(f64::NAN)),
+            s => s.parse().ok().map(Value::Float),
+        },
+        (BINARY_TAG, serde_json::Value::Object(binary)) => match binary.get("base64")? {
+            serde_json::Value::String(s) => BlobEncoding::Base64.decode(s).map(Value::Blob),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Recognizes a tagged blob: an object whose only entry maps [BINARY_TAG] to a string encoded in
+/// `encoding`.
+fn tagged_blob(
+    object: &serde_json::Map<String, serde_json::Value>,
+    encoding: BlobEncoding,
+) -> Option<Vec<u8>> {
+    if object.len() != 1 {
+        return None;
+    }
+    match object.get(BINARY_TAG)? {
+        serde_json::Value::String(s) => encoding.decode(s),
+        _ => None,
+    }
+}
+
+/// Gets the value of an ASCII hexadecimal digit.
+fn hex_digit(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use serde_json::json;
+
+    fn document() -> Value {
+        Value::Array(vec![
+            Value::Null,
+            Value::Boolean(true),
+            Value::Blob(vec![0, 1, 0xfe, 0xff]),
+            Value::Integer(-42),
+            Value::Float(1.5),
+            Value::Object(Map::from([(
+                String::from("key"),
+                Value::String("text".into()),
+            )])),
+        ])
+    }
+
+    fn encoded(value: Value) -> Vec<u8> {
+        value.to_vec().unwrap()
+    }
+
+    #[test]
+    fn tagged_blobs() {
+        let options = JsonOptions::new().with_tagged_blobs(true);
+        let json = document().to_json_with(&options);
+        assert_eq!(json[2], json!({ "$binary": "AAH+/w==" }));
+        assert_eq!(encoded(Value::from(json)), encoded(document()));
+
+        let json = document().to_json();
+        assert_eq!(json[2], json!("AAH+/w=="));
+        assert!(matches!(Value::from(json), Value::Array(a) if matches!(&a[2], Value::String(_))));
+    }
+
+    #[test]
+    fn malformed_tagged_blobs() {
+        for json in [
+            json!({ "$binary": "not base64!" }),
+            json!({ "$binary": 1 }),
+            json!({ "$binary": "AAH+/w==", "other": 1 }),
+        ] {
+            let len = json.as_object().unwrap().len();
+            match Value::from(json) {
+                Value::Object(o) => {
+                    assert_eq!(o.len(), len);
+                    assert!(o.contains_key(BINARY_TAG));
+                }
+                other => panic!("expected an object, found {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn blob_encodings() {
+        let blob = vec![0xfb, 0xff, 0x0f];
+        let cases = [
+            (BlobEncoding::Base64, "+/8P"),
+            (BlobEncoding::Base64NoPad, "+/8P"),
+            (BlobEncoding::UrlSafeBase64, "-_8P"),
+            (BlobEncoding::UrlSafeBase64NoPad, "-_8P"),
+            (BlobEncoding::Hex, "fbff0f"),
+        ];
+        for (encoding, text) in cases {
+            assert_eq!(encoding.encode(&blob), text);
+            assert_eq!(encoding.decode(text), Some(blob.clone()));
+
+            let options = JsonOptions::new()
+                .with_tagged_blobs(true)
+                .with_blob_encoding(encoding);
+            let json = Value::Blob(blob.clone()).to_json_with(&options);
+            assert_eq!(json, json!({ "$binary": text }));
+            let value = Value::from_json_with(json, &options);
+            assert!(matches!(value, Value::Blob(b) if b == blob));
+        }
+        assert_eq!(BlobEncoding::Base64.encode(&[1]), "AQ==");
+        assert_eq!(BlobEncoding::Base64NoPad.encode(&[1]), "AQ");
+        assert_eq!(BlobEncoding::Hex.decode("FBFF0F"), Some(blob));
+    }
+
+    #[test]
+    fn invalid_blob_encodings() {
+        assert_eq!(BlobEncoding::Hex.decode("abc"), None);
+        assert_eq!(BlobEncoding::Hex.decode("zz"), None);
+        assert_eq!(BlobEncoding::Base64.decode("-_8P"), None);
+        assert_eq!(BlobEncoding::UrlSafeBase64.decode("+/8P"), None);
+
+        let options = JsonOptions::new().with_blob_encoding(BlobEncoding::Hex);
+        let value = Value::from_json_with(json!({ "$binary": "+/8P" }), &options);
+        assert!(matches!(value, Value::Object(_)));
+    }
+
+    #[test]
+    fn try_to_json() {
+        let value = Value::Object(Map::from([(String::from("n"), Value::Integer(1))]));
+        assert_eq!(value.try_to_json().unwrap(), json!({ "n": 1 }));
+        let options = JsonOptions::new().with_tagged_blobs(true);
+        let json = document().try_to_json_with(&options).unwrap();
+        assert_eq!(json, document().to_json_with(&options));
+    }
+
+    #[test]
+    fn try_to_json_errors() {
+        let error = document().try_to_json().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let value = Value::Array(vec![Value::Float(f)]);
+            let error = value.clone().try_to_json().unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Unsupported);
+            assert!(value.to_json()[0].is_string());
+        }
+    }
+
+    #[test]
+    fn extended_json() {
+        let options = JsonOptions::new().with_extended_json(true);
+        let value = Value::Array(vec![
+            Value::Integer(i64::MAX),
+            Value::Blob(vec![1, 2, 3]),
+            Value::Float(f64::NEG_INFINITY),
+            Value::Float(1.5),
+        ]);
+        let json = value.try_to_json_with(&options).unwrap();
+        assert_eq!(
+            json,
+            json!([
+                { "$numberLong": "9223372036854775807" },
+                { "$binary": { "base64": "AQID", "subType": "00" } },
+                { "$numberDouble": "-Infinity" },
+                1.5,
+            ])
+        );
+
+        let Value::Array(a) = Value::from_json_with(json, &options) else {
+            panic!("expected an array");
+        };
+        assert!(matches!(a[0], Value::Integer(i64::MAX)));
+        assert!(matches!(&a[1], Value::Blob(b) if b == &[1, 2, 3]));
+        assert!(matches!(a[2], Value::Float(f) if f == f64::NEG_INFINITY));
+        assert!(matches!(a[3], Value::Float(f) if f == 1.5));
+
+        let nan = Value::from_json_with(json!({ "$numberDouble": "NaN" }), &options);
+        assert!(matches!(nan, Value::Float(f) if f.is_nan()));
+        let int = Value::from_json_with(json!({ "$numberInt": "-7" }), &options);
+        assert!(matches!(int, Value::Integer(-7)));
+    }
+
+    #[test]
+    fn malformed_extended_json() {
+        let options = JsonOptions::new().with_extended_json(true);
+        for json in [
+            json!({ "$numberLong": "x" }),
+            json!({ "$numberLong": 1 }),
+            json!({ "$numberDouble": "fast" }),
+            json!({ "$binary": { "subType": "00" } }),
+            json!({ "$numberLong": "1", "other": 1 }),
+        ] {
+            assert!(matches!(
+                Value::from_json_with(json, &options),
+                Value::Object(_)
+            ));
+        }
+        let plain = Value::from_json_with(json!({ "$numberLong": "1" }), &JsonOptions::new());
+        assert!(matches!(plain, Value::Object(_)));
+    }
+
+    #[cfg(feature = "base64-simd")]
+    #[test]
+    fn simd_blob_encodings() {
+        let blob: Vec<u8> = (0..=255).collect();
+        for encoding in [
+            BlobEncoding::Base64,
+            BlobEncoding::Base64NoPad,
+            BlobEncoding::UrlSafeBase64,
+            BlobEncoding::UrlSafeBase64NoPad,
+        ] {
+            let config = encoding.base64_config().unwrap();
+            for len in 0..blob.len() {
+                let text = encoding.encode(&blob[..len]);
+                assert_eq!(text, base64::encode_config(&blob[..len], config));
+                assert_eq!(encoding.decode(&text).as_deref(), Some(&blob[..len]));
+            }
+            assert_eq!(encoding.decode("not base64!"), None);
+        }
+
+        let options = JsonOptions::new().with_extended_json(true);
+        let value = Value::Blob(blob.clone());
+        let json = value.try_to_json_with(&options).unwrap();
+        let value = Value::from_json_with(json, &options);
+        assert!(matches!(value, Value::Blob(b) if b == blob));
+    }
+}
@@ -0,0 +1,121 @@
+//! Parsing JSON5, e.g. human-edited configuration files, into [Value]s.
+//!
+//! JSON5 extends JSON with comments, trailing commas, unquoted keys, single-quoted strings and
+//! more number syntaxes, such as hexadecimal integers, `Infinity` and `NaN`. Unlike going through
+//! `serde_json::Value`, non-finite floats are kept as floats.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value},
+};
+use serde::de::{self, Deserialize, Deserializer};
+use std::fmt;
+
+/// Parses JSON5 text into a [Value].
+pub fn from_json5(text: &str) -> Result<Value> {
+    ::json5::from_str::<Json5Value>(text)
+        .map(|value| value.0)
+        .map_err(|e| Error::Custom(e.to_string()))
+}
+
+/// A [Value] deserialized from JSON5, where null is [Value::Null].
+struct Json5Value(Value);
+impl<'de> Deserialize<'de> for Json5Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(Json5Visitor).map(Json5Value)
+    }
+}
+
+struct Json5Visitor;
+impl<'de> de::Visitor<'de> for Json5Visitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON5 value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<Value, E> {
+        Ok(Value::Boolean(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<Value, E> {
+        Ok(Value::Integer(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<Value, E> {
+        Ok(Value::Integer(v as _))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Value, E> {
+        Ok(Value::String(v.into()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> std::result::Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Value, A::Error> {
+        let mut array = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(Json5Value(value)) = seq.next_element()? {
+            array.push(value);
+        }
+        Ok(Value::Array(array))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> std::result::Result<Value, A::Error> {
+        let mut object = Map::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, Json5Value(value))) = map.next_entry()? {
+            object.insert(key, value);
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn parse() {
+        let text = "{
+            // comment
+            unquoted: 'single',
+            hex: 0x1F,
+            list: [null, true, -1.5, Infinity, NaN,],
+        }";
+        let Value::Object(o) = from_json5(text).unwrap() else {
+            panic!("expected an object");
+        };
+        assert!(matches!(&o["unquoted"], Value::String(s) if s == "single"));
+        assert!(matches!(o["hex"], Value::Integer(31)));
+        let Value::Array(list) = &o["list"] else {
+            panic!("expected an array");
+        };
+        assert!(list[0].is_null());
+        assert!(matches!(list[1], Value::Boolean(true)));
+        assert!(matches!(list[2], Value::Float(f) if f == -1.5));
+        assert!(matches!(list[3], Value::Float(f) if f == f64::INFINITY));
+        assert!(matches!(list[4], Value::Float(f) if f.is_nan()));
+
+        let bytes = crate::to_vec(&o).unwrap();
+        let decoded: Value = crate::from_slice(&bytes).unwrap();
+        assert!(matches!(decoded, Value::Object(d) if d.len() == 3));
+    }
+
+    #[test]
+    fn errors() {
+        for text in ["", "{", "[1 2]", "{a: }"] {
+            let error = from_json5(text).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Custom, "{}", text);
+        }
+    }
+}
@@ -1,36 +1,455 @@
 //! `bincode-json` is a wrapper around `bincode` to encode/decode JSON-like objects.
 //!
+//! The [Value] type, its serializer and deserializer, and [to_vec]/[from_slice] only need `alloc`,
+//! so the crate can be used on `no_std` targets by disabling the default `std` feature.
+//!
 //! ## Features
-//!  - `json`: enables converting from/to `serde_json::Value`.
+//!  - `std` (default): enables everything beyond the core, such as I/O, compression and
+//!    envelopes. Every other feature except `bytes` implies it.
+//!  - `json`: enables converting from/to `serde_json::Value`, and NDJSON streams.
+//!  - `tokio`: enables asynchronous encoding/decoding over `tokio`'s I/O traits.
+//!  - `futures`: enables `Sink`/`Stream` adapters of length-prefixed frames over `futures`' I/O traits.
+//!  - `bytes`: enables encoding/decoding over the `bytes` crate's `BufMut`/`Buf` traits.
+//!  - `codec`: enables a `tokio-util` codec of length-prefixed frames.
+//!  - `zstd`: enables Zstandard compression.
+//!  - `deflate`: enables DEFLATE compression.
+//!  - `crc32c`: enables CRC-32C checksums.
+//!  - `xxhash`: enables xxHash checksums.
+//!  - `digest`: enables content hashing and Merkle trees with any `digest` hash function.
+//!  - `hmac`: enables HMAC-SHA256 signed envelopes.
+//!  - `legacy`: enables the wire layout preset compatible with bincode 1.x.
+//!  - `mmap`: enables decoding from memory-mapped files.
+//!  - `wasm`: enables converting from/to `wasm-bindgen`'s `JsValue`.
+//!  - `bson`: enables converting from/to `bson::Bson` and `bson::Document`.
+//!  - `cbor`: enables converting from/to `ciborium::Value`.
+//!  - `msgpack`: enables converting from/to `rmpv::Value` and MessagePack.
+//!  - `yaml`: enables converting from/to `serde_yaml::Value`.
+//!  - `toml`: enables converting from/to `toml::Value` and `toml::Table`.
+//!  - `ron`: enables converting from/to `ron::Value`.
+//!  - `simd-json`: enables parsing JSON with `simd-json` and converting from its values.
+//!  - `protobuf`: enables converting from/to `prost-types`' `Struct` and `Value`.
+//!  - `avro`: enables converting from/to `apache-avro` values.
+//!  - `arrow`: enables converting arrays of objects from/to Arrow `RecordBatch`es.
+//!  - `csv`: enables CSV export/import of arrays of flat objects.
+//!  - `json5`: enables parsing JSON5 into values.
+//!  - `xml`: enables converting XML documents from/to values.
+//!  - `axum`: enables an `axum` extractor and response of Bincode JSON bodies.
+//!  - `actix`: enables an `actix-web` extractor and responder of Bincode JSON bodies.
+//!  - `http`: enables `reqwest` helpers sending and receiving Bincode JSON bodies.
+//!  - `rocket`: enables a Rocket data guard and responder of Bincode JSON bodies.
+//!  - `redis`: enables passing Bincode JSON documents as `redis` arguments and responses.
+//!  - `sqlx`: enables storing Bincode JSON documents in Postgres `BYTEA` columns with `sqlx`.
+//!  - `rusqlite`: enables storing Bincode JSON documents in SQLite `BLOB` columns with `rusqlite`.
+//!  - `sled`: enables storing Bincode JSON documents in `sled` trees.
+//!  - `dynamodb`: enables converting from/to DynamoDB's `AttributeValue` and items.
+//!  - `uuid`: enables converting `Uuid`s from/to compact 16-byte blobs.
+//!  - `chrono`: enables storing `chrono` dates and times as epoch milliseconds.
+//!  - `time`: enables storing `time` date-times as epoch milliseconds.
+//!  - `decimal`: enables storing `rust_decimal`'s `Decimal`s losslessly as strings.
+//!  - `figment`: enables a `figment` provider of Bincode JSON configuration.
+//!  - `python`: enables PyO3 conversions from/to Python objects and Python bindings.
+//!  - `borsh`: enables borsh encoding of values and conversions of typed documents.
+//!  - `schemars`: enables validation of values against the JSON Schema of a type.
+//!  - `tracing`: enables `tracing` spans and events of top-level encoding and decoding.
+//!  - `cli`: builds the `bincode-json` command-line tool, converting, querying and diffing
+//!    documents.
+//!  - `backtrace`: enables capturing backtraces where errors are created.
+//!  - `miette`: enables `miette` diagnostics of errors, labeling where decoding stopped.
+//!  - `compact_str`: enables `CompactValue`, which stores short strings and keys inline.
+//!  - `rayon`: enables encoding large arrays and objects in parallel with `rayon`.
+//!  - `ahash`: hashes the keys of `Map`s with `ahash`, which is faster than the default SipHash
+//!    but offers weaker guarantees against HashDoS attacks. The API of `Map` is the same either way.
+//!  - `base64-simd`: enables SIMD-accelerated base64 of blobs converted from/to JSON.
+
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+#[cfg(feature = "actix")]
+pub mod actix;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "tokio")]
+pub mod async_io;
+#[cfg(feature = "avro")]
+pub mod avro;
+#[cfg(feature = "axum")]
+pub mod axum;
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod blob;
+pub mod borrowed;
+#[cfg(feature = "borsh")]
+pub mod borsh;
+#[cfg(feature = "bson")]
+pub mod bson;
+#[cfg(feature = "bytes")]
+pub mod buf;
+#[cfg(feature = "std")]
+pub mod canonical;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "std")]
+pub mod checksum;
+#[cfg(feature = "chrono")]
+pub mod chrono;
+#[cfg(feature = "std")]
+pub mod chunked;
+#[cfg(feature = "codec")]
+pub mod codec;
+#[cfg(feature = "compact_str")]
+pub mod compact;
+#[cfg(feature = "std")]
+pub mod compress;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "csv")]
+pub mod csv;
 pub mod de;
+#[cfg(feature = "decimal")]
+pub mod decimal;
+pub mod delta;
+pub mod dict;
+#[cfg(feature = "dynamodb")]
+pub mod dynamodb;
+#[cfg(feature = "std")]
+pub mod envelope;
 pub mod error;
+#[cfg(feature = "std")]
+pub mod event;
+#[cfg(feature = "figment")]
+pub mod figment;
+#[cfg(feature = "std")]
+pub mod file;
+#[cfg(feature = "std")]
+mod frame;
+#[cfg(feature = "futures")]
+pub mod framed;
+#[cfg(feature = "digest")]
+pub mod hash;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod incremental;
+#[cfg(feature = "std")]
+pub mod index;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "json5")]
+pub mod json5;
+pub mod limits;
+#[cfg(feature = "std")]
+pub mod log;
+pub mod map;
+pub mod merge;
+#[cfg(feature = "digest")]
+pub mod merkle;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "miette")]
+mod miette;
+#[cfg(feature = "std")]
+pub mod migrate;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+#[cfg(feature = "json")]
+pub mod ndjson;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod raw;
+pub mod redact;
+#[cfg(feature = "redis")]
+pub mod redis;
+#[cfg(feature = "rocket")]
+pub mod rocket;
+#[cfg(feature = "ron")]
+pub mod ron;
+#[cfg(feature = "rusqlite")]
+pub mod rusqlite;
+#[cfg(feature = "schemars")]
+pub mod schemars;
+pub mod selector;
 pub mod ser;
+pub mod shared;
+#[cfg(feature = "std")]
+pub mod signed;
+#[cfg(feature = "simd-json")]
+pub mod simd_json;
+#[cfg(feature = "sled")]
+pub mod sled;
+#[cfg(feature = "sqlx")]
+pub mod sqlx;
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(test)]
+mod test_util;
+#[cfg(feature = "time")]
+pub mod time;
+#[cfg(feature = "toml")]
+pub mod toml;
+#[cfg(feature = "tracing")]
+mod trace;
+#[cfg(feature = "std")]
+pub mod transcode;
+pub mod tree;
+pub mod unchecked;
+#[cfg(feature = "uuid")]
+pub mod uuid;
+pub mod validate;
 pub mod value;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod wire;
+#[cfg(feature = "xml")]
+pub mod xml;
+#[cfg(feature = "yaml")]
+pub mod yaml;
 
-pub use error::{Error, Result};
-pub use value::Value;
+#[cfg(feature = "tokio")]
+pub use async_io::{from_reader_async, to_writer_async, to_writer_async_iter};
+pub use batch::{
+    from_slice_many, from_slice_many_dict, from_slice_many_dict_with_limits,
+    from_slice_many_with_limits, to_vec_many, to_vec_many_dict, EncodedBatch,
+};
+#[cfg(feature = "std")]
+pub use blob::{from_reader_with_blob_sink, BlobSink};
+pub use borrowed::BorrowedValue;
+#[cfg(feature = "bytes")]
+pub use buf::{decode_from_buf, encode_to_buf};
+#[cfg(feature = "std")]
+pub use canonical::to_vec_canonical;
+#[cfg(feature = "std")]
+pub use checksum::{from_slice_checksummed, to_vec_checksummed, Checksum};
+#[cfg(feature = "std")]
+pub use chunked::{to_writer_chunked, to_writer_chunked_iter, ChunkedOptions};
+#[cfg(feature = "compact_str")]
+pub use compact::CompactValue;
+#[cfg(feature = "std")]
+pub use compress::{
+    from_reader_compressed, from_reader_compressed_with_limits, from_slice_compressed,
+    from_slice_compressed_with_limits, to_vec_compressed, to_writer_compressed, Compression,
+};
+pub use dict::{
+    from_slice_dict, from_slice_dict_with_limits, to_vec_dict, to_vec_dict_with, DictOptions,
+};
+#[cfg(feature = "std")]
+pub use envelope::{
+    from_slice_enveloped, from_slice_enveloped_with_limits, to_vec_enveloped, Envelope,
+};
+pub use error::{Description, Error, ErrorKind, ErrorRepr, Result};
+#[cfg(feature = "std")]
+pub use file::{from_file, to_file};
+#[cfg(feature = "digest")]
+pub use hash::hash_value;
+pub use incremental::{Feed, IncrementalDecoder};
+#[cfg(feature = "std")]
+pub use index::{to_vec_indexed, OffsetIndex};
+pub use limits::DecodeLimits;
+pub use merge::MergeStrategy;
+#[cfg(feature = "digest")]
+pub use merkle::{hash_tree, MerkleTree};
+#[cfg(feature = "std")]
+pub use metrics::{set_metrics_sink, MetricsSink};
+#[cfg(feature = "std")]
+pub use migrate::{from_slice_versioned, to_vec_versioned, Migrations};
+#[cfg(feature = "rayon")]
+pub use parallel::{to_vec_parallel, ParallelOptions};
+pub use raw::{extract, patch, RawDocument};
+pub use redact::Redactor;
+pub use selector::Selector;
+pub use shared::SharedValue;
+#[cfg(feature = "std")]
+pub use signed::{from_slice_verified, to_vec_signed};
+#[cfg(feature = "std")]
+pub use transcode::{transcode, transcode_to, transcode_to_with_limits, transcode_with_limits};
+pub use unchecked::from_slice_utf8_unchecked;
+pub use validate::Validator;
+pub use value::{Value, ValueKind};
 
+use alloc::{vec, vec::Vec};
 use serde::{de::DeserializeOwned, Serialize};
 
+/// The media type of Bincode JSON bodies, e.g. in HTTP.
+pub const CONTENT_TYPE: &str = "application/x-bincode-json";
+
+/// Checks whether a `Content-Type` header value is [CONTENT_TYPE], ignoring parameters.
+#[cfg(any(feature = "actix", feature = "axum", feature = "http"))]
+fn is_content_type(value: &str) -> bool {
+    let essence = value.split(';').next().unwrap_or_default();
+    essence.trim().eq_ignore_ascii_case(CONTENT_TYPE)
+}
+
 /// Interpret a [Value] as an instance of type `T`.
 pub fn from_value<T: DeserializeOwned>(val: Value) -> Result<T> {
     T::deserialize(de::Deserializer::from(val))
 }
 
-/// Convert a `T` into [Value].
+/// Convert a `T` into [Value]. A [Value] is cloned directly rather than serialized.
 pub fn to_value<T: Serialize>(val: &T) -> Result<Value> {
-    val.serialize(ser::Serializer::new())
+    match as_value(val) {
+        Some(value) => Ok(value.clone()),
+        None => val.serialize(ser::Serializer::new()),
+    }
+}
+
+/// Gets `val` as a [Value], if `T` is [Value].
+fn as_value<T: ?Sized>(val: &T) -> Option<&Value> {
+    match typeid::of::<T>() == typeid::of::<Value>() {
+        // SAFETY: `typeid` only erases lifetimes, and `Value` has none, so `T` is exactly `Value`.
+        true => Some(unsafe { &*(val as *const T as *const Value) }),
+        false => None,
+    }
 }
 
-/// Serialize the given data structure as a byte vector.
+/// Serialize the given data structure as a byte vector. A [Value] is encoded directly rather
+/// than converted first, like [Value::to_vec].
 pub fn to_vec<T: Serialize>(val: &T) -> Result<Vec<u8>> {
-    let value = to_value(val)?;
-    Ok(bincode::encode_to_vec(value, bincode::config::standard())?)
+    encode_instrumented::<T>(|| match as_value(val) {
+        Some(value) => encode_value(value),
+        None => encode_value(&to_value(val)?),
+    })
+}
+
+/// Encodes `value` as a byte vector.
+pub(crate) fn encode_value(value: &Value) -> Result<Vec<u8>> {
+    // Sizing the output first allocates it once, however large the document.
+    let mut buf = vec![0; wire::encoded_len(value)];
+    let len = bincode::encode_into_slice(value, &mut buf, bincode::config::standard())?;
+    debug_assert_eq!(len, buf.len());
+    Ok(buf)
 }
 
-/// Deserialize an instance of type `T` from bytes of Bincode JSON.
+/// Runs `encode` of a `T`, recording its trace and metrics where enabled.
+#[cfg_attr(not(feature = "tracing"), allow(clippy::extra_unused_type_parameters))]
+pub(crate) fn encode_instrumented<T: ?Sized>(
+    encode: impl FnOnce() -> Result<Vec<u8>>,
+) -> Result<Vec<u8>> {
+    #[cfg(feature = "tracing")]
+    let encode = || trace::encode::<T>(encode);
+    #[cfg(feature = "std")]
+    return metrics::encode(encode);
+    #[cfg(not(feature = "std"))]
+    encode()
+}
+
+/// Deserialize an instance of type `T` from bytes of Bincode JSON, enforcing the default
+/// [DecodeLimits].
 pub fn from_slice<T: DeserializeOwned>(val: &[u8]) -> Result<T> {
-    let (value, _) = bincode::decode_from_slice(val, bincode::config::standard())?;
-    from_value(value)
+    from_slice_with_limits(val, &DecodeLimits::default())
+}
+
+/// Deserialize an instance of type `T` from bytes of Bincode JSON, enforcing `limits`.
+pub fn from_slice_with_limits<T: DeserializeOwned>(val: &[u8], limits: &DecodeLimits) -> Result<T> {
+    decode_instrumented(val.len(), || {
+        let (value, _) = wire::decode_from_slice(val, limits)?;
+        from_value(value)
+    })
+}
+
+/// Runs `decode` of `len` bytes, recording its trace and metrics where enabled.
+pub(crate) fn decode_instrumented<T>(len: usize, decode: impl FnOnce() -> Result<T>) -> Result<T> {
+    #[cfg(feature = "tracing")]
+    let decode = || trace::decode(len, decode);
+    #[cfg(feature = "std")]
+    return metrics::decode(len, decode);
+    #[cfg(not(feature = "std"))]
+    {
+        let _ = len;
+        decode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::{
+        collections::BTreeMap,
+        string::{String, ToString},
+    };
+
+    type Document = (
+        Option<bool>,
+        i64,
+        f64,
+        String,
+        Vec<u8>,
+        BTreeMap<String, u32>,
+    );
+
+    fn document() -> Document {
+        let map = BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+        (Some(true), -42, 1.5, "text".to_string(), vec![1, 2, 3], map)
+    }
+
+    #[test]
+    fn round_trip() {
+        let val = document();
+        let bytes = to_vec(&val).unwrap();
+        assert_eq!(from_slice::<Document>(&bytes).unwrap(), val);
+        assert_eq!(
+            from_value::<Document>(to_value(&val).unwrap()).unwrap(),
+            val
+        );
+        let bytes = to_value(&val).unwrap().to_vec().unwrap();
+        assert_eq!(from_slice::<Document>(&bytes).unwrap(), val);
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = to_vec(&document()).unwrap();
+        for len in 0..bytes.len() {
+            let error = from_slice::<Document>(&bytes[..len]).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Eof);
+        }
+
+        let error = from_slice::<(String, u32)>(&bytes).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = from_slice::<u8>(&to_vec(&300u32).unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = from_slice::<u8>(&[0xff]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn value_fast_path() {
+        let value = to_value(&document()).unwrap();
+        assert!(as_value(&document()).is_none());
+        assert!(as_value(&&value).is_none());
+        assert!(core::ptr::eq(as_value(&value).unwrap(), &value));
+
+        let bytes = value.to_vec().unwrap();
+        assert_eq!(bytes.len(), to_vec(&document()).unwrap().len());
+        assert_eq!(from_slice::<Document>(&bytes).unwrap(), document());
+        let bytes = to_vec(&value).unwrap();
+        assert_eq!(from_slice::<Document>(&bytes).unwrap(), document());
+        let bytes = to_value(&value).unwrap().to_vec().unwrap();
+        assert_eq!(from_slice::<Document>(&bytes).unwrap(), document());
+
+        let nested = vec![value.clone(), Value::Array(vec![value])];
+        let bytes = to_value(&nested).unwrap().to_vec().unwrap();
+        let (first, [second]) = from_slice::<(Document, [Document; 1])>(&bytes).unwrap();
+        assert_eq!(first, document());
+        assert_eq!(second, document());
+    }
+
+    #[test]
+    fn value_encoding_errors() {
+        let keys = BTreeMap::from([(1u32, 1u32)]);
+        let error = to_value(&keys).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = to_vec(&keys).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+        let value = to_value(&document()).unwrap();
+        let bytes = value.to_vec().unwrap();
+        let error = from_slice::<Document>(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+        let error = from_value::<(String, u32)>(value).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
 }
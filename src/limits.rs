@@ -0,0 +1,216 @@
+//! Resource limits enforced while decoding untrusted input.
+
+use core::fmt::{self, Display};
+
+/// The default of [DecodeLimits::max_expanded_bytes], 64 MiB.
+pub const DEFAULT_MAX_EXPANDED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Limits enforced while decoding, to bound the resources a hostile payload can consume.
+///
+/// Every limit may be set to `usize::MAX` to disable it. [DecodeLimits::default] leaves sizes
+/// unlimited but restricts nesting to a depth of 128, like `serde_json`, which keeps decoding
+/// from overflowing the stack, and strings expanded from references of the
+/// [key dictionary profile](crate::dict) to [DEFAULT_MAX_EXPANDED_BYTES].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DecodeLimits {
+    /// Maximum size of the whole input, in bytes. Strings which documents of the key dictionary
+    /// profile repeat by reference count towards it once per reference, as if they were written
+    /// in full.
+    pub max_bytes: usize,
+
+    /// Maximum length of a string or an object key, in bytes.
+    pub max_string_len: usize,
+
+    /// Maximum length of a blob, in bytes.
+    pub max_blob_len: usize,
+
+    /// Maximum number of elements of an array, or entries of an object.
+    pub max_elements: usize,
+
+    /// Maximum nesting depth of arrays and objects.
+    pub max_depth: usize,
+
+    /// Maximum total length of the strings which a document of the key dictionary profile
+    /// repeats by reference, in bytes.
+    pub max_expanded_bytes: usize,
+}
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            max_expanded_bytes: DEFAULT_MAX_EXPANDED_BYTES,
+            ..Self::unlimited()
+        }
+    }
+}
+impl DecodeLimits {
+    /// Constructs a new [DecodeLimits] with default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a new [DecodeLimits] which limits nothing.
+    pub fn unlimited() -> Self {
+        Self {
+            max_bytes: usize::MAX,
+            max_string_len: usize::MAX,
+            max_blob_len: usize::MAX,
+            max_elements: usize::MAX,
+            max_depth: usize::MAX,
+            max_expanded_bytes: usize::MAX,
+        }
+    }
+
+    /// Sets the maximum size of the whole input, in bytes.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Sets the maximum length of a string or an object key, in bytes.
+    pub fn with_max_string_len(mut self, max_string_len: usize) -> Self {
+        self.max_string_len = max_string_len;
+        self
+    }
+
+    /// Sets the maximum length of a blob, in bytes.
+    pub fn with_max_blob_len(mut self, max_blob_len: usize) -> Self {
+        self.max_blob_len = max_blob_len;
+        self
+    }
+
+    /// Sets the maximum number of elements of an array, or entries of an object.
+    pub fn with_max_elements(mut self, max_elements: usize) -> Self {
+        self.max_elements = max_elements;
+        self
+    }
+
+    /// Sets the maximum nesting depth of arrays and objects.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum total length of the strings which a document of the key dictionary
+    /// profile repeats by reference, in bytes.
+    pub fn with_max_expanded_bytes(mut self, max_expanded_bytes: usize) -> Self {
+        self.max_expanded_bytes = max_expanded_bytes;
+        self
+    }
+
+    /// Fails with [crate::Error::LimitExceeded] if `actual` exceeds the limit of `limit`.
+    pub(crate) fn check(&self, limit: Limit, actual: usize) -> crate::Result<()> {
+        let max = match limit {
+            Limit::Bytes => self.max_bytes,
+            Limit::StringLength => self.max_string_len,
+            Limit::BlobLength => self.max_blob_len,
+            Limit::Elements => self.max_elements,
+            Limit::Depth => self.max_depth,
+            Limit::ExpandedBytes => self.max_expanded_bytes,
+        };
+        if actual > max {
+            return Err(crate::Error::LimitExceeded(limit, actual));
+        }
+        Ok(())
+    }
+}
+
+/// Identifies one of the limits of [DecodeLimits].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// [DecodeLimits::max_bytes].
+    Bytes,
+
+    /// [DecodeLimits::max_string_len].
+    StringLength,
+
+    /// [DecodeLimits::max_blob_len].
+    BlobLength,
+
+    /// [DecodeLimits::max_elements].
+    Elements,
+
+    /// [DecodeLimits::max_depth].
+    Depth,
+
+    /// [DecodeLimits::max_expanded_bytes].
+    ExpandedBytes,
+}
+impl Display for Limit {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(match self {
+            Self::Bytes => "input size",
+            Self::StringLength => "string length",
+            Self::BlobLength => "blob length",
+            Self::Elements => "element count",
+            Self::Depth => "nesting depth",
+            Self::ExpandedBytes => "expanded size",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, value::Value};
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    fn nested(depth: usize) -> Value {
+        (0..depth).fold(Value::Null, |value, _| Value::Array(vec![value]))
+    }
+
+    #[test]
+    fn within_limits() {
+        let bytes = crate::to_vec(&("abc", vec![1u8, 2, 3])).unwrap();
+        let limits = DecodeLimits::new()
+            .with_max_bytes(bytes.len())
+            .with_max_string_len(3)
+            .with_max_elements(3)
+            .with_max_depth(2);
+        let decoded: (alloc::string::String, Vec<u8>) =
+            crate::from_slice_with_limits(&bytes, &limits).unwrap();
+        assert_eq!(decoded, ("abc".into(), vec![1, 2, 3]));
+
+        let bytes = crate::to_vec(&nested(128)).unwrap();
+        crate::from_slice::<Value>(&bytes).unwrap();
+        let unlimited = DecodeLimits::unlimited();
+        let bytes = crate::to_vec(&nested(200)).unwrap();
+        crate::from_slice_with_limits::<Value>(&bytes, &unlimited).unwrap();
+    }
+
+    #[test]
+    fn exceeded() {
+        let cases = [
+            (
+                crate::to_vec(&"abcd").unwrap(),
+                DecodeLimits::new().with_max_bytes(4),
+                Limit::Bytes,
+            ),
+            (
+                crate::to_vec(&"abcd").unwrap(),
+                DecodeLimits::new().with_max_string_len(3),
+                Limit::StringLength,
+            ),
+            (
+                crate::to_vec(&Value::Blob(vec![0; 4])).unwrap(),
+                DecodeLimits::new().with_max_blob_len(3),
+                Limit::BlobLength,
+            ),
+            (
+                crate::to_vec(&[1u8, 2, 3, 4]).unwrap(),
+                DecodeLimits::new().with_max_elements(3),
+                Limit::Elements,
+            ),
+            (
+                crate::to_vec(&nested(129)).unwrap(),
+                DecodeLimits::new(),
+                Limit::Depth,
+            ),
+        ];
+        for (bytes, limits, limit) in cases {
+            let error = crate::from_slice_with_limits::<Value>(&bytes, &limits).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::LimitExceeded, "{}", limit);
+            assert!(error.to_string().contains(&limit.to_string()), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,444 @@
+//! Append-only logs of checksummed `bincode-json` records, e.g. for write-ahead logging.
+//!
+//! A log file starts with a header of the magic bytes `BJLG`, a version byte and the
+//! [Checksum::id] of its checksum algorithm. Each record follows as a 4-byte big-endian length
+//! and the little-endian checksum of it, that many bytes of an encoded document, and the
+//! little-endian checksum of everything before it in the record.
+//!
+//! A crash while appending may leave a torn record at the end of the log. Reading stops cleanly
+//! at a final record which is incomplete or fails its checksum, and [LogWriter::open] truncates
+//! such a tail away before appending. A record which fails its checksum but is followed by more
+//! data is corruption rather than a torn write, and fails with [Error::ChecksumMismatch] instead,
+//! so that the valid records after it are never truncated away. Since the length has a checksum
+//! of its own, a corrupt length is caught before it is trusted to find the end of the record.
+
+use crate::{
+    checksum::Checksum,
+    error::{Error, Result},
+    frame::{encode_frame, read_full, LENGTH_PREFIX_LEN},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    path::Path,
+};
+
+/// The magic bytes every log starts with.
+pub const MAGIC: [u8; 4] = *b"BJLG";
+
+/// The version of the log layout.
+pub const VERSION: u8 = 1;
+
+/// Length of the log header.
+const HEADER_LEN: usize = MAGIC.len() + 2;
+
+/// Appends records to a log file.
+pub struct LogWriter {
+    file: File,
+    checksum: Checksum,
+    position: u64,
+    buf: Vec<u8>,
+}
+impl LogWriter {
+    /// Opens the log at `path` for appending, creating it with `checksum` if it does not exist.
+    ///
+    /// An existing log keeps the checksum algorithm it was created with, and a torn record at its
+    /// end is truncated away. Opening fails without touching the file if the feature of
+    /// `checksum` is disabled, and fails if the log is corrupt, or if the file is not empty but
+    /// too short to hold a log header.
+    pub fn open<P: AsRef<Path>>(path: P, checksum: Checksum) -> Result<Self> {
+        // Records could never be appended to a log created with an unavailable checksum.
+        checksum.compute(&[])?;
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let (checksum, position) = if file.metadata()?.len() == 0 {
+            file.write_all(&header(checksum))?;
+            (checksum, HEADER_LEN as u64)
+        } else {
+            let mut reader = LogReader::new(&file)?;
+            while reader.next_payload()?.is_some() {}
+            (reader.checksum, reader.valid_len())
+        };
+        file.set_len(position)?;
+        file.seek(SeekFrom::Start(position))?;
+        Ok(Self {
+            file,
+            checksum,
+            position,
+            buf: Vec::new(),
+        })
+    }
+
+    /// Appends `val` as a record.
+    ///
+    /// The record is not guaranteed to be durable until [LogWriter::sync] returns.
+    pub fn append<T: Serialize>(&mut self, val: &T) -> Result<()> {
+        self.buf.clear();
+        encode_frame(val, &mut self.buf)?;
+        let len_checksum = self.checksum.compute(&self.buf[..LENGTH_PREFIX_LEN])?;
+        self.buf.splice(
+            LENGTH_PREFIX_LEN..LENGTH_PREFIX_LEN,
+            len_checksum.to_le_bytes()[..self.checksum.size()]
+                .iter()
+                .copied(),
+        );
+        self.checksum.append(&mut self.buf)?;
+        self.file.write_all(&self.buf)?;
+        self.position += self.buf.len() as u64;
+        Ok(())
+    }
+
+    /// Syncs the records appended so far to disk.
+    pub fn sync(&mut self) -> Result<()> {
+        Ok(self.file.sync_data()?)
+    }
+
+    /// Gets the checksum algorithm of the log.
+    pub fn checksum(&self) -> Checksum {
+        self.checksum
+    }
+
+    /// Gets the length of the log, in bytes.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Consumes the log writer, returning the underlying file.
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+}
+
+/// Reads the records of a log.
+pub struct LogReader<R> {
+    reader: R,
+    checksum: Checksum,
+    valid_len: u64,
+    torn: bool,
+    failed: bool,
+}
+impl<R: Read> LogReader<R> {
+    /// Constructs a new [LogReader] reading from `reader`, which must be at the start of a log.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0; HEADER_LEN];
+        reader.read_exact(&mut header).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => Error::Eof,
+            _ => Error::Io(e),
+        })?;
+        if header[..MAGIC.len()] != MAGIC {
+            return Err(Error::Expected(
+                "log magic".into(),
+                format!("{:?}", &header[..MAGIC.len()]).into(),
+            ));
+        }
+        if header[MAGIC.len()] != VERSION {
+            return Err(Error::Unsupported(format!(
+                "log version {}",
+                header[MAGIC.len()]
+            )));
+        }
+        Ok(Self {
+            reader,
+            checksum: Checksum::from_id(header[MAGIC.len() + 1])?,
+            valid_len: HEADER_LEN as u64,
+            torn: false,
+            failed: false,
+        })
+    }
+
+    /// Reads the next record, returning `None` at the end of the log or at a torn record. Reading
+    /// stops after a corrupt record, since the boundary of the next one is unknown.
+    pub fn read_record<T: DeserializeOwned>(&mut self) -> Result<Option<T>> {
+        match self.next_payload()? {
+            Some(payload) => crate::from_slice(&payload).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns an iterator over the remaining records, decoding each as a `T`.
+    pub fn records<T: DeserializeOwned>(&mut self) -> Records<'_, R, T> {
+        Records {
+            reader: self,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gets the checksum algorithm of the log.
+    pub fn checksum(&self) -> Checksum {
+        self.checksum
+    }
+
+    /// Gets the length of the header and the valid records read so far, in bytes. Once reading
+    /// stops at a torn record, this is the length the log should be truncated to.
+    pub fn valid_len(&self) -> u64 {
+        self.valid_len
+    }
+
+    /// Returns `true` if reading stopped at a torn record rather than at the end of the log.
+    pub fn is_torn(&self) -> bool {
+        self.torn
+    }
+
+    /// Consumes the log reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the payload of the next valid record.
+    fn next_payload(&mut self) -> Result<Option<Vec<u8>>> {
+        if self.torn || self.failed {
+            return Ok(None);
+        }
+
+        let prefix_len = LENGTH_PREFIX_LEN + self.checksum.size();
+        let mut record = vec![0; prefix_len];
+        let n = read_full(&mut self.reader, &mut record)?;
+        if n == 0 {
+            return Ok(None);
+        } else if n < prefix_len {
+            self.torn = true;
+            return Ok(None);
+        }
+        if !self.verify(&record)? {
+            return Ok(None);
+        }
+
+        let mut len = [0; LENGTH_PREFIX_LEN];
+        len.copy_from_slice(&record[..LENGTH_PREFIX_LEN]);
+        let rest = u32::from_be_bytes(len) as u64 + self.checksum.size() as u64;
+        let n = (&mut self.reader).take(rest).read_to_end(&mut record)?;
+        if (n as u64) < rest {
+            self.torn = true;
+            return Ok(None);
+        }
+        if !self.verify(&record)? {
+            return Ok(None);
+        }
+
+        self.valid_len += record.len() as u64;
+        record.truncate(record.len() - self.checksum.size());
+        record.drain(..prefix_len);
+        Ok(Some(record))
+    }
+
+    /// Verifies the checksum at the end of `bytes`, returning `false` if the record is torn.
+    fn verify(&mut self, bytes: &[u8]) -> Result<bool> {
+        match self.checksum.verify(bytes) {
+            Ok(_) => Ok(true),
+            // Only the final record can be torn by a crash while appending.
+            Err(e @ Error::ChecksumMismatch(..)) => {
+                if read_full(&mut self.reader, &mut [0])? == 0 {
+                    self.torn = true;
+                    return Ok(false);
+                }
+                self.failed = true;
+                Err(e.at_offset(self.valid_len as usize))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// An iterator over the records of a [LogReader].
+pub struct Records<'a, R, T> {
+    reader: &'a mut LogReader<R>,
+    _marker: PhantomData<fn() -> T>,
+}
+impl<R: Read, T: DeserializeOwned> Iterator for Records<'_, R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_record().transpose()
+    }
+}
+
+/// Builds the header of a log using `checksum`.
+fn header(checksum: Checksum) -> [u8; HEADER_LEN] {
+    let mut header = [0; HEADER_LEN];
+    header[..MAGIC.len()].copy_from_slice(&MAGIC);
+    header[MAGIC.len()] = VERSION;
+    header[MAGIC.len() + 1] = checksum.id();
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use std::{fs, io::Cursor, path::PathBuf};
+
+    /// The checksum algorithms whose features are enabled.
+    fn available() -> impl Iterator<Item = Checksum> {
+        [Checksum::Crc32c, Checksum::Xxh64]
+            .into_iter()
+            .filter(|checksum| checksum.compute(&[]).is_ok())
+    }
+
+    /// A log file using a checksum algorithm, removed when dropped.
+    struct TempLog(PathBuf, Checksum);
+    impl TempLog {
+        fn new(name: &str, checksum: Checksum) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "bincode-json-log-{}-{}-{:?}",
+                std::process::id(),
+                name,
+                checksum
+            ));
+            let _ = fs::remove_file(&path);
+            Self(path, checksum)
+        }
+
+        fn write(&self, records: &[u32]) {
+            let mut writer = LogWriter::open(&self.0, self.1).unwrap();
+            for record in records {
+                writer.append(record).unwrap();
+            }
+            writer.sync().unwrap();
+        }
+
+        fn records(&self) -> (Vec<u32>, bool) {
+            let mut reader = LogReader::new(File::open(&self.0).unwrap()).unwrap();
+            let records = reader.records().collect::<Result<_>>().unwrap();
+            (records, reader.is_torn())
+        }
+    }
+    impl Drop for TempLog {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn append_and_reopen() {
+        for checksum in available() {
+            let log = TempLog::new("reopen", checksum);
+            log.write(&[1, 2]);
+            log.write(&[3]);
+            assert_eq!(log.records(), (vec![1, 2, 3], false));
+        }
+    }
+
+    #[test]
+    fn torn_tail() {
+        for checksum in available() {
+            let log = TempLog::new("torn", checksum);
+            log.write(&[1, 2, 300]);
+            let len = fs::metadata(&log.0).unwrap().len();
+            File::options()
+                .write(true)
+                .open(&log.0)
+                .unwrap()
+                .set_len(len - 2)
+                .unwrap();
+            assert_eq!(log.records(), (vec![1, 2], true));
+
+            log.write(&[4]);
+            assert_eq!(log.records(), (vec![1, 2, 4], false));
+        }
+    }
+
+    #[test]
+    fn corrupt_final_record_is_torn() {
+        for checksum in available() {
+            let log = TempLog::new("final", checksum);
+            log.write(&[1, 2]);
+            let mut bytes = fs::read(&log.0).unwrap();
+            *bytes.last_mut().unwrap() ^= 1;
+            fs::write(&log.0, &bytes).unwrap();
+            assert_eq!(log.records(), (vec![1], true));
+        }
+    }
+
+    #[test]
+    fn corrupt_record() {
+        for checksum in available() {
+            let log = TempLog::new("corrupt", checksum);
+            log.write(&[1, 2, 3]);
+            let mut bytes = fs::read(&log.0).unwrap();
+            bytes[HEADER_LEN + LENGTH_PREFIX_LEN + checksum.size()] ^= 1;
+            fs::write(&log.0, &bytes).unwrap();
+
+            let mut reader = LogReader::new(Cursor::new(&bytes)).unwrap();
+            let error = reader.read_record::<u32>().unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Integrity);
+            assert!(!reader.is_torn());
+            assert_eq!(reader.read_record::<u32>().unwrap(), None);
+
+            assert!(LogWriter::open(&log.0, checksum).is_err());
+            assert_eq!(fs::read(&log.0).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn corrupt_length() {
+        for checksum in available() {
+            let log = TempLog::new("length", checksum);
+            log.write(&[1, 2, 3]);
+            let mut bytes = fs::read(&log.0).unwrap();
+            let record_len = (bytes.len() - HEADER_LEN) / 3;
+            // Claims the second record runs far past the end of the log.
+            bytes[HEADER_LEN + record_len] = 0x7f;
+            fs::write(&log.0, &bytes).unwrap();
+
+            let mut reader = LogReader::new(Cursor::new(&bytes)).unwrap();
+            assert_eq!(reader.read_record::<u32>().unwrap(), Some(1));
+            let error = reader.read_record::<u32>().unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Integrity);
+            assert_eq!(error.offset(), Some(HEADER_LEN + record_len));
+            assert!(!reader.is_torn());
+
+            assert!(LogWriter::open(&log.0, checksum).is_err());
+            assert_eq!(fs::read(&log.0).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn torn_length() {
+        for checksum in available() {
+            let log = TempLog::new("torn-length", checksum);
+            log.write(&[1, 2]);
+            let len = fs::metadata(&log.0).unwrap().len();
+            let record_len = (len - HEADER_LEN as u64) / 2;
+            File::options()
+                .write(true)
+                .open(&log.0)
+                .unwrap()
+                .set_len(len - record_len + LENGTH_PREFIX_LEN as u64 + 2)
+                .unwrap();
+            assert_eq!(log.records(), (vec![1], true));
+
+            log.write(&[3]);
+            assert_eq!(log.records(), (vec![1, 3], false));
+        }
+    }
+
+    #[test]
+    fn short_file() {
+        for checksum in available() {
+            let log = TempLog::new("short", checksum);
+            fs::write(&log.0, &MAGIC[..2]).unwrap();
+            let error = LogWriter::open(&log.0, checksum).err().unwrap();
+            assert_eq!(error.kind(), ErrorKind::Eof);
+            assert_eq!(fs::read(&log.0).unwrap(), &MAGIC[..2]);
+        }
+    }
+
+    #[test]
+    fn unavailable_checksum() {
+        for checksum in [Checksum::Crc32c, Checksum::Xxh64] {
+            if checksum.compute(&[]).is_ok() {
+                continue;
+            }
+            let log = TempLog::new("unavailable", checksum);
+            let error = LogWriter::open(&log.0, checksum).err().unwrap();
+            assert_eq!(error.kind(), ErrorKind::Unsupported);
+            assert!(!log.0.exists());
+        }
+    }
+}
@@ -0,0 +1,401 @@
+//! The map type of `bincode-json` objects.
+//!
+//! [Map] is backed by a `HashMap` with the `std` feature (hashed with `ahash` under the `ahash`
+//! feature), or a `BTreeMap` without it. Its API is the same under every feature, so enabling a
+//! feature in one crate of a build cannot break another that uses [Map].
+
+use core::{borrow::Borrow, fmt, hash::Hash, iter::FusedIterator, ops::Index};
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::btree_map as imp;
+#[cfg(feature = "std")]
+use std::collections::hash_map as imp;
+
+#[cfg(all(feature = "std", not(feature = "ahash")))]
+type Inner<K, V> = imp::HashMap<K, V>;
+#[cfg(feature = "ahash")]
+type Inner<K, V> = imp::HashMap<K, V, ahash::RandomState>;
+#[cfg(not(feature = "std"))]
+type Inner<K, V> = imp::BTreeMap<K, V>;
+
+/// Represents a `bincode-json` key/value type.
+///
+/// The iteration order of entries is unspecified.
+pub struct Map<K, V> {
+    inner: Inner<K, V>,
+}
+
+impl<K, V> Map<K, V> {
+    /// Constructs an empty map.
+    pub fn new() -> Self {
+        Self {
+            inner: Inner::default(),
+        }
+    }
+
+    /// Constructs an empty map with room for at least `capacity` entries, where supported.
+    pub fn with_capacity(capacity: usize) -> Self {
+        #[cfg(feature = "std")]
+        return Self {
+            inner: Inner::with_capacity_and_hasher(capacity, Default::default()),
+        };
+        #[cfg(not(feature = "std"))]
+        {
+            let _ = capacity;
+            Self::new()
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns true if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Removes every entry of the map.
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    /// Returns an iterator over the entries of the map.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.inner.iter())
+    }
+
+    /// Returns an iterator over the entries of the map, with mutable references to the values.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut(self.inner.iter_mut())
+    }
+
+    /// Returns an iterator over the keys of the map.
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.inner.keys())
+    }
+
+    /// Returns an iterator over the values of the map.
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.inner.values())
+    }
+
+    /// Returns an iterator over mutable references to the values of the map.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut(self.inner.values_mut())
+    }
+}
+
+impl<K: Hash + Ord, V> Map<K, V> {
+    /// Returns a reference to the value of `key`, if present.
+    pub fn get<Q: Hash + Ord + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.inner.get(key)
+    }
+
+    /// Returns a mutable reference to the value of `key`, if present.
+    pub fn get_mut<Q: Hash + Ord + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        self.inner.get_mut(key)
+    }
+
+    /// Returns true if the map has an entry of `key`.
+    pub fn contains_key<Q: Hash + Ord + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.inner.contains_key(key)
+    }
+
+    /// Inserts an entry, returning the value previously held by `key`, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.inner.insert(key, value)
+    }
+
+    /// Removes the entry of `key`, returning its value, if present.
+    pub fn remove<Q: Hash + Ord + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        self.inner.remove(key)
+    }
+
+    /// Keeps only the entries for which `f` returns true.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, f: F) {
+        self.inner.retain(f)
+    }
+
+    /// Returns the entry of `key`, for in-place manipulation.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        Entry(self.inner.entry(key))
+    }
+}
+
+/// An entry of a [Map], returned by [Map::entry].
+pub struct Entry<'a, K, V>(imp::Entry<'a, K, V>);
+
+impl<'a, K: Hash + Ord, V> Entry<'a, K, V> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        self.0.key()
+    }
+
+    /// Inserts `default` if the entry is vacant, returning a mutable reference to its value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.0.or_insert(default)
+    }
+
+    /// Inserts the result of `default` if the entry is vacant, returning a mutable reference to its
+    /// value.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        self.0.or_insert_with(default)
+    }
+
+    /// Calls `f` with the value if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        Self(self.0.and_modify(f))
+    }
+}
+
+impl<'a, K: Hash + Ord, V: Default> Entry<'a, K, V> {
+    /// Inserts the default value if the entry is vacant, returning a mutable reference to its
+    /// value.
+    pub fn or_default(self) -> &'a mut V {
+        self.0.or_default()
+    }
+}
+
+impl<K, V> Default for Map<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for Map<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Map<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<K: Hash + Ord, V: PartialEq> PartialEq for Map<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<K: Hash + Ord, V: Eq> Eq for Map<K, V> {}
+
+impl<K: Hash + Ord + Borrow<Q>, Q: Hash + Ord + ?Sized, V> Index<&Q> for Map<K, V> {
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+impl<K: Hash + Ord, V> FromIterator<(K, V)> for Map<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self {
+            inner: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<K: Hash + Ord, V> Extend<(K, V)> for Map<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.inner.extend(iter)
+    }
+}
+
+impl<K: Hash + Ord, V, const N: usize> From<[(K, V); N]> for Map<K, V> {
+    fn from(entries: [(K, V); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<K, V> IntoIterator for Map<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter(self.inner.into_iter())
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Map<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut Map<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+macro_rules! iterator {
+    ($(#[$doc:meta] $name:ident<$($lt:lifetime,)? K, V> => $item:ty;)*) => {
+        $(
+            #[$doc]
+            pub struct $name<$($lt,)? K, V>(imp::$name<$($lt,)? K, V>);
+
+            impl<$($lt,)? K, V> Iterator for $name<$($lt,)? K, V> {
+                type Item = $item;
+
+                fn next(&mut self) -> Option<Self::Item> {
+                    self.0.next()
+                }
+
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    self.0.size_hint()
+                }
+            }
+
+            impl<$($lt,)? K, V> ExactSizeIterator for $name<$($lt,)? K, V> {}
+            impl<$($lt,)? K, V> FusedIterator for $name<$($lt,)? K, V> {}
+        )*
+    };
+}
+
+iterator! {
+    /// An iterator over the entries of a [Map].
+    Iter<'a, K, V> => (&'a K, &'a V);
+    /// An iterator over the entries of a [Map], with mutable references to the values.
+    IterMut<'a, K, V> => (&'a K, &'a mut V);
+    /// An owning iterator over the entries of a [Map].
+    IntoIter<K, V> => (K, V);
+    /// An iterator over the keys of a [Map].
+    Keys<'a, K, V> => &'a K;
+    /// An iterator over the values of a [Map].
+    Values<'a, K, V> => &'a V;
+    /// An iterator over mutable references to the values of a [Map].
+    ValuesMut<'a, K, V> => &'a mut V;
+}
+
+impl<K: Clone, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<K: serde::Serialize, V: serde::Serialize> serde::Serialize for Map<K, V> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self)
+    }
+}
+
+impl<'de, K, V> serde::Deserialize<'de> for Map<K, V>
+where
+    K: serde::Deserialize<'de> + Hash + Ord,
+    V: serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self {
+            inner: Inner::deserialize(deserializer)?,
+        })
+    }
+}
+
+impl<K: bincode::Encode + Hash + Ord, V: bincode::Encode> bincode::Encode for Map<K, V> {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        self.inner.encode(encoder)
+    }
+}
+
+impl<K: bincode::Decode + Hash + Ord, V: bincode::Decode> bincode::Decode for Map<K, V> {
+    fn decode<D: bincode::de::Decoder>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self {
+            inner: Inner::decode(decoder)?,
+        })
+    }
+}
+
+impl<'de, K: bincode::BorrowDecode<'de> + Hash + Ord, V: bincode::BorrowDecode<'de>>
+    bincode::BorrowDecode<'de> for Map<K, V>
+{
+    fn borrow_decode<D: bincode::de::BorrowDecoder<'de>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self {
+            inner: Inner::borrow_decode(decoder)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::{string::String, vec::Vec};
+
+    #[test]
+    fn operations() {
+        let mut map = Map::new();
+        assert!(map.is_empty());
+        assert_eq!(map.insert(String::from("a"), 1), None);
+        assert_eq!(map.insert(String::from("a"), 2), Some(1));
+        map.extend([(String::from("b"), 3), (String::from("c"), 4)]);
+        *map.entry(String::from("d")).or_default() += 5;
+        assert_eq!(map.len(), 4);
+        assert_eq!(map["a"], 2);
+        assert_eq!(map.get("d"), Some(&5));
+        assert!(map.contains_key("b"));
+        assert_eq!(map.remove("b"), Some(3));
+        map.retain(|_, v| *v != 4);
+
+        let mut keys: Vec<_> = map.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, ["a", "d"]);
+        assert_eq!(
+            map,
+            Map::from([(String::from("d"), 5), (String::from("a"), 2)])
+        );
+    }
+
+    #[test]
+    fn bincode_round_trip() {
+        let map = Map::from([(String::from("a"), 1u8), (String::from("b"), 2)]);
+        let bytes = bincode::encode_to_vec(&map, bincode::config::standard()).unwrap();
+        let (decoded, len): (Map<String, u8>, _) =
+            bincode::decode_from_slice(&bytes, bincode::config::standard()).unwrap();
+        assert_eq!(len, bytes.len());
+        assert_eq!(decoded, map);
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let map = Map::from([(String::from("a"), 1u8), (String::from("b"), 2)]);
+        let bytes = crate::to_vec(&map).unwrap();
+        let decoded: Map<String, u8> = crate::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, map);
+
+        let error =
+            crate::from_slice::<Map<String, u8>>(&crate::to_vec(&1u8).unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,243 @@
+//! Reconciliation of concurrently updated documents with pluggable strategies.
+//!
+//! [Value::merge_with] merges another replica of a document into this one. Objects are merged
+//! entry by entry, keeping entries present in either side, and conflicting values are resolved
+//! by the [MergeStrategy]:
+//!  - A resolver registered for the path of the value, written like those of [crate::Selector],
+//!    e.g. `tags` or `items[*].count`, decides first.
+//!  - With last-writer-wins, an object whose metadata field, such as `updated_at`, is greater than
+//!    that of the other side replaces it wholesale.
+//!  - With array union, arrays keep their elements followed by the elements of the other side
+//!    they do not contain yet.
+//!  - Otherwise, the value of the other side wins.
+
+use crate::{
+    delta::same,
+    error::Result,
+    selector::{Segment, Selector},
+    value::Value,
+};
+use alloc::{string::String, vec::Vec};
+use core::cmp::Ordering;
+
+/// A custom resolver of conflicting values, taking this side's value and the other side's.
+pub type Resolver = fn(Value, Value) -> Result<Value>;
+
+/// How conflicting values are resolved by [Value::merge_with].
+#[derive(Debug, Clone, Default)]
+pub struct MergeStrategy {
+    /// The metadata field by which the last writer of objects is determined, if any.
+    pub last_writer_field: Option<String>,
+
+    /// Whether arrays are merged into their union rather than replaced.
+    pub array_union: bool,
+
+    resolvers: Vec<(Selector, Resolver)>,
+}
+impl MergeStrategy {
+    /// Constructs a new [MergeStrategy] where the other side always wins.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the metadata field by which the last writer of objects wins, such as `updated_at`.
+    /// Integers, floats and strings are compared; objects lacking comparable fields are merged
+    /// entry by entry.
+    pub fn with_last_writer_wins(mut self, field: &str) -> Self {
+        self.last_writer_field = Some(field.into());
+        self
+    }
+
+    /// Sets whether arrays are merged into their union rather than replaced.
+    pub fn with_array_union(mut self, array_union: bool) -> Self {
+        self.array_union = array_union;
+        self
+    }
+
+    /// Registers `resolver` for the values at `path`, failing with [crate::Error::Custom] if the
+    /// path is malformed. Resolvers registered earlier take precedence.
+    pub fn with_resolver(mut self, path: &str, resolver: Resolver) -> Result<Self> {
+        self.resolvers.push((Selector::compile(path)?, resolver));
+        Ok(self)
+    }
+
+    fn merge(&self, ours: Value, theirs: Value, path: &mut Vec<Segment>) -> Result<Value> {
+        if let Some((_, resolver)) = self.resolvers.iter().find(|(s, _)| s.matches(path)) {
+            return resolver(ours, theirs);
+        }
+        match (ours, theirs) {
+            (Value::Object(ours), Value::Object(theirs)) => {
+                let newer = self.last_writer_field.as_ref().and_then(|field| {
+                    compare(ours.get(field)?, theirs.get(field)?).filter(|o| o.is_ne())
+                });
+                match newer {
+                    Some(Ordering::Greater) => return Ok(Value::Object(ours)),
+                    Some(_) => return Ok(Value::Object(theirs)),
+                    None => {}
+                }
+                let mut merged = ours;
+                for (key, theirs) in theirs {
+                    let value = match merged.remove(&key) {
+                        Some(ours) => {
+                            path.push(Segment::Key(key.clone()));
+                            let value = self.merge(ours, theirs, path);
+                            path.pop();
+                            value?
+                        }
+                        None => theirs,
+                    };
+                    merged.insert(key, value);
+                }
+                Ok(Value::Object(merged))
+            }
+            (Value::Array(mut ours), Value::Array(theirs)) if self.array_union => {
+                for value in theirs {
+                    if !ours.iter().any(|v| same(v, &value)) {
+                        ours.push(value);
+                    }
+                }
+                Ok(Value::Array(ours))
+            }
+            (_, theirs) => Ok(theirs),
+        }
+    }
+}
+
+impl Value {
+    /// Merges `other`, a concurrently updated replica of the value, into the value, resolving
+    /// conflicts with `strategy`. Fails only if a resolver fails.
+    pub fn merge_with(self, other: Value, strategy: &MergeStrategy) -> Result<Value> {
+        strategy.merge(self, other, &mut Vec::new())
+    }
+}
+
+/// Compares two metadata fields, or returns `None` if they are not comparable.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+        (Value::Integer(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+        (Value::Float(a), Value::Integer(b)) => a.partial_cmp(&(*b as f64)),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::error::{Error, ErrorKind};
+    use alloc::{vec, vec::Vec};
+
+    fn object(entries: Vec<(&str, Value)>) -> Value {
+        Value::Object(entries.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    fn assert_same(a: &Value, b: &Value) {
+        assert_eq!(
+            crate::to_vec_canonical(a).unwrap(),
+            crate::to_vec_canonical(b).unwrap()
+        );
+    }
+
+    fn integers(a: &[i64]) -> Value {
+        Value::Array(a.iter().copied().map(Value::Integer).collect())
+    }
+
+    #[test]
+    fn entries_and_arrays() {
+        let ours = object(vec![
+            ("a", Value::Integer(1)),
+            ("tags", integers(&[1, 2])),
+            ("nested", object(vec![("x", Value::Null)])),
+        ]);
+        let theirs = object(vec![
+            ("b", Value::Integer(2)),
+            ("tags", integers(&[2, 3])),
+            ("nested", object(vec![("y", Value::Null)])),
+        ]);
+
+        let merged = ours
+            .clone()
+            .merge_with(theirs.clone(), &MergeStrategy::new())
+            .unwrap();
+        let expected = object(vec![
+            ("a", Value::Integer(1)),
+            ("b", Value::Integer(2)),
+            ("tags", integers(&[2, 3])),
+            (
+                "nested",
+                object(vec![("x", Value::Null), ("y", Value::Null)]),
+            ),
+        ]);
+        assert_same(&merged, &expected);
+
+        let strategy = MergeStrategy::new().with_array_union(true);
+        let Value::Object(merged) = ours.merge_with(theirs, &strategy).unwrap() else {
+            panic!("expected an object");
+        };
+        assert_same(&merged["tags"], &integers(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn last_writer_wins() {
+        let strategy = MergeStrategy::new().with_last_writer_wins("updated_at");
+        let ours = object(vec![
+            ("updated_at", Value::Integer(2)),
+            ("a", Value::Integer(1)),
+        ]);
+        let theirs = object(vec![
+            ("updated_at", Value::Float(1.5)),
+            ("b", Value::Integer(2)),
+        ]);
+        assert_same(
+            &ours.clone().merge_with(theirs.clone(), &strategy).unwrap(),
+            &ours,
+        );
+        assert_same(
+            &theirs.clone().merge_with(ours.clone(), &strategy).unwrap(),
+            &ours,
+        );
+
+        // Incomparable fields fall back to merging entry by entry.
+        let theirs = object(vec![("updated_at", Value::Null), ("b", Value::Integer(2))]);
+        let Value::Object(merged) = ours.merge_with(theirs, &strategy).unwrap() else {
+            panic!("expected an object");
+        };
+        assert_eq!(merged.len(), 3);
+    }
+
+    #[test]
+    fn resolvers() {
+        fn sum(ours: Value, theirs: Value) -> Result<Value> {
+            match (ours, theirs) {
+                (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a + b)),
+                _ => Err(Error::Custom("expected counts".into())),
+            }
+        }
+        let strategy = MergeStrategy::new()
+            .with_resolver("items[*].count", sum)
+            .unwrap();
+        let item = |count| object(vec![("count", count)]);
+        let ours = object(vec![("items", Value::Array(vec![item(Value::Integer(1))]))]);
+        let theirs = object(vec![("items", Value::Array(vec![item(Value::Integer(2))]))]);
+        // Arrays are replaced rather than merged element by element, so the resolver is only
+        // reached through objects.
+        let merged = ours.merge_with(theirs.clone(), &strategy).unwrap();
+        assert_same(&merged, &theirs);
+
+        let strategy = MergeStrategy::new().with_resolver("*.count", sum).unwrap();
+        let ours = object(vec![("item", item(Value::Integer(1)))]);
+        let theirs = object(vec![("item", item(Value::Integer(2)))]);
+        let merged = ours.merge_with(theirs, &strategy).unwrap();
+        assert_same(&merged, &object(vec![("item", item(Value::Integer(3)))]));
+
+        let ours = object(vec![("item", item(Value::Null))]);
+        let theirs = object(vec![("item", item(Value::Integer(2)))]);
+        let error = ours.merge_with(theirs, &strategy).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+
+        let error = MergeStrategy::new().with_resolver("a[", sum).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+    }
+}
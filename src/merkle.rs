@@ -0,0 +1,221 @@
+//! Merkle trees of `bincode-json` documents, locating the differences between replicas.
+//!
+//! [hash_tree] hashes every value of a document: scalars over their canonical encoding, like
+//! [Value::content_hash], and arrays and objects over their lengths, keys and the digests of
+//! their children, with object entries in ascending byte order of their keys. Two replicas can then
+//! exchange digests from the root down, descending only into subtrees whose digests differ, and
+//! [MerkleTree::diff] lists the paths of the values which differ, written like those of
+//! [crate::extract], e.g. `a.b[3]`.
+
+use crate::{value::Value, wire};
+use digest::{Digest, Output};
+use std::collections::BTreeMap;
+
+/// The digests of a value and of all the values nested in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree<D: Digest> {
+    digest: Output<D>,
+    children: Children<D>,
+}
+
+/// The subtrees of the children of a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Children<D: Digest> {
+    Leaf,
+    Array(Vec<MerkleTree<D>>),
+    Object(BTreeMap<String, MerkleTree<D>>),
+}
+
+impl<D: Digest> MerkleTree<D> {
+    /// Gets the digest of the value, which covers every value nested in it.
+    pub fn digest(&self) -> &Output<D> {
+        &self.digest
+    }
+
+    /// Gets the subtree of the entry of `key`, if the value is an object with such an entry.
+    pub fn get(&self, key: &str) -> Option<&MerkleTree<D>> {
+        match &self.children {
+            Children::Object(o) => o.get(key),
+            _ => None,
+        }
+    }
+
+    /// Gets the subtree of the element at `index`, if the value is an array that long.
+    pub fn get_index(&self, index: usize) -> Option<&MerkleTree<D>> {
+        match &self.children {
+            Children::Array(a) => a.get(index),
+            _ => None,
+        }
+    }
+
+    /// Gets the paths of the values which differ between the two trees, in ascending order of
+    /// keys and indices. Only the outermost differing values are listed: values of different types,
+    /// scalars, and entries or elements present in one tree only. Identical subtrees are skipped
+    /// by their digests without being visited.
+    pub fn diff(&self, other: &MerkleTree<D>) -> Vec<String> {
+        let mut paths = Vec::new();
+        self.diff_into(other, &mut String::new(), &mut paths);
+        paths
+    }
+
+    fn diff_into(&self, other: &MerkleTree<D>, path: &mut String, paths: &mut Vec<String>) {
+        if self.digest == other.digest {
+            return;
+        }
+        let len = path.len();
+        match (&self.children, &other.children) {
+            (Children::Array(a), Children::Array(b)) => {
+                for i in 0..a.len().max(b.len()) {
+                    path.push_str(&format!("[{}]", i));
+                    match (a.get(i), b.get(i)) {
+                        (Some(a), Some(b)) => a.diff_into(b, path, paths),
+                        _ => paths.push(path.clone()),
+                    }
+                    path.truncate(len);
+                }
+            }
+            (Children::Object(a), Children::Object(b)) => {
+                let mut keys: Vec<_> = a.keys().chain(b.keys()).collect();
+                keys.sort_unstable();
+                keys.dedup();
+                for key in keys {
+                    if len != 0 {
+                        path.push('.');
+                    }
+                    path.push_str(key);
+                    match (a.get(key), b.get(key)) {
+                        (Some(a), Some(b)) => a.diff_into(b, path, paths),
+                        _ => paths.push(path.clone()),
+                    }
+                    path.truncate(len);
+                }
+            }
+            _ => paths.push(path.clone()),
+        }
+    }
+}
+
+/// Computes the Merkle tree of `value` with the hash function `D`.
+pub fn hash_tree<D: Digest>(value: &Value) -> MerkleTree<D> {
+    let config = bincode::config::standard();
+    let header = |tag: u32, len: usize| {
+        let mut digest = D::new();
+        digest.update(
+            bincode::encode_to_vec((tag, len as u64), config)
+                .expect("encoding into a vector never fails"),
+        );
+        digest
+    };
+    match value {
+        Value::Array(a) => {
+            let mut digest = header(wire::ARRAY, a.len());
+            let children: Vec<_> = a.iter().map(hash_tree::<D>).collect();
+            children.iter().for_each(|c| digest.update(&c.digest));
+            MerkleTree {
+                digest: digest.finalize(),
+                children: Children::Array(children),
+            }
+        }
+        Value::Object(o) => {
+            let mut digest = header(wire::OBJECT, o.len());
+            let children: BTreeMap<_, _> = o
+                .iter()
+                .map(|(k, v)| (k.clone(), hash_tree::<D>(v)))
+                .collect();
+            for (k, c) in &children {
+                digest.update(
+                    bincode::encode_to_vec(k, config).expect("encoding into a vector never fails"),
+                );
+                digest.update(&c.digest);
+            }
+            MerkleTree {
+                digest: digest.finalize(),
+                children: Children::Object(children),
+            }
+        }
+        value => MerkleTree {
+            digest: value.content_hash::<D>(),
+            children: Children::Leaf,
+        },
+    }
+}
+
+#[cfg(all(test, feature = "hmac"))]
+mod tests {
+    use super::*;
+    use crate::value::Map;
+    use sha2::Sha256;
+
+    fn document() -> Value {
+        Value::Object(Map::from([
+            (String::from("name"), Value::String("text".into())),
+            (
+                String::from("items"),
+                Value::Array(vec![Value::Integer(1), Value::Integer(2)]),
+            ),
+            (
+                String::from("nested"),
+                Value::Object(Map::from([(String::from("flag"), Value::Boolean(true))])),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn stable() {
+        let tree = hash_tree::<Sha256>(&document());
+        let decoded: Value = crate::from_slice(&crate::to_vec(&document()).unwrap()).unwrap();
+        assert_eq!(hash_tree::<Sha256>(&decoded).digest(), tree.digest());
+        assert!(tree.diff(&hash_tree(&decoded)).is_empty());
+
+        let name = tree.get("name").unwrap();
+        assert_eq!(
+            *name.digest(),
+            Value::String("text".into()).content_hash::<Sha256>()
+        );
+        assert!(tree.get("items").unwrap().get_index(1).is_some());
+        assert!(tree.get("items").unwrap().get_index(2).is_none());
+        assert!(name.get("name").is_none());
+        assert!(name.get_index(0).is_none());
+
+        // Containers hash their structure, not only their contents.
+        let empty_array = hash_tree::<Sha256>(&Value::Array(vec![]));
+        let empty_object = hash_tree::<Sha256>(&Value::Object(Map::new()));
+        assert_ne!(empty_array.digest(), empty_object.digest());
+    }
+
+    #[test]
+    fn diff() {
+        let tree = hash_tree::<Sha256>(&document());
+        let Value::Object(mut changed) = document() else {
+            panic!("expected an object");
+        };
+        changed.insert(
+            "items".into(),
+            Value::Array(vec![Value::Integer(1), Value::Integer(3), Value::Null]),
+        );
+        changed.insert(
+            "nested".into(),
+            Value::Object(Map::from([(String::from("other"), Value::Null)])),
+        );
+        changed.remove("name");
+        changed.insert("added".into(), Value::Null);
+        let other = hash_tree::<Sha256>(&Value::Object(changed));
+
+        assert_ne!(tree.digest(), other.digest());
+        assert_eq!(
+            tree.diff(&other),
+            [
+                "added",
+                "items[1]",
+                "items[2]",
+                "name",
+                "nested.flag",
+                "nested.other"
+            ]
+        );
+        assert_eq!(other.diff(&tree).len(), 6);
+
+        let scalar = hash_tree::<Sha256>(&Value::Integer(1));
+        assert_eq!(tree.diff(&scalar), [""]);
+    }
+}
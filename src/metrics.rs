@@ -0,0 +1,106 @@
+//! Metrics hooks of top-level encoding and decoding, e.g. to feed Prometheus counters.
+//!
+//! Once a [MetricsSink] is installed with [set_metrics_sink], it is invoked after every call of
+//! [crate::to_vec] and [crate::from_slice] with the kind of operation, the number of bytes
+//! produced or consumed and whether it succeeded. Without a sink, the hooks cost a single atomic
+//! load.
+
+use crate::error::{Error, Result};
+use std::sync::OnceLock;
+
+/// A kind of top-level operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// A document was encoded.
+    Encode,
+
+    /// A document was decoded.
+    Decode,
+}
+
+/// The outcome of a top-level operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// The operation succeeded.
+    Success,
+
+    /// The operation failed.
+    Failure,
+}
+
+/// A receiver of metrics of top-level operations.
+pub trait MetricsSink: Send + Sync {
+    /// Records an `operation` which produced or consumed `bytes` bytes, with its `outcome`. The
+    /// bytes of failed encodings are 0.
+    fn record(&self, operation: Operation, bytes: usize, outcome: Outcome);
+}
+impl<F: Fn(Operation, usize, Outcome) + Send + Sync> MetricsSink for F {
+    fn record(&self, operation: Operation, bytes: usize, outcome: Outcome) {
+        self(operation, bytes, outcome)
+    }
+}
+
+static SINK: OnceLock<Box<dyn MetricsSink>> = OnceLock::new();
+
+/// Installs the process-wide `sink`, failing with [Error::Custom] if one is installed already.
+pub fn set_metrics_sink<S: MetricsSink + 'static>(sink: S) -> Result<()> {
+    SINK.set(Box::new(sink))
+        .map_err(|_| Error::Custom("metrics sink is already set".into()))
+}
+
+/// Runs `encode`, recording its metrics.
+pub(crate) fn encode(encode: impl FnOnce() -> Result<Vec<u8>>) -> Result<Vec<u8>> {
+    let result = encode();
+    if let Some(sink) = SINK.get() {
+        match &result {
+            Ok(bytes) => sink.record(Operation::Encode, bytes.len(), Outcome::Success),
+            Err(_) => sink.record(Operation::Encode, 0, Outcome::Failure),
+        }
+    }
+    result
+}
+
+/// Runs `decode` of `bytes` bytes, recording its metrics.
+pub(crate) fn decode<T>(bytes: usize, decode: impl FnOnce() -> Result<T>) -> Result<T> {
+    let result = decode();
+    if let Some(sink) = SINK.get() {
+        let outcome = match result {
+            Ok(_) => Outcome::Success,
+            Err(_) => Outcome::Failure,
+        };
+        sink.record(Operation::Decode, bytes, outcome);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use std::sync::Mutex;
+
+    static RECORDED: Mutex<Vec<(Operation, usize, Outcome)>> = Mutex::new(Vec::new());
+
+    #[test]
+    fn sink() {
+        set_metrics_sink(|operation, bytes, outcome| {
+            RECORDED.lock().unwrap().push((operation, bytes, outcome))
+        })
+        .unwrap();
+        let error = set_metrics_sink(|_: Operation, _: usize, _: Outcome| {}).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+
+        // Other tests run concurrently in the process, so the sizes are chosen to be distinctive.
+        let bytes = crate::to_vec(&"x".repeat(777)).unwrap();
+        assert_eq!(bytes.len(), 781);
+        crate::from_slice::<String>(&bytes).unwrap();
+        let mut invalid = vec![0xff];
+        invalid.resize(913, 0);
+        crate::from_slice::<String>(&invalid).unwrap_err();
+
+        let recorded = RECORDED.lock().unwrap();
+        assert!(recorded.contains(&(Operation::Encode, 781, Outcome::Success)));
+        assert!(recorded.contains(&(Operation::Decode, 781, Outcome::Success)));
+        assert!(recorded.contains(&(Operation::Decode, 913, Outcome::Failure)));
+    }
+}
@@ -0,0 +1,90 @@
+//! [miette] diagnostics of errors, so that command-line tools report them with codes, help and
+//! labels.
+//!
+//! Every [Error] is a [Diagnostic] whose code is `bincode_json::` followed by the name of its
+//! [ErrorKind], such as `bincode_json::type_mismatch`. When the byte offset where decoding stopped
+//! is known, the diagnostic labels it, which miette renders over the input once it is attached,
+//! e.g. with `miette::Report::new(error).with_source_code(input.to_vec())`.
+
+use crate::error::{Error, ErrorKind};
+use miette::{Diagnostic, LabeledSpan};
+use std::fmt::Display;
+
+impl Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(format!("bincode_json::{}", self.kind())))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let help = match self.kind() {
+            ErrorKind::Decode => "the input is not a Bincode JSON document, or it is corrupt",
+            ErrorKind::Eof => "the input is truncated, check that the whole document was received",
+            ErrorKind::TypeMismatch
+            | ErrorKind::MissingField
+            | ErrorKind::DuplicateField
+            | ErrorKind::UnknownField => {
+                "the document does not match the type, check that both sides agree on its schema"
+            }
+            ErrorKind::Integrity => "the input is corrupt, or it was tampered with",
+            ErrorKind::LimitExceeded => {
+                "the input is larger than allowed, raise the limits if it is trusted"
+            }
+            _ => return None,
+        };
+        Some(Box::new(help))
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let offset = self.offset()?;
+        let label = match self.path() {
+            Some(path) => format!("decoding `{}` stopped here", path),
+            None => "decoding stopped here".into(),
+        };
+        Some(Box::new(std::iter::once(LabeledSpan::at_offset(
+            offset, label,
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnostics() {
+        let bytes = crate::to_vec(&vec![1u32, 2]).unwrap();
+        let error = crate::from_slice::<Vec<u32>>(&bytes[..3]).unwrap_err();
+        assert_eq!(error.code().unwrap().to_string(), "bincode_json::eof");
+        assert!(error.help().unwrap().to_string().contains("truncated"));
+        let labels: Vec<_> = error.labels().unwrap().collect();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].offset(), 3);
+        assert_eq!(labels[0].label(), Some("decoding stopped here"));
+
+        let report = miette::Report::new(error).with_source_code(bytes);
+        assert!(report.to_string().ends_with("at byte 3"));
+        assert!(report.source_code().is_some());
+    }
+
+    #[test]
+    fn without_labels() {
+        let error = crate::from_slice::<u32>(&crate::to_vec(&"text").unwrap()).unwrap_err();
+        assert_eq!(
+            error.code().unwrap().to_string(),
+            "bincode_json::type_mismatch"
+        );
+        assert!(error.help().unwrap().to_string().contains("schema"));
+        assert!(error.labels().is_none());
+
+        let error = Error::At(
+            "a.b".into(),
+            Box::new(Error::Offset(4, Box::new(Error::Eof))),
+        );
+        let labels: Vec<_> = error.labels().unwrap().collect();
+        assert_eq!(labels[0].label(), Some("decoding `a.b` stopped here"));
+
+        let error = Error::Custom("custom".into());
+        assert_eq!(error.code().unwrap().to_string(), "bincode_json::custom");
+        assert!(error.help().is_none());
+    }
+}
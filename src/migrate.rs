@@ -0,0 +1,152 @@
+//! Versioned documents, upgraded by registered migrations before typed deserialization.
+//!
+//! A versioned document is the little-endian `u32` version of its schema followed by the encoded
+//! document. [Migrations] holds the upgrade steps of a schema, where the step at index `n` upgrades
+//! a document of version `n` to `n + 1`, so the current version is the number of steps.
+//! [from_slice_versioned] applies every step a document is missing before deserializing it.
+
+use crate::{
+    error::{Error, Result},
+    value::Value,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+const VERSION_LEN: usize = 4;
+
+/// An upgrade step of a document from one version to the next.
+pub type Migration = fn(Value) -> Result<Value>;
+
+/// The ordered upgrade steps of a document schema.
+#[derive(Debug, Clone, Default)]
+pub struct Migrations {
+    steps: Vec<Migration>,
+}
+impl Migrations {
+    /// Constructs a new [Migrations] without steps, whose current version is 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a step upgrading documents of the current version, incrementing it.
+    pub fn with_step(mut self, step: Migration) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Gets the current version, which documents are upgraded to.
+    pub fn version(&self) -> u32 {
+        self.steps.len() as u32
+    }
+
+    /// Upgrades `value` from `version` to the current version, failing with
+    /// [Error::Unsupported] if `version` is newer than it.
+    pub fn migrate(&self, mut value: Value, version: u32) -> Result<Value> {
+        let pending = self
+            .steps
+            .get(version as usize..)
+            .ok_or_else(|| Error::Unsupported(format!("document version {}", version)))?;
+        for step in pending {
+            value = step(value)?;
+        }
+        Ok(value)
+    }
+
+    /// Serialize the given data structure as a document of the current version.
+    pub fn to_vec<T: Serialize>(&self, val: &T) -> Result<Vec<u8>> {
+        to_vec_versioned(val, self.version())
+    }
+}
+
+/// Serialize the given data structure as a document of `version`.
+pub fn to_vec_versioned<T: Serialize>(val: &T, version: u32) -> Result<Vec<u8>> {
+    let mut buf = version.to_le_bytes().to_vec();
+    buf.extend_from_slice(&crate::to_vec(val)?);
+    Ok(buf)
+}
+
+/// Deserialize an instance of type `T` from a versioned document, upgraded by `migrations`.
+pub fn from_slice_versioned<T: DeserializeOwned>(val: &[u8], migrations: &Migrations) -> Result<T> {
+    let (version, body) = split_version(val)?;
+    let value = migrations.migrate(crate::from_slice(body)?, version)?;
+    crate::from_value(value)
+}
+
+/// Gets the version of a versioned document.
+pub fn version_of(val: &[u8]) -> Result<u32> {
+    split_version(val).map(|(version, _)| version)
+}
+
+fn split_version(val: &[u8]) -> Result<(u32, &[u8])> {
+    if val.len() < VERSION_LEN {
+        return Err(Error::Eof);
+    }
+    let (version, body) = val.split_at(VERSION_LEN);
+    Ok((u32::from_le_bytes(version.try_into().unwrap()), body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    /// Wraps a bare count into a `(count, name)` pair.
+    fn pair(value: Value) -> Result<Value> {
+        Ok(Value::Array(vec![value, Value::String("unnamed".into())]))
+    }
+
+    /// Doubles the count of a pair, failing on anything else.
+    fn double(value: Value) -> Result<Value> {
+        match value {
+            Value::Array(mut a) => match a[0] {
+                Value::Integer(i) => {
+                    a[0] = Value::Integer(i * 2);
+                    Ok(Value::Array(a))
+                }
+                _ => Err(Error::Custom("expected a count".into())),
+            },
+            _ => Err(Error::Custom("expected a pair".into())),
+        }
+    }
+
+    fn migrations() -> Migrations {
+        Migrations::new().with_step(pair).with_step(double)
+    }
+
+    #[test]
+    fn round_trip() {
+        let migrations = migrations();
+        assert_eq!(migrations.version(), 2);
+
+        let current = migrations.to_vec(&(4u32, "named")).unwrap();
+        assert_eq!(version_of(&current).unwrap(), 2);
+        let decoded: (u32, String) = from_slice_versioned(&current, &migrations).unwrap();
+        assert_eq!(decoded, (4, String::from("named")));
+
+        let old = to_vec_versioned(&4u32, 0).unwrap();
+        let decoded: (u32, String) = from_slice_versioned(&old, &migrations).unwrap();
+        assert_eq!(decoded, (8, String::from("unnamed")));
+        let old = to_vec_versioned(&(4u32, "named"), 1).unwrap();
+        let decoded: (u32, String) = from_slice_versioned(&old, &migrations).unwrap();
+        assert_eq!(decoded, (8, String::from("named")));
+    }
+
+    #[test]
+    fn errors() {
+        let migrations = migrations();
+        let newer = to_vec_versioned(&4u32, 3).unwrap();
+        let error = from_slice_versioned::<u32>(&newer, &migrations).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+
+        assert_eq!(version_of(&[2, 0, 0]).unwrap_err().kind(), ErrorKind::Eof);
+        let empty = 0u32.to_le_bytes();
+        let error = from_slice_versioned::<u32>(&empty, &migrations).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+
+        let failing = to_vec_versioned(&"text", 1).unwrap();
+        let error = from_slice_versioned::<(u32, String)>(&failing, &migrations).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+        let stale = to_vec_versioned(&4u32, 2).unwrap();
+        let error = from_slice_versioned::<(u32, String)>(&stale, &migrations).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,145 @@
+//! Conversions between `bincode-json` values and MessagePack values, and between the encoded
+//! documents of both formats.
+//!
+//! Blobs convert to binaries and objects to maps keyed by strings. Converting back, integer map
+//! keys become their decimal string. Integers outside the range of an `i64`, strings which are not
+//! valid UTF-8, extension types and map keys of any other type are unsupported.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value},
+};
+use rmpv::Value as MsgpackValue;
+
+impl From<Value> for MsgpackValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => MsgpackValue::Nil,
+            Value::Boolean(b) => MsgpackValue::Boolean(b),
+            Value::Blob(b) => MsgpackValue::Binary(b),
+            Value::Array(a) => MsgpackValue::Array(a.into_iter().map(MsgpackValue::from).collect()),
+            Value::Integer(i) => MsgpackValue::from(i),
+            Value::Float(f) => MsgpackValue::F64(f),
+            Value::Object(o) => MsgpackValue::Map(
+                o.into_iter()
+                    .map(|(k, v)| (MsgpackValue::from(k), v.into()))
+                    .collect(),
+            ),
+            Value::String(s) => MsgpackValue::from(s),
+        }
+    }
+}
+impl TryFrom<MsgpackValue> for Value {
+    type Error = Error;
+
+    fn try_from(value: MsgpackValue) -> Result<Self> {
+        Ok(match value {
+            MsgpackValue::Nil => Value::Null,
+            MsgpackValue::Boolean(b) => Value::Boolean(b),
+            MsgpackValue::Binary(b) => Value::Blob(b),
+            MsgpackValue::Array(a) => {
+                Value::Array(a.into_iter().map(Value::try_from).collect::<Result<_>>()?)
+            }
+            MsgpackValue::Integer(i) => Value::Integer(
+                i.as_i64()
+                    .ok_or_else(|| Error::Unsupported(format!("integer {}", i)))?,
+            ),
+            MsgpackValue::F32(f) => Value::Float(f.into()),
+            MsgpackValue::F64(f) => Value::Float(f),
+            MsgpackValue::Map(m) => {
+                let mut map = Map::with_capacity(m.len());
+                for (k, v) in m {
+                    map.insert(key(k)?, v.try_into()?);
+                }
+                Value::Object(map)
+            }
+            MsgpackValue::String(s) => Value::String(string(s)?),
+            MsgpackValue::Ext(ty, _) => {
+                return Err(Error::Unsupported(format!(
+                    "MessagePack extension type {}",
+                    ty
+                )))
+            }
+        })
+    }
+}
+
+/// Converts a MessagePack document to Bincode JSON.
+pub fn from_msgpack(bytes: &[u8]) -> Result<Vec<u8>> {
+    let value =
+        rmpv::decode::read_value(&mut &bytes[..]).map_err(|e| Error::Custom(e.to_string()))?;
+    crate::to_vec(&Value::try_from(value)?)
+}
+
+/// Converts a Bincode JSON document to MessagePack.
+pub fn to_msgpack(bytes: &[u8]) -> Result<Vec<u8>> {
+    let value: Value = crate::from_slice(bytes)?;
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, &value.into()).map_err(|e| Error::Custom(e.to_string()))?;
+    Ok(buf)
+}
+
+/// Converts a MessagePack string, failing if it is not valid UTF-8.
+fn string(s: rmpv::Utf8String) -> Result<String> {
+    s.into_str()
+        .ok_or_else(|| Error::Unsupported("MessagePack string which is not UTF-8".into()))
+}
+
+/// Converts a MessagePack map key to an object key.
+fn key(key: MsgpackValue) -> Result<String> {
+    match key {
+        MsgpackValue::String(s) => string(s),
+        MsgpackValue::Integer(i) => Ok(i.to_string()),
+        _ => Err(Error::Unsupported(
+            "MessagePack map key other than string or integer".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::document;
+
+    fn canonical(bytes: &[u8]) -> Vec<u8> {
+        crate::to_vec_canonical(&crate::from_slice::<Value>(bytes).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = document().to_vec().unwrap();
+        let msgpack = to_msgpack(&bytes).unwrap();
+        assert_eq!(
+            canonical(&from_msgpack(&msgpack).unwrap()),
+            canonical(&bytes)
+        );
+
+        let value = Value::try_from(MsgpackValue::from(document())).unwrap();
+        assert_eq!(canonical(&value.to_vec().unwrap()), canonical(&bytes));
+
+        let map = MsgpackValue::Map(vec![(MsgpackValue::from(7), MsgpackValue::F32(0.5))]);
+        let Value::Object(o) = Value::try_from(map).unwrap() else {
+            panic!("expected an object");
+        };
+        assert!(matches!(o["7"], Value::Float(f) if f == 0.5));
+    }
+
+    #[test]
+    fn errors() {
+        let error = Value::try_from(MsgpackValue::from(u64::MAX)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        let error = Value::try_from(MsgpackValue::Ext(1, vec![0])).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        let error = from_msgpack(&[0xa1, 0xff]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        let map = MsgpackValue::Map(vec![(MsgpackValue::Nil, MsgpackValue::Nil)]);
+        let error = Value::try_from(map).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+
+        let error = from_msgpack(&[0xa2, b'a']).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+        let error = to_msgpack(&[0xff]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
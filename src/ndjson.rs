@@ -0,0 +1,196 @@
+//! Streams of newline-delimited JSON (NDJSON), one document per line.
+//!
+//! Documents are converted with a [JsonOptions], so blobs written as tagged blobs are read back as
+//! blobs. Blank lines are skipped when reading.
+
+use crate::{
+    error::{Error, Result},
+    json::JsonOptions,
+    stream::{RecordReader, RecordWriter},
+    value::Value,
+};
+use std::io::{BufRead, Read, Write};
+
+/// Reads [Value]s from NDJSON.
+pub struct NdjsonReader<R> {
+    reader: R,
+    options: JsonOptions,
+    line: String,
+    line_number: u64,
+}
+impl<R: BufRead> NdjsonReader<R> {
+    /// Constructs a new [NdjsonReader] reading from `reader` with default [JsonOptions].
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            options: JsonOptions::default(),
+            line: String::new(),
+            line_number: 0,
+        }
+    }
+
+    /// Sets the options documents are converted with.
+    pub fn with_options(mut self, options: JsonOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Reads the next document, returning `None` at the end of the input.
+    pub fn read_value(&mut self) -> Result<Option<Value>> {
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            if self.line.trim().is_empty() {
+                continue;
+            }
+
+            let json: serde_json::Value = serde_json::from_str(&self.line)
+                .map_err(|e| Error::Custom(format!("line {}: {}", self.line_number, e)))?;
+            return Ok(Some(Value::from_json_with(json, &self.options)));
+        }
+    }
+
+    /// Gets the number of lines read so far.
+    pub fn line_number(&self) -> u64 {
+        self.line_number
+    }
+
+    /// Consumes the NDJSON reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = Result<Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_value().transpose()
+    }
+}
+
+/// Writes [Value]s as NDJSON.
+pub struct NdjsonWriter<W> {
+    writer: W,
+    options: JsonOptions,
+    buf: Vec<u8>,
+}
+impl<W: Write> NdjsonWriter<W> {
+    /// Constructs a new [NdjsonWriter] writing into `writer` with default [JsonOptions].
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            options: JsonOptions::default(),
+            buf: Vec::new(),
+        }
+    }
+
+    /// Sets the options documents are converted with.
+    pub fn with_options(mut self, options: JsonOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes `val` as a line.
+    pub fn write_value(&mut self, val: Value) -> Result<()> {
+        self.buf.clear();
+        serde_json::to_writer(&mut self.buf, &val.to_json_with(&self.options))
+            .map_err(|e| Error::Custom(e.to_string()))?;
+        self.buf.push(b'\n');
+        self.writer.write_all(&self.buf)?;
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Consumes the NDJSON writer, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Writes every document of an NDJSON stream as a record, returning the number of records.
+pub fn ndjson_to_records<R: BufRead, W: Write>(
+    reader: &mut NdjsonReader<R>,
+    writer: &mut RecordWriter<W>,
+) -> Result<u64> {
+    let mut n = 0;
+    while let Some(value) = reader.read_value()? {
+        writer.write_record(&value)?;
+        n += 1;
+    }
+    Ok(n)
+}
+
+/// Writes every record of a record stream as an NDJSON line, returning the number of lines.
+pub fn records_to_ndjson<R: Read, W: Write>(
+    reader: &mut RecordReader<R>,
+    writer: &mut NdjsonWriter<W>,
+) -> Result<u64> {
+    let mut n = 0;
+    while let Some(value) = reader.read_record()? {
+        writer.write_value(value)?;
+        n += 1;
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    const NDJSON: &str = "{\"a\":1}\n\n[true,null]\n\"text\"\n";
+
+    #[test]
+    fn round_trip() {
+        let options = JsonOptions::new().with_tagged_blobs(true);
+        let mut writer = NdjsonWriter::new(Vec::new()).with_options(options);
+        writer.write_value(Value::Blob(vec![1, 2, 3])).unwrap();
+        writer.write_value(Value::Integer(7)).unwrap();
+        writer.flush().unwrap();
+        let text = writer.into_inner();
+        assert_eq!(text, b"{\"$binary\":\"AQID\"}\n7\n");
+
+        let mut reader = NdjsonReader::new(&text[..]).with_options(options);
+        assert!(matches!(reader.read_value().unwrap(), Some(Value::Blob(b)) if b == [1, 2, 3]));
+        assert!(matches!(
+            reader.read_value().unwrap(),
+            Some(Value::Integer(7))
+        ));
+        assert!(reader.read_value().unwrap().is_none());
+        assert_eq!(reader.line_number(), 2);
+    }
+
+    #[test]
+    fn records() {
+        let mut reader = NdjsonReader::new(NDJSON.as_bytes());
+        let mut writer = RecordWriter::new(Vec::new());
+        assert_eq!(ndjson_to_records(&mut reader, &mut writer).unwrap(), 3);
+        assert_eq!(reader.line_number(), 4);
+
+        let records = writer.into_inner();
+        let mut reader = RecordReader::new(&records[..]);
+        let mut writer = NdjsonWriter::new(Vec::new());
+        assert_eq!(records_to_ndjson(&mut reader, &mut writer).unwrap(), 3);
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            NDJSON.replace("\n\n", "\n")
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut reader = NdjsonReader::new("1\n\n{\n".as_bytes());
+        assert!(reader.next().unwrap().is_ok());
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+        assert!(error.to_string().contains("line 3"), "{}", error);
+        assert!(reader.next().is_none());
+    }
+}
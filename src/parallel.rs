@@ -0,0 +1,148 @@
+//! Parallel encoding of large documents with `rayon`, e.g. for multi-hundred-megabyte exports.
+//!
+//! [to_vec_parallel] encodes the elements of every array, and the entries of every object, longer
+//! than a threshold on the `rayon` thread pool. Each task encodes a run of consecutive elements
+//! into its own buffer, and the buffers are then stitched in order. The output is a standard
+//! encoding, which decodes to the same value as that of [crate::to_vec].
+
+use crate::{error::Result, value::Value, wire};
+use bincode::config;
+use rayon::prelude::*;
+use serde::Serialize;
+
+/// Options of [to_vec_parallel].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParallelOptions {
+    threshold: usize,
+}
+impl Default for ParallelOptions {
+    fn default() -> Self {
+        Self { threshold: 1024 }
+    }
+}
+impl ParallelOptions {
+    /// Constructs the default [ParallelOptions], encoding arrays and objects of at least 1024
+    /// elements or entries in parallel.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of elements or entries from which arrays and objects are encoded in
+    /// parallel.
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+/// Serialize the given data structure as a byte vector, encoding large arrays and objects in
+/// parallel.
+pub fn to_vec_parallel<T: Serialize>(val: &T, options: &ParallelOptions) -> Result<Vec<u8>> {
+    let value = crate::to_value(val)?;
+    let mut buf = Vec::new();
+    encode_into(&value, options, &mut buf)?;
+    Ok(buf)
+}
+
+/// Appends the encoding of `value` to `buf`.
+fn encode_into(value: &Value, options: &ParallelOptions, buf: &mut Vec<u8>) -> Result<()> {
+    match value {
+        Value::Array(a) if a.len() >= options.threshold.max(1) => {
+            encode_header(wire::ARRAY, a.len(), buf)?;
+            stitch(a, buf, |v, buf| encode_into(v, options, buf))
+        }
+        Value::Object(o) if o.len() >= options.threshold.max(1) => {
+            encode_header(wire::OBJECT, o.len(), buf)?;
+            let entries: Vec<_> = o.iter().collect();
+            stitch(&entries, buf, |(k, v), buf| {
+                bincode::encode_into_std_write(k.as_str(), buf, config::standard())?;
+                encode_into(v, options, buf)
+            })
+        }
+        value => {
+            bincode::encode_into_std_write(value, buf, config::standard())?;
+            Ok(())
+        }
+    }
+}
+
+/// Appends the tag and the length of an array or object to `buf`.
+fn encode_header(tag: u32, len: usize, buf: &mut Vec<u8>) -> Result<()> {
+    bincode::encode_into_std_write((tag, len as u64), buf, config::standard())?;
+    Ok(())
+}
+
+/// Encodes runs of consecutive `items` in parallel with `encode`, appending them to `buf` in
+/// order.
+fn stitch<I, F>(items: &[I], buf: &mut Vec<u8>, encode: F) -> Result<()>
+where
+    I: Sync,
+    F: Fn(&I, &mut Vec<u8>) -> Result<()> + Sync,
+{
+    // A few runs per thread balance the load when the sizes of the items vary.
+    let run = items
+        .len()
+        .div_ceil(4 * rayon::current_num_threads())
+        .max(1);
+    let buffers = items
+        .par_chunks(run)
+        .map(|items| {
+            let mut buf = Vec::new();
+            items.iter().try_for_each(|item| encode(item, &mut buf))?;
+            Ok(buf)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    buf.reserve(buffers.iter().map(Vec::len).sum());
+    buffers.iter().for_each(|b| buf.extend_from_slice(b));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, value::Map};
+    use std::collections::BTreeMap;
+
+    fn document() -> Value {
+        let record = |i: i64| {
+            Value::Object(Map::from([
+                (String::from("id"), Value::Integer(i)),
+                (String::from("name"), Value::String(format!("record {}", i))),
+                (
+                    String::from("tags"),
+                    Value::Array(vec![Value::Null; i as usize % 5]),
+                ),
+            ]))
+        };
+        Value::Array((0..3000).map(record).collect())
+    }
+
+    #[test]
+    fn round_trip() {
+        let records: Vec<(u32, String)> = (0..3000).map(|i| (i, format!("record {}", i))).collect();
+        for threshold in [0, 2, 1024, usize::MAX] {
+            let options = ParallelOptions::new().with_threshold(threshold);
+            let bytes = to_vec_parallel(&records, &options).unwrap();
+            assert_eq!(bytes, crate::to_vec(&records).unwrap());
+
+            let bytes = to_vec_parallel(&document(), &options).unwrap();
+            let decoded: Value = crate::from_slice(&bytes).unwrap();
+            assert_eq!(
+                crate::to_vec_canonical(&decoded).unwrap(),
+                crate::to_vec_canonical(&document()).unwrap()
+            );
+        }
+        assert_eq!(
+            to_vec_parallel(&Vec::<u32>::new(), &ParallelOptions::new()).unwrap(),
+            crate::to_vec(&Vec::<u32>::new()).unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let map = BTreeMap::from([((1u32, 2u32), 3u32)]);
+        let error = to_vec_parallel(&map, &ParallelOptions::new()).unwrap_err();
+        assert_eq!(error.kind(), crate::to_vec(&map).unwrap_err().kind());
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,147 @@
+//! Conversions between `bincode-json` values and protobuf's dynamic `google.protobuf.Struct` and
+//! `google.protobuf.Value`, for gRPC services.
+//!
+//! Protobuf values only have double-precision numbers, so integers convert to numbers and integral
+//! numbers convert back into integers; integers whose magnitude exceeds 2^53 have no exact double
+//! and are unsupported. Blobs convert to base64 strings, like `bytes` fields in protobuf's JSON
+//! mapping, which convert back into strings.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value, ValueKind},
+};
+use prost_types::{value::Kind, ListValue, NullValue, Struct};
+
+/// The largest magnitude up to which every integer is exactly representable as a double.
+const MAX_EXACT_INTEGER: i64 = 1 << 53;
+
+impl TryFrom<Value> for prost_types::Value {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self> {
+        let kind = match value {
+            Value::Null => Kind::NullValue(NullValue::NullValue.into()),
+            Value::Boolean(b) => Kind::BoolValue(b),
+            Value::Blob(b) => Kind::StringValue(base64::encode(b)),
+            Value::Array(a) => Kind::ListValue(ListValue {
+                values: a.into_iter().map(Value::try_into).collect::<Result<_>>()?,
+            }),
+            Value::Integer(i) if i.unsigned_abs() > MAX_EXACT_INTEGER as u64 => {
+                return Err(Error::Unsupported(format!("integer {} in protobuf", i)));
+            }
+            Value::Integer(i) => Kind::NumberValue(i as f64),
+            Value::Float(f) => Kind::NumberValue(f),
+            Value::Object(o) => Kind::StructValue(Value::Object(o).try_into()?),
+            Value::String(s) => Kind::StringValue(s),
+        };
+        Ok(prost_types::Value { kind: Some(kind) })
+    }
+}
+impl TryFrom<Value> for Struct {
+    type Error = Error;
+
+    /// Converts an object into a protobuf struct, failing if the value is not an object.
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(o) => Ok(Struct {
+                fields: o
+                    .into_iter()
+                    .map(|(k, v)| Ok((k, v.try_into()?)))
+                    .collect::<Result<_>>()?,
+            }),
+            v => Err(Error::Expected(ValueKind::Object.into(), v.kind().into())),
+        }
+    }
+}
+impl From<prost_types::Value> for Value {
+    fn from(value: prost_types::Value) -> Self {
+        match value.kind {
+            None | Some(Kind::NullValue(_)) => Value::Null,
+            Some(Kind::BoolValue(b)) => Value::Boolean(b),
+            Some(Kind::NumberValue(f))
+                if f.fract() == 0.0 && f.abs() <= MAX_EXACT_INTEGER as f64 =>
+            {
+                Value::Integer(f as i64)
+            }
+            Some(Kind::NumberValue(f)) => Value::Float(f),
+            Some(Kind::StringValue(s)) => Value::String(s),
+            Some(Kind::ListValue(l)) => {
+                Value::Array(l.values.into_iter().map(Value::from).collect())
+            }
+            Some(Kind::StructValue(s)) => s.into(),
+        }
+    }
+}
+impl From<Struct> for Value {
+    fn from(value: Struct) -> Self {
+        let mut map = Map::with_capacity(value.fields.len());
+        for (k, v) in value.fields {
+            map.insert(k, v.into());
+        }
+        Value::Object(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn document() -> Value {
+        Value::Object(Map::from([
+            (String::from("null"), Value::Null),
+            (String::from("bool"), Value::Boolean(true)),
+            (
+                String::from("array"),
+                Value::Array(vec![Value::Integer(-42)]),
+            ),
+            (String::from("float"), Value::Float(1.5)),
+            (String::from("string"), Value::String("text".into())),
+        ]))
+    }
+
+    #[test]
+    fn round_trip() {
+        let proto = Struct::try_from(document()).unwrap();
+        assert_eq!(
+            proto.fields["array"],
+            prost_types::Value {
+                kind: Some(Kind::ListValue(ListValue {
+                    values: vec![prost_types::Value {
+                        kind: Some(Kind::NumberValue(-42.0))
+                    }]
+                }))
+            }
+        );
+        let value = Value::from(proto);
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+
+        let blob = prost_types::Value::try_from(Value::Blob(vec![1, 2, 3])).unwrap();
+        assert!(matches!(Value::from(blob), Value::String(s) if s == "AQID"));
+    }
+
+    #[test]
+    fn integer_precision() {
+        for i in [MAX_EXACT_INTEGER, -MAX_EXACT_INTEGER] {
+            let proto = prost_types::Value::try_from(Value::Integer(i)).unwrap();
+            assert!(matches!(Value::from(proto), Value::Integer(n) if n == i));
+        }
+        for i in [MAX_EXACT_INTEGER + 1, i64::MIN] {
+            let error = prost_types::Value::try_from(Value::Integer(i)).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Unsupported);
+        }
+        let big = prost_types::Value {
+            kind: Some(Kind::NumberValue(1e300)),
+        };
+        assert!(matches!(Value::from(big), Value::Float(f) if f == 1e300));
+    }
+
+    #[test]
+    fn errors() {
+        let error = Struct::try_from(Value::Array(Vec::new())).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,162 @@
+//! PyO3 bindings, converting [Value]s from/to Python objects and exposing Bincode JSON encoding to
+//! Python.
+//!
+//! `None`, `bool`, `int`, `float`, `str`, `bytes`, `list`, `tuple` and `dict` objects convert into
+//! the matching values, and values convert back into them, where arrays become lists. Dictionary
+//! keys must be strings, and integers must fit an `i64`. Extension modules expose `to_vec` and
+//! `from_slice` to Python by calling [register] from their `#[pymodule]`.
+
+use crate::{error::Error, value::Value};
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple},
+};
+
+impl<'py> IntoPyObject<'py> for Value {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Self::Output> {
+        Ok(match self {
+            Value::Null => py.None().into_bound(py),
+            Value::Boolean(b) => PyBool::new(py, b).to_owned().into_any(),
+            Value::Blob(b) => PyBytes::new(py, &b).into_any(),
+            Value::Array(a) => PyList::new(py, a)?.into_any(),
+            Value::Integer(i) => PyInt::new(py, i).into_any(),
+            Value::Float(f) => PyFloat::new(py, f).into_any(),
+            Value::Object(o) => {
+                let dict = PyDict::new(py);
+                for (k, v) in o {
+                    dict.set_item(k, v)?;
+                }
+                dict.into_any()
+            }
+            Value::String(s) => PyString::new(py, &s).into_any(),
+        })
+    }
+}
+impl<'a, 'py> FromPyObject<'a, 'py> for Value {
+    type Error = PyErr;
+
+    fn extract(obj: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        Ok(if obj.is_none() {
+            Value::Null
+        } else if let Ok(b) = obj.cast::<PyBool>() {
+            Value::Boolean(b.is_true())
+        } else if let Ok(i) = obj.cast::<PyInt>() {
+            Value::Integer(i.extract()?)
+        } else if let Ok(f) = obj.cast::<PyFloat>() {
+            Value::Float(f.value())
+        } else if let Ok(s) = obj.cast::<PyString>() {
+            Value::String(s.to_str()?.into())
+        } else if let Ok(b) = obj.cast::<PyBytes>() {
+            Value::Blob(b.as_bytes().into())
+        } else if let Ok(l) = obj.cast::<PyList>() {
+            Value::Array(l.iter().map(|v| v.extract()).collect::<PyResult<_>>()?)
+        } else if let Ok(t) = obj.cast::<PyTuple>() {
+            Value::Array(t.iter().map(|v| v.extract()).collect::<PyResult<_>>()?)
+        } else if let Ok(d) = obj.cast::<PyDict>() {
+            let mut map = crate::value::Map::with_capacity(d.len());
+            for (k, v) in d.iter() {
+                map.insert(k.cast::<PyString>()?.to_str()?.into(), v.extract()?);
+            }
+            Value::Object(map)
+        } else {
+            return Err(PyTypeError::new_err(format!(
+                "unsupported Python type {}",
+                obj.get_type().name()?
+            )));
+        })
+    }
+}
+
+impl From<Error> for PyErr {
+    /// Converts an [Error] into a Python `ValueError`.
+    fn from(e: Error) -> Self {
+        PyValueError::new_err(e.to_string())
+    }
+}
+
+/// Encodes a Python object as Bincode JSON.
+#[pyfunction]
+fn to_vec(py: Python<'_>, obj: Value) -> PyResult<Bound<'_, PyBytes>> {
+    Ok(PyBytes::new(py, &crate::to_vec(&obj)?))
+}
+
+/// Decodes a Python object from Bincode JSON.
+#[pyfunction]
+fn from_slice(data: &[u8]) -> PyResult<Value> {
+    Ok(crate::from_slice(data)?)
+}
+
+/// Adds the `to_vec` and `from_slice` functions to a Python module.
+pub fn register(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(to_vec, module)?)?;
+    module.add_function(wrap_pyfunction!(from_slice, module)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::document;
+
+    #[test]
+    fn round_trip() {
+        Python::initialize();
+        Python::attach(|py| {
+            let obj = document().into_pyobject(py).unwrap();
+            assert!(obj.cast::<PyDict>().is_ok());
+            let value: Value = obj.extract().unwrap();
+            assert_eq!(
+                crate::to_vec_canonical(&value).unwrap(),
+                crate::to_vec_canonical(&document()).unwrap()
+            );
+
+            let tuple = PyTuple::new(py, [1, 2]).unwrap();
+            let value: Value = tuple.extract().unwrap();
+            assert!(matches!(value, Value::Array(a) if a.len() == 2));
+
+            let module = PyModule::new(py, "bincode_json").unwrap();
+            register(&module).unwrap();
+            let bytes = module.getattr("to_vec").unwrap().call1((obj,)).unwrap();
+            let decoded = module
+                .getattr("from_slice")
+                .unwrap()
+                .call1((bytes,))
+                .unwrap();
+            let value: Value = decoded.extract().unwrap();
+            assert_eq!(
+                crate::to_vec_canonical(&value).unwrap(),
+                crate::to_vec_canonical(&document()).unwrap()
+            );
+        });
+    }
+
+    #[test]
+    fn errors() {
+        Python::initialize();
+        Python::attach(|py| {
+            let set = py.eval(c"{1}", None, None).unwrap();
+            let error = set.extract::<Value>().unwrap_err();
+            assert!(error.is_instance_of::<PyTypeError>(py));
+
+            let dict = py.eval(c"{1: 2}", None, None).unwrap();
+            assert!(dict.extract::<Value>().is_err());
+            let big = py.eval(c"2 ** 64", None, None).unwrap();
+            assert!(big.extract::<Value>().is_err());
+
+            let module = PyModule::new(py, "bincode_json").unwrap();
+            register(&module).unwrap();
+            let data = PyBytes::new(py, &[0xff]);
+            let error = module
+                .getattr("from_slice")
+                .unwrap()
+                .call1((data,))
+                .unwrap_err();
+            assert!(error.is_instance_of::<PyValueError>(py));
+        });
+    }
+}
@@ -0,0 +1,337 @@
+//! A lazy view over an encoded document, which decodes only the parts that are accessed.
+//!
+//! A [RawDocument] wraps the bytes of an encoded value without decoding them. Looking up an entry
+//! or an element skips over everything before it and returns another [RawDocument] over the bytes
+//! of the addressed value, so reading two fields of a large document costs a scan of its
+//! structure rather than a full decode. Like `bson`'s raw documents, malformed input is only
+//! detected as far as it is read.
+//!
+//! [extract] projects a single value out of a document by its path, such as `a.b[3].c`, where
+//! keys are separated by dots and array indices are bracketed, and [patch] replaces one by
+//! splicing its encoding into the document.
+
+use crate::{
+    borrowed::{take_bytes, BorrowedValue},
+    error::{Error, Result},
+    limits::{DecodeLimits, Limit},
+    selector::{Segment, Selector},
+    value::{Value, ValueKind},
+    wire::{self, SliceReader},
+};
+use alloc::{format, vec::Vec};
+use bincode::{
+    config::Configuration,
+    de::{read::BorrowReader, BorrowDecoder, Decoder, DecoderImpl},
+};
+use core::ops::Range;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A view over the bytes of an encoded value, resolving entries and elements on demand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RawDocument<'a> {
+    bytes: &'a [u8],
+    limits: DecodeLimits,
+}
+impl<'a> RawDocument<'a> {
+    /// Constructs a new [RawDocument] over the value at the beginning of `bytes`, enforcing the
+    /// default [DecodeLimits].
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            limits: DecodeLimits::default(),
+        }
+    }
+
+    /// Sets the limits enforced while scanning and decoding the value.
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Gets the bytes of the view, which are exactly those of the value for views returned by
+    /// [RawDocument::get] and [RawDocument::get_index].
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Gets a view of the value of the entry of `key`, or `None` if the object has no such entry,
+    /// failing with [Error::Expected] if the value is not an object.
+    pub fn get(&self, key: &str) -> Result<Option<RawDocument<'a>>> {
+        Ok(self.entry_range(key)?.map(|range| self.slice(range)))
+    }
+
+    /// Gets a view of the element at `index`, or `None` if the array is not that long, failing
+    /// with [Error::Expected] if the value is not an array.
+    pub fn get_index(&self, index: usize) -> Result<Option<RawDocument<'a>>> {
+        Ok(self.element_range(index)?.map(|range| self.slice(range)))
+    }
+
+    /// Gets a view of the value at `path`, such as `a.b[3].c`, or `None` if there is none. A path
+    /// with wildcards gets the first value it selects.
+    pub fn get_path(&self, path: &str) -> Result<Option<RawDocument<'a>>> {
+        Ok(Selector::compile(path)?.select_raw(*self)?.first().copied())
+    }
+
+    /// Returns `true` if the value is an object.
+    pub fn is_object(&self) -> Result<bool> {
+        Ok(wire::decode_tag(&mut self.decoder()?)? == wire::OBJECT)
+    }
+
+    /// Returns `true` if the value is an array.
+    pub fn is_array(&self) -> Result<bool> {
+        Ok(wire::decode_tag(&mut self.decoder()?)? == wire::ARRAY)
+    }
+
+    /// Deserializes the value as an instance of type `T`.
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        crate::from_slice_with_limits(self.bytes, &self.limits)
+    }
+
+    /// Decodes the value as a [Value].
+    pub fn to_value(&self) -> Result<Value> {
+        wire::decode_from_slice(self.bytes, &self.limits).map(|(value, _)| value)
+    }
+
+    /// Decodes the value as a [BorrowedValue], borrowing its strings and blobs.
+    pub fn to_borrowed(&self) -> Result<BorrowedValue<'a>> {
+        BorrowedValue::from_slice_with_limits(self.bytes, &self.limits)
+    }
+
+    fn decoder(&self) -> Result<DecoderImpl<SliceReader<'a>, Configuration>> {
+        self.limits.check(Limit::Bytes, self.bytes.len())?;
+        Ok(DecoderImpl::new(
+            SliceReader::new(self.bytes),
+            bincode::config::standard(),
+        ))
+    }
+
+    /// Reads the header of a container of type `tag`, returning its length.
+    fn container(
+        &self,
+        decoder: &mut DecoderImpl<SliceReader<'a>, Configuration>,
+        tag: u32,
+    ) -> Result<usize> {
+        let found = wire::decode_tag(decoder)?;
+        if found != tag {
+            return Err(Error::Expected(
+                wire::value_kind(tag).into(),
+                wire::value_kind(found).into(),
+            ));
+        }
+        self.limits.check(Limit::Depth, 1)?;
+        let len = wire::decode_len(decoder)?;
+        self.limits.check(Limit::Elements, len)?;
+        Ok(len)
+    }
+
+    /// Gets views of the elements of an array or the entry values of an object, or nothing for
+    /// other values.
+    pub(crate) fn children(&self) -> Result<Vec<RawDocument<'a>>> {
+        let mut decoder = self.decoder()?;
+        let object = match wire::decode_tag(&mut decoder)? {
+            wire::ARRAY => false,
+            wire::OBJECT => true,
+            _ => return Ok(Vec::new()),
+        };
+        self.limits.check(Limit::Depth, 1)?;
+        let len = wire::decode_len(&mut decoder)?;
+        self.limits.check(Limit::Elements, len)?;
+        let mut children = Vec::with_capacity(len.min(wire::MAX_PREALLOCATED));
+        for _ in 0..len {
+            if object {
+                let key_len = wire::decode_len(&mut decoder)?;
+                self.limits.check(Limit::StringLength, key_len)?;
+                take_bytes(&mut decoder, key_len)?;
+            }
+            let range = self.next_range(&mut decoder)?;
+            children.push(self.slice(range));
+        }
+        Ok(children)
+    }
+
+    /// Locates the value of the entry of `key` within the bytes of the view.
+    fn entry_range(&self, key: &str) -> Result<Option<Range<usize>>> {
+        let mut decoder = self.decoder()?;
+        let len = self.container(&mut decoder, wire::OBJECT)?;
+        for _ in 0..len {
+            let key_len = wire::decode_len(&mut decoder)?;
+            self.limits.check(Limit::StringLength, key_len)?;
+            decoder.claim_bytes_read(key_len)?;
+            if decoder.borrow_reader().take_bytes(key_len)? == key.as_bytes() {
+                return self.next_range(&mut decoder).map(Some);
+            }
+            wire::skip_value(&mut decoder, &self.limits, 1)?;
+        }
+        Ok(None)
+    }
+
+    /// Locates the element at `index` within the bytes of the view.
+    fn element_range(&self, index: usize) -> Result<Option<Range<usize>>> {
+        let mut decoder = self.decoder()?;
+        let len = self.container(&mut decoder, wire::ARRAY)?;
+        if index >= len {
+            return Ok(None);
+        }
+        for _ in 0..index {
+            wire::skip_value(&mut decoder, &self.limits, 1)?;
+        }
+        self.next_range(&mut decoder).map(Some)
+    }
+
+    /// Locates the length prefix of the object, returning it with the length.
+    fn object_len_range(&self) -> Result<(Range<usize>, usize)> {
+        let mut decoder = self.decoder()?;
+        let found = wire::decode_tag(&mut decoder)?;
+        if found != wire::OBJECT {
+            return Err(Error::Expected(
+                ValueKind::Object.into(),
+                wire::value_kind(found).into(),
+            ));
+        }
+        let start = decoder.reader().position();
+        let len = wire::decode_len(&mut decoder)?;
+        Ok((start..decoder.reader().position(), len))
+    }
+
+    /// Skips over the next value, returning the range of its bytes.
+    fn next_range(
+        &self,
+        decoder: &mut DecoderImpl<SliceReader<'a>, Configuration>,
+    ) -> Result<Range<usize>> {
+        let start = decoder.reader().position();
+        wire::skip_value(decoder, &self.limits, 1)?;
+        Ok(start..decoder.reader().position())
+    }
+
+    /// Constructs a view of the bytes in `range`.
+    fn slice(&self, range: Range<usize>) -> Self {
+        Self {
+            bytes: &self.bytes[range],
+            limits: self.limits,
+        }
+    }
+}
+
+/// Deserialize an instance of type `T` from the value at `path` in bytes of Bincode JSON, such as
+/// `a.b[3].c`, skipping over the rest of the document. Fails with [Error::Missing] if there is no
+/// value at `path`.
+pub fn extract<T: DeserializeOwned>(bytes: &[u8], path: &str) -> Result<T> {
+    Selector::compile(path)?.extract(bytes)
+}
+
+/// Replaces the value at `path` in the encoded document `doc` with `value`, such as `a.b[3].c`, or
+/// inserts it into its object if it has no entry of the key yet. Only the bytes of the replaced
+/// value, or the length prefix of the object, are spliced, so the rest of the document is neither
+/// decoded nor re-encoded. Fails with [Error::Missing] if a parent of the value, or the element of
+/// an array index, does not exist.
+pub fn patch<T: Serialize>(doc: &mut Vec<u8>, path: &str, value: &T) -> Result<()> {
+    let value = crate::to_vec(value)?;
+    let missing = || Error::Missing(path.into());
+    let root = RawDocument::new(doc);
+    let selector = Selector::compile(path)?;
+    if !selector.is_definite() {
+        return Err(Error::Unsupported(format!(
+            "wildcard in patched path {}",
+            path
+        )));
+    }
+    let Some((last, parents)) = selector.segments().split_last() else {
+        let end = root.next_range(&mut root.decoder()?)?.end;
+        doc.splice(..end, value);
+        return Ok(());
+    };
+
+    let mut parent = root;
+    let mut offset = 0;
+    for segment in parents {
+        let range = match segment {
+            Segment::Key(key) => parent.entry_range(key)?,
+            Segment::Index(index) => parent.element_range(*index)?,
+            Segment::Wildcard => unreachable!("selector is definite"),
+        }
+        .ok_or_else(missing)?;
+        offset += range.start;
+        parent = parent.slice(range);
+    }
+
+    let (range, bytes) = match last {
+        Segment::Key(key) => match parent.entry_range(key)? {
+            Some(range) => (range, value),
+            None => {
+                let (range, len) = parent.object_len_range()?;
+                let mut bytes =
+                    bincode::encode_to_vec((len as u64 + 1, key), bincode::config::standard())?;
+                bytes.extend_from_slice(&value);
+                (range, bytes)
+            }
+        },
+        Segment::Index(index) => (parent.element_range(*index)?.ok_or_else(missing)?, value),
+        Segment::Wildcard => unreachable!("selector is definite"),
+    };
+    doc.splice(offset + range.start..offset + range.end, bytes);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::{borrow::ToOwned, collections::BTreeMap, string::String, vec};
+
+    type Document = BTreeMap<String, Vec<BTreeMap<String, u32>>>;
+
+    fn document() -> Document {
+        let element = |n: u32| [("n".to_owned(), n)].into();
+        [("a".to_owned(), vec![element(1), element(2)])].into()
+    }
+
+    #[test]
+    fn patch_replaces() {
+        let mut doc = crate::to_vec(&document()).unwrap();
+        patch(&mut doc, "a[1].n", &300u32).unwrap();
+        let mut expected = document();
+        expected.get_mut("a").unwrap()[1].insert("n".to_owned(), 300);
+        assert_eq!(crate::from_slice::<Document>(&doc).unwrap(), expected);
+        assert_eq!(extract::<u32>(&doc, "a[1].n").unwrap(), 300);
+    }
+
+    #[test]
+    fn patch_inserts() {
+        let mut doc = crate::to_vec(&document()).unwrap();
+        patch(&mut doc, "a[0].m", &7u32).unwrap();
+        let mut expected = document();
+        expected.get_mut("a").unwrap()[0].insert("m".to_owned(), 7);
+        assert_eq!(crate::from_slice::<Document>(&doc).unwrap(), expected);
+    }
+
+    #[test]
+    fn patch_root() {
+        let mut doc = crate::to_vec(&document()).unwrap();
+        patch(&mut doc, "", &"replaced").unwrap();
+        assert_eq!(crate::from_slice::<String>(&doc).unwrap(), "replaced");
+    }
+
+    #[test]
+    fn patch_missing() {
+        let mut doc = crate::to_vec(&document()).unwrap();
+        let original = doc.clone();
+        let error = patch(&mut doc, "a[5].n", &1u32).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::MissingField);
+        let error = patch(&mut doc, "b.n", &1u32).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::MissingField);
+        assert_eq!(doc, original);
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let doc = crate::to_vec(&vec![1u32]).unwrap();
+        let error = RawDocument::new(&doc).get("a").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let (expected, found) = error.mismatch().unwrap();
+        assert_eq!(expected.kind(), Some(ValueKind::Object));
+        assert_eq!(found.kind(), Some(ValueKind::Array));
+
+        let error = patch(&mut doc.clone(), "a", &1u32).unwrap_err();
+        assert_eq!(error.mismatch().unwrap().1.kind(), Some(ValueKind::Array));
+    }
+}
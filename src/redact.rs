@@ -0,0 +1,225 @@
+//! Redaction of sensitive fields, such as passwords, before logging or exporting documents.
+//!
+//! A [Redactor] matches values by glob-like paths, written like those of [crate::Selector]:
+//!  - `*` matches any single key or index, as in `*.password`.
+//!  - `*` within a key matches any part of it, as in `*_token` or `secret*`.
+//!  - `**` matches any number of levels, as in `**.ssn`.
+//!
+//! [Value::redact] replaces the matched values with a placeholder, `"[REDACTED]"` by default.
+
+use crate::{
+    error::Result,
+    selector::{Segment, Selector},
+    value::Value,
+};
+use alloc::vec::Vec;
+
+/// A set of glob-like paths of values to redact.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    paths: Vec<Selector>,
+    placeholder: Value,
+}
+impl Default for Redactor {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            placeholder: Value::String("[REDACTED]".into()),
+        }
+    }
+}
+impl Redactor {
+    /// Constructs a new [Redactor] which matches nothing yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a glob-like path of values to redact, such as `*.password` or `user.ssn`, failing with
+    /// [crate::Error::Custom] if it is malformed.
+    pub fn with_path(mut self, path: &str) -> Result<Self> {
+        self.paths.push(Selector::compile(path)?);
+        Ok(self)
+    }
+
+    /// Sets the value which replaces redacted values.
+    pub fn with_placeholder(mut self, placeholder: Value) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    /// Returns `true` if a path matches the value at the wildcard-free `path`.
+    pub fn matches(&self, path: &str) -> bool {
+        Selector::compile(path).is_ok_and(|path| {
+            self.paths
+                .iter()
+                .any(|p| matches(p.segments(), path.segments()))
+        })
+    }
+
+    fn redact(&self, value: &mut Value, path: &mut Vec<Segment>) {
+        if !path.is_empty() && self.paths.iter().any(|p| matches(p.segments(), path)) {
+            *value = self.placeholder.clone();
+            return;
+        }
+        match value {
+            Value::Array(a) => {
+                for (i, v) in a.iter_mut().enumerate() {
+                    path.push(Segment::Index(i));
+                    self.redact(v, path);
+                    path.pop();
+                }
+            }
+            Value::Object(o) => {
+                for (k, v) in o.iter_mut() {
+                    path.push(Segment::Key(k.clone()));
+                    self.redact(v, path);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Value {
+    /// Replaces the values matched by `redactor` with its placeholder, leaving the rest of the
+    /// value untouched.
+    pub fn redact(&mut self, redactor: &Redactor) {
+        redactor.redact(self, &mut Vec::new());
+    }
+}
+
+/// Checks whether the glob-like `pattern` matches `path`.
+fn matches(pattern: &[Segment], path: &[Segment]) -> bool {
+    let Some((first, rest)) = pattern.split_first() else {
+        return path.is_empty();
+    };
+    match first {
+        Segment::Key(key) if key == "**" => (0..=path.len()).any(|i| matches(rest, &path[i..])),
+        first => {
+            let Some((segment, path)) = path.split_first() else {
+                return false;
+            };
+            let matched = match (first, segment) {
+                (Segment::Wildcard, _) => true,
+                (Segment::Key(pattern), Segment::Key(key)) => glob(pattern, key),
+                (Segment::Index(a), Segment::Index(b)) => a == b,
+                _ => false,
+            };
+            matched && matches(rest, path)
+        }
+    }
+}
+
+/// Checks whether `key` matches `pattern`, where each `*` matches any part of the key.
+fn glob(pattern: &str, key: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut key) = key.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return key.is_empty();
+    };
+    for part in middle {
+        match key.find(part) {
+            Some(i) => key = &key[i + part.len()..],
+            None => return false,
+        }
+    }
+    key.ends_with(last)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::vec;
+
+    fn object(entries: Vec<(&str, Value)>) -> Value {
+        Value::Object(entries.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    fn text(s: &str) -> Value {
+        Value::String(s.into())
+    }
+
+    #[test]
+    fn redact() {
+        let mut value = object(vec![
+            ("password", text("top")),
+            (
+                "users",
+                Value::Array(vec![object(vec![
+                    ("password", text("secret")),
+                    ("name", text("a")),
+                    ("profile", object(vec![("ssn", text("123"))])),
+                ])]),
+            ),
+            ("api_token", text("t")),
+            ("secret_key", text("k")),
+            ("token_count", Value::Integer(1)),
+        ]);
+        let redactor = Redactor::new()
+            .with_path("*.*.password")
+            .unwrap()
+            .with_path("**.ssn")
+            .unwrap()
+            .with_path("*_token")
+            .unwrap()
+            .with_path("secret*")
+            .unwrap();
+        value.redact(&redactor);
+
+        let redacted = text("[REDACTED]");
+        let expected = object(vec![
+            ("password", text("top")),
+            (
+                "users",
+                Value::Array(vec![object(vec![
+                    ("password", redacted.clone()),
+                    ("name", text("a")),
+                    ("profile", object(vec![("ssn", redacted.clone())])),
+                ])]),
+            ),
+            ("api_token", redacted.clone()),
+            ("secret_key", redacted),
+            ("token_count", Value::Integer(1)),
+        ]);
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&expected).unwrap()
+        );
+
+        assert!(redactor.matches("users[3].password"));
+        assert!(redactor.matches("ssn"));
+        assert!(redactor.matches("a.b.c.ssn"));
+        assert!(!redactor.matches("users[3].name"));
+        assert!(!redactor.matches("a[b"));
+    }
+
+    #[test]
+    fn placeholders_and_errors() {
+        let redactor = Redactor::new()
+            .with_path("users[0]")
+            .unwrap()
+            .with_placeholder(Value::Null);
+        let mut value = object(vec![("users", Value::Array(vec![text("a"), text("b")]))]);
+        value.redact(&redactor);
+        let Value::Object(o) = value else {
+            panic!("expected an object");
+        };
+        assert!(
+            matches!(&o["users"], Value::Array(a) if matches!(a[..], [Value::Null, Value::String(ref s)] if s == "b"))
+        );
+
+        // The root itself is never redacted, even by a pattern matching any path.
+        let mut root = text("root");
+        root.redact(&Redactor::new().with_path("**").unwrap());
+        assert!(matches!(root, Value::String(s) if s == "root"));
+
+        let error = Redactor::new().with_path("a..b").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+    }
+}
@@ -0,0 +1,141 @@
+//! `redis` argument and response conversions of Bincode JSON documents, so they can be `SET`,
+//! `GET` and passed through streams directly.
+//!
+//! [Value]s and [BincodeJson]s are written as bulk strings of Bincode JSON, and read back from
+//! them with the default [DecodeLimits](crate::DecodeLimits). Missing keys are read with
+//! `Option`, like any other type.
+//!
+//! Writing a [BincodeJson] as an argument panics if its document fails to serialize, since
+//! `redis` arguments cannot fail; [BincodeJson::to_redis_bytes] encodes it fallibly instead, for
+//! documents which may not serialize.
+
+use crate::{error::Result, value::Value};
+use ::redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs};
+use serde::{de::DeserializeOwned, Serialize};
+use std::ops::{Deref, DerefMut};
+
+/// A Bincode JSON document of a `T`, stored in Redis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BincodeJson<T>(pub T);
+impl<T: Serialize> BincodeJson<T> {
+    /// Encodes the document as the bytes of a Redis argument, failing if `T` cannot be serialized.
+    pub fn to_redis_bytes(&self) -> Result<Vec<u8>> {
+        crate::to_vec(&self.0)
+    }
+}
+impl<T: Serialize> ToRedisArgs for BincodeJson<T> {
+    /// Writes the document as an argument.
+    ///
+    /// # Panics
+    /// Panics if `T` cannot be serialized, as arguments cannot fail. Use
+    /// [BincodeJson::to_redis_bytes] for documents which may fail to serialize.
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        let buf = self
+            .to_redis_bytes()
+            .expect("a Redis argument failed to serialize");
+        out.write_arg(&buf);
+    }
+}
+impl<T: DeserializeOwned> FromRedisValue for BincodeJson<T> {
+    fn from_redis_value(v: &::redis::Value) -> RedisResult<Self> {
+        crate::from_slice(bulk_string(v)?)
+            .map(Self)
+            .map_err(decode_error)
+    }
+}
+impl<T> From<T> for BincodeJson<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+impl<T> Deref for BincodeJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for BincodeJson<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl ToRedisArgs for Value {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        let buf = bincode::encode_to_vec(self, bincode::config::standard())
+            .expect("encoding a value into a vector never fails");
+        out.write_arg(&buf);
+    }
+}
+impl FromRedisValue for Value {
+    fn from_redis_value(v: &::redis::Value) -> RedisResult<Self> {
+        crate::from_slice(bulk_string(v)?).map_err(decode_error)
+    }
+}
+
+/// Gets the bytes of a bulk string response.
+fn bulk_string(v: &::redis::Value) -> RedisResult<&[u8]> {
+    match v {
+        ::redis::Value::BulkString(bytes) => Ok(bytes),
+        v => Err(RedisError::from((
+            ErrorKind::TypeError,
+            "Response was of incompatible type",
+            format!(
+                "expected bulk string of Bincode JSON (response was {:?})",
+                v
+            ),
+        ))),
+    }
+}
+
+/// Converts a decoding error into a [RedisError].
+fn decode_error(e: crate::Error) -> RedisError {
+    RedisError::from((
+        ErrorKind::TypeError,
+        "Response was not valid Bincode JSON",
+        e.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::ser::Error as _;
+
+    struct Unserializable;
+    impl Serialize for Unserializable {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> std::result::Result<S::Ok, S::Error> {
+            Err(S::Error::custom("unserializable"))
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let doc = BincodeJson(vec![("a".to_owned(), 1u32)]);
+        let args = doc.to_redis_args();
+        assert_eq!(args, [doc.to_redis_bytes().unwrap()]);
+        let response = ::redis::Value::BulkString(args[0].clone());
+        assert_eq!(BincodeJson::from_redis_value(&response).unwrap(), doc);
+
+        let value = Value::String("text".into());
+        let response = ::redis::Value::BulkString(value.to_redis_args().remove(0));
+        let decoded = Value::from_redis_value(&response).unwrap();
+        assert!(matches!(decoded, Value::String(s) if s == "text"));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(BincodeJson(Unserializable).to_redis_bytes().is_err());
+
+        let error = BincodeJson::<u32>::from_redis_value(&::redis::Value::Int(1)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeError);
+        let response = ::redis::Value::BulkString(vec![9]);
+        let error = Value::from_redis_value(&response).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeError);
+        assert_eq!(
+            Option::<BincodeJson<u32>>::from_redis_value(&::redis::Value::Nil).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! A Rocket data guard and responder of Bincode JSON bodies, like `rocket::serde::json::Json`.
+//!
+//! Request bodies are read up to the [LIMIT] data limit, 1 MiB by default, and decoded with the
+//! [DecodeLimits] managed by the Rocket instance, or with the default ones.
+
+use crate::{error::Error, limits::DecodeLimits};
+use ::rocket::{
+    data::{Data, FromData, Outcome, ToByteUnit},
+    http::{ContentType, Status},
+    request::Request,
+    response::{self, Responder},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt::{self, Display},
+    io,
+    ops::{Deref, DerefMut},
+};
+
+/// The name of the data limit of [BincodeJson] request bodies.
+pub const LIMIT: &str = "bincode-json";
+
+/// A Bincode JSON request or response body of a `T`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BincodeJson<T>(pub T);
+#[::rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromData<'r> for BincodeJson<T> {
+    type Error = BincodeJsonError;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        let limit = req.limits().get(LIMIT).unwrap_or(1.mebibytes());
+        let bytes = match data.open(limit).into_bytes().await {
+            Ok(bytes) if bytes.is_complete() => bytes.into_inner(),
+            Ok(_) => {
+                let e = io::Error::new(io::ErrorKind::UnexpectedEof, "data limit exceeded");
+                return Outcome::Error((Status::PayloadTooLarge, BincodeJsonError::Io(e)));
+            }
+            Err(e) => return Outcome::Error((Status::BadRequest, BincodeJsonError::Io(e))),
+        };
+
+        let limits = req
+            .rocket()
+            .state::<DecodeLimits>()
+            .copied()
+            .unwrap_or_default();
+        match crate::from_slice_with_limits(&bytes, &limits) {
+            Ok(value) => Outcome::Success(Self(value)),
+            Err(e) => Outcome::Error((Status::BadRequest, BincodeJsonError::Decode(e))),
+        }
+    }
+}
+impl<'r, T: Serialize> Responder<'r, 'static> for BincodeJson<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let buf = crate::to_vec(&self.0).map_err(|_| Status::InternalServerError)?;
+        (ContentType::new("application", "x-bincode-json"), buf).respond_to(req)
+    }
+}
+impl<T> From<T> for BincodeJson<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+impl<T> Deref for BincodeJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for BincodeJson<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Why a [BincodeJson] could not be read from a request.
+#[derive(Debug)]
+pub enum BincodeJsonError {
+    /// The body could not be read, or exceeds the data limit.
+    Io(io::Error),
+
+    /// The body could not be decoded.
+    Decode(Error),
+}
+impl Display for BincodeJsonError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(formatter, "failed to read the request body: {}", e),
+            Self::Decode(e) => write!(formatter, "failed to decode the request body: {}", e),
+        }
+    }
+}
+impl std::error::Error for BincodeJsonError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, CONTENT_TYPE};
+    use ::rocket::{
+        config::Config, data::Limits, local::blocking::Client, post, routes, Build, Rocket,
+    };
+
+    #[post("/", data = "<body>")]
+    fn echo(body: BincodeJson<(String, u32)>) -> BincodeJson<(String, u32)> {
+        body
+    }
+
+    #[post("/error", data = "<body>")]
+    fn error(body: Result<BincodeJson<String>, BincodeJsonError>) -> String {
+        match body {
+            Ok(_) => String::from("ok"),
+            Err(BincodeJsonError::Decode(e)) => e.kind().as_str().to_owned(),
+            Err(BincodeJsonError::Io(e)) => e.to_string(),
+        }
+    }
+
+    fn rocket() -> Rocket<Build> {
+        let config = Config {
+            limits: Limits::default().limit(LIMIT, 16.bytes()),
+            ..Config::debug_default()
+        };
+        ::rocket::custom(config).mount("/", routes![echo, error])
+    }
+
+    #[test]
+    fn round_trip() {
+        let client = Client::untracked(rocket()).unwrap();
+        let body = crate::to_vec(&("key", 1u32)).unwrap();
+        let response = client.post("/").body(&body).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type().unwrap().to_string(), CONTENT_TYPE);
+        assert_eq!(response.into_bytes().unwrap(), body);
+    }
+
+    #[test]
+    fn errors() {
+        let client = Client::untracked(rocket()).unwrap();
+        let response = client.post("/").body([0xff]).dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+        let response = client.post("/").body([0; 17]).dispatch();
+        assert_eq!(response.status(), Status::PayloadTooLarge);
+
+        let body = crate::to_vec(&1u32).unwrap();
+        let response = client.post("/error").body(&body).dispatch();
+        assert_eq!(
+            response.into_string().unwrap(),
+            ErrorKind::TypeMismatch.as_str()
+        );
+
+        let limits = DecodeLimits::new().with_max_string_len(2);
+        let client = Client::untracked(rocket().manage(limits)).unwrap();
+        let body = crate::to_vec(&"text").unwrap();
+        let response = client.post("/error").body(&body).dispatch();
+        assert_eq!(
+            response.into_string().unwrap(),
+            ErrorKind::LimitExceeded.as_str()
+        );
+    }
+}
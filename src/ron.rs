@@ -0,0 +1,135 @@
+//! Conversions between `bincode-json` values and RON values, for data authored in RON.
+//!
+//! Null converts to `None` and blobs to byte strings. Converting back, `Some` is dropped in favor
+//! of the value it holds, units become null and chars become strings. RON maps may have keys of
+//! any type: string keys are kept, char, boolean and integer keys become their text, and keys of
+//! other types are unsupported, as are integers outside the range of an `i64`.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value},
+};
+use ::ron::{value::Number, Map as RonMap, Value as RonValue};
+
+impl From<Value> for RonValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => RonValue::Option(None),
+            Value::Boolean(b) => RonValue::Bool(b),
+            Value::Blob(b) => RonValue::Bytes(b),
+            Value::Array(a) => RonValue::Seq(a.into_iter().map(RonValue::from).collect()),
+            Value::Integer(i) => RonValue::Number(i.into()),
+            Value::Float(f) => RonValue::Number(f.into()),
+            Value::Object(o) => RonValue::Map(
+                o.into_iter()
+                    .map(|(k, v)| (RonValue::String(k), RonValue::from(v)))
+                    .collect::<RonMap>(),
+            ),
+            Value::String(s) => RonValue::String(s),
+        }
+    }
+}
+impl TryFrom<RonValue> for Value {
+    type Error = Error;
+
+    fn try_from(value: RonValue) -> Result<Self> {
+        Ok(match value {
+            RonValue::Unit | RonValue::Option(None) => Value::Null,
+            RonValue::Option(Some(value)) => (*value).try_into()?,
+            RonValue::Bool(b) => Value::Boolean(b),
+            RonValue::Char(c) => Value::String(c.into()),
+            RonValue::String(s) => Value::String(s),
+            RonValue::Bytes(b) => Value::Blob(b),
+            RonValue::Number(n) => number(n)?,
+            RonValue::Seq(a) => {
+                Value::Array(a.into_iter().map(Value::try_from).collect::<Result<_>>()?)
+            }
+            RonValue::Map(m) => {
+                let mut map = Map::with_capacity(m.len());
+                for (k, v) in m {
+                    map.insert(key(k)?, v.try_into()?);
+                }
+                Value::Object(map)
+            }
+        })
+    }
+}
+
+/// Converts a RON number, failing if it is an integer outside the range of an `i64`.
+fn number(n: Number) -> Result<Value> {
+    Ok(match n {
+        Number::I8(i) => Value::Integer(i.into()),
+        Number::I16(i) => Value::Integer(i.into()),
+        Number::I32(i) => Value::Integer(i.into()),
+        Number::I64(i) => Value::Integer(i),
+        Number::U8(i) => Value::Integer(i.into()),
+        Number::U16(i) => Value::Integer(i.into()),
+        Number::U32(i) => Value::Integer(i.into()),
+        Number::U64(i) => Value::Integer(
+            i64::try_from(i).map_err(|_| Error::Unsupported(format!("integer {}", i)))?,
+        ),
+        n => Value::Float(n.into_f64()),
+    })
+}
+
+/// Converts a RON map key to an object key.
+fn key(key: RonValue) -> Result<String> {
+    match key {
+        RonValue::String(s) => Ok(s),
+        RonValue::Char(c) => Ok(c.into()),
+        RonValue::Bool(b) => Ok(b.to_string()),
+        RonValue::Number(n) => match number(n)? {
+            Value::Integer(i) => Ok(i.to_string()),
+            _ => Err(Error::Unsupported("RON map key of a float".into())),
+        },
+        _ => Err(Error::Unsupported(
+            "RON map key other than a string, char, boolean or integer".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::document;
+
+    #[test]
+    fn round_trip() {
+        let ron = RonValue::from(document());
+        let text = ::ron::to_string(&ron).unwrap();
+        let value = Value::try_from(::ron::from_str::<RonValue>(&text).unwrap()).unwrap();
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&Value::try_from(ron).unwrap()).unwrap()
+        );
+
+        let value = Value::try_from(RonValue::from(document())).unwrap();
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+    }
+
+    #[test]
+    fn foreign_types() {
+        let value: RonValue = ::ron::from_str("{'c': Some(()), 7: 'x', true: 1.5}").unwrap();
+        let Value::Object(o) = Value::try_from(value).unwrap() else {
+            panic!("expected an object");
+        };
+        assert!(o["c"].is_null());
+        assert!(matches!(&o["7"], Value::String(s) if s == "x"));
+        assert!(matches!(o["true"], Value::Float(f) if f == 1.5));
+    }
+
+    #[test]
+    fn errors() {
+        let error = Value::try_from(RonValue::Number(u64::MAX.into())).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        for key in ["1.5", "[1]", "()"] {
+            let value: RonValue = ::ron::from_str(&format!("{{{}: 1}}", key)).unwrap();
+            let error = Value::try_from(value).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Unsupported, "{}", key);
+        }
+    }
+}
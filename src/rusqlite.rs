@@ -0,0 +1,137 @@
+//! `rusqlite` binding and reading of Bincode JSON documents as SQLite `BLOB`s.
+//!
+//! Typed documents are stored as [BincodeJson]s, and loosely typed ones as [Value]s. Both are read
+//! back from `BLOB` columns only, with the default [DecodeLimits](crate::DecodeLimits).
+
+use crate::value::Value;
+use ::rusqlite::{
+    types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef},
+    ToSql,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::ops::{Deref, DerefMut};
+
+/// A Bincode JSON document of a `T`, stored as a `BLOB`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BincodeJson<T>(pub T);
+impl<T: Serialize> ToSql for BincodeJson<T> {
+    fn to_sql(&self) -> ::rusqlite::Result<ToSqlOutput<'_>> {
+        crate::to_vec(&self.0)
+            .map(ToSqlOutput::from)
+            .map_err(|e| ::rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+}
+impl<T: DeserializeOwned> FromSql for BincodeJson<T> {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        crate::from_slice(value.as_blob()?)
+            .map(Self)
+            .map_err(|e| FromSqlError::Other(Box::new(e)))
+    }
+}
+impl<T> From<T> for BincodeJson<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+impl<T> Deref for BincodeJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for BincodeJson<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl ToSql for Value {
+    fn to_sql(&self) -> ::rusqlite::Result<ToSqlOutput<'_>> {
+        bincode::encode_to_vec(self, bincode::config::standard())
+            .map(ToSqlOutput::from)
+            .map_err(|e| ::rusqlite::Error::ToSqlConversionFailure(Box::new(crate::Error::from(e))))
+    }
+}
+impl FromSql for Value {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        crate::from_slice(value.as_blob()?).map_err(|e| FromSqlError::Other(Box::new(e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{Error, ErrorKind};
+    use ::rusqlite::{params, Connection};
+    use std::collections::HashMap;
+
+    fn connection() -> Connection {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute("CREATE TABLE docs (doc)", []).unwrap();
+        connection
+    }
+
+    #[test]
+    fn round_trip() {
+        let connection = connection();
+        connection
+            .execute(
+                "INSERT INTO docs VALUES (?1), (?2)",
+                params![BincodeJson(("key", 42u32)), Value::Integer(7)],
+            )
+            .unwrap();
+
+        let mut statement = connection.prepare("SELECT doc FROM docs").unwrap();
+        let mut rows = statement.query([]).unwrap();
+        let BincodeJson(first): BincodeJson<(String, u32)> =
+            rows.next().unwrap().unwrap().get(0).unwrap();
+        assert_eq!(first, (String::from("key"), 42));
+        let second: Value = rows.next().unwrap().unwrap().get(0).unwrap();
+        assert!(matches!(second, Value::Integer(7)));
+    }
+
+    #[test]
+    fn errors() {
+        let connection = connection();
+        let error = connection
+            .execute(
+                "INSERT INTO docs VALUES (?1)",
+                [BincodeJson(HashMap::from([(1u8, 2u8)]))],
+            )
+            .unwrap_err();
+        let ::rusqlite::Error::ToSqlConversionFailure(e) = error else {
+            panic!("expected a conversion failure, found {:?}", error);
+        };
+        assert_eq!(
+            e.downcast::<Error>().unwrap().kind(),
+            ErrorKind::TypeMismatch
+        );
+
+        connection
+            .execute("INSERT INTO docs VALUES ('text'), (x'ff')", [])
+            .unwrap();
+        let mut statement = connection.prepare("SELECT doc FROM docs").unwrap();
+        let mut rows = statement.query([]).unwrap();
+        let error = rows
+            .next()
+            .unwrap()
+            .unwrap()
+            .get::<_, Value>(0)
+            .unwrap_err();
+        assert!(matches!(error, ::rusqlite::Error::InvalidColumnType(..)));
+        let error = rows
+            .next()
+            .unwrap()
+            .unwrap()
+            .get::<_, Value>(0)
+            .unwrap_err();
+        let ::rusqlite::Error::FromSqlConversionFailure(_, _, e) = error else {
+            panic!("expected a conversion failure, found {:?}", error);
+        };
+        assert_eq!(
+            e.downcast::<Error>().unwrap().kind(),
+            ErrorKind::Unsupported
+        );
+    }
+}
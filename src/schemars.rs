@@ -0,0 +1,67 @@
+//! `schemars` integration, validating dynamic documents against the JSON Schema of a Rust type.
+//!
+//! The schema of a `T: JsonSchema` is derived by [schema_for] and checked with `jsonschema`, after
+//! converting the value with [Value::to_json]. As blobs convert into base64 strings there, a blob
+//! matches a `string` schema rather than the array of integers `Vec<u8>` is described as.
+
+use crate::{
+    error::{Error, Result},
+    value::Value,
+};
+use ::schemars::{JsonSchema, Schema};
+
+/// Derives the JSON Schema of `T`.
+pub fn schema_for<T: JsonSchema>() -> Schema {
+    ::schemars::schema_for!(T)
+}
+
+/// Validates `value` against the JSON Schema of `T`, failing with the first violation found.
+pub fn validate_value_against<T: JsonSchema>(value: &Value) -> Result<()> {
+    let schema = schema_for::<T>();
+    let validator = jsonschema::validator_for(schema.as_value())
+        .map_err(|e| Error::Custom(format!("invalid schema: {}", e)))?;
+    let instance = value.clone().to_json();
+    validator.validate(&instance).map_err(|e| {
+        let path = e.instance_path().to_string();
+        if path.is_empty() {
+            Error::Custom(e.to_string())
+        } else {
+            Error::Custom(format!("{} at {}", e, path))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, value::Map};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn valid() {
+        let value = Value::Object(Map::from([
+            (String::from("a"), Value::Integer(1)),
+            (String::from("b"), Value::Integer(2)),
+        ]));
+        validate_value_against::<BTreeMap<String, u32>>(&value).unwrap();
+        let value = Value::Array(vec![Value::String("key".into()), Value::Integer(42)]);
+        validate_value_against::<(String, u32)>(&value).unwrap();
+        validate_value_against::<String>(&Value::Blob(vec![1, 2, 3])).unwrap();
+
+        let schema = schema_for::<Option<bool>>();
+        assert!(schema.as_value().is_object());
+    }
+
+    #[test]
+    fn violations() {
+        let error = validate_value_against::<u32>(&Value::String("1".into())).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+
+        let value = Value::Object(Map::from([(String::from("a"), Value::Integer(-1))]));
+        let error = validate_value_against::<BTreeMap<String, u32>>(&value).unwrap_err();
+        assert!(error.to_string().ends_with("at /a"));
+
+        let error = validate_value_against::<Vec<u8>>(&Value::Blob(vec![1])).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+    }
+}
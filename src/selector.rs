@@ -0,0 +1,257 @@
+//! Precompiled paths, selecting values out of documents without parsing the path again.
+//!
+//! A path such as `a.b[*].c` separates keys by dots and brackets array indices. A `*` key selects
+//! the values of every entry of an object and a `[*]` index every element of an array, so a path
+//! may select any number of values. [Selector::compile] parses a path once, after which the
+//! [Selector] can be evaluated against any number of [Value]s, or of encoded documents through
+//! [RawDocument]s, which skip over everything not selected.
+
+use crate::{
+    error::{Error, Result},
+    raw::RawDocument,
+    value::Value,
+};
+use alloc::{format, string::String, vec, vec::Vec};
+use core::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+use serde::de::DeserializeOwned;
+
+/// A step of a path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// A compiled path, selecting values out of documents.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Selector {
+    path: String,
+    segments: Vec<Segment>,
+}
+impl Selector {
+    /// Compiles `path`, such as `a.b[*].c`, failing with [Error::Custom] if it is malformed.
+    pub fn compile(path: &str) -> Result<Self> {
+        let invalid = || Error::Custom(format!("invalid path {}", path));
+        let mut segments = Vec::new();
+        let mut rest = path;
+        let mut first = true;
+        while !rest.is_empty() {
+            if let Some(index) = rest.strip_prefix('[') {
+                let end = index.find(']').ok_or_else(invalid)?;
+                segments.push(match &index[..end] {
+                    "*" => Segment::Wildcard,
+                    index => Segment::Index(index.parse().map_err(|_| invalid())?),
+                });
+                rest = &index[end + 1..];
+            } else {
+                let key = match rest.strip_prefix('.') {
+                    Some(key) => key,
+                    None if first => rest,
+                    None => return Err(invalid()),
+                };
+                let end = key.find(['.', '[']).unwrap_or(key.len());
+                segments.push(match &key[..end] {
+                    "" => return Err(invalid()),
+                    "*" => Segment::Wildcard,
+                    key => Segment::Key(key.into()),
+                });
+                rest = &key[end..];
+            }
+            first = false;
+        }
+        Ok(Self {
+            path: path.into(),
+            segments,
+        })
+    }
+
+    /// Gets the path the selector was compiled from.
+    pub fn as_str(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns `true` if the selector has no wildcard, so selects at most one value.
+    pub fn is_definite(&self) -> bool {
+        !self.segments.contains(&Segment::Wildcard)
+    }
+
+    /// Selects the values at the path in `value`, in document order.
+    pub fn select<'v>(&self, value: &'v Value) -> Vec<&'v Value> {
+        let mut selected = vec![value];
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for value in selected {
+                match (segment, value) {
+                    (Segment::Key(key), Value::Object(o)) => next.extend(o.get(key)),
+                    (Segment::Index(index), Value::Array(a)) => next.extend(a.get(*index)),
+                    (Segment::Wildcard, Value::Array(a)) => next.extend(a),
+                    (Segment::Wildcard, Value::Object(o)) => next.extend(o.values()),
+                    _ => {}
+                }
+            }
+            selected = next;
+        }
+        selected
+    }
+
+    /// Selects views of the values at the path in an encoded document, in document order.
+    pub fn select_raw<'a>(&self, doc: RawDocument<'a>) -> Result<Vec<RawDocument<'a>>> {
+        let mut selected = vec![doc];
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for doc in selected {
+                match segment {
+                    Segment::Key(key) if doc.is_object()? => next.extend(doc.get(key)?),
+                    Segment::Index(index) if doc.is_array()? => next.extend(doc.get_index(*index)?),
+                    Segment::Wildcard => next.extend(doc.children()?),
+                    _ => {}
+                }
+            }
+            selected = next;
+        }
+        Ok(selected)
+    }
+
+    /// Deserialize an instance of type `T` from the first value at the path in bytes of Bincode
+    /// JSON, failing with [Error::Missing] if there is none.
+    pub fn extract<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+        self.select_raw(RawDocument::new(bytes))?
+            .first()
+            .ok_or_else(|| Error::Missing(self.path.clone()))?
+            .decode()
+    }
+
+    pub(crate) fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns `true` if the selector selects the value at the wildcard-free `path`.
+    pub(crate) fn matches(&self, path: &[Segment]) -> bool {
+        self.segments.len() == path.len()
+            && self.segments.iter().zip(path).all(|(s, p)| match s {
+                Segment::Wildcard => true,
+                s => s == p,
+            })
+    }
+}
+impl Display for Selector {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.path)
+    }
+}
+impl FromStr for Selector {
+    type Err = Error;
+
+    fn from_str(path: &str) -> Result<Self> {
+        Self::compile(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, value::Map};
+    use alloc::string::ToString;
+
+    fn user(name: &str, ids: &[i64]) -> Value {
+        Value::Object(Map::from([
+            (String::from("name"), Value::String(name.into())),
+            (
+                String::from("ids"),
+                Value::Array(ids.iter().copied().map(Value::Integer).collect()),
+            ),
+        ]))
+    }
+
+    fn document() -> Value {
+        Value::Object(Map::from([(
+            String::from("users"),
+            Value::Array(vec![user("a", &[1, 2]), user("b", &[3])]),
+        )]))
+    }
+
+    #[test]
+    fn select() {
+        let document = document();
+        let selector = Selector::compile("users[*].ids[0]").unwrap();
+        assert!(!selector.is_definite());
+        assert_eq!(selector.as_str(), "users[*].ids[0]");
+        let selected = selector.select(&document);
+        assert!(matches!(
+            selected[..],
+            [Value::Integer(1), Value::Integer(3)]
+        ));
+
+        let selector: Selector = "users[1].name".parse().unwrap();
+        assert!(selector.is_definite());
+        assert_eq!(selector.to_string(), "users[1].name");
+        assert!(matches!(selector.select(&document)[..], [Value::String(s)] if s == "b"));
+
+        assert_eq!(
+            Selector::compile("users.*")
+                .unwrap()
+                .select(&document)
+                .len(),
+            2
+        );
+        assert_eq!(
+            Selector::compile("*.*.name")
+                .unwrap()
+                .select(&document)
+                .len(),
+            2
+        );
+        assert_eq!(
+            Selector::compile("users[2]")
+                .unwrap()
+                .select(&document)
+                .len(),
+            0
+        );
+        assert_eq!(Selector::compile("").unwrap().select(&document).len(), 1);
+    }
+
+    #[test]
+    fn select_raw() {
+        let bytes = crate::to_vec(&document()).unwrap();
+        let selector = Selector::compile("users[*].ids[*]").unwrap();
+        let selected = selector.select_raw(RawDocument::new(&bytes)).unwrap();
+        let ids: Vec<i64> = selected.iter().map(|doc| doc.decode().unwrap()).collect();
+        assert_eq!(ids, [1, 2, 3]);
+
+        let name: String = Selector::compile("users[0].name")
+            .unwrap()
+            .extract(&bytes)
+            .unwrap();
+        assert_eq!(name, "a");
+    }
+
+    #[test]
+    fn errors() {
+        for path in ["a..b", "a[", "a[x]", "[0]b", "a."] {
+            let error = Selector::compile(path).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Custom, "{}", path);
+        }
+
+        let bytes = crate::to_vec(&document()).unwrap();
+        let error = Selector::compile("users[5].name")
+            .unwrap()
+            .extract::<String>(&bytes)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::MissingField);
+        let error = Selector::compile("users[0].name")
+            .unwrap()
+            .extract::<u32>(&bytes)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = Selector::compile("users[*]")
+            .unwrap()
+            .select_raw(RawDocument::new(&bytes[..bytes.len() - 1]))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+    }
+}
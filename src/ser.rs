@@ -1,8 +1,14 @@
 //! Serializes Rust data into `bincode-json` data.
 
 use crate::{
-    error::{Error, Result},
-    value::{Map, Value},
+    error::{Description, Error, Result},
+    value::{Map, Value, ValueKind},
+};
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
 };
 use serde::{ser, Serialize};
 
@@ -16,7 +22,7 @@ impl Default for Serializer {
 impl Serializer {
     /// Constructs a new [Serializer] with default configuration.
     pub fn new() -> Self {
-        Self::default()
+        Self
     }
 }
 impl ser::Serializer for Serializer {
@@ -75,9 +81,9 @@ impl ser::Serializer for Serializer {
     fn serialize_none(self) -> Result<Self::Ok> {
         Ok(Value::Null)
     }
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
@@ -95,13 +101,13 @@ impl ser::Serializer for Serializer {
     ) -> Result<Self::Ok> {
         self.serialize_str(variant)
     }
-    fn serialize_newtype_struct<T: ?Sized>(self, _: &'static str, value: &T) -> Result<Self::Ok>
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _: &'static str,
         _: u32,
@@ -109,7 +115,7 @@ impl ser::Serializer for Serializer {
         value: &T,
     ) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         let mut map: Map<String, Value> = Map::with_capacity(1);
         map.insert(variant.to_string(), value.serialize(self)?);
@@ -177,9 +183,9 @@ impl ser::SerializeSeq for SeqSerializer {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.inner.push(value.serialize(Serializer::new())?);
         Ok(())
@@ -192,9 +198,9 @@ impl ser::SerializeTuple for SeqSerializer {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.inner.push(value.serialize(Serializer::new())?);
         Ok(())
@@ -207,9 +213,9 @@ impl ser::SerializeTupleStruct for SeqSerializer {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.inner.push(value.serialize(Serializer::new())?);
         Ok(())
@@ -227,9 +233,9 @@ impl ser::SerializeTupleVariant for SeqVariantSerializer {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.inner.push(value.serialize(Serializer::new())?);
         Ok(())
@@ -254,8 +260,8 @@ impl ser::SerializeMap for MapSerializer {
             Value::String(s) => Some(s),
             other => {
                 return Err(Error::Expected(
-                    "type str".into(),
-                    other.error_description().into(),
+                    Description::from("type str").with_kind(ValueKind::String),
+                    other.kind().into(),
                 ))
             }
         };
@@ -280,9 +286,9 @@ impl ser::SerializeStruct for StructSerializer {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.inner
             .insert(key.to_owned(), value.serialize(Serializer::new())?);
@@ -302,9 +308,9 @@ impl ser::SerializeStructVariant for StructVariantSerializer {
     type Ok = Value;
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.inner
             .insert(key.to_owned(), value.serialize(Serializer::new())?);
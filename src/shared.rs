@@ -0,0 +1,340 @@
+//! A persistent counterpart of [Value], whose clones share their contents.
+//!
+//! Strings, blobs, arrays and objects of a [SharedValue] are reference-counted, so cloning a value
+//! is O(1) however large it is. Modifications copy on write: [SharedValue::as_array_mut] and
+//! [SharedValue::as_object_mut] only copy a container if it is shared, and even then the copy
+//! shares every element with the original, so a change copies the path to it but no sub-tree
+//! beside it. Caches can thus hand out snapshots to many readers while they are updated.
+
+use crate::{
+    error::Result,
+    limits::DecodeLimits,
+    value::{Map, Value},
+    wire,
+};
+use alloc::{string::String, sync::Arc, vec::Vec};
+use bincode::{
+    enc::{Encode, Encoder},
+    error::EncodeError,
+};
+use serde::{de, ser};
+
+/// Represents any valid `bincode-json` value, with strings, blobs, arrays and objects shared
+/// between clones.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedValue {
+    /// Represents a `bincode-json` null value.
+    Null,
+
+    /// Represents a `bincode-json` bool value.
+    Boolean(bool),
+
+    /// Represents a `bincode-json` blob value.
+    Blob(Arc<[u8]>),
+
+    /// Represents a `bincode-json` array value.
+    Array(Arc<Vec<SharedValue>>),
+
+    /// Represents a `bincode-json` integer value.
+    Integer(i64),
+
+    /// Represents a `bincode-json` float value.
+    Float(f64),
+
+    /// Represents a `bincode-json` object value.
+    Object(Arc<Map<String, SharedValue>>),
+
+    /// Represents a `bincode-json` string value.
+    String(Arc<str>),
+}
+impl SharedValue {
+    /// Decodes a value from the beginning of `bytes`, enforcing the default [DecodeLimits].
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        Self::from_slice_with_limits(bytes, &DecodeLimits::default())
+    }
+
+    /// Decodes a value from the beginning of `bytes`, enforcing `limits`.
+    pub fn from_slice_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self> {
+        wire::decode_from_slice(bytes, limits).map(|(value, _)| value.into())
+    }
+
+    /// Copies the shared parts of the value, converting it into a [Value].
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Null => Value::Null,
+            Self::Boolean(b) => Value::Boolean(*b),
+            Self::Blob(b) => Value::Blob(b.to_vec()),
+            Self::Array(a) => Value::Array(a.iter().map(Self::to_value).collect()),
+            Self::Integer(i) => Value::Integer(*i),
+            Self::Float(f) => Value::Float(*f),
+            Self::Object(o) => {
+                Value::Object(o.iter().map(|(k, v)| (k.clone(), v.to_value())).collect())
+            }
+            Self::String(s) => Value::String(s.as_ref().into()),
+        }
+    }
+
+    /// Returns `true` if both values are the same shared string, blob, array or object, which
+    /// implies they are equal without comparing their contents.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Blob(a), Self::Blob(b)) => Arc::ptr_eq(a, b),
+            (Self::Array(a), Self::Array(b)) => Arc::ptr_eq(a, b),
+            (Self::Object(a), Self::Object(b)) => Arc::ptr_eq(a, b),
+            (Self::String(a), Self::String(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if this value is `Null`.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// Gets the string this value holds, if it is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Gets the bytes this value holds, if it is a blob.
+    pub fn as_blob(&self) -> Option<&[u8]> {
+        match self {
+            Self::Blob(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Gets the elements of this value, if it is an array.
+    pub fn as_array(&self) -> Option<&[SharedValue]> {
+        match self {
+            Self::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Gets the entries of this value, if it is an object.
+    pub fn as_object(&self) -> Option<&Map<String, SharedValue>> {
+        match self {
+            Self::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Gets the elements of this value mutably, if it is an array, first copying them if they are
+    /// shared with another clone.
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<SharedValue>> {
+        match self {
+            Self::Array(a) => Some(Arc::make_mut(a)),
+            _ => None,
+        }
+    }
+
+    /// Gets the entries of this value mutably, if it is an object, first copying them if they are
+    /// shared with another clone.
+    pub fn as_object_mut(&mut self) -> Option<&mut Map<String, SharedValue>> {
+        match self {
+            Self::Object(o) => Some(Arc::make_mut(o)),
+            _ => None,
+        }
+    }
+
+    /// Gets the value of the entry of `key`, if this value is an object which has one.
+    pub fn get(&self, key: &str) -> Option<&SharedValue> {
+        self.as_object()?.get(key)
+    }
+
+    /// Gets the element at `index`, if this value is an array which has one.
+    pub fn get_index(&self, index: usize) -> Option<&SharedValue> {
+        self.as_array()?.get(index)
+    }
+}
+impl From<Value> for SharedValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::Boolean(b) => Self::Boolean(b),
+            Value::Blob(b) => Self::Blob(b.into()),
+            Value::Array(a) => Self::Array(Arc::new(a.into_iter().map(Self::from).collect())),
+            Value::Integer(i) => Self::Integer(i),
+            Value::Float(f) => Self::Float(f),
+            Value::Object(o) => Self::Object(Arc::new(
+                o.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            )),
+            Value::String(s) => Self::String(s.into()),
+        }
+    }
+}
+impl From<&SharedValue> for Value {
+    fn from(value: &SharedValue) -> Self {
+        value.to_value()
+    }
+}
+impl Encode for SharedValue {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> core::result::Result<(), EncodeError> {
+        match self {
+            Self::Null => wire::NULL.encode(encoder),
+            Self::Boolean(b) => {
+                wire::BOOLEAN.encode(encoder)?;
+                b.encode(encoder)
+            }
+            Self::Blob(b) => {
+                wire::BLOB.encode(encoder)?;
+                b[..].encode(encoder)
+            }
+            Self::Array(a) => {
+                wire::ARRAY.encode(encoder)?;
+                (a.len() as u64).encode(encoder)?;
+                a.iter().try_for_each(|v| v.encode(encoder))
+            }
+            Self::Integer(i) => {
+                wire::INTEGER.encode(encoder)?;
+                i.encode(encoder)
+            }
+            Self::Float(f) => {
+                wire::FLOAT.encode(encoder)?;
+                f.encode(encoder)
+            }
+            Self::Object(o) => {
+                wire::OBJECT.encode(encoder)?;
+                (o.len() as u64).encode(encoder)?;
+                for (k, v) in o.iter() {
+                    k.encode(encoder)?;
+                    v.encode(encoder)?;
+                }
+                Ok(())
+            }
+            Self::String(s) => {
+                wire::STRING.encode(encoder)?;
+                s[..].encode(encoder)
+            }
+        }
+    }
+}
+impl ser::Serialize for SharedValue {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Null => serializer.serialize_none(),
+            Self::Boolean(b) => serializer.serialize_bool(*b),
+            Self::Blob(b) => serializer.serialize_bytes(b),
+            Self::Integer(n) => serializer.serialize_i64(*n),
+            Self::Float(f) => serializer.serialize_f64(*f),
+            Self::String(s) => serializer.serialize_str(s),
+            Self::Array(v) => v.serialize(serializer),
+            Self::Object(m) => {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(m.len()))?;
+                for (k, v) in m.iter() {
+                    map.serialize_entry(k, v)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+impl<'de> de::Deserialize<'de> for SharedValue {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        Value::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::vec;
+
+    fn document() -> Value {
+        Value::Object(Map::from([
+            (String::from("null"), Value::Null),
+            (String::from("bool"), Value::Boolean(true)),
+            (String::from("blob"), Value::Blob(vec![1, 2, 3])),
+            (
+                String::from("array"),
+                Value::Array(vec![Value::Integer(-42)]),
+            ),
+            (String::from("float"), Value::Float(1.5)),
+            (String::from("string"), Value::String("text".into())),
+        ]))
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = crate::to_vec(&document()).unwrap();
+        let shared = SharedValue::from_slice(&bytes).unwrap();
+        assert_eq!(shared, SharedValue::from(document()));
+        assert_eq!(
+            shared.get("string").and_then(SharedValue::as_str),
+            Some("text")
+        );
+        assert_eq!(
+            shared.get("blob").and_then(SharedValue::as_blob),
+            Some(&[1, 2, 3][..])
+        );
+        assert!(shared.get("null").unwrap().is_null());
+        assert_eq!(
+            crate::to_vec_canonical(&shared.to_value()).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+
+        let decoded: SharedValue = crate::from_slice(&crate::to_vec(&shared).unwrap()).unwrap();
+        assert_eq!(decoded, shared);
+
+        let array = SharedValue::from(Value::Array(vec![Value::Integer(1), document()]));
+        let encoded = bincode::encode_to_vec(&array, bincode::config::standard()).unwrap();
+        assert_eq!(SharedValue::from_slice(&encoded).unwrap(), array);
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let original = SharedValue::from(document());
+        let mut copy = original.clone();
+        assert!(copy.ptr_eq(&original));
+
+        copy.as_object_mut()
+            .unwrap()
+            .get_mut("array")
+            .unwrap()
+            .as_array_mut()
+            .unwrap()
+            .push(SharedValue::Integer(1));
+        assert!(!copy.ptr_eq(&original));
+        assert_eq!(original.get("array").unwrap().as_array().unwrap().len(), 1);
+        assert_eq!(
+            copy.get("array").unwrap().get_index(1),
+            Some(&SharedValue::Integer(1))
+        );
+        assert!(copy
+            .get("string")
+            .unwrap()
+            .ptr_eq(original.get("string").unwrap()));
+        assert!(copy
+            .get("blob")
+            .unwrap()
+            .ptr_eq(original.get("blob").unwrap()));
+
+        assert!(SharedValue::Integer(1).as_array_mut().is_none());
+        assert!(!SharedValue::Null.ptr_eq(&SharedValue::Null));
+    }
+
+    #[test]
+    fn errors() {
+        let error = SharedValue::from_slice(&[0xff]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        let bytes = crate::to_vec(&document()).unwrap();
+        let error = SharedValue::from_slice(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+
+        let limits = DecodeLimits::new().with_max_elements(2);
+        let error = SharedValue::from_slice_with_limits(&bytes, &limits).unwrap_err();
+        assert!(error.is_limit_exceeded());
+    }
+}
@@ -0,0 +1,181 @@
+//! Authenticated envelopes around encoded `bincode-json` documents.
+//!
+//! A signed document is the canonical encoding of the document followed by its signature, so
+//! it can be passed through untrusted intermediaries and verified on arrival. Signing schemes
+//! plug in through the [Signer] and [Verifier] traits; HMAC-SHA256 is provided by `HmacSha256`
+//! with the `hmac` feature.
+
+use crate::error::{Error, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A signing scheme producing fixed-size signatures.
+pub trait Signer {
+    /// Gets the length of the signatures, in bytes.
+    fn signature_len(&self) -> usize;
+
+    /// Signs `message`, returning a signature of [Signer::signature_len] bytes.
+    fn sign(&self, message: &[u8]) -> Vec<u8>;
+}
+
+/// A verifier of the signatures of a [Signer].
+pub trait Verifier {
+    /// Gets the length of the signatures, in bytes.
+    fn signature_len(&self) -> usize;
+
+    /// Returns `true` if `signature` is a valid signature of `message`.
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool;
+}
+
+/// Serialize the given data structure in the canonical encoding, followed by its signature,
+/// failing if `signer` returns a signature other than [Signer::signature_len] bytes long.
+pub fn to_vec_signed<T: Serialize, S: Signer + ?Sized>(val: &T, signer: &S) -> Result<Vec<u8>> {
+    let mut buf = crate::to_vec_canonical(val)?;
+    let signature = signer.sign(&buf);
+    // Verifiers split signed bytes at the signature length, so a signature of another length
+    // would make the envelope unreadable.
+    if signature.len() != signer.signature_len() {
+        return Err(Error::Expected(
+            format!("{} signature bytes", signer.signature_len()).into(),
+            format!("{} signature bytes", signature.len()).into(),
+        ));
+    }
+    buf.extend_from_slice(&signature);
+    Ok(buf)
+}
+
+/// Deserialize an instance of type `T` from signed bytes of Bincode JSON, failing with
+/// [Error::InvalidSignature] if the signature does not match.
+pub fn from_slice_verified<T: DeserializeOwned, V: Verifier + ?Sized>(
+    val: &[u8],
+    verifier: &V,
+) -> Result<T> {
+    let split = val
+        .len()
+        .checked_sub(verifier.signature_len())
+        .ok_or(Error::Eof)?;
+    let (message, signature) = val.split_at(split);
+    if !verifier.verify(message, signature) {
+        return Err(Error::InvalidSignature);
+    }
+    crate::from_slice(message)
+}
+
+/// An HMAC-SHA256 key, which both signs and verifies.
+#[cfg(feature = "hmac")]
+#[derive(Clone)]
+pub struct HmacSha256 {
+    mac: hmac::Hmac<sha2::Sha256>,
+}
+#[cfg(feature = "hmac")]
+impl HmacSha256 {
+    /// Constructs a new [HmacSha256] from a secret key of any length.
+    pub fn new(key: &[u8]) -> Self {
+        use hmac::Mac;
+
+        Self {
+            mac: hmac::Hmac::new_from_slice(key).expect("HMAC accepts keys of any length"),
+        }
+    }
+}
+#[cfg(feature = "hmac")]
+impl Signer for HmacSha256 {
+    fn signature_len(&self) -> usize {
+        32
+    }
+
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        use hmac::Mac;
+
+        let mut mac = self.mac.clone();
+        mac.update(message);
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+#[cfg(feature = "hmac")]
+impl Verifier for HmacSha256 {
+    fn signature_len(&self) -> usize {
+        32
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        use hmac::Mac;
+
+        let mut mac = self.mac.clone();
+        mac.update(message);
+        mac.verify_slice(signature).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn round_trip() {
+        let key = HmacSha256::new(b"secret");
+        let bytes = to_vec_signed(&("key", 42u32), &key).unwrap();
+        assert_eq!(
+            bytes.len(),
+            crate::to_vec(&("key", 42u32)).unwrap().len() + 32
+        );
+        let decoded: (String, u32) = from_slice_verified(&bytes, &key).unwrap();
+        assert_eq!(decoded, (String::from("key"), 42));
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn tampering() {
+        let key = HmacSha256::new(b"secret");
+        let bytes = to_vec_signed(&"value", &key).unwrap();
+        for i in 0..bytes.len() {
+            let mut tampered = bytes.clone();
+            tampered[i] ^= 1;
+            let error = from_slice_verified::<String, _>(&tampered, &key).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Integrity);
+        }
+
+        let error =
+            from_slice_verified::<String, _>(&bytes, &HmacSha256::new(b"other")).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Integrity);
+        let error = from_slice_verified::<String, _>(&bytes[..31], &key).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+    }
+
+    #[test]
+    fn signature_lengths() {
+        /// A signer returning signatures of `len` bytes while claiming they are 4 bytes long.
+        struct Faulty(usize);
+        impl Signer for Faulty {
+            fn signature_len(&self) -> usize {
+                4
+            }
+
+            fn sign(&self, _: &[u8]) -> Vec<u8> {
+                vec![7; self.0]
+            }
+        }
+        impl Verifier for Faulty {
+            fn signature_len(&self) -> usize {
+                4
+            }
+
+            fn verify(&self, _: &[u8], signature: &[u8]) -> bool {
+                signature == [7; 4]
+            }
+        }
+
+        let bytes = to_vec_signed(&"value", &Faulty(4)).unwrap();
+        let decoded: String = from_slice_verified(&bytes, &Faulty(4)).unwrap();
+        assert_eq!(decoded, "value");
+
+        for len in [0, 3, 5, 64] {
+            let error = to_vec_signed(&"value", &Faulty(len)).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+            let (expected, found) = error.mismatch().unwrap();
+            assert_eq!(expected.to_string(), "4 signature bytes");
+            assert_eq!(found.to_string(), format!("{} signature bytes", len));
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Conversions from `simd-json` values, and parsing JSON text with `simd-json`, for ingesting
+//! JSON faster than through `serde_json`.
+//!
+//! Unsigned integers above `i64::MAX` wrap around, like [Value]'s conversion from
+//! `serde_json::Value`.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value},
+};
+use ::simd_json::{OwnedValue, StaticNode};
+
+impl From<OwnedValue> for Value {
+    fn from(value: OwnedValue) -> Self {
+        match value {
+            OwnedValue::Static(StaticNode::Null) => Value::Null,
+            OwnedValue::Static(StaticNode::Bool(b)) => Value::Boolean(b),
+            OwnedValue::Static(StaticNode::I64(i)) => Value::Integer(i),
+            OwnedValue::Static(StaticNode::U64(u)) => Value::Integer(u as _),
+            OwnedValue::Static(StaticNode::F64(f)) => Value::Float(f),
+            OwnedValue::String(s) => Value::String(s),
+            OwnedValue::Array(a) => Value::Array(a.into_iter().map(Value::from).collect()),
+            OwnedValue::Object(o) => {
+                let mut map = Map::with_capacity(o.len());
+                for (k, v) in *o {
+                    map.insert(k, v.into());
+                }
+                Value::Object(map)
+            }
+        }
+    }
+}
+
+/// Parses JSON text into a [Value] with `simd-json`, which uses `json` as scratch space.
+pub fn from_json(json: &mut [u8]) -> Result<Value> {
+    ::simd_json::to_owned_value(json)
+        .map(Value::from)
+        .map_err(|e| Error::Custom(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn round_trip() {
+        let mut json = br#"{"a": [null, true, -42, 1.5, "text"], "b": {}}"#.to_vec();
+        let value = from_json(&mut json).unwrap();
+        let Value::Object(o) = &value else {
+            panic!("expected an object");
+        };
+        let Value::Array(a) = &o["a"] else {
+            panic!("expected an array");
+        };
+        assert!(a[0].is_null());
+        assert!(matches!(a[1], Value::Boolean(true)));
+        assert!(matches!(a[2], Value::Integer(-42)));
+        assert!(matches!(a[3], Value::Float(f) if f == 1.5));
+        assert!(matches!(&a[4], Value::String(s) if s == "text"));
+        assert!(matches!(&o["b"], Value::Object(b) if b.is_empty()));
+
+        let bytes = crate::to_vec_canonical(&value).unwrap();
+        let decoded: Value = crate::from_slice(&bytes).unwrap();
+        assert_eq!(crate::to_vec_canonical(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn large_unsigned_integers_wrap() {
+        let mut json = u64::MAX.to_string().into_bytes();
+        assert!(matches!(from_json(&mut json).unwrap(), Value::Integer(-1)));
+    }
+
+    #[test]
+    fn errors() {
+        for json in ["", "{", "[1,]", "nul"] {
+            let error = from_json(&mut json.as_bytes().to_vec()).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Custom, "{}", json);
+        }
+    }
+}
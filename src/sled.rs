@@ -0,0 +1,188 @@
+//! `sled` integration, storing Bincode JSON documents in an embedded database.
+//!
+//! [Value]s convert from/to `IVec`s directly, and a [TypedTree] wraps a `Tree` whose keys and
+//! values are encoded as Bincode JSON. As keys are encoded, a tree iterates in the order of their
+//! encodings, which is not the order of the keys themselves.
+
+use crate::{
+    error::{Error, Result},
+    value::Value,
+};
+use ::sled::{IVec, Tree};
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+
+impl From<Value> for IVec {
+    fn from(value: Value) -> Self {
+        bincode::encode_to_vec(value, bincode::config::standard())
+            .expect("encoding a value into a vector never fails")
+            .into()
+    }
+}
+impl TryFrom<IVec> for Value {
+    type Error = Error;
+
+    fn try_from(value: IVec) -> Result<Self> {
+        crate::from_slice(&value)
+    }
+}
+
+/// A `Tree` of `K` keys and `V` values, both encoded as Bincode JSON.
+pub struct TypedTree<K, V> {
+    tree: Tree,
+    _marker: PhantomData<fn() -> (K, V)>,
+}
+impl<K, V> TypedTree<K, V> {
+    /// Constructs a new [TypedTree] storing documents in `tree`.
+    pub fn new(tree: Tree) -> Self {
+        Self {
+            tree,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gets the underlying tree.
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// Consumes the typed tree, returning the underlying tree.
+    pub fn into_inner(self) -> Tree {
+        self.tree
+    }
+
+    /// Gets the number of entries. This scans the whole tree.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Checks whether the tree has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Removes every entry.
+    pub fn clear(&self) -> Result<()> {
+        self.tree.clear().map_err(sled_error)
+    }
+
+    /// Flushes the tree to disk, returning the number of bytes flushed.
+    pub fn flush(&self) -> Result<usize> {
+        self.tree.flush().map_err(sled_error)
+    }
+}
+impl<K, V> TypedTree<K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    /// Gets the value of `key`.
+    pub fn get(&self, key: &K) -> Result<Option<V>> {
+        let value = self.tree.get(crate::to_vec(key)?).map_err(sled_error)?;
+        value.map(|value| crate::from_slice(&value)).transpose()
+    }
+
+    /// Inserts `value` at `key`, returning the previous value.
+    pub fn insert(&self, key: &K, value: &V) -> Result<Option<V>> {
+        let value = crate::to_vec(value)?;
+        let previous = self
+            .tree
+            .insert(crate::to_vec(key)?, value)
+            .map_err(sled_error)?;
+        previous.map(|value| crate::from_slice(&value)).transpose()
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove(&self, key: &K) -> Result<Option<V>> {
+        let previous = self.tree.remove(crate::to_vec(key)?).map_err(sled_error)?;
+        previous.map(|value| crate::from_slice(&value)).transpose()
+    }
+
+    /// Checks whether `key` has a value.
+    pub fn contains_key(&self, key: &K) -> Result<bool> {
+        self.tree
+            .contains_key(crate::to_vec(key)?)
+            .map_err(sled_error)
+    }
+
+    /// Iterates over every entry, in the order of the encodings of the keys.
+    pub fn iter(&self) -> impl Iterator<Item = Result<(K, V)>> {
+        self.tree.iter().map(|entry| {
+            let (key, value) = entry.map_err(sled_error)?;
+            Ok((crate::from_slice(&key)?, crate::from_slice(&value)?))
+        })
+    }
+}
+impl<K, V> Clone for TypedTree<K, V> {
+    fn clone(&self) -> Self {
+        Self::new(self.tree.clone())
+    }
+}
+impl<K, V> From<Tree> for TypedTree<K, V> {
+    fn from(tree: Tree) -> Self {
+        Self::new(tree)
+    }
+}
+
+/// Converts a `sled` error into an [Error].
+fn sled_error(e: ::sled::Error) -> Error {
+    match e {
+        ::sled::Error::Io(e) => Error::Io(e),
+        e => Error::Custom(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn tree() -> Tree {
+        let db = ::sled::Config::new().temporary(true).open().unwrap();
+        db.open_tree("docs").unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let tree = TypedTree::<String, (u32, bool)>::new(tree());
+        assert!(tree.is_empty());
+        assert_eq!(tree.insert(&"a".into(), &(1, true)).unwrap(), None);
+        assert_eq!(
+            tree.insert(&"a".into(), &(2, false)).unwrap(),
+            Some((1, true))
+        );
+        tree.insert(&"b".into(), &(3, true)).unwrap();
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.get(&"a".into()).unwrap(), Some((2, false)));
+        assert!(tree.contains_key(&"b".into()).unwrap());
+        let mut entries = tree.iter().collect::<Result<Vec<_>>>().unwrap();
+        entries.sort();
+        assert_eq!(entries, [("a".into(), (2, false)), ("b".into(), (3, true))]);
+        assert_eq!(tree.remove(&"b".into()).unwrap(), Some((3, true)));
+        assert_eq!(tree.get(&"b".into()).unwrap(), None);
+        tree.clear().unwrap();
+        assert!(tree.is_empty());
+
+        let ivec = IVec::from(Value::Integer(7));
+        assert!(matches!(Value::try_from(ivec).unwrap(), Value::Integer(7)));
+    }
+
+    #[test]
+    fn errors() {
+        let raw = tree();
+        raw.insert(
+            crate::to_vec(&"a").unwrap(),
+            crate::to_vec(&"text").unwrap(),
+        )
+        .unwrap();
+        let tree = TypedTree::<String, u32>::new(raw.clone());
+        let error = tree.get(&"a".into()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = tree.iter().next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+        let error = Value::try_from(IVec::from(&[0xff][..])).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
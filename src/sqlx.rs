@@ -0,0 +1,111 @@
+//! `sqlx` encoding and decoding of Bincode JSON documents in Postgres `BYTEA` columns.
+//!
+//! Typed documents are stored as [BincodeJson]s, and loosely typed ones as [Value]s. Both are read
+//! back with the default [DecodeLimits](crate::DecodeLimits). With the checked query macros, the
+//! type of a `BYTEA` column is overridden like `SELECT doc AS "doc: BincodeJson<Doc>"`.
+
+use crate::value::Value;
+use ::sqlx::{
+    encode::IsNull,
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef, Postgres},
+    Decode, Encode, Type,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::ops::{Deref, DerefMut};
+
+/// A Bincode JSON document of a `T`, stored in a `BYTEA` column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BincodeJson<T>(pub T);
+impl<T> Type<Postgres> for BincodeJson<T> {
+    fn type_info() -> PgTypeInfo {
+        <Vec<u8> as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <Vec<u8> as Type<Postgres>>::compatible(ty)
+    }
+}
+impl<T: Serialize> Encode<'_, Postgres> for BincodeJson<T> {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <Vec<u8> as Encode<Postgres>>::encode(crate::to_vec(&self.0)?, buf)
+    }
+}
+impl<'r, T: DeserializeOwned> Decode<'r, Postgres> for BincodeJson<T> {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let bytes = <Vec<u8> as Decode<Postgres>>::decode(value)?;
+        Ok(Self(crate::from_slice(&bytes)?))
+    }
+}
+impl<T> From<T> for BincodeJson<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+impl<T> Deref for BincodeJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for BincodeJson<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl Type<Postgres> for Value {
+    fn type_info() -> PgTypeInfo {
+        <Vec<u8> as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <Vec<u8> as Type<Postgres>>::compatible(ty)
+    }
+}
+impl Encode<'_, Postgres> for Value {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        let bytes = bincode::encode_to_vec(self, bincode::config::standard())?;
+        <Vec<u8> as Encode<Postgres>>::encode(bytes, buf)
+    }
+}
+impl<'r> Decode<'r, Postgres> for Value {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let bytes = <Vec<u8> as Decode<Postgres>>::decode(value)?;
+        Ok(crate::from_slice(&bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{Error, ErrorKind};
+    use std::collections::HashMap;
+
+    fn encode<'q, T: Encode<'q, Postgres>>(val: &T) -> Result<Vec<u8>, BoxDynError> {
+        let mut buf = PgArgumentBuffer::default();
+        assert!(matches!(val.encode_by_ref(&mut buf)?, IsNull::No));
+        Ok(buf.to_vec())
+    }
+
+    #[test]
+    fn encodes_bytea() {
+        let bytea = <Vec<u8> as Type<Postgres>>::type_info();
+        assert_eq!(BincodeJson::<u32>::type_info(), bytea);
+        assert_eq!(Value::type_info(), bytea);
+        assert!(<Value as Type<Postgres>>::compatible(&bytea));
+
+        let bytes = crate::to_vec(&("key", 42u32)).unwrap();
+        assert_eq!(encode(&BincodeJson(("key", 42u32))).unwrap(), bytes);
+        let value: Value = crate::from_slice(&bytes).unwrap();
+        assert_eq!(encode(&value).unwrap(), bytes);
+    }
+
+    #[test]
+    fn errors() {
+        let error = encode(&BincodeJson(HashMap::from([(1u8, 2u8)]))).unwrap_err();
+        let error = error.downcast::<Error>().unwrap();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
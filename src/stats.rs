@@ -0,0 +1,217 @@
+//! Statistics of the contents of values, finding out which fields dominate storage and bandwidth.
+//!
+//! [Value::stats] walks a value once, counting values by type and measuring the size of the
+//! standard encoding of every part of it, without encoding it.
+
+use crate::{
+    value::{Map, Value},
+    wire::varint_len,
+};
+use alloc::{format, string::String, vec::Vec};
+
+/// The number of largest blobs and strings kept by [Stats].
+const LARGEST: usize = 10;
+
+/// Statistics of the contents of a value.
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    /// The number of null values.
+    pub nulls: usize,
+
+    /// The number of bool values.
+    pub booleans: usize,
+
+    /// The number of blob values.
+    pub blobs: usize,
+
+    /// The number of array values.
+    pub arrays: usize,
+
+    /// The number of integer values.
+    pub integers: usize,
+
+    /// The number of float values.
+    pub floats: usize,
+
+    /// The number of object values.
+    pub objects: usize,
+
+    /// The number of string values.
+    pub strings: usize,
+
+    /// The size of the encoded value in bytes.
+    pub encoded_len: usize,
+
+    /// The greatest nesting depth of arrays and objects, which is 0 for other values.
+    pub max_depth: usize,
+
+    /// The cumulative encoded size in bytes of the entries of every key, key included, over all
+    /// the objects nested in the value.
+    pub key_sizes: Map<String, usize>,
+
+    /// The paths and lengths in bytes of the largest blobs, largest first.
+    pub largest_blobs: Vec<(String, usize)>,
+
+    /// The paths and lengths in bytes of the largest strings, largest first.
+    pub largest_strings: Vec<(String, usize)>,
+}
+impl Stats {
+    /// Gets the keys whose entries take the most space, with their cumulative encoded sizes,
+    /// largest first.
+    pub fn largest_keys(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut keys: Vec<_> = self
+            .key_sizes
+            .iter()
+            .map(|(k, size)| (k.as_str(), *size))
+            .collect();
+        keys.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        keys.truncate(n);
+        keys
+    }
+
+    /// Records `value` at `path` nested at `depth`, returning its encoded size.
+    fn record(&mut self, value: &Value, path: &mut String, depth: usize) -> usize {
+        let payload = match value {
+            Value::Null => {
+                self.nulls += 1;
+                0
+            }
+            Value::Boolean(_) => {
+                self.booleans += 1;
+                1
+            }
+            Value::Integer(i) => {
+                self.integers += 1;
+                varint_len(((i << 1) ^ (i >> 63)) as u64)
+            }
+            Value::Float(_) => {
+                self.floats += 1;
+                8
+            }
+            Value::Blob(b) => {
+                self.blobs += 1;
+                insert_largest(&mut self.largest_blobs, path, b.len());
+                varint_len(b.len() as u64) + b.len()
+            }
+            Value::String(s) => {
+                self.strings += 1;
+                insert_largest(&mut self.largest_strings, path, s.len());
+                varint_len(s.len() as u64) + s.len()
+            }
+            Value::Array(a) => {
+                self.arrays += 1;
+                self.max_depth = self.max_depth.max(depth + 1);
+                let len = path.len();
+                let mut size = varint_len(a.len() as u64);
+                for (i, v) in a.iter().enumerate() {
+                    path.push_str(&format!("[{}]", i));
+                    size += self.record(v, path, depth + 1);
+                    path.truncate(len);
+                }
+                size
+            }
+            Value::Object(o) => {
+                self.objects += 1;
+                self.max_depth = self.max_depth.max(depth + 1);
+                let len = path.len();
+                let mut size = varint_len(o.len() as u64);
+                for (k, v) in o {
+                    if len != 0 {
+                        path.push('.');
+                    }
+                    path.push_str(k);
+                    let entry =
+                        varint_len(k.len() as u64) + k.len() + self.record(v, path, depth + 1);
+                    path.truncate(len);
+                    *self.key_sizes.entry(k.clone()).or_default() += entry;
+                    size += entry;
+                }
+                size
+            }
+        };
+        1 + payload
+    }
+}
+
+impl Value {
+    /// Computes statistics of the contents of the value.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        stats.encoded_len = stats.record(self, &mut String::new(), 0);
+        stats
+    }
+}
+
+/// Inserts `path` of length `len` into `largest` if it is among the [LARGEST] ones.
+fn insert_largest(largest: &mut Vec<(String, usize)>, path: &str, len: usize) {
+    let i = largest.partition_point(|(_, l)| *l >= len);
+    if i < LARGEST {
+        largest.insert(i, (path.into(), len));
+        largest.truncate(LARGEST);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+
+    fn document() -> Value {
+        Value::Object(Map::from([
+            (String::from("null"), Value::Null),
+            (String::from("bool"), Value::Boolean(true)),
+            (String::from("blob"), Value::Blob(vec![0; 300])),
+            (
+                String::from("array"),
+                Value::Array(vec![Value::Integer(-42), Value::Integer(i64::MIN)]),
+            ),
+            (String::from("float"), Value::Float(1.5)),
+            (
+                String::from("nested"),
+                Value::Object(Map::from([(
+                    String::from("string"),
+                    Value::String("text".into()),
+                )])),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn counts_and_sizes() {
+        let stats = document().stats();
+        assert_eq!(
+            [
+                stats.nulls,
+                stats.booleans,
+                stats.blobs,
+                stats.arrays,
+                stats.integers,
+                stats.floats,
+                stats.objects,
+                stats.strings
+            ],
+            [1, 1, 1, 1, 2, 1, 2, 1]
+        );
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(stats.encoded_len, crate::to_vec(&document()).unwrap().len());
+        assert_eq!(stats.largest_blobs, [("blob".to_string(), 300)]);
+        assert_eq!(stats.largest_strings, [("nested.string".to_string(), 4)]);
+        assert_eq!(stats.largest_keys(1), [("blob", 1 + 4 + 1 + 3 + 300)]);
+        assert_eq!(stats.largest_keys(10).len(), 7);
+
+        let scalar = Value::Integer(0).stats();
+        assert_eq!((scalar.encoded_len, scalar.max_depth), (2, 0));
+    }
+
+    #[test]
+    fn keeps_the_largest() {
+        let strings = (0..LARGEST + 5)
+            .map(|i| Value::String("x".repeat(i)))
+            .collect();
+        let stats = Value::Array(strings).stats();
+        assert_eq!(stats.largest_strings.len(), LARGEST);
+        assert_eq!(stats.largest_strings[0], (String::from("[14]"), 14));
+        assert!(stats.largest_strings.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert!(stats.key_sizes.is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+//! Sequences of independently decodable records over synchronous I/O.
+//!
+//! Records use the same length-prefixed frame layout as the streaming adapters: a 4-byte
+//! big-endian length followed by that many bytes of an encoded document.
+
+use crate::{
+    error::Result,
+    frame::{encode_frame, read_full, LENGTH_PREFIX_LEN},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    io::{Read, Write},
+    marker::PhantomData,
+};
+
+/// Writes a sequence of records into a [Write].
+pub struct RecordWriter<W> {
+    writer: W,
+    buf: Vec<u8>,
+}
+impl<W: Write> RecordWriter<W> {
+    /// Constructs a new [RecordWriter] writing into `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Writes `val` as a record.
+    pub fn write_record<T: Serialize>(&mut self, val: &T) -> Result<()> {
+        self.buf.clear();
+        encode_frame(val, &mut self.buf)?;
+        self.writer.write_all(&self.buf)?;
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Consumes the record writer, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads a sequence of records from a [Read].
+///
+/// A record which is cut off by the end of the input is not an error: reading simply stops, and
+/// the length of the incomplete tail is reported by [RecordReader::truncated_tail].
+pub struct RecordReader<R> {
+    reader: R,
+    position: u64,
+    truncated_tail: Option<usize>,
+}
+impl<R: Read> RecordReader<R> {
+    /// Constructs a new [RecordReader] reading from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            position: 0,
+            truncated_tail: None,
+        }
+    }
+
+    /// Reads the next record, returning `None` at the end of the input.
+    pub fn read_record<T: DeserializeOwned>(&mut self) -> Result<Option<T>> {
+        if self.truncated_tail.is_some() {
+            return Ok(None);
+        }
+
+        let mut header = [0; LENGTH_PREFIX_LEN];
+        let n = read_full(&mut self.reader, &mut header)?;
+        if n == 0 {
+            return Ok(None);
+        } else if n < header.len() {
+            self.truncated_tail = Some(n);
+            return Ok(None);
+        }
+
+        let len = u32::from_be_bytes(header) as usize;
+        let mut payload = Vec::new();
+        let n = (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut payload)?;
+        if n < len {
+            self.truncated_tail = Some(LENGTH_PREFIX_LEN + n);
+            return Ok(None);
+        }
+
+        self.position += (LENGTH_PREFIX_LEN + len) as u64;
+        crate::from_slice(&payload).map(Some)
+    }
+
+    /// Returns an iterator over the remaining records, decoding each as a `T`.
+    pub fn records<T: DeserializeOwned>(&mut self) -> Records<'_, R, T> {
+        Records {
+            reader: self,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gets the number of bytes occupied by the complete records read so far.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Gets the length of the incomplete record found at the end of the input, if any.
+    pub fn truncated_tail(&self) -> Option<usize> {
+        self.truncated_tail
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consumes the record reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// An iterator over the records of a [RecordReader].
+pub struct Records<'a, R, T> {
+    reader: &'a mut RecordReader<R>,
+    _marker: PhantomData<fn() -> T>,
+}
+impl<R: Read, T: DeserializeOwned> Iterator for Records<'_, R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn write_records(records: &[(String, u32)]) -> Vec<u8> {
+        let mut writer = RecordWriter::new(Vec::new());
+        for record in records {
+            writer.write_record(record).unwrap();
+        }
+        writer.flush().unwrap();
+        writer.into_inner()
+    }
+
+    #[test]
+    fn round_trip() {
+        let records = vec![(String::from("a"), 1), (String::from("bc"), 2)];
+        let bytes = write_records(&records);
+
+        let mut reader = RecordReader::new(&bytes[..]);
+        let decoded = reader
+            .records()
+            .collect::<Result<Vec<(String, u32)>>>()
+            .unwrap();
+        assert_eq!(decoded, records);
+        assert_eq!(reader.position(), bytes.len() as u64);
+        assert_eq!(reader.truncated_tail(), None);
+    }
+
+    #[test]
+    fn truncated_tail() {
+        let bytes = write_records(&[(String::from("a"), 1), (String::from("b"), 2)]);
+        let first = bytes.len() / 2;
+
+        for cut in [first + 2, bytes.len() - 1] {
+            let mut reader = RecordReader::new(&bytes[..cut]);
+            assert_eq!(
+                reader.read_record::<(String, u32)>().unwrap(),
+                Some((String::from("a"), 1))
+            );
+            assert_eq!(reader.read_record::<(String, u32)>().unwrap(), None);
+            assert_eq!(reader.position(), first as u64);
+            assert_eq!(reader.truncated_tail(), Some(cut - first));
+            assert_eq!(reader.read_record::<(String, u32)>().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn malformed_record() {
+        let mut bytes = write_records(&[(String::from("a"), 1)]);
+        bytes[LENGTH_PREFIX_LEN] = 0xff;
+
+        let mut reader = RecordReader::new(&bytes[..]);
+        let error = reader.read_record::<(String, u32)>().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+
+        let bytes = write_records(&[(String::from("a"), 1)]);
+        let error = RecordReader::new(&bytes[..])
+            .read_record::<u32>()
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
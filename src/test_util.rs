@@ -0,0 +1,36 @@
+//! Fixtures and helpers shared by the tests of several modules.
+
+// Each helper is only used by the tests of some features.
+#![allow(dead_code)]
+
+use crate::value::{Map, Value};
+use alloc::{string::String, vec};
+use core::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+/// A document holding every kind of value, as an object so that it converts to formats whose
+/// top level must be a map.
+pub(crate) fn document() -> Value {
+    Value::Object(Map::from([
+        (String::from("null"), Value::Null),
+        (String::from("bool"), Value::Boolean(true)),
+        (String::from("blob"), Value::Blob(vec![1, 2, 3])),
+        (
+            String::from("array"),
+            Value::Array(vec![Value::Integer(-42)]),
+        ),
+        (String::from("float"), Value::Float(1.5)),
+        (String::from("string"), Value::String("text".into())),
+    ]))
+}
+
+/// Polls a future which never waits, as reading from and writing to memory never does.
+pub(crate) fn ready<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("the future is not ready"),
+    }
+}
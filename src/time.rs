@@ -0,0 +1,104 @@
+//! `time` integration, storing date-times compactly as milliseconds since the Unix epoch.
+//!
+//! [OffsetDateTime]s convert into integer milliseconds since 1970-01-01T00:00:00Z, dropping
+//! their offset and any precision below milliseconds, and fields opt into that representation with
+//! `#[serde(with = "bincode_json::time::millis")]`. The accessor also accepts RFC 3339 strings.
+
+use crate::value::Value;
+use ::time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+impl From<OffsetDateTime> for Value {
+    fn from(datetime: OffsetDateTime) -> Self {
+        Value::Integer(millis::to_millis(datetime))
+    }
+}
+
+impl Value {
+    /// Gets an [OffsetDateTime] in UTC from milliseconds since the Unix epoch, or an
+    /// [OffsetDateTime] from an RFC 3339 string.
+    pub fn as_offset_datetime(&self) -> Option<OffsetDateTime> {
+        match self {
+            Value::Integer(i) => millis::from_millis(*i),
+            Value::String(s) => OffsetDateTime::parse(s, &Rfc3339).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// (De)serializes an [OffsetDateTime] as milliseconds since the Unix epoch, with `#[serde(with =
+/// "bincode_json::time::millis")]`.
+pub mod millis {
+    use ::time::OffsetDateTime;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    /// Serializes an [OffsetDateTime] as milliseconds since the Unix epoch.
+    pub fn serialize<S: Serializer>(
+        datetime: &OffsetDateTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(to_millis(*datetime))
+    }
+
+    /// Deserializes an [OffsetDateTime] in UTC from milliseconds since the Unix epoch.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<OffsetDateTime, D::Error> {
+        let millis = i64::deserialize(deserializer)?;
+        from_millis(millis)
+            .ok_or_else(|| D::Error::custom(format!("timestamp {} is out of range", millis)))
+    }
+
+    pub(super) fn to_millis(datetime: OffsetDateTime) -> i64 {
+        // Every representable date-time is within ±10^4 years, which fits in i64 milliseconds.
+        datetime.unix_timestamp_nanos().div_euclid(1_000_000) as i64
+    }
+
+    pub(super) fn from_millis(millis: i64) -> Option<OffsetDateTime> {
+        OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{de::Deserializer, error::ErrorKind, ser::Serializer};
+    use ::time::UtcOffset;
+
+    #[test]
+    fn round_trip() {
+        let datetime = OffsetDateTime::parse("2024-02-29T14:34:56.789+02:00", &Rfc3339).unwrap();
+        let value = Value::from(datetime);
+        assert!(matches!(value, Value::Integer(1709210096789)));
+        let utc = value.as_offset_datetime().unwrap();
+        assert_eq!(utc, datetime);
+        assert_eq!(utc.offset(), UtcOffset::UTC);
+        let string = Value::String("2024-02-29T14:34:56.789+02:00".into());
+        assert_eq!(
+            string.as_offset_datetime().unwrap().offset(),
+            datetime.offset()
+        );
+
+        // Precision below milliseconds is dropped, rounding towards the past.
+        let before = OffsetDateTime::from_unix_timestamp_nanos(-1).unwrap();
+        assert!(matches!(Value::from(before), Value::Integer(-1)));
+
+        let value = millis::serialize(&datetime, Serializer::new()).unwrap();
+        assert!(matches!(value, Value::Integer(1709210096789)));
+        assert_eq!(
+            millis::deserialize(Deserializer::from(value)).unwrap(),
+            datetime
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Value::Integer(i64::MAX).as_offset_datetime(), None);
+        assert_eq!(Value::String("yesterday".into()).as_offset_datetime(), None);
+        assert_eq!(Value::Float(0.0).as_offset_datetime(), None);
+
+        let error = millis::deserialize(Deserializer::from(Value::Integer(i64::MAX))).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Custom);
+        let error = millis::deserialize(Deserializer::from(Value::Null)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,130 @@
+//! Conversions between `bincode-json` values and TOML values, e.g. for storing and diffing
+//! application configuration.
+//!
+//! TOML datetimes convert to strings in RFC 3339 form, which convert back into plain strings. TOML
+//! has no null or binary type, so converting a value holding a null or a blob is unsupported,
+//! except for null entries of objects, which are left out of the table like absent options.
+//!
+//! Convert with `try_into`: `toml::Value::try_from` is an inherent method of `toml`, which
+//! converts any serializable type rather than using these conversions.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value, ValueKind},
+};
+use ::toml::{Table, Value as TomlValue};
+
+impl From<TomlValue> for Value {
+    fn from(value: TomlValue) -> Self {
+        match value {
+            TomlValue::String(s) => Value::String(s),
+            TomlValue::Integer(i) => Value::Integer(i),
+            TomlValue::Float(f) => Value::Float(f),
+            TomlValue::Boolean(b) => Value::Boolean(b),
+            TomlValue::Datetime(dt) => Value::String(dt.to_string()),
+            TomlValue::Array(a) => Value::Array(a.into_iter().map(Value::from).collect()),
+            TomlValue::Table(t) => t.into(),
+        }
+    }
+}
+impl From<Table> for Value {
+    fn from(table: Table) -> Self {
+        let mut map = Map::with_capacity(table.len());
+        for (k, v) in table {
+            map.insert(k, v.into());
+        }
+        Value::Object(map)
+    }
+}
+impl TryFrom<Value> for TomlValue {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self> {
+        Ok(match value {
+            Value::Null => return Err(Error::Unsupported("null in TOML".into())),
+            Value::Blob(_) => return Err(Error::Unsupported("blob in TOML".into())),
+            Value::Boolean(b) => TomlValue::Boolean(b),
+            Value::Array(a) => {
+                TomlValue::Array(a.into_iter().map(Value::try_into).collect::<Result<_>>()?)
+            }
+            Value::Integer(i) => TomlValue::Integer(i),
+            Value::Float(f) => TomlValue::Float(f),
+            Value::Object(o) => TomlValue::Table(Value::Object(o).try_into()?),
+            Value::String(s) => TomlValue::String(s),
+        })
+    }
+}
+impl TryFrom<Value> for Table {
+    type Error = Error;
+
+    /// Converts an object into a TOML table, failing if the value is not an object.
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(o) => o
+                .into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| Ok((k, v.try_into()?)))
+                .collect(),
+            v => Err(Error::Expected(ValueKind::Object.into(), v.kind().into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn document() -> Value {
+        Value::Object(Map::from([
+            (String::from("bool"), Value::Boolean(true)),
+            (
+                String::from("array"),
+                Value::Array(vec![Value::Integer(-42)]),
+            ),
+            (String::from("float"), Value::Float(1.5)),
+            (String::from("string"), Value::String("text".into())),
+            (
+                String::from("table"),
+                Value::Object(Map::from([(String::from("n"), Value::Integer(1))])),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn round_trip() {
+        let table: Table = document().try_into().unwrap();
+        let text = ::toml::to_string(&table).unwrap();
+        let value = Value::from(text.parse::<Table>().unwrap());
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+    }
+
+    #[test]
+    fn nulls_and_datetimes() {
+        let value = Value::Object(Map::from([
+            (String::from("absent"), Value::Null),
+            (String::from("n"), Value::Integer(1)),
+        ]));
+        let table: Table = value.try_into().unwrap();
+        assert_eq!(table.len(), 1);
+
+        let table: Table = "at = 1979-05-27T07:32:00Z".parse().unwrap();
+        let Value::Object(o) = Value::from(table) else {
+            panic!("expected an object");
+        };
+        assert!(matches!(&o["at"], Value::String(s) if s == "1979-05-27T07:32:00Z"));
+    }
+
+    #[test]
+    fn errors() {
+        for value in [Value::Array(vec![Value::Null]), Value::Blob(vec![1])] {
+            let error = TryInto::<TomlValue>::try_into(value).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Unsupported);
+        }
+        let error = TryInto::<Table>::try_into(Value::Integer(1)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
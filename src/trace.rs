@@ -0,0 +1,131 @@
+//! `tracing` instrumentation of top-level encoding and decoding.
+//!
+//! With the `tracing` feature, [crate::to_vec] and [crate::from_slice] run within a `DEBUG` span
+//! named after the operation, carrying the type being encoded or decoded and the payload size.
+//! Each operation ends with an event: a `DEBUG` one with the elapsed time on success, or a `WARN`
+//! one with the error on failure.
+
+use crate::error::Result;
+use std::time::Instant;
+
+/// Runs `encode`, an encoding of a `T`, within an `encode` span.
+pub(crate) fn encode<T: ?Sized>(encode: impl FnOnce() -> Result<Vec<u8>>) -> Result<Vec<u8>> {
+    let span = tracing::debug_span!(
+        "encode",
+        r#type = core::any::type_name::<T>(),
+        bytes = tracing::field::Empty,
+    );
+    let _entered = span.enter();
+    let start = Instant::now();
+    let result = encode();
+    let elapsed_us = start.elapsed().as_micros() as u64;
+    match &result {
+        Ok(bytes) => {
+            span.record("bytes", bytes.len());
+            tracing::debug!(bytes = bytes.len(), elapsed_us, "encoded document");
+        }
+        Err(error) => tracing::warn!(%error, elapsed_us, "failed to encode document"),
+    }
+    result
+}
+
+/// Runs `decode`, a decoding of a `T` from `bytes` bytes, within a `decode` span.
+pub(crate) fn decode<T>(bytes: usize, decode: impl FnOnce() -> Result<T>) -> Result<T> {
+    let span = tracing::debug_span!("decode", r#type = core::any::type_name::<T>(), bytes);
+    let _entered = span.enter();
+    let start = Instant::now();
+    let result = decode();
+    let elapsed_us = start.elapsed().as_micros() as u64;
+    match &result {
+        Ok(_) => tracing::debug!(bytes, elapsed_us, "decoded document"),
+        Err(error) => tracing::warn!(%error, bytes, elapsed_us, "failed to decode document"),
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use std::{
+        fmt::Debug,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    /// Records the names of spans and the levels and fields of events.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+    impl Visit for Recorder {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{} = {:?}", field.name(), value));
+        }
+    }
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            let mut log = self.0.lock().unwrap();
+            log.push(format!("span {}", span.metadata().name()));
+            span::Id::from_u64(log.len() as u64)
+        }
+
+        fn record(&self, _: &span::Id, values: &span::Record<'_>) {
+            values.record(&mut self.clone());
+        }
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let level = event.metadata().level();
+            self.0.lock().unwrap().push(format!("{}", level));
+            event.record(&mut self.clone());
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    /// Runs `f` with a [Recorder] as the default subscriber, returning what it recorded.
+    fn record(f: impl FnOnce()) -> Vec<String> {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), f);
+        let log = recorder.0.lock().unwrap();
+        log.clone()
+    }
+
+    #[test]
+    fn success() {
+        let log = record(|| {
+            let bytes = crate::to_vec(&42u32).unwrap();
+            assert_eq!(crate::from_slice::<u32>(&bytes).unwrap(), 42);
+        });
+        assert_eq!(log[0], "span encode");
+        assert!(log.contains(&String::from("bytes = 2")));
+        assert!(log.contains(&String::from("message = encoded document")));
+        assert!(log.contains(&String::from("span decode")));
+        assert!(log.contains(&String::from("message = decoded document")));
+        assert!(!log.iter().any(|line| line == "WARN"));
+    }
+
+    #[test]
+    fn failure() {
+        let log = record(|| {
+            let error = crate::from_slice::<u32>(&[0xff]).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Unsupported);
+        });
+        assert_eq!(log[0], "span decode");
+        let warning = log.iter().position(|line| line == "WARN").unwrap();
+        assert!(log[warning..]
+            .iter()
+            .any(|line| line == "message = failed to decode document"));
+    }
+}
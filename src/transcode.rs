@@ -0,0 +1,453 @@
+//! Transcoding between `bincode-json` and other serde formats without building a [Value].
+//!
+//! [transcode] streams whatever a [Deserializer] yields straight into the wire format, and
+//! [transcode_to] streams an encoded document straight into a [Serializer]. Both give the same
+//! result as going through a [Value], e.g. converting JSON with `serde_json::Deserializer`, but
+//! neither side ever materializes a tree of values.
+//!
+//! [Value]: crate::Value
+
+use crate::{
+    error::{Error, Result},
+    limits::{DecodeLimits, Limit},
+    wire::{self, SliceReader},
+};
+use bincode::{
+    config::Configuration,
+    de::{read::BorrowReader, Decode, Decoder, DecoderImpl},
+    enc::Encode,
+    error::DecodeError,
+};
+use serde::{
+    de::{self, DeserializeSeed, Deserializer, Visitor},
+    ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer},
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    fmt,
+};
+
+/// Encodes everything `deserializer` yields as Bincode JSON, enforcing the default
+/// [DecodeLimits].
+///
+/// Sequences and maps of unknown length are encoded in place and their length prefix is inserted
+/// once they end, so only the encoded bytes are ever held in memory.
+pub fn transcode<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>> {
+    transcode_with_limits(deserializer, &DecodeLimits::default())
+}
+
+/// Encodes everything `deserializer` yields as Bincode JSON like [transcode], enforcing `limits`
+/// on the document as it is written, as decoding it would.
+///
+/// Unlike converting through a [Value](crate::Value), which keeps the last of duplicate keys of a map, a map
+/// with a duplicate key fails with [Error::Duplicated], as the entries before it have already
+/// been written. Errors of `deserializer` itself, such as syntax errors, are reported as
+/// malformed input.
+pub fn transcode_with_limits<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    limits: &DecodeLimits,
+) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let state = State {
+        limits: *limits,
+        error: Cell::new(None),
+    };
+    let transcoder = Transcoder {
+        buf: &mut buf,
+        state: &state,
+        depth: 0,
+    };
+    match transcoder.deserialize(deserializer) {
+        Ok(()) => Ok(buf),
+        Err(e) => Err(state
+            .error
+            .take()
+            .unwrap_or_else(|| DecodeError::OtherString(e.to_string()).into())),
+    }
+}
+
+/// Streams a Bincode JSON document into `serializer`, enforcing the default [DecodeLimits].
+///
+/// Strings, blobs and object keys are passed to `serializer` borrowed from `bytes`.
+pub fn transcode_to<S: Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    transcode_to_with_limits(bytes, serializer, &DecodeLimits::default())
+}
+
+/// Streams a Bincode JSON document into `serializer` like [transcode_to], enforcing `limits`.
+pub fn transcode_to_with_limits<S: Serializer>(
+    bytes: &[u8],
+    serializer: S,
+    limits: &DecodeLimits,
+) -> std::result::Result<S::Ok, S::Error> {
+    limits
+        .check(Limit::Bytes, bytes.len())
+        .map_err(ser::Error::custom)?;
+    let decoder = RefCell::new(DecoderImpl::new(
+        SliceReader::new(bytes),
+        bincode::config::standard(),
+    ));
+    Transcoded {
+        decoder: &decoder,
+        limits,
+        depth: 0,
+    }
+    .serialize(serializer)
+}
+
+/// The state shared by the transcoders of a document.
+struct State {
+    limits: DecodeLimits,
+
+    /// The error transcoding failed with, unless it was raised by the deserializer, whose error
+    /// type can only carry its message.
+    error: Cell<Option<Error>>,
+}
+impl State {
+    /// Records `e` as the error transcoding failed with.
+    fn fail<E: de::Error>(&self, e: Error) -> E {
+        let error = E::custom(&e);
+        self.error.set(Some(e));
+        error
+    }
+
+    fn check<E: de::Error>(&self, limit: Limit, n: usize) -> std::result::Result<(), E> {
+        self.limits.check(limit, n).map_err(|e| self.fail(e))
+    }
+
+    /// Appends `val` to `buf` in the standard configuration.
+    fn encode<V: Encode, E: de::Error>(
+        &self,
+        buf: &mut Vec<u8>,
+        val: V,
+    ) -> std::result::Result<(), E> {
+        bincode::encode_into_std_write(val, buf, bincode::config::standard())
+            .map_err(|e| self.fail(e.into()))?;
+        self.check(Limit::Bytes, buf.len())
+    }
+
+    /// Inserts the length prefix of a container whose contents start at `start`.
+    fn insert_len<E: de::Error>(
+        &self,
+        buf: &mut Vec<u8>,
+        start: usize,
+        len: u64,
+    ) -> std::result::Result<(), E> {
+        let mut prefix = Vec::new();
+        self.encode(&mut prefix, len)?;
+        buf.splice(start..start, prefix);
+        self.check(Limit::Bytes, buf.len())
+    }
+}
+
+/// Encodes a value yielded by a [Deserializer] nested at `depth` at the end of a buffer, the way
+/// the [crate::Value] it would deserialize into is encoded.
+struct Transcoder<'a> {
+    buf: &'a mut Vec<u8>,
+    state: &'a State,
+    depth: usize,
+}
+impl Transcoder<'_> {
+    fn child(&mut self) -> Transcoder<'_> {
+        Transcoder {
+            buf: self.buf,
+            state: self.state,
+            depth: self.depth + 1,
+        }
+    }
+
+    fn encode<V: Encode, E: de::Error>(&mut self, val: V) -> std::result::Result<(), E> {
+        self.state.encode(self.buf, val)
+    }
+}
+impl<'de> DeserializeSeed<'de> for Transcoder<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+impl<'de> Visitor<'de> for Transcoder<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a Bincode JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(mut self, v: bool) -> std::result::Result<(), E> {
+        self.encode(wire::BOOLEAN)?;
+        self.encode(v)
+    }
+
+    fn visit_i64<E: de::Error>(mut self, v: i64) -> std::result::Result<(), E> {
+        self.encode(wire::INTEGER)?;
+        self.encode(v)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<(), E> {
+        self.visit_i64(v as _)
+    }
+
+    fn visit_f64<E: de::Error>(mut self, v: f64) -> std::result::Result<(), E> {
+        self.encode(wire::FLOAT)?;
+        self.encode(v)
+    }
+
+    fn visit_str<E: de::Error>(mut self, v: &str) -> std::result::Result<(), E> {
+        self.state.check(Limit::StringLength, v.len())?;
+        self.encode(wire::STRING)?;
+        self.encode(v)
+    }
+
+    fn visit_bytes<E: de::Error>(mut self, v: &[u8]) -> std::result::Result<(), E> {
+        self.state.check(Limit::BlobLength, v.len())?;
+        self.encode(wire::BLOB)?;
+        self.encode(v)
+    }
+
+    fn visit_none<E: de::Error>(mut self) -> std::result::Result<(), E> {
+        self.encode(wire::NULL)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_unit<E: de::Error>(mut self) -> std::result::Result<(), E> {
+        self.encode(wire::ARRAY)?;
+        self.encode(0u64)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(mut self, mut seq: A) -> std::result::Result<(), A::Error> {
+        self.state.check(Limit::Depth, self.depth + 1)?;
+        self.encode(wire::ARRAY)?;
+        let start = self.buf.len();
+        let mut len = 0;
+        while seq.next_element_seed(self.child())?.is_some() {
+            len += 1;
+            self.state.check(Limit::Elements, len)?;
+        }
+        self.state.insert_len(self.buf, start, len as u64)
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(mut self, mut map: A) -> std::result::Result<(), A::Error> {
+        self.state.check(Limit::Depth, self.depth + 1)?;
+        self.encode(wire::OBJECT)?;
+        let start = self.buf.len();
+        let mut keys = HashSet::new();
+        while map
+            .next_key_seed(KeyTranscoder {
+                buf: self.buf,
+                state: self.state,
+                keys: &mut keys,
+            })?
+            .is_some()
+        {
+            map.next_value_seed(self.child())?;
+            self.state.check(Limit::Elements, keys.len())?;
+        }
+        self.state.insert_len(self.buf, start, keys.len() as u64)
+    }
+}
+
+/// Encodes an object key, which must be a string and must not be one of `keys`.
+struct KeyTranscoder<'a> {
+    buf: &'a mut Vec<u8>,
+    state: &'a State,
+    keys: &'a mut HashSet<String>,
+}
+impl<'de> DeserializeSeed<'de> for KeyTranscoder<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_string(self)
+    }
+}
+impl<'de> Visitor<'de> for KeyTranscoder<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string key")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<(), E> {
+        self.state.check(Limit::StringLength, v.len())?;
+        if !self.keys.insert(v.to_owned()) {
+            return Err(self.state.fail(Error::Duplicated(v.to_owned())));
+        }
+        self.state.encode(self.buf, v)
+    }
+}
+
+/// The next value of an encoded document, serialized the way the [crate::Value] it would decode
+/// into is.
+struct Transcoded<'a, 'b> {
+    decoder: &'b RefCell<DecoderImpl<SliceReader<'a>, Configuration>>,
+    limits: &'b DecodeLimits,
+    depth: usize,
+}
+impl<'a> Transcoded<'a, '_> {
+    fn child(&self) -> Self {
+        Self {
+            decoder: self.decoder,
+            limits: self.limits,
+            depth: self.depth + 1,
+        }
+    }
+
+    fn decode<T: Decode>(&self) -> Result<T> {
+        Ok(T::decode(&mut *self.decoder.borrow_mut())?)
+    }
+
+    fn decode_tag(&self) -> Result<u32> {
+        wire::decode_tag(&mut *self.decoder.borrow_mut())
+    }
+
+    fn decode_len(&self, limit: Limit) -> Result<usize> {
+        let len = wire::decode_len(&mut *self.decoder.borrow_mut())?;
+        self.limits.check(limit, len)?;
+        Ok(len)
+    }
+
+    fn take_bytes(&self, limit: Limit) -> Result<&'a [u8]> {
+        let len = self.decode_len(limit)?;
+        Ok(self.decoder.borrow_mut().reader().take_bytes(len)?)
+    }
+
+    fn take_str(&self) -> Result<&'a str> {
+        let bytes = self.take_bytes(Limit::StringLength)?;
+        std::str::from_utf8(bytes)
+            .map_err(|inner| bincode::error::DecodeError::Utf8 { inner }.into())
+    }
+
+    fn serialize_next<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<std::result::Result<S::Ok, S::Error>> {
+        Ok(match self.decode_tag()? {
+            wire::NULL => serializer.serialize_none(),
+            wire::BOOLEAN => serializer.serialize_bool(self.decode()?),
+            wire::BLOB => serializer.serialize_bytes(self.take_bytes(Limit::BlobLength)?),
+            wire::ARRAY => {
+                self.limits.check(Limit::Depth, self.depth + 1)?;
+                let len = self.decode_len(Limit::Elements)?;
+                let mut seq = match serializer.serialize_seq(Some(len)) {
+                    Ok(seq) => seq,
+                    Err(e) => return Ok(Err(e)),
+                };
+                for _ in 0..len {
+                    if let Err(e) = seq.serialize_element(&self.child()) {
+                        return Ok(Err(e));
+                    }
+                }
+                seq.end()
+            }
+            wire::INTEGER => serializer.serialize_i64(self.decode()?),
+            wire::FLOAT => serializer.serialize_f64(self.decode()?),
+            wire::OBJECT => {
+                self.limits.check(Limit::Depth, self.depth + 1)?;
+                let len = self.decode_len(Limit::Elements)?;
+                let mut map = match serializer.serialize_map(Some(len)) {
+                    Ok(map) => map,
+                    Err(e) => return Ok(Err(e)),
+                };
+                for _ in 0..len {
+                    let key = self.take_str()?;
+                    if let Err(e) = map.serialize_entry(key, &self.child()) {
+                        return Ok(Err(e));
+                    }
+                }
+                map.end()
+            }
+            _ => serializer.serialize_str(self.take_str()?),
+        })
+    }
+}
+impl Serialize for Transcoded<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.serialize_next(serializer)
+            .map_err(ser::Error::custom)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, value::Value};
+    use serde::de::value::{Error as ValueError, MapDeserializer};
+
+    fn document() -> Value {
+        let mut document =
+            crate::to_value(&(vec![("key", (1u8, -2i64, 0.5f64))], "text", Some(true), ()))
+                .unwrap();
+        if let Value::Array(items) = &mut document {
+            items.push(Value::Blob(vec![1, 2]));
+        }
+        document
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = transcode(crate::de::Deserializer::from(document())).unwrap();
+        assert_eq!(bytes, crate::to_vec(&document()).unwrap());
+
+        let value = transcode_to(&bytes, crate::ser::Serializer::new()).unwrap();
+        assert_eq!(crate::to_vec(&value).unwrap(), bytes);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let entries = MapDeserializer::<_, ValueError>::new([("a", 1), ("a", 2)].into_iter());
+        let error = transcode(entries).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::DuplicateField);
+    }
+
+    #[test]
+    fn limits() {
+        let nested = crate::to_value(&vec![vec![vec![1u8]]]).unwrap();
+        let limits = DecodeLimits::new().with_max_depth(2);
+        let error = transcode_with_limits(crate::de::Deserializer::from(nested.clone()), &limits)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+
+        let bytes = crate::to_vec(&nested).unwrap();
+        let result = transcode_to_with_limits(&bytes, crate::ser::Serializer::new(), &limits);
+        assert!(result.is_err());
+
+        let limits = DecodeLimits::new().with_max_string_len(3);
+        let error =
+            transcode_with_limits(crate::de::Deserializer::from(document()), &limits).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json() {
+        let json = r#"[{"a": [1, "x", null]}, 2.5]"#;
+        let bytes = transcode(&mut serde_json::Deserializer::from_str(json)).unwrap();
+        let expected: Value = serde_json::from_str(json).unwrap();
+        assert_eq!(bytes, crate::to_vec(&expected).unwrap());
+
+        let error = transcode(&mut serde_json::Deserializer::from_str("[1,")).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Decode);
+    }
+}
@@ -0,0 +1,137 @@
+//! Human-readable rendering of values as indented trees, for logs and test failure output.
+//!
+//! [Value::display_tree], and the alternate [Debug](core::fmt::Debug) format `{:#?}` of [Value],
+//! render one value per line, indented by nesting. Object entries are sorted by key so that the
+//! output is stable, and long strings and blobs are truncated to a preview followed by their
+//! length.
+
+use crate::value::Value;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Write};
+
+/// The number of characters of strings shown before truncating them.
+const STRING_PREVIEW: usize = 64;
+
+/// The number of bytes of blobs shown before truncating them.
+const BLOB_PREVIEW: usize = 16;
+
+/// A [Display] wrapper around a [Value] which renders it as an indented tree.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayTree<'a>(&'a Value);
+impl Display for DisplayTree<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_tree(formatter, self.0, 0)
+    }
+}
+
+impl Value {
+    /// Renders the value as an indented tree with truncated strings and blobs, like `{:#?}`.
+    pub fn display_tree(&self) -> DisplayTree<'_> {
+        DisplayTree(self)
+    }
+}
+
+/// Writes `value` nested at `depth`, where its key or index is already written.
+pub(crate) fn write_tree<W: Write>(w: &mut W, value: &Value, depth: usize) -> fmt::Result {
+    let indent = |w: &mut W| (0..=depth).try_for_each(|_| w.write_str("  "));
+    match value {
+        Value::Null => w.write_str("null"),
+        Value::Boolean(b) => write!(w, "{}", b),
+        Value::Integer(i) => write!(w, "{}", i),
+        Value::Float(f) => write!(w, "{:?}", f),
+        Value::String(s) => match s.char_indices().nth(STRING_PREVIEW) {
+            Some((end, _)) => write!(w, "{:?}… ({} bytes)", &s[..end], s.len()),
+            None => write!(w, "{:?}", s),
+        },
+        Value::Blob(b) => {
+            write!(w, "blob ({} bytes)", b.len())?;
+            b.iter()
+                .take(BLOB_PREVIEW)
+                .try_for_each(|byte| write!(w, " {:02x}", byte))?;
+            match b.len() > BLOB_PREVIEW {
+                true => w.write_str(" …"),
+                false => Ok(()),
+            }
+        }
+        Value::Array(a) => {
+            write!(w, "array ({} elements)", a.len())?;
+            for (i, v) in a.iter().enumerate() {
+                w.write_char('\n')?;
+                indent(w)?;
+                write!(w, "[{}]: ", i)?;
+                write_tree(w, v, depth + 1)?;
+            }
+            Ok(())
+        }
+        Value::Object(o) => {
+            write!(w, "object ({} entries)", o.len())?;
+            let mut entries: Vec<_> = o.iter().collect();
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            for (k, v) in entries {
+                w.write_char('\n')?;
+                indent(w)?;
+                write!(w, "{}: ", k)?;
+                write_tree(w, v, depth + 1)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Map;
+    use alloc::{
+        format,
+        string::{String, ToString},
+        vec,
+    };
+
+    #[test]
+    fn render() {
+        let value = Value::Object(Map::from([
+            (
+                String::from("b"),
+                Value::Array(vec![Value::Null, Value::Float(1.0)]),
+            ),
+            (String::from("a"), Value::Boolean(true)),
+            (String::from("c"), Value::Blob(vec![0xab, 1])),
+            (String::from("d"), Value::Integer(-42)),
+            (String::from("e"), Value::String("text".into())),
+        ]));
+        let expected = "object (5 entries)
+  a: true
+  b: array (2 elements)
+    [0]: null
+    [1]: 1.0
+  c: blob (2 bytes) ab 01
+  d: -42
+  e: \"text\"";
+        assert_eq!(value.display_tree().to_string(), expected);
+        assert_eq!(format!("{:#?}", value), expected);
+    }
+
+    #[test]
+    fn truncation() {
+        let long = "é".repeat(STRING_PREVIEW + 1);
+        let rendered = Value::String(long.clone()).display_tree().to_string();
+        let preview = "é".repeat(STRING_PREVIEW);
+        assert_eq!(rendered, format!("{:?}… ({} bytes)", preview, long.len()));
+        let exact = "é".repeat(STRING_PREVIEW);
+        assert_eq!(
+            Value::String(exact.clone()).display_tree().to_string(),
+            format!("{:?}", exact)
+        );
+
+        let rendered = Value::Blob(vec![0; BLOB_PREVIEW + 1])
+            .display_tree()
+            .to_string();
+        assert_eq!(
+            rendered,
+            format!("blob (17 bytes){} …", " 00".repeat(BLOB_PREVIEW))
+        );
+        let empty = Value::Array(vec![]).display_tree().to_string();
+        assert_eq!(empty, "array (0 elements)");
+    }
+}
@@ -0,0 +1,160 @@
+//! Decoding of trusted input without validating its strings, for internal pipelines.
+//!
+//! Validating that strings and object keys are UTF-8 is a visible fraction of the time spent
+//! decoding string-heavy documents. When the input is known to come from this crate, e.g. from a
+//! trusted service or a local cache, [from_slice_utf8_unchecked] skips that validation.
+
+use crate::{
+    borrowed::take_bytes,
+    dict,
+    error::Result,
+    limits::{DecodeLimits, Limit},
+    value::{Map, Value},
+    wire::{self, SliceReader},
+};
+use alloc::{string::String, vec::Vec};
+use bincode::de::{BorrowDecoder, Decode, Decoder};
+use serde::de::DeserializeOwned;
+
+/// Deserialize an instance of type `T` from bytes of Bincode JSON without validating that its
+/// strings are UTF-8, enforcing `limits`. Documents of the key dictionary profile are rejected,
+/// as by [crate::from_slice].
+///
+/// # Safety
+/// Every string and object key of the input must be valid UTF-8, as in any input encoded by this
+/// crate. Invalid strings are undefined behavior once decoded.
+pub unsafe fn from_slice_utf8_unchecked<T: DeserializeOwned>(
+    bytes: &[u8],
+    limits: &DecodeLimits,
+) -> Result<T> {
+    limits.check(Limit::Bytes, bytes.len())?;
+    dict::reject_profile(bytes.first().copied())?;
+    let mut decoder =
+        bincode::de::DecoderImpl::new(SliceReader::new(bytes), bincode::config::standard());
+    let value = decode_unchecked(&mut decoder, limits, 0)
+        .map_err(|e| e.at_offset(decoder.reader().position()))?;
+    crate::from_value(value)
+}
+
+/// Decodes a length-prefixed string without validating it.
+///
+/// # Safety
+/// The string must be valid UTF-8.
+unsafe fn take_string<'de, D: BorrowDecoder<'de>>(
+    decoder: &mut D,
+    limits: &DecodeLimits,
+) -> Result<String> {
+    let len = wire::decode_len(decoder)?;
+    limits.check(Limit::StringLength, len)?;
+    let bytes = take_bytes(decoder, len)?;
+    Ok(String::from(core::str::from_utf8_unchecked(bytes)))
+}
+
+/// Decodes a [Value] nested at `depth` without validating its strings, enforcing `limits`.
+///
+/// # Safety
+/// Every string and object key of the value must be valid UTF-8.
+unsafe fn decode_unchecked<'de, D: BorrowDecoder<'de>>(
+    decoder: &mut D,
+    limits: &DecodeLimits,
+    depth: usize,
+) -> Result<Value> {
+    match wire::decode_tag(decoder)? {
+        wire::NULL => Ok(Value::Null),
+        wire::BOOLEAN => Ok(Value::Boolean(bool::decode(decoder)?)),
+        wire::BLOB => {
+            let len = wire::decode_len(decoder)?;
+            limits.check(Limit::BlobLength, len)?;
+            Ok(Value::Blob(take_bytes(decoder, len)?.into()))
+        }
+        wire::ARRAY => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = wire::decode_len(decoder)?;
+            limits.check(Limit::Elements, len)?;
+            let mut array = Vec::with_capacity(len.min(wire::MAX_PREALLOCATED));
+            for _ in 0..len {
+                array.push(decode_unchecked(decoder, limits, depth + 1)?);
+            }
+            Ok(Value::Array(array))
+        }
+        wire::INTEGER => Ok(Value::Integer(i64::decode(decoder)?)),
+        wire::FLOAT => Ok(Value::Float(f64::decode(decoder)?)),
+        wire::OBJECT => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = wire::decode_len(decoder)?;
+            limits.check(Limit::Elements, len)?;
+            let mut object = Map::with_capacity(len.min(wire::MAX_PREALLOCATED));
+            for _ in 0..len {
+                let key = take_string(decoder, limits)?;
+                object.insert(key, decode_unchecked(decoder, limits, depth + 1)?);
+            }
+            Ok(Value::Object(object))
+        }
+        _ => Ok(Value::String(take_string(decoder, limits)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::{collections::BTreeMap, vec};
+
+    type Document = (
+        Option<bool>,
+        i64,
+        f64,
+        String,
+        Vec<u8>,
+        BTreeMap<String, u32>,
+    );
+
+    fn document() -> Document {
+        let map = BTreeMap::from([(String::from("ключ"), 1), (String::from("b"), 2)]);
+        (
+            Some(true),
+            -42,
+            1.5,
+            String::from("текст"),
+            vec![1, 2, 3],
+            map,
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = crate::to_vec(&document()).unwrap();
+        let limits = DecodeLimits::default();
+        // SAFETY: the input was encoded by this crate.
+        let decoded: Document = unsafe { from_slice_utf8_unchecked(&bytes, &limits) }.unwrap();
+        assert_eq!(decoded, document());
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = crate::to_vec(&document()).unwrap();
+        let limits = DecodeLimits::default();
+        for len in 0..bytes.len() {
+            // SAFETY: every string of the input, truncated or not, is valid UTF-8.
+            let error = unsafe { from_slice_utf8_unchecked::<Document>(&bytes[..len], &limits) }
+                .unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Eof);
+        }
+
+        // SAFETY: as above.
+        let error =
+            unsafe { from_slice_utf8_unchecked::<(String, u32)>(&bytes, &limits) }.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+
+        let limits = DecodeLimits::new().with_max_string_len(4);
+        // SAFETY: as above.
+        let error = unsafe { from_slice_utf8_unchecked::<Document>(&bytes, &limits) }.unwrap_err();
+        assert!(error.is_limit_exceeded());
+
+        let dictionary = crate::dict::to_vec_dict(&document()).unwrap();
+        // SAFETY: as above.
+        let error =
+            unsafe { from_slice_utf8_unchecked::<Document>(&dictionary, &limits) }.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
@@ -0,0 +1,64 @@
+//! First-class handling of `uuid`'s [Uuid]s, stored as compact 16-byte blobs.
+//!
+//! With `uuid`'s `serde` feature, [Uuid]s already serialize into 16-byte blobs, as the serializer
+//! is not human-readable, and deserialize from either 16-byte blobs or their hyphenated strings.
+//! This module converts them from/to [Value]s the same way.
+
+use crate::{
+    error::{Error, Result},
+    value::Value,
+};
+use ::uuid::Uuid;
+
+impl From<Uuid> for Value {
+    fn from(uuid: Uuid) -> Self {
+        Value::Blob(uuid.as_bytes().to_vec())
+    }
+}
+impl TryFrom<Value> for Uuid {
+    type Error = Error;
+
+    /// Converts a 16-byte blob or a string into a [Uuid].
+    fn try_from(value: Value) -> Result<Self> {
+        value.as_uuid().ok_or_else(|| {
+            Error::Expected("16-byte blob or UUID string".into(), value.kind().into())
+        })
+    }
+}
+
+impl Value {
+    /// Gets a [Uuid] from a 16-byte blob or a string, if the value is either.
+    pub fn as_uuid(&self) -> Option<Uuid> {
+        match self {
+            Value::Blob(b) => Uuid::from_slice(b).ok(),
+            Value::String(s) => Uuid::parse_str(s).ok(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    const UUID: &str = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+
+    #[test]
+    fn round_trip() {
+        let uuid = Uuid::parse_str(UUID).unwrap();
+        let value = Value::from(uuid);
+        assert!(matches!(&value, Value::Blob(b) if b == uuid.as_bytes()));
+        assert_eq!(Uuid::try_from(value).unwrap(), uuid);
+        assert_eq!(Uuid::try_from(Value::String(UUID.into())).unwrap(), uuid);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Value::Blob(vec![0; 15]).as_uuid(), None);
+        assert_eq!(Value::String("not a uuid".into()).as_uuid(), None);
+
+        let error = Uuid::try_from(Value::Integer(1)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,397 @@
+//! A lightweight validation DSL for values, for services which cannot adopt full JSON Schema.
+//!
+//! A [Validator] is built from rules on paths, written like those of [crate::Selector], such as
+//! `Validator::new().require("id", Type::Integer).optional("tags", Type::Array(&Type::String))`.
+//! [Validator::validate] checks every rule and reports all violations, each with the path of the
+//! offending value, e.g. `tags[2]`.
+
+use crate::{
+    selector::{Segment, Selector},
+    value::Value,
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Display},
+    ops::{Bound, RangeBounds},
+};
+
+/// The expected type of a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    /// Any value.
+    Any,
+
+    /// A null value.
+    Null,
+
+    /// A bool value.
+    Boolean,
+
+    /// A blob value.
+    Blob,
+
+    /// An array whose elements are all of the given type.
+    Array(&'static Type),
+
+    /// An integer value.
+    Integer,
+
+    /// A float value.
+    Float,
+
+    /// An integer or float value.
+    Number,
+
+    /// An object value.
+    Object,
+
+    /// A string value.
+    String,
+}
+impl Type {
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Any => "any type",
+            Self::Null => "type null",
+            Self::Boolean => "type boolean",
+            Self::Blob => "type blob",
+            Self::Array(_) => "type array",
+            Self::Integer => "type integer",
+            Self::Float => "type float",
+            Self::Number => "type integer or float",
+            Self::Object => "type object",
+            Self::String => "type string",
+        }
+    }
+
+    /// Checks that `value` at `path` is of the type, reporting violations.
+    fn check(&self, value: &Value, path: &str, violations: &mut Vec<Violation>) {
+        let matched = match (self, value) {
+            (Self::Any, _)
+            | (Self::Null, Value::Null)
+            | (Self::Boolean, Value::Boolean(_))
+            | (Self::Blob, Value::Blob(_))
+            | (Self::Integer, Value::Integer(_))
+            | (Self::Float, Value::Float(_))
+            | (Self::Number, Value::Integer(_) | Value::Float(_))
+            | (Self::Object, Value::Object(_))
+            | (Self::String, Value::String(_)) => true,
+            (Self::Array(element), Value::Array(a)) => {
+                for (i, v) in a.iter().enumerate() {
+                    element.check(v, &format!("{}[{}]", path, i), violations);
+                }
+                true
+            }
+            _ => false,
+        };
+        if !matched {
+            violations.push(Violation::new(
+                path,
+                format!(
+                    "expected {}, found {}",
+                    self.description(),
+                    value.error_description()
+                ),
+            ));
+        }
+    }
+}
+
+/// A value which breaks a rule of a [Validator].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Violation {
+    /// The path of the value.
+    pub path: String,
+
+    /// A description of the broken rule.
+    pub message: String,
+}
+impl Violation {
+    fn new(path: &str, message: String) -> Self {
+        Self {
+            path: path.into(),
+            message,
+        }
+    }
+}
+impl Display for Violation {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}: {}", self.path, self.message)
+    }
+}
+
+/// A check of the values at a path.
+#[derive(Debug, Clone)]
+enum Check {
+    Type { ty: Type, required: bool },
+    Range(Bound<i64>, Bound<i64>),
+}
+
+/// A set of rules which values must follow.
+#[derive(Debug, Clone, Default)]
+pub struct Validator {
+    rules: Vec<(String, Option<Selector>, Check)>,
+}
+impl Validator {
+    /// Constructs a new [Validator] without rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires the values at `path` to exist and be of type `ty`.
+    pub fn require(self, path: &str, ty: Type) -> Self {
+        self.rule(path, Check::Type { ty, required: true })
+    }
+
+    /// Requires the values at `path` to be of type `ty` if they exist.
+    pub fn optional(self, path: &str, ty: Type) -> Self {
+        self.rule(
+            path,
+            Check::Type {
+                ty,
+                required: false,
+            },
+        )
+    }
+
+    /// Requires the values at `path` to be numbers within `range` if they exist.
+    pub fn range<R: RangeBounds<i64>>(self, path: &str, range: R) -> Self {
+        let check = Check::Range(range.start_bound().cloned(), range.end_bound().cloned());
+        self.rule(path, check)
+    }
+
+    fn rule(mut self, path: &str, check: Check) -> Self {
+        self.rules
+            .push((path.into(), Selector::compile(path).ok(), check));
+        self
+    }
+
+    /// Checks `value` against every rule, returning all violations if any.
+    pub fn validate(&self, value: &Value) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        for (path, selector, check) in &self.rules {
+            let Some(selector) = selector else {
+                violations.push(Violation::new(path, "invalid path".into()));
+                continue;
+            };
+            let (last, parents) = match selector.segments().split_last() {
+                Some((last, parents)) => (Some(last), parents),
+                None => (None, &[][..]),
+            };
+            for (path, parent) in select(value, parents) {
+                let Some(parent) = parent else {
+                    if let Check::Type { required: true, .. } = check {
+                        violations.push(Violation::new(&path, "missing".into()));
+                    }
+                    continue;
+                };
+                for (path, value) in children(path, parent, last) {
+                    match (check, value) {
+                        (Check::Type { required: true, .. }, None) => {
+                            violations.push(Violation::new(&path, "missing".into()))
+                        }
+                        (Check::Type { ty, .. }, Some(value)) => {
+                            ty.check(value, &path, &mut violations)
+                        }
+                        (Check::Range(start, end), Some(value)) => {
+                            check_range(value, (*start, *end), &path, &mut violations)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(violations),
+        }
+    }
+}
+
+/// Selects the values at `segments` in `value`, along with their paths, where `None` is the first
+/// missing value on the way.
+fn select<'v>(value: &'v Value, segments: &[Segment]) -> Vec<(String, Option<&'v Value>)> {
+    let mut selected = vec![(String::new(), Some(value))];
+    for segment in segments {
+        selected = selected
+            .into_iter()
+            .flat_map(|(path, value)| match value {
+                Some(value) => children(path, value, Some(segment)),
+                None => vec![(path, None)],
+            })
+            .collect();
+    }
+    selected
+}
+
+/// Gets the children of `value` at `segment`, along with their paths, where `None` is a missing
+/// entry or element. Without a segment, `value` itself is returned.
+fn children<'v>(
+    path: String,
+    value: &'v Value,
+    segment: Option<&Segment>,
+) -> Vec<(String, Option<&'v Value>)> {
+    let child = |key: &dyn Display| match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{}.{}", path, key),
+    };
+    match (segment, value) {
+        (None, value) => vec![(path, Some(value))],
+        (Some(Segment::Key(key)), Value::Object(o)) => vec![(child(key), o.get(key))],
+        (Some(Segment::Index(i)), Value::Array(a)) => {
+            vec![(format!("{}[{}]", path, i), a.get(*i))]
+        }
+        (Some(Segment::Wildcard), Value::Object(o)) => {
+            o.iter().map(|(k, v)| (child(k), Some(v))).collect()
+        }
+        (Some(Segment::Wildcard), Value::Array(a)) => a
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("{}[{}]", path, i), Some(v)))
+            .collect(),
+        (Some(Segment::Key(key)), _) => vec![(child(key), None)],
+        (Some(Segment::Index(i)), _) => vec![(format!("{}[{}]", path, i), None)],
+        (Some(Segment::Wildcard), _) => Vec::new(),
+    }
+}
+
+/// Checks that `value` at `path` is a number within `range`, reporting violations.
+fn check_range(
+    value: &Value,
+    range: (Bound<i64>, Bound<i64>),
+    path: &str,
+    violations: &mut Vec<Violation>,
+) {
+    let n = match value {
+        Value::Integer(i) => *i as f64,
+        Value::Float(f) => *f,
+        value => {
+            return violations.push(Violation::new(
+                path,
+                format!(
+                    "expected type integer or float, found {}",
+                    value.error_description()
+                ),
+            ))
+        }
+    };
+    let above = match range.0 {
+        Bound::Included(start) => n >= start as f64,
+        Bound::Excluded(start) => n > start as f64,
+        Bound::Unbounded => true,
+    };
+    let below = match range.1 {
+        Bound::Included(end) => n <= end as f64,
+        Bound::Excluded(end) => n < end as f64,
+        Bound::Unbounded => true,
+    };
+    if !(above && below) {
+        violations.push(Violation::new(path, format!("{} is out of range", n)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(entries: Vec<(&str, Value)>) -> Value {
+        Value::Object(entries.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    fn validator() -> Validator {
+        Validator::new()
+            .require("id", Type::Integer)
+            .optional("tags", Type::Array(&Type::String))
+            .require("items[*].name", Type::String)
+            .range("items[*].count", 1..=10)
+            .optional("meta.score", Type::Number)
+    }
+
+    fn item(name: Value, count: i64) -> Value {
+        object(vec![("name", name), ("count", Value::Integer(count))])
+    }
+
+    #[test]
+    fn valid() {
+        let value = object(vec![
+            ("id", Value::Integer(1)),
+            ("tags", Value::Array(vec![Value::String("a".into())])),
+            (
+                "items",
+                Value::Array(vec![
+                    item(Value::String("a".into()), 1),
+                    item(Value::String("b".into()), 10),
+                ]),
+            ),
+            ("meta", object(vec![("score", Value::Float(0.5))])),
+        ]);
+        validator().validate(&value).unwrap();
+
+        // Optional values and wildcards over empty containers need nothing.
+        let value = object(vec![
+            ("id", Value::Integer(1)),
+            ("items", Value::Array(vec![])),
+        ]);
+        validator().validate(&value).unwrap();
+        Validator::new()
+            .require("", Type::Any)
+            .validate(&Value::Null)
+            .unwrap();
+    }
+
+    #[test]
+    fn violations() {
+        let value = object(vec![
+            ("id", Value::String("1".into())),
+            (
+                "tags",
+                Value::Array(vec![Value::String("a".into()), Value::Integer(2)]),
+            ),
+            (
+                "items",
+                Value::Array(vec![
+                    item(Value::Null, 0),
+                    item(Value::String("b".into()), 11),
+                ]),
+            ),
+            ("meta", Value::Integer(1)),
+        ]);
+        let violations = validator().validate(&value).unwrap_err();
+        let paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "id",
+                "tags[1]",
+                "items[0].name",
+                "items[0].count",
+                "items[1].count"
+            ]
+        );
+        assert!(violations[0].message.starts_with("expected type integer"));
+        assert_eq!(
+            violations[3].to_string(),
+            "items[0].count: 0 is out of range"
+        );
+
+        let violations = validator().validate(&object(vec![])).unwrap_err();
+        let paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(paths, ["id", "items"]);
+        assert!(violations.iter().all(|v| v.message == "missing"));
+
+        let violations = Validator::new()
+            .require("a[", Type::Any)
+            .range("n", ..0)
+            .validate(&object(vec![("n", Value::Boolean(true))]))
+            .unwrap_err();
+        assert_eq!(violations[0].message, "invalid path");
+        assert!(violations[1]
+            .message
+            .starts_with("expected type integer or float"));
+    }
+}
@@ -1,9 +1,13 @@
 //! The Value enum, a loosely typed way of representing any valid `bincode-json` value.
 
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
 use serde::{de, ser};
 
-/// Represents a `bincode-json` key/value type.
-pub type Map<K, V> = std::collections::HashMap<K, V>;
+pub use crate::map::Map;
 
 macro_rules! value_from_int {
     ($x:tt) => {
@@ -19,7 +23,7 @@ macro_rules! value_is {
         pub fn $x(&self) -> bool {
             matches!(self, Self::$v(_))
         }
-    }
+    };
 }
 macro_rules! value_as {
     ($x:tt, $v:ident, $t:ty) => {
@@ -29,11 +33,11 @@ macro_rules! value_as {
                 _ => None,
             }
         }
-    }
+    };
 }
 
 /// Represents any valid `bincode-json` value.
-#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+#[derive(Clone, bincode::Encode, bincode::Decode)]
 pub enum Value {
     /// Represents a `bincode-json` null value.
     Null,
@@ -45,6 +49,10 @@ pub enum Value {
     Blob(Vec<u8>),
 
     /// Represents a `bincode-json` array value.
+    ///
+    /// The elements are not stored inline for short arrays: a value cannot embed values of its own
+    /// type, so any inline buffer would itself have to be boxed, costing the same one allocation a
+    /// non-empty `Vec` does. Empty arrays do not allocate.
     Array(Vec<Value>),
 
     /// Represents a `bincode-json` integer value.
@@ -59,6 +67,25 @@ pub enum Value {
     /// Represents a `bincode-json` string value.
     String(String),
 }
+impl core::fmt::Debug for Value {
+    /// Formats the value like a derived implementation, or as an indented tree with truncated
+    /// strings and blobs in the alternate format `{:#?}`, like [Value::display_tree].
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if f.alternate() {
+            return crate::tree::write_tree(f, self, 0);
+        }
+        match self {
+            Self::Null => f.write_str("Null"),
+            Self::Boolean(b) => f.debug_tuple("Boolean").field(b).finish(),
+            Self::Blob(b) => f.debug_tuple("Blob").field(b).finish(),
+            Self::Array(a) => f.debug_tuple("Array").field(a).finish(),
+            Self::Integer(i) => f.debug_tuple("Integer").field(i).finish(),
+            Self::Float(x) => f.debug_tuple("Float").field(x).finish(),
+            Self::Object(o) => f.debug_tuple("Object").field(o).finish(),
+            Self::String(s) => f.debug_tuple("String").field(s).finish(),
+        }
+    }
+}
 impl<'a> From<&'a str> for Value {
     fn from(s: &'a str) -> Self {
         Self::String(s.into())
@@ -73,48 +100,121 @@ value_from_int!(u32);
 value_from_int!(i64);
 value_from_int!(u64);
 
+/// The type of a `bincode-json` value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueKind {
+    /// The type of [Value::Null].
+    Null,
+
+    /// The type of [Value::Boolean].
+    Boolean,
+
+    /// The type of [Value::Blob].
+    Blob,
+
+    /// The type of [Value::Array].
+    Array,
+
+    /// The type of [Value::Integer].
+    Integer,
+
+    /// The type of [Value::Float].
+    Float,
+
+    /// The type of [Value::Object].
+    Object,
+
+    /// The type of [Value::String].
+    String,
+}
+impl ValueKind {
+    /// Gets the description of the type, such as `type string`.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Null => "type null",
+            Self::Blob => "type blob",
+            Self::Boolean => "type boolean",
+            Self::Integer => "type integer",
+            Self::Float => "type float",
+            Self::Object => "type object",
+            Self::String => "type string",
+            Self::Array => "type array",
+        }
+    }
+}
+impl core::fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
 impl Value {
+    /// Gets the type of the value.
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Self::Null => ValueKind::Null,
+            Self::Boolean(_) => ValueKind::Boolean,
+            Self::Blob(_) => ValueKind::Blob,
+            Self::Array(_) => ValueKind::Array,
+            Self::Integer(_) => ValueKind::Integer,
+            Self::Float(_) => ValueKind::Float,
+            Self::Object(_) => ValueKind::Object,
+            Self::String(_) => ValueKind::String,
+        }
+    }
+
     /// Gets the `bincode-json` type of the value.
     pub(crate) fn error_description(&self) -> &'static str {
+        self.kind().description()
+    }
+
+    /// Gets the index of the value's variant, as written on the wire.
+    #[cfg(feature = "std")]
+    pub(crate) fn variant_index(&self) -> u32 {
+        use crate::wire;
+
         match self {
-            Self::Null => "type null",
-            Self::Blob(_) => "type blob",
-            Self::Boolean(_) => "type boolean",
-            Self::Integer(_) => "type integer",
-            Self::Float(_) => "type float",
-            Self::Object(_) => "type object",
-            Self::String(_) => "type string",
-            Self::Array(_) => "type array",
+            Self::Null => wire::NULL,
+            Self::Boolean(_) => wire::BOOLEAN,
+            Self::Blob(_) => wire::BLOB,
+            Self::Array(_) => wire::ARRAY,
+            Self::Integer(_) => wire::INTEGER,
+            Self::Float(_) => wire::FLOAT,
+            Self::Object(_) => wire::OBJECT,
+            Self::String(_) => wire::STRING,
         }
     }
 
     #[cfg(feature = "json")]
     /// Converts a [Value] to a [serde_json::Value].
     pub fn to_json(self) -> serde_json::Value {
+        self.to_json_with(&crate::json::JsonOptions::default())
+    }
+
+    #[cfg(feature = "json")]
+    /// Converts a [Value] to a [serde_json::Value], failing with [crate::Error::Unsupported]
+    /// rather than degrading data JSON cannot represent exactly.
+    pub fn try_to_json(self) -> crate::Result<serde_json::Value> {
+        self.try_to_json_with(&crate::json::JsonOptions::default())
+    }
+
+    /// Serialize the value as a byte vector, like [crate::to_vec] but without converting it
+    /// first.
+    pub fn to_vec(&self) -> crate::Result<Vec<u8>> {
+        crate::encode_instrumented::<Value>(|| crate::encode_value(self))
+    }
+
+    /// Flattens nested objects and arrays into a single object whose keys are the paths to the
+    /// nested values, joined by `separator`, e.g. `a.b.0`. Empty objects and arrays are kept as
+    /// values, and values other than objects and arrays are returned unchanged.
+    pub fn flatten(self, separator: &str) -> Value {
         match self {
-            Self::Null => serde_json::Value::Null,
-            Self::Blob(blob) => serde_json::Value::String(base64::encode(blob)),
-            Self::Boolean(b) => serde_json::Value::Bool(b),
-            Self::Integer(i) => serde_json::Value::Number(i.into()),
-            Self::Float(f) => match serde_json::Number::from_f64(f) {
-                Some(n) => serde_json::Value::Number(n),
-                None => serde_json::Value::String(f.to_string()),
-            },
-            Self::Object(o) => {
-                let mut map = serde_json::Map::with_capacity(o.len());
-                for (k, v) in o {
-                    map.insert(k, v.to_json());
-                }
-                serde_json::Value::Object(map)
-            }
-            Self::String(s) => serde_json::Value::String(s),
-            Self::Array(a) => {
-                let mut arr = Vec::with_capacity(a.len());
-                for v in a {
-                    arr.push(v.to_json());
-                }
-                serde_json::Value::Array(arr)
+            Self::Object(_) | Self::Array(_) => {
+                let mut map = Map::default();
+                flatten_into(&mut map, &mut String::new(), self, separator);
+                Self::Object(map)
             }
+            v => v,
         }
     }
 
@@ -138,6 +238,61 @@ impl Value {
     value_is!(is_bool, Boolean);
     value_as!(as_bool, Boolean, bool);
 }
+
+/// Converts an instance of a type which implements [bincode::Encode] but not [ser::Serialize]
+/// into a [Value::Blob] of its standard bincode encoding, so that it can be embedded in documents.
+pub fn from_encode<T: bincode::Encode>(val: &T) -> crate::Result<Value> {
+    Ok(Value::Blob(bincode::encode_to_vec(
+        val,
+        bincode::config::standard(),
+    )?))
+}
+
+/// Decodes an instance of a type which implements [bincode::Decode] from a [Value::Blob] made by
+/// [from_encode], failing if the blob has bytes left over.
+pub fn to_decode<T: bincode::Decode>(value: &Value) -> crate::Result<T> {
+    let blob = value
+        .as_blob()
+        .ok_or_else(|| crate::Error::Expected(ValueKind::Blob.into(), value.kind().into()))?;
+    let (val, len) = bincode::decode_from_slice(blob, bincode::config::standard())?;
+    if len != blob.len() {
+        return Err(crate::Error::Expected(
+            "end of blob".into(),
+            alloc::format!("{} trailing bytes", blob.len() - len).into(),
+        ));
+    }
+    Ok(val)
+}
+
+/// Inserts the leaves of a non-empty object or array into `map`, keyed by `path` and their path
+/// below it.
+fn flatten_into(map: &mut Map<String, Value>, path: &mut String, value: Value, separator: &str) {
+    let entries: Vec<(String, Value)> = match value {
+        Value::Object(o) => o.into_iter().collect(),
+        Value::Array(a) => a
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect(),
+        _ => return,
+    };
+    let len = path.len();
+    for (key, value) in entries {
+        if len != 0 {
+            path.push_str(separator);
+        }
+        path.push_str(&key);
+        match value {
+            Value::Object(ref o) if !o.is_empty() => flatten_into(map, path, value, separator),
+            Value::Array(ref a) if !a.is_empty() => flatten_into(map, path, value, separator),
+            value => {
+                map.insert(path.clone(), value);
+            }
+        }
+        path.truncate(len);
+    }
+}
+
 impl<'de> de::Deserialize<'de> for Value {
     fn deserialize<D>(deserializer: D) -> Result<Value, D::Error>
     where
@@ -175,36 +330,7 @@ impl ser::Serialize for Value {
 #[cfg(feature = "json")]
 impl From<serde_json::Value> for Value {
     fn from(v: serde_json::Value) -> Self {
-        match v {
-            serde_json::Value::Null => Self::Null,
-            serde_json::Value::Bool(b) => Self::Boolean(b),
-            serde_json::Value::Array(array) => {
-                let mut arr: Vec<Value> = Vec::with_capacity(array.len());
-                for i in array {
-                    arr.push(i.into());
-                }
-                Self::Array(arr)
-            }
-            serde_json::Value::Number(n) => {
-                if n.is_i64() {
-                    Self::Integer(n.as_i64().unwrap())
-                } else if n.is_u64() {
-                    Self::Integer(n.as_u64().unwrap() as _)
-                } else if n.is_f64() {
-                    Self::Float(n.as_f64().unwrap())
-                } else {
-                    unreachable!()
-                }
-            }
-            serde_json::Value::Object(o) => {
-                let mut map = Map::with_capacity(o.len());
-                for (k, v) in o {
-                    map.insert(k, v.into());
-                }
-                Self::Object(map)
-            }
-            serde_json::Value::String(s) => Self::String(s),
-        }
+        Self::from_json_with(v, &crate::json::JsonOptions::default())
     }
 }
 
@@ -212,7 +338,7 @@ struct Visitor;
 impl<'de> de::Visitor<'de> for Visitor {
     type Value = Value;
 
-    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.write_str("a Bincode JSON value")
     }
 
@@ -334,7 +460,7 @@ impl<'de> de::Visitor<'de> for Visitor {
     where
         V: de::SeqAccess<'de>,
     {
-        let mut values = Vec::new();
+        let mut values = Vec::with_capacity(cautious(visitor.size_hint()));
 
         while let Some(elem) = visitor.next_element()? {
             values.push(elem);
@@ -347,7 +473,7 @@ impl<'de> de::Visitor<'de> for Visitor {
     where
         V: de::MapAccess<'de>,
     {
-        let mut map: Map<String, Value> = Map::new();
+        let mut map: Map<String, Value> = Map::with_capacity(cautious(visitor.size_hint()));
 
         while let Some((k, v)) = visitor.next_entry()? {
             map.insert(k, v);
@@ -356,3 +482,115 @@ impl<'de> de::Visitor<'de> for Visitor {
         Ok(Value::Object(map))
     }
 }
+
+/// Gets the capacity to preallocate for a container of `size_hint` elements, which is capped
+/// because hints may come from untrusted input.
+fn cautious(size_hint: Option<usize>) -> usize {
+    size_hint.unwrap_or(0).min(crate::wire::MAX_PREALLOCATED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{Error, ErrorKind};
+    use serde::de::{
+        value::{MapDeserializer, SeqDeserializer},
+        Deserialize,
+    };
+
+    #[test]
+    fn preallocates_from_size_hints() {
+        let seq = SeqDeserializer::<_, Error>::new(vec![1i64, 2, 3].into_iter());
+        let Value::Array(a) = Value::deserialize(seq).unwrap() else {
+            panic!("expected an array");
+        };
+        assert_eq!(a.capacity(), 3);
+        assert!(matches!(
+            a[..],
+            [Value::Integer(1), Value::Integer(2), Value::Integer(3)]
+        ));
+
+        let entries = vec![(String::from("a"), 1i64), (String::from("b"), 2)];
+        let map = MapDeserializer::<_, Error>::new(entries.into_iter());
+        let Value::Object(o) = Value::deserialize(map).unwrap() else {
+            panic!("expected an object");
+        };
+        assert!(matches!(o["b"], Value::Integer(2)));
+
+        assert_eq!(cautious(None), 0);
+        assert_eq!(cautious(Some(3)), 3);
+        assert_eq!(cautious(Some(usize::MAX)), crate::wire::MAX_PREALLOCATED);
+    }
+
+    #[test]
+    fn untrusted_hints() {
+        /// A sequence of one element claiming to hold `usize::MAX` of them.
+        struct Lying(bool);
+        impl<'de> de::SeqAccess<'de> for Lying {
+            type Error = Error;
+
+            fn next_element_seed<T: de::DeserializeSeed<'de>>(
+                &mut self,
+                seed: T,
+            ) -> Result<Option<T::Value>, Error> {
+                if core::mem::take(&mut self.0) {
+                    seed.deserialize(de::value::I64Deserializer::new(1))
+                        .map(Some)
+                } else {
+                    Ok(None)
+                }
+            }
+
+            fn size_hint(&self) -> Option<usize> {
+                Some(usize::MAX)
+            }
+        }
+        let Value::Array(a) = de::Visitor::visit_seq(Visitor, Lying(true)).unwrap() else {
+            panic!("expected an array");
+        };
+        assert_eq!(a.len(), 1);
+        assert!(a.capacity() <= crate::wire::MAX_PREALLOCATED);
+
+        let entries = vec![(1i64, 1i64)];
+        let map = MapDeserializer::<_, Error>::new(entries.into_iter());
+        let error = Value::deserialize(map).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn encode_adapters() {
+        let val = (7u32, String::from("text"), vec![1u8, 2, 3]);
+        let value = from_encode(&val).unwrap();
+        let blob = bincode::encode_to_vec(&val, bincode::config::standard()).unwrap();
+        assert!(matches!(&value, Value::Blob(b) if *b == blob));
+        assert_eq!(to_decode::<(u32, String, Vec<u8>)>(&value).unwrap(), val);
+
+        let document = Value::Array(vec![from_encode(&val).unwrap()]);
+        let document = crate::from_slice::<Value>(&document.to_vec().unwrap()).unwrap();
+        let Value::Array(a) = document else {
+            panic!("expected an array");
+        };
+        assert_eq!(to_decode::<(u32, String, Vec<u8>)>(&a[0]).unwrap(), val);
+    }
+
+    #[test]
+    fn decode_adapter_errors() {
+        let error = to_decode::<u32>(&Value::Integer(7)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let (expected, found) = error.mismatch().unwrap();
+        assert_eq!(expected.kind(), Some(ValueKind::Blob));
+        assert_eq!(found.kind(), Some(ValueKind::Integer));
+
+        let value = from_encode(&(7u32, 8u32)).unwrap();
+        let error = to_decode::<u32>(&value).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let (expected, found) = error.mismatch().unwrap();
+        assert_eq!(expected.to_string(), "end of blob");
+        assert_eq!(found.to_string(), "1 trailing bytes");
+
+        let error = to_decode::<String>(&Value::Blob(vec![5, b'a'])).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+        let error = to_decode::<bool>(&Value::Blob(vec![2])).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Decode);
+    }
+}
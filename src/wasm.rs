@@ -0,0 +1,25 @@
+//! Conversions between `bincode-json` values and JavaScript values, for browser clients.
+
+use crate::{
+    error::{Error, Result},
+    value::Value,
+};
+use serde::Serialize;
+use wasm_bindgen::JsValue;
+
+impl Value {
+    /// Converts the value into a plain JavaScript value: objects become plain objects, blobs
+    /// become `Uint8Array`s, and integers become numbers.
+    ///
+    /// Fails if an integer is outside the range JavaScript numbers represent exactly.
+    pub fn to_js_value(&self) -> Result<JsValue> {
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        self.serialize(&serializer)
+            .map_err(|e| Error::Custom(e.to_string()))
+    }
+
+    /// Converts a JavaScript value into a [Value].
+    pub fn from_js_value(val: JsValue) -> Result<Self> {
+        serde_wasm_bindgen::from_value(val).map_err(|e| Error::Custom(e.to_string()))
+    }
+}
@@ -0,0 +1,391 @@
+//! Low-level decoding of the `bincode-json` wire format.
+//!
+//! [crate::Value] derives [bincode::Decode], but the derived implementation can neither enforce
+//! [DecodeLimits] nor guard against unbounded recursion; this module decodes the same layout by
+//! hand over any bincode [Decoder].
+
+use crate::{
+    dict,
+    error::{Error, Result},
+    limits::{DecodeLimits, Limit},
+    value::{Map, Value, ValueKind},
+};
+use alloc::{string::String, vec::Vec};
+use bincode::{
+    de::{
+        read::{BorrowReader, Reader},
+        BorrowDecoder, Decode, Decoder,
+    },
+    error::{AllowedEnumVariants, DecodeError},
+};
+
+pub(crate) const NULL: u32 = 0;
+pub(crate) const BOOLEAN: u32 = 1;
+pub(crate) const BLOB: u32 = 2;
+pub(crate) const ARRAY: u32 = 3;
+pub(crate) const INTEGER: u32 = 4;
+pub(crate) const FLOAT: u32 = 5;
+pub(crate) const OBJECT: u32 = 6;
+pub(crate) const STRING: u32 = 7;
+
+/// Bytes are read in chunks of at most this size, so that a hostile length prefix cannot make
+/// the decoder allocate more memory than the input actually contains.
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// Containers are preallocated for at most this many elements, for the same reason.
+pub(crate) const MAX_PREALLOCATED: usize = 4096;
+
+/// A bincode [Reader] over a byte slice which keeps track of its position.
+pub(crate) struct SliceReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+impl<'a> SliceReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Gets the number of bytes read so far.
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Gets the number of bytes left to read.
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+}
+impl Reader for SliceReader<'_> {
+    fn read(&mut self, bytes: &mut [u8]) -> core::result::Result<(), DecodeError> {
+        let src = self.take_bytes(bytes.len())?;
+        bytes.copy_from_slice(src);
+        Ok(())
+    }
+
+    fn peek_read(&mut self, n: usize) -> Option<&[u8]> {
+        self.bytes[self.pos..].get(..n)
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.pos = (self.pos + n).min(self.bytes.len());
+    }
+}
+impl<'a> BorrowReader<'a> for SliceReader<'a> {
+    fn take_bytes(&mut self, length: usize) -> core::result::Result<&'a [u8], DecodeError> {
+        let remaining = self.bytes.len() - self.pos;
+        if length > remaining {
+            return Err(DecodeError::UnexpectedEnd {
+                additional: length - remaining,
+            });
+        }
+        let bytes = &self.bytes[self.pos..self.pos + length];
+        self.pos += length;
+        Ok(bytes)
+    }
+}
+
+/// Gets the size of the standard encoding of `value` in bytes, without encoding it.
+pub(crate) fn encoded_len(value: &Value) -> usize {
+    1 + match value {
+        Value::Null => 0,
+        Value::Boolean(_) => 1,
+        Value::Integer(i) => varint_len(((i << 1) ^ (i >> 63)) as u64),
+        Value::Float(_) => 8,
+        Value::Blob(b) => varint_len(b.len() as u64) + b.len(),
+        Value::String(s) => varint_len(s.len() as u64) + s.len(),
+        Value::Array(a) => varint_len(a.len() as u64) + a.iter().map(encoded_len).sum::<usize>(),
+        Value::Object(o) => {
+            varint_len(o.len() as u64)
+                + o.iter()
+                    .map(|(k, v)| varint_len(k.len() as u64) + k.len() + encoded_len(v))
+                    .sum::<usize>()
+        }
+    }
+}
+
+/// Gets the size of the varint encoding of `n`.
+pub(crate) fn varint_len(n: u64) -> usize {
+    match n {
+        0..=250 => 1,
+        251..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+/// Decodes the discriminant of the next value.
+pub(crate) fn decode_tag<D: Decoder>(decoder: &mut D) -> Result<u32> {
+    let tag = u32::decode(decoder)?;
+    if tag > STRING {
+        return Err(DecodeError::UnexpectedVariant {
+            type_name: "Value",
+            allowed: &AllowedEnumVariants::Range {
+                min: NULL,
+                max: STRING,
+            },
+            found: tag,
+        }
+        .into());
+    }
+    Ok(tag)
+}
+
+/// Gets the type of the values tagged `tag`, which must have been checked by [decode_tag].
+pub(crate) fn value_kind(tag: u32) -> ValueKind {
+    match tag {
+        NULL => ValueKind::Null,
+        BOOLEAN => ValueKind::Boolean,
+        BLOB => ValueKind::Blob,
+        ARRAY => ValueKind::Array,
+        INTEGER => ValueKind::Integer,
+        FLOAT => ValueKind::Float,
+        OBJECT => ValueKind::Object,
+        _ => ValueKind::String,
+    }
+}
+
+/// Decodes a length prefix.
+pub(crate) fn decode_len<D: Decoder>(decoder: &mut D) -> Result<usize> {
+    let len = u64::decode(decoder)?;
+    Ok(usize::try_from(len).map_err(|_| DecodeError::OutsideUsizeRange(len))?)
+}
+
+/// Reads `len` bytes.
+pub(crate) fn read_bytes<D: Decoder>(decoder: &mut D, len: usize) -> Result<Vec<u8>> {
+    decoder.claim_bytes_read(len)?;
+    let mut buf = Vec::with_capacity(len.min(CHUNK_LEN));
+    while buf.len() < len {
+        let start = buf.len();
+        let end = len.min(start + CHUNK_LEN);
+        buf.resize(end, 0);
+        decoder.reader().read(&mut buf[start..end])?;
+    }
+    Ok(buf)
+}
+
+/// Decodes a length-prefixed string.
+pub(crate) fn decode_string<D: Decoder>(decoder: &mut D, limits: &DecodeLimits) -> Result<String> {
+    let len = decode_len(decoder)?;
+    limits.check(Limit::StringLength, len)?;
+    into_string(read_bytes(decoder, len)?)
+}
+
+/// Converts the bytes of a string into a [String], validating them as UTF-8.
+pub(crate) fn into_string(bytes: Vec<u8>) -> Result<String> {
+    String::from_utf8(bytes).map_err(|e| {
+        Error::from(DecodeError::Utf8 {
+            inner: e.utf8_error(),
+        })
+    })
+}
+
+/// Decodes a [Value] nested at `depth`, enforcing `limits`.
+pub(crate) fn decode_value<D: Decoder>(
+    decoder: &mut D,
+    limits: &DecodeLimits,
+    depth: usize,
+) -> Result<Value> {
+    decode_value_with(decoder, limits, depth, &mut |decoder, len| {
+        Ok(Value::Blob(read_bytes(decoder, len)?))
+    })
+}
+
+/// Decodes a [Value] nested at `depth` like [decode_value], except that the contents of every
+/// blob are decoded by `blob`, given the length of the blob.
+pub(crate) fn decode_value_with<D, F>(
+    decoder: &mut D,
+    limits: &DecodeLimits,
+    depth: usize,
+    blob: &mut F,
+) -> Result<Value>
+where
+    D: Decoder,
+    F: FnMut(&mut D, usize) -> Result<Value>,
+{
+    match decode_tag(decoder)? {
+        NULL => Ok(Value::Null),
+        BOOLEAN => Ok(Value::Boolean(bool::decode(decoder)?)),
+        BLOB => {
+            let len = decode_len(decoder)?;
+            limits.check(Limit::BlobLength, len)?;
+            blob(decoder, len)
+        }
+        ARRAY => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = decode_len(decoder)?;
+            limits.check(Limit::Elements, len)?;
+            let mut array = Vec::with_capacity(len.min(MAX_PREALLOCATED));
+            for _ in 0..len {
+                array.push(decode_value_with(decoder, limits, depth + 1, blob)?);
+            }
+            Ok(Value::Array(array))
+        }
+        INTEGER => Ok(Value::Integer(i64::decode(decoder)?)),
+        FLOAT => Ok(Value::Float(f64::decode(decoder)?)),
+        OBJECT => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = decode_len(decoder)?;
+            limits.check(Limit::Elements, len)?;
+            let mut object = Map::with_capacity(len.min(MAX_PREALLOCATED));
+            for _ in 0..len {
+                let key = decode_string(decoder, limits)?;
+                object.insert(key, decode_value_with(decoder, limits, depth + 1, blob)?);
+            }
+            Ok(Value::Object(object))
+        }
+        _ => Ok(Value::String(decode_string(decoder, limits)?)),
+    }
+}
+
+/// Skips over a value nested at `depth` without decoding it, enforcing `limits`.
+pub(crate) fn skip_value<'de, D: BorrowDecoder<'de>>(
+    decoder: &mut D,
+    limits: &DecodeLimits,
+    depth: usize,
+) -> Result<()> {
+    match decode_tag(decoder)? {
+        NULL => {}
+        BOOLEAN => {
+            bool::decode(decoder)?;
+        }
+        BLOB => {
+            let len = decode_len(decoder)?;
+            limits.check(Limit::BlobLength, len)?;
+            skip_bytes(decoder, len)?;
+        }
+        ARRAY => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = decode_len(decoder)?;
+            limits.check(Limit::Elements, len)?;
+            for _ in 0..len {
+                skip_value(decoder, limits, depth + 1)?;
+            }
+        }
+        INTEGER => {
+            i64::decode(decoder)?;
+        }
+        FLOAT => skip_bytes(decoder, 8)?,
+        OBJECT => {
+            limits.check(Limit::Depth, depth + 1)?;
+            let len = decode_len(decoder)?;
+            limits.check(Limit::Elements, len)?;
+            for _ in 0..len {
+                let key_len = decode_len(decoder)?;
+                limits.check(Limit::StringLength, key_len)?;
+                skip_bytes(decoder, key_len)?;
+                skip_value(decoder, limits, depth + 1)?;
+            }
+        }
+        _ => {
+            let len = decode_len(decoder)?;
+            limits.check(Limit::StringLength, len)?;
+            skip_bytes(decoder, len)?;
+        }
+    }
+    Ok(())
+}
+
+/// Skips over `len` bytes without copying them.
+fn skip_bytes<'de, D: BorrowDecoder<'de>>(decoder: &mut D, len: usize) -> Result<()> {
+    decoder.claim_bytes_read(len)?;
+    decoder.borrow_reader().take_bytes(len)?;
+    Ok(())
+}
+
+/// A bincode [Reader] over a [std::io::Read].
+#[cfg(feature = "std")]
+pub(crate) struct IoReader<R>(pub(crate) R);
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Reader for IoReader<R> {
+    fn read(&mut self, bytes: &mut [u8]) -> core::result::Result<(), DecodeError> {
+        self.0.read_exact(bytes).map_err(|inner| DecodeError::Io {
+            inner,
+            additional: bytes.len(),
+        })
+    }
+}
+
+/// Decodes a [Value] from the beginning of `bytes`, returning it with the number of bytes read.
+/// Errors carry the offset in `bytes` decoding stopped at. Documents of the key dictionary profile
+/// are rejected.
+pub(crate) fn decode_from_slice(bytes: &[u8], limits: &DecodeLimits) -> Result<(Value, usize)> {
+    limits.check(Limit::Bytes, bytes.len())?;
+    dict::reject_profile(bytes.first().copied())?;
+    decode_standard(bytes, limits)
+}
+
+/// Decodes a [Value] from the beginning of `bytes` like [decode_from_slice], except that documents
+/// of the key dictionary profile are accepted, their strings resolved through `dictionary`.
+pub(crate) fn decode_from_slice_with_dict(
+    bytes: &[u8],
+    limits: &DecodeLimits,
+    dictionary: &mut dict::Dictionary,
+) -> Result<(Value, usize)> {
+    limits.check(Limit::Bytes, bytes.len())?;
+    if let Some((&marker @ (dict::MARKER | dict::STRINGS_MARKER), body)) = bytes.split_first() {
+        let mut decoder =
+            bincode::de::DecoderImpl::new(SliceReader::new(body), bincode::config::standard());
+        let value = dictionary
+            .decode_document(&mut decoder, limits, marker, bytes.len())
+            .map_err(|e| e.at_offset(1 + decoder.reader().position()))?;
+        return Ok((value, 1 + decoder.reader().position()));
+    }
+    decode_standard(bytes, limits)
+}
+
+/// Decodes a [Value] in the standard profile from the beginning of `bytes`.
+fn decode_standard(bytes: &[u8], limits: &DecodeLimits) -> Result<(Value, usize)> {
+    let mut decoder =
+        bincode::de::DecoderImpl::new(SliceReader::new(bytes), bincode::config::standard());
+    let value = decode_value(&mut decoder, limits, 0)
+        .map_err(|e| e.at_offset(decoder.reader().position()))?;
+    Ok((value, decoder.reader().position()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use alloc::{collections::BTreeMap, format, vec};
+
+    #[test]
+    fn exact_lengths() {
+        let mut values = vec![Value::Null, Value::Boolean(false), Value::Float(0.5)];
+        for i in [
+            0,
+            -1,
+            125,
+            -126,
+            126,
+            32767,
+            -32768,
+            32768,
+            i64::MAX,
+            i64::MIN,
+        ] {
+            values.push(Value::Integer(i));
+        }
+        for len in [0, 250, 251, 65535, 65536] {
+            values.push(Value::Blob(vec![0; len]));
+            values.push(Value::String("x".repeat(len)));
+        }
+        let object = (0..300).map(|i| (format!("{}", i), Value::Integer(i)));
+        values.push(Value::Array(values.clone()));
+        values.push(Value::Object(object.collect()));
+
+        for value in values {
+            let encoded = bincode::encode_to_vec(&value, bincode::config::standard()).unwrap();
+            assert_eq!(encoded_len(&value), encoded.len());
+            let bytes = value.to_vec().unwrap();
+            assert_eq!(bytes, encoded);
+            assert_eq!(bytes.capacity(), bytes.len());
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let map = BTreeMap::from([(vec![1u8], 1u32)]);
+        let error = crate::to_vec(&map).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+    }
+}
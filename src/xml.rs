@@ -0,0 +1,339 @@
+//! Conversions between XML documents and [Value]s, e.g. for ingesting legacy XML feeds.
+//!
+//! A document is an object with a single entry mapping the name of its root element to the
+//! element's value. An element with neither attributes nor child elements is its text, or null if
+//! it has none. Any other element is an object of its attributes, keyed by
+//! [XmlOptions::attribute_prefix] and their name, of its child elements, keyed by their name,
+//! where repeated elements are collected into an array, and of its text, keyed by
+//! [XmlOptions::text_key].
+//!
+//! Writing does the reverse: arrays become repeated elements, entries whose key starts with the
+//! attribute prefix become attributes, and blobs are written as base64. Every value is read back
+//! as a string, as XML has no other types.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value},
+};
+use quick_xml::{
+    events::{BytesEnd, BytesStart, BytesText, Event},
+    Reader, Writer,
+};
+use std::borrow::Cow;
+
+/// Options of [from_xml] and [to_xml].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct XmlOptions {
+    /// The prefix of the keys of attributes, `@` by default. With an empty prefix, attributes are
+    /// read like child elements, and nothing is written as an attribute.
+    pub attribute_prefix: String,
+
+    /// The key of the text of elements which also have attributes or child elements, `#text` by
+    /// default.
+    pub text_key: String,
+}
+impl Default for XmlOptions {
+    fn default() -> Self {
+        Self {
+            attribute_prefix: "@".into(),
+            text_key: "#text".into(),
+        }
+    }
+}
+impl XmlOptions {
+    /// Constructs a new [XmlOptions] with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the prefix of the keys of attributes.
+    pub fn with_attribute_prefix(mut self, attribute_prefix: impl Into<String>) -> Self {
+        self.attribute_prefix = attribute_prefix.into();
+        self
+    }
+
+    /// Sets the key of the text of elements.
+    pub fn with_text_key(mut self, text_key: impl Into<String>) -> Self {
+        self.text_key = text_key.into();
+        self
+    }
+}
+
+/// Parses an XML document into a [Value].
+pub fn from_xml(xml: &str, options: &XmlOptions) -> Result<Value> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+    loop {
+        let (name, value) = match reader.read_event().map_err(xml_error)? {
+            Event::Start(start) => {
+                stack.push(Element::new(&start, options)?);
+                continue;
+            }
+            Event::Empty(start) => {
+                let element = Element::new(&start, options)?;
+                (element.name.clone(), element.into_value(options))
+            }
+            Event::End(_) => {
+                let element = stack.pop().expect("end events are matched by the reader");
+                (element.name.clone(), element.into_value(options))
+            }
+            Event::Text(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text.unescape().map_err(xml_error)?);
+                }
+                continue;
+            }
+            Event::CData(data) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&data.decode().map_err(xml_error)?);
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        match stack.last_mut() {
+            Some(parent) => insert(&mut parent.entries, name, value),
+            None if root.is_none() => root = Some((name, value)),
+            None => return Err(Error::Unsupported("XML document with several roots".into())),
+        }
+    }
+
+    let (name, value) = root.ok_or(Error::Eof)?;
+    let mut document = Map::default();
+    document.insert(name, value);
+    Ok(Value::Object(document))
+}
+
+/// Writes a [Value] as an XML document.
+pub fn to_xml(value: &Value, options: &XmlOptions) -> Result<String> {
+    let (name, root) = match value {
+        Value::Object(o) if o.len() == 1 => o.iter().next().expect("the object has an entry"),
+        v => {
+            return Err(Error::Expected(
+                "object with a single root element".into(),
+                v.kind().into(),
+            ))
+        }
+    };
+    if matches!(root, Value::Array(_)) {
+        return Err(Error::Unsupported("XML document with several roots".into()));
+    }
+
+    let mut writer = Writer::new(Vec::new());
+    write_element(&mut writer, name, root, options)?;
+    String::from_utf8(writer.into_inner()).map_err(|e| Error::Custom(e.to_string()))
+}
+
+/// An element whose contents are being read.
+struct Element {
+    name: String,
+    entries: Map<String, Value>,
+    text: String,
+}
+impl Element {
+    fn new(start: &BytesStart, options: &XmlOptions) -> Result<Self> {
+        let mut entries = Map::default();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(xml_error)?;
+            let key = format!(
+                "{}{}",
+                options.attribute_prefix,
+                utf8(attribute.key.as_ref())?
+            );
+            let value = attribute.unescape_value().map_err(xml_error)?;
+            insert(&mut entries, key, Value::String(value.into_owned()));
+        }
+        Ok(Self {
+            name: utf8(start.name().as_ref())?.into(),
+            entries,
+            text: String::new(),
+        })
+    }
+
+    fn into_value(mut self, options: &XmlOptions) -> Value {
+        match (self.entries.is_empty(), self.text.is_empty()) {
+            (true, true) => Value::Null,
+            (true, false) => Value::String(self.text),
+            (false, true) => Value::Object(self.entries),
+            (false, false) => {
+                insert(
+                    &mut self.entries,
+                    options.text_key.clone(),
+                    Value::String(self.text),
+                );
+                Value::Object(self.entries)
+            }
+        }
+    }
+}
+
+/// Inserts an element into its parent, collecting repeated elements into an array.
+fn insert(entries: &mut Map<String, Value>, key: String, value: Value) {
+    match entries.get_mut(&key) {
+        Some(Value::Array(repeated)) => repeated.push(value),
+        Some(existing) => {
+            let first = std::mem::replace(existing, Value::Null);
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            entries.insert(key, value);
+        }
+    }
+}
+
+/// Writes `value` as elements named `name`.
+fn write_element(
+    writer: &mut Writer<Vec<u8>>,
+    name: &str,
+    value: &Value,
+    options: &XmlOptions,
+) -> Result<()> {
+    let mut start = BytesStart::new(name);
+    let mut text = None;
+    let mut children = Vec::new();
+    match value {
+        Value::Array(repeated) => {
+            for value in repeated {
+                write_element(writer, name, value, options)?;
+            }
+            return Ok(());
+        }
+        Value::Object(o) => {
+            for (key, value) in o {
+                match key.strip_prefix(&options.attribute_prefix) {
+                    Some(attribute) if !options.attribute_prefix.is_empty() => {
+                        start.push_attribute((attribute, &*scalar_text(key, value)?));
+                    }
+                    _ if *key == options.text_key => text = Some(scalar_text(key, value)?),
+                    _ => children.push((key, value)),
+                }
+            }
+        }
+        Value::Null => {}
+        value => text = Some(scalar_text(name, value)?),
+    }
+
+    if text.is_none() && children.is_empty() {
+        return write_event(writer, Event::Empty(start));
+    }
+    write_event(writer, Event::Start(start))?;
+    if let Some(text) = text {
+        write_event(writer, Event::Text(BytesText::new(&text)))?;
+    }
+    for (key, value) in children {
+        write_element(writer, key, value, options)?;
+    }
+    write_event(writer, Event::End(BytesEnd::new(name)))
+}
+
+/// Gets the text of a scalar written under `key`.
+fn scalar_text<'a>(key: &str, value: &'a Value) -> Result<Cow<'a, str>> {
+    Ok(match value {
+        Value::Null => "".into(),
+        Value::Boolean(b) => b.to_string().into(),
+        Value::Integer(i) => i.to_string().into(),
+        Value::Float(f) => f.to_string().into(),
+        Value::String(s) => s.as_str().into(),
+        Value::Blob(b) => base64::encode(b).into(),
+        Value::Array(_) | Value::Object(_) => {
+            return Err(Error::Unsupported(format!(
+                "nested value in attribute or text {}",
+                key
+            )))
+        }
+    })
+}
+
+fn write_event(writer: &mut Writer<Vec<u8>>, event: Event) -> Result<()> {
+    Ok(writer.write_event(event)?)
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str> {
+    std::str::from_utf8(bytes).map_err(|e| Error::Custom(e.to_string()))
+}
+
+/// Converts an XML error into an [Error].
+fn xml_error<E: std::fmt::Display>(e: E) -> Error {
+    Error::Custom(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    const XML: &str = r#"<feed lang="en"><item id="1">first</item><item id="2"/><title>News</title><empty/></feed>"#;
+
+    #[test]
+    fn parse() {
+        let Value::Object(document) = from_xml(XML, &XmlOptions::new()).unwrap() else {
+            panic!("expected an object");
+        };
+        let Value::Object(feed) = &document["feed"] else {
+            panic!("expected an object");
+        };
+        assert!(matches!(&feed["@lang"], Value::String(s) if s == "en"));
+        assert!(matches!(&feed["title"], Value::String(s) if s == "News"));
+        assert!(feed["empty"].is_null());
+        let Value::Array(items) = &feed["item"] else {
+            panic!("expected an array");
+        };
+        let Value::Object(first) = &items[0] else {
+            panic!("expected an object");
+        };
+        assert!(matches!(&first["@id"], Value::String(s) if s == "1"));
+        assert!(matches!(&first["#text"], Value::String(s) if s == "first"));
+    }
+
+    #[test]
+    fn round_trip() {
+        for options in [
+            XmlOptions::new(),
+            XmlOptions::new()
+                .with_attribute_prefix("_")
+                .with_text_key("$"),
+        ] {
+            let value = from_xml(XML, &XmlOptions::new()).unwrap();
+            let value = from_xml(&to_xml(&value, &XmlOptions::new()).unwrap(), &options).unwrap();
+            let xml = to_xml(&value, &options).unwrap();
+            let reparsed = from_xml(&xml, &options).unwrap();
+            assert_eq!(
+                crate::to_vec_canonical(&reparsed).unwrap(),
+                crate::to_vec_canonical(&value).unwrap()
+            );
+        }
+
+        let value = Value::Object(Map::from([(String::from("n"), Value::Blob(vec![1, 2, 3]))]));
+        assert_eq!(to_xml(&value, &XmlOptions::new()).unwrap(), "<n>AQID</n>");
+    }
+
+    #[test]
+    fn errors() {
+        for xml in ["", "<a>", "<a></b>", "<a/><b/>"] {
+            assert!(from_xml(xml, &XmlOptions::new()).is_err(), "{}", xml);
+        }
+        let error = from_xml("<a/><b/>", &XmlOptions::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        let error = from_xml("", &XmlOptions::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+
+        let error = to_xml(&Value::Integer(1), &XmlOptions::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let roots = Value::Object(Map::from([(
+            String::from("a"),
+            Value::Array(vec![Value::Null, Value::Null]),
+        )]));
+        let error = to_xml(&roots, &XmlOptions::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+        let nested = Value::Object(Map::from([(
+            String::from("a"),
+            Value::Object(Map::from([(String::from("@b"), Value::Array(Vec::new()))])),
+        )]));
+        let error = to_xml(&nested, &XmlOptions::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}
@@ -0,0 +1,138 @@
+//! Conversions between `bincode-json` values and YAML values, e.g. for snapshotting configuration.
+//!
+//! Blobs convert to base64 strings tagged `!binary`, and back. The standard `!!binary` tag is not
+//! used, as `serde_yaml` resolves it away while parsing. Any other tag is dropped in favor of the
+//! value it tags.
+//!
+//! YAML mappings may have keys of any type: string keys are kept, null, boolean and number keys
+//! become their YAML text, and keys of other types are unsupported.
+
+use crate::{
+    error::{Error, Result},
+    value::{Map, Value},
+};
+use serde_yaml::value::{Tag, TaggedValue};
+
+/// The tag of blobs.
+const BINARY_TAG: &str = "!binary";
+
+impl From<Value> for serde_yaml::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => serde_yaml::Value::Null,
+            Value::Boolean(b) => serde_yaml::Value::Bool(b),
+            Value::Blob(b) => serde_yaml::Value::Tagged(Box::new(TaggedValue {
+                tag: Tag::new(BINARY_TAG),
+                value: serde_yaml::Value::String(base64::encode(b)),
+            })),
+            Value::Array(a) => {
+                serde_yaml::Value::Sequence(a.into_iter().map(serde_yaml::Value::from).collect())
+            }
+            Value::Integer(i) => serde_yaml::Value::Number(i.into()),
+            Value::Float(f) => serde_yaml::Value::Number(f.into()),
+            Value::Object(o) => serde_yaml::Value::Mapping(
+                o.into_iter()
+                    .map(|(k, v)| (serde_yaml::Value::String(k), v.into()))
+                    .collect(),
+            ),
+            Value::String(s) => serde_yaml::Value::String(s),
+        }
+    }
+}
+impl TryFrom<serde_yaml::Value> for Value {
+    type Error = Error;
+
+    fn try_from(value: serde_yaml::Value) -> Result<Self> {
+        Ok(match value {
+            serde_yaml::Value::Null => Value::Null,
+            serde_yaml::Value::Bool(b) => Value::Boolean(b),
+            serde_yaml::Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    Value::Integer(i)
+                } else if let Some(u) = n.as_u64() {
+                    Value::Integer(u as _)
+                } else {
+                    Value::Float(n.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            serde_yaml::Value::String(s) => Value::String(s),
+            serde_yaml::Value::Sequence(a) => {
+                Value::Array(a.into_iter().map(Value::try_from).collect::<Result<_>>()?)
+            }
+            serde_yaml::Value::Mapping(m) => {
+                let mut map = Map::with_capacity(m.len());
+                for (k, v) in m {
+                    map.insert(key(k)?, v.try_into()?);
+                }
+                Value::Object(map)
+            }
+            serde_yaml::Value::Tagged(tagged) => match (tagged.tag, tagged.value) {
+                (tag, serde_yaml::Value::String(s)) if tag == BINARY_TAG => {
+                    let blob: String = s.split_whitespace().collect();
+                    Value::Blob(base64::decode(blob).map_err(|e| {
+                        Error::Expected("base64 binary".into(), e.to_string().into())
+                    })?)
+                }
+                (_, value) => value.try_into()?,
+            },
+        })
+    }
+}
+
+/// Converts a YAML mapping key to an object key.
+fn key(key: serde_yaml::Value) -> Result<String> {
+    match key {
+        serde_yaml::Value::String(s) => Ok(s),
+        serde_yaml::Value::Null => Ok("null".into()),
+        serde_yaml::Value::Bool(b) => Ok(b.to_string()),
+        serde_yaml::Value::Number(n) => Ok(n.to_string()),
+        serde_yaml::Value::Tagged(tagged) => self::key(tagged.value),
+        _ => Err(Error::Unsupported(
+            "YAML mapping key other than a scalar".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::test_util::document;
+
+    fn parse(text: &str) -> Result<Value> {
+        serde_yaml::from_str::<serde_yaml::Value>(text)
+            .unwrap()
+            .try_into()
+    }
+
+    #[test]
+    fn round_trip() {
+        let text = serde_yaml::to_string(&serde_yaml::Value::from(document())).unwrap();
+        assert!(text.contains("!binary AQID"), "{}", text);
+        let value = parse(&text).unwrap();
+        assert_eq!(
+            crate::to_vec_canonical(&value).unwrap(),
+            crate::to_vec_canonical(&document()).unwrap()
+        );
+    }
+
+    #[test]
+    fn keys_and_tags() {
+        let Value::Object(o) = parse("1: one\ntrue: yes\n~: nothing\nx: !custom 2\n").unwrap()
+        else {
+            panic!("expected an object");
+        };
+        assert!(matches!(&o["1"], Value::String(s) if s == "one"));
+        assert!(matches!(&o["true"], Value::String(s) if s == "yes"));
+        assert!(matches!(&o["null"], Value::String(s) if s == "nothing"));
+        assert!(matches!(o["x"], Value::Integer(2)));
+    }
+
+    #[test]
+    fn errors() {
+        let error = parse("!binary '!!!'").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        let error = parse("[1, 2]: pair").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+}